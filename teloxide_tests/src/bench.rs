@@ -0,0 +1,237 @@
+//! Measuring how fast the handler tree handles updates, see [`MockBot::bench`]
+//!
+//! [`MockBot::bench`]: crate::MockBot::bench
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use teloxide::{
+    dispatching::{DpHandlerDescription, UpdateHandler},
+    dptree::{self, di::DependencyMap, HandlerDescription, HandlerSignature},
+    types::{Update, UpdateKind},
+};
+
+/// Latency percentiles of the updates of one kind
+#[derive(Clone, Debug, PartialEq)]
+pub struct LatencyStats {
+    pub count: usize,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl LatencyStats {
+    fn new(mut latencies: Vec<Duration>) -> Self {
+        latencies.sort();
+        // Nearest-rank percentile
+        let percentile = |p: usize| latencies[(latencies.len() * p).div_ceil(100).max(1) - 1];
+        Self {
+            count: latencies.len(),
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: latencies[latencies.len() - 1],
+        }
+    }
+}
+
+/// The results of [`MockBot::bench`]
+///
+/// [`MockBot::bench`]: crate::MockBot::bench
+#[derive(Clone, Debug)]
+pub struct BenchReport {
+    /// How many updates went through the handler tree
+    pub updates: usize,
+    /// Time from the start of handling the first update to the end of handling the last one
+    pub total: Duration,
+    /// Latencies by the kind of the update, like `message` or `callback_query`
+    pub latencies: BTreeMap<String, LatencyStats>,
+    /// Latencies of the branches wrapped into [`timed`], by their names. Only the updates the
+    /// branch handled are counted.
+    pub handlers: BTreeMap<String, LatencyStats>,
+}
+
+impl BenchReport {
+    /// Handled updates per second
+    pub fn throughput(&self) -> f64 {
+        self.updates as f64 / self.total.as_secs_f64()
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} updates in {:?} ({:.1} updates/s)",
+            self.updates,
+            self.total,
+            self.throughput()
+        )?;
+        for (kind, stats) in &self.latencies {
+            writeln!(
+                f,
+                "{kind}: {} updates, p50 {:?}, p90 {:?}, p99 {:?}, max {:?}",
+                stats.count, stats.p50, stats.p90, stats.p99, stats.max
+            )?;
+        }
+        for (name, stats) in &self.handlers {
+            writeln!(
+                f,
+                "handler {name}: {} updates, p50 {:?}, p90 {:?}, p99 {:?}, max {:?}",
+                stats.count, stats.p50, stats.p90, stats.p99, stats.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+struct Measurement {
+    update: Arc<Update>,
+    start: Instant,
+    end: Instant,
+}
+
+/// Measures the time every update spends in the handler tree
+#[derive(Clone, Default)]
+pub(crate) struct LatencyRecorder {
+    updates: Arc<Mutex<Vec<Measurement>>>,
+    /// The names of the [`timed`] branches and how long they took to handle an update
+    handlers: Arc<Mutex<Vec<(&'static str, Duration)>>>,
+}
+
+/// Wraps a branch of the handler tree, so that [`MockBot::bench`] reports its latencies under
+/// `name`, separately from the other branches that handle the same kind of updates. Outside of
+/// `bench` it does nothing.
+///
+/// # Example
+/// ```
+/// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+/// # use teloxide_tests::{bench::timed, MockBot, MockMessageText};
+/// # type HandlerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+/// # async fn start(bot: Bot, message: Message) -> HandlerResult { Ok(()) }
+/// # async fn help(bot: Bot, message: Message) -> HandlerResult { Ok(()) }
+/// # #[tokio::main]
+/// # async fn main() {
+/// let is_start = |message: Message| message.text() == Some("/start");
+/// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+///     Update::filter_message()
+///         .branch(timed("start", dptree::filter(is_start).endpoint(start)))
+///         .branch(timed("help", dptree::endpoint(help)));
+/// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+/// let report = bot.bench(100).await;
+/// assert_eq!(report.handlers["start"].count, 100);
+/// // The help branch never handled an update
+/// assert!(!report.handlers.contains_key("help"));
+/// # }
+/// ```
+///
+/// [`MockBot::bench`]: crate::MockBot::bench
+pub fn timed<Err>(name: &'static str, handler: UpdateHandler<Err>) -> UpdateHandler<Err>
+where
+    Err: Send + Sync + 'static,
+{
+    let timer = dptree::from_fn_with_description(
+        DpHandlerDescription::entry(),
+        move |deps: DependencyMap, cont| async move {
+            let Some(recorder) = deps.try_get::<LatencyRecorder>() else {
+                return cont(deps).await;
+            };
+            let start = Instant::now();
+            let result = cont(deps).await;
+            // The branch may not handle the update, then it isn't counted
+            if result.is_break() {
+                let latency = start.elapsed();
+                recorder.handlers.lock().unwrap().push((name, latency));
+            }
+            result
+        },
+        HandlerSignature::Entry,
+    );
+    timer.chain(handler)
+}
+
+impl LatencyRecorder {
+    /// Returns the handler tree, that records the time of every update going through `tree`
+    pub(crate) fn wrap<Err>(&self, tree: UpdateHandler<Err>) -> UpdateHandler<Err>
+    where
+        Err: Send + Sync + 'static,
+    {
+        let recorder = self.clone();
+        let timer = dptree::from_fn_with_description(
+            DpHandlerDescription::entry(),
+            move |deps: DependencyMap, cont| {
+                let recorder = recorder.clone();
+                async move {
+                    let update = deps.get::<Update>();
+                    let start = Instant::now();
+                    let result = cont(deps).await;
+                    let end = Instant::now();
+                    recorder
+                        .updates
+                        .lock()
+                        .unwrap()
+                        .push(Measurement { update, start, end });
+                    result
+                }
+            },
+            HandlerSignature::Entry,
+        );
+        timer.chain(tree)
+    }
+
+    pub(crate) fn report(&self) -> BenchReport {
+        let measurements = std::mem::take(&mut *self.updates.lock().unwrap());
+        let mut by_handler: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+        for (name, latency) in std::mem::take(&mut *self.handlers.lock().unwrap()) {
+            by_handler
+                .entry(name.to_string())
+                .or_default()
+                .push(latency);
+        }
+        let handlers = by_handler
+            .into_iter()
+            .map(|(name, latencies)| (name, LatencyStats::new(latencies)))
+            .collect();
+        let (Some(first_start), Some(last_end)) = (
+            measurements.iter().map(|m| m.start).min(),
+            measurements.iter().map(|m| m.end).max(),
+        ) else {
+            return BenchReport {
+                updates: 0,
+                total: Duration::ZERO,
+                latencies: BTreeMap::new(),
+                handlers,
+            };
+        };
+
+        let mut by_kind: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+        for measurement in &measurements {
+            by_kind
+                .entry(kind_name(&measurement.update.kind))
+                .or_default()
+                .push(measurement.end - measurement.start);
+        }
+        BenchReport {
+            updates: measurements.len(),
+            total: last_end - first_start,
+            latencies: by_kind
+                .into_iter()
+                .map(|(kind, latencies)| (kind, LatencyStats::new(latencies)))
+                .collect(),
+            handlers,
+        }
+    }
+}
+
+// The name of the update field in the Bot API, like `message`
+fn kind_name(kind: &UpdateKind) -> String {
+    match serde_json::to_value(kind) {
+        Ok(serde_json::Value::Object(map)) => map.keys().next().cloned(),
+        _ => None,
+    }
+    .unwrap_or_else(|| "unknown".to_string())
+}
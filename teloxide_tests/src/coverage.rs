@@ -0,0 +1,208 @@
+//! Which Bot API methods the bot called and which of them the fake server implements, see
+//! [`MockBot::route_coverage`]
+//!
+//! [`MockBot::route_coverage`]: crate::MockBot::route_coverage
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    server::{requests::method_name, BOT_METHODS},
+    state::State,
+};
+
+/// The env variable with the path of the JSON file, that every bot adds its route coverage to
+/// when it is dropped, see [`RouteCoverage::merge_into_json`]
+pub const COVERAGE_ENV: &str = "TELOXIDE_TESTS_COVERAGE";
+
+/// How long a merge waits for the other processes to finish theirs
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The Bot API methods the bot called, compared to the ones the fake server implements
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteCoverage {
+    /// How many times the bot called every method, like `sendMessage`, including the ones the
+    /// fake server doesn't implement
+    pub calls: BTreeMap<String, usize>,
+}
+
+impl RouteCoverage {
+    pub(crate) fn record(&mut self, method: &str) {
+        *self.calls.entry(method.to_string()).or_default() += 1;
+    }
+
+    /// The methods the fake server implements, like `sendMessage`
+    pub fn implemented() -> Vec<String> {
+        let mut methods: Vec<String> = BOT_METHODS.iter().map(|route| method_name(route)).collect();
+        methods.sort();
+        methods
+    }
+
+    /// The methods the fake server implements, but the bot never called
+    pub fn uncalled(&self) -> Vec<String> {
+        Self::implemented()
+            .into_iter()
+            .filter(|method| !self.calls.contains_key(method))
+            .collect()
+    }
+
+    /// The methods the bot called, but the fake server doesn't implement
+    pub fn unsupported(&self) -> Vec<String> {
+        let implemented = Self::implemented();
+        self.calls
+            .keys()
+            .filter(|method| !implemented.contains(method))
+            .cloned()
+            .collect()
+    }
+
+    /// Adds the calls of `other`, e.g. to get the coverage of several tests
+    pub fn merge(&mut self, other: &RouteCoverage) {
+        for (method, count) in &other.calls {
+            *self.calls.entry(method.clone()).or_default() += count;
+        }
+    }
+
+    /// Writes the coverage to a JSON file
+    pub fn write_json(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// Reads the coverage from a JSON file written by `write_json`
+    pub fn read_json(path: impl AsRef<Path>) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Adds the calls to the coverage in the JSON file, creating it if it doesn't exist. Every
+    /// bot does it when it is dropped if the `TELOXIDE_TESTS_COVERAGE` env variable has the path
+    /// of the file, so after a test run the file has the coverage of all of the tests.
+    ///
+    /// The merges of parallel test processes, like the ones of nextest, wait for each other with
+    /// a `.lock` file next to the JSON file, and the JSON file is replaced as a whole, so it is
+    /// never left half-written.
+    ///
+    /// # Example
+    /// ```
+    /// let path = std::env::temp_dir().join("teloxide_tests_merge_into_json_example.json");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let mut coverage = teloxide_tests::RouteCoverage::default();
+    /// coverage.calls.insert("sendMessage".to_string(), 2);
+    /// coverage.merge_into_json(&path).unwrap();
+    /// coverage.merge_into_json(&path).unwrap();
+    ///
+    /// let total = teloxide_tests::RouteCoverage::read_json(&path).unwrap();
+    /// assert_eq!(total.calls["sendMessage"], 4);
+    /// assert!(total.uncalled().contains(&"sendPhoto".to_string()));
+    /// ```
+    pub fn merge_into_json(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let _lock = FileLock::acquire(path)?;
+        let mut total = match Self::read_json(path) {
+            Ok(total) => total,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(error) => return Err(error),
+        };
+        total.merge(self);
+        let temp_path = with_suffix(path, ".tmp");
+        total.write_json(&temp_path)?;
+        fs::rename(temp_path, path)
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+    path.into()
+}
+
+/// Holds the `.lock` file of a coverage file, so that only one process at a time changes it
+struct FileLock(PathBuf);
+
+impl FileLock {
+    fn acquire(path: &Path) -> io::Result<Self> {
+        let lock_path = with_suffix(path, ".lock");
+        let started = Instant::now();
+        loop {
+            // Creating a new file is atomic, so only one of the processes succeeds
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock_path)
+            {
+                Ok(_) => return Ok(Self(lock_path)),
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                    if started.elapsed() > LOCK_TIMEOUT {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!(
+                                "{lock_path:?} wasn't removed for {LOCK_TIMEOUT:?}, delete it if \
+                                 no tests are running"
+                            ),
+                        ));
+                    }
+                    thread::sleep(Duration::from_millis(5));
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+impl fmt::Display for RouteCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let implemented = Self::implemented();
+        let uncalled = self.uncalled();
+        writeln!(
+            f,
+            "Called {} of {} implemented methods",
+            implemented.len() - uncalled.len(),
+            implemented.len()
+        )?;
+        for (method, count) in &self.calls {
+            writeln!(f, "{method}: {count}")?;
+        }
+        if !uncalled.is_empty() {
+            writeln!(f, "Not called: {}", uncalled.join(", "))?;
+        }
+        let unsupported = self.unsupported();
+        if !unsupported.is_empty() {
+            writeln!(f, "Not implemented: {}", unsupported.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds the route coverage of the bot to the file in `TELOXIDE_TESTS_COVERAGE` when the bot is
+/// dropped
+pub(crate) struct CoverageDump(pub Arc<Mutex<State>>);
+
+impl Drop for CoverageDump {
+    fn drop(&mut self) {
+        let Some(path) = std::env::var_os(COVERAGE_ENV) else {
+            return;
+        };
+        let state = self.0.lock().unwrap_or_else(|error| error.into_inner());
+        if let Err(error) = state.coverage.merge_into_json(&path) {
+            log::error!("Failed to write the route coverage to {path:?}: {error}");
+        }
+    }
+}
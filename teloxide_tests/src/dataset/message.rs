@@ -4,7 +4,9 @@ use chrono::{DateTime, Utc};
 use teloxide::types::*;
 
 use super::chat::MockPrivateChat;
-use crate::{proc_macros::Changeable, MockUser};
+use crate::{
+    proc_macros::Changeable, MockMessagePhoto, MockMessageVideo, MockSharedUser, MockUser,
+};
 
 macro_rules! Message {
     (
@@ -12,6 +14,7 @@ macro_rules! Message {
         $pub:vis struct $name:ident {
             $($fpub:vis $field:ident : $type:ty,)*
         }
+        $(builder { $($builder_item:item)* })?
     ) => {
         #[derive($($derive),*)]
         $pub struct $name {  // This is basically a template
@@ -59,6 +62,34 @@ macro_rules! Message {
             }
         }
 
+        impl crate::dataset::MockMessageBuilder for $name {
+            fn id(mut self, id: i32) -> Self {
+                self.id = MessageId(id);
+                self
+            }
+
+            fn chat(mut self, chat: Chat) -> Self {
+                self.chat = chat;
+                self
+            }
+
+            fn from(mut self, user: User) -> Self {
+                self.from = Some(user);
+                self
+            }
+
+            fn date(mut self, date: DateTime<Utc>) -> Self {
+                self.date = date;
+                self
+            }
+
+            fn build(self) -> Message {
+                $name::build(self)
+            }
+
+            $($($builder_item)*)?
+        }
+
         impl crate::dataset::IntoUpdate for $name {
             /// Converts the mock message into an updates vector
             ///
@@ -76,10 +107,12 @@ macro_rules! Message {
             /// ```
             ///
             fn into_update(self, id: &AtomicI32) -> Vec<Update> {
-                vec![Update {
+                let update = Update {
                     id: UpdateId(id.fetch_add(1, Ordering::Relaxed) as u32),
                     kind: UpdateKind::Message(self.build()),
-                }]
+                };
+                crate::dataset::validate_update(&update);
+                vec![update]
             }
         }
     }
@@ -87,6 +120,45 @@ macro_rules! Message {
 
 pub(crate) use Message;
 
+/// The setters every `MockMessage*` builder has, so test helpers can take any mock message
+///
+/// # Example
+/// ```
+/// use teloxide_tests::{MockGroupChat, MockMessageBuilder, MockMessagePhoto, MockMessageText};
+///
+/// fn in_group<T: MockMessageBuilder>(message: T) -> T {
+///     message.chat(MockGroupChat::new().build()).id(100)
+/// }
+///
+/// let text = MockMessageBuilder::build(in_group(MockMessageText::new()));
+/// let photo = MockMessageBuilder::build(in_group(MockMessagePhoto::new()));
+/// assert_eq!(text.chat.id, photo.chat.id);
+/// assert_eq!(photo.id.0, 100);
+/// ```
+pub trait MockMessageBuilder: crate::dataset::IntoUpdate + Clone + Sized {
+    fn id(self, id: i32) -> Self;
+
+    fn chat(self, chat: Chat) -> Self;
+
+    fn from(self, user: User) -> Self;
+
+    fn date(self, date: DateTime<Utc>) -> Self;
+
+    /// Makes the message a reply to `message`. Service messages, like `new_chat_members`, can't
+    /// be replies, so for them it panics.
+    fn reply_to(self, _message: Message) -> Self {
+        panic!("Service messages can't be replies!");
+    }
+
+    /// Sets the inline keyboard of the message. Service messages, like `new_chat_members`, can't
+    /// have one, so for them it panics.
+    fn reply_markup(self, _markup: InlineKeyboardMarkup) -> Self {
+        panic!("Service messages can't have a reply markup!");
+    }
+
+    fn build(self) -> Message;
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct MockEditedMessage(Message);
 
@@ -133,10 +205,200 @@ impl crate::dataset::IntoUpdate for MockEditedMessage {
     /// ```
     ///
     fn into_update(self, id: &AtomicI32) -> Vec<Update> {
-        vec![Update {
+        let update = Update {
             id: UpdateId(id.fetch_add(1, Ordering::Relaxed) as u32),
             kind: UpdateKind::EditedMessage(self.0),
-        }]
+        };
+        crate::dataset::validate_update(&update);
+        vec![update]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockChannelPost(Message);
+
+impl MockChannelPost {
+    /// Creates a new MockChannelPost wrapper.
+    ///
+    /// This is useful for testing the `UpdateKind::ChannelPost` variant. Like real channel posts,
+    /// the message is sent on behalf of its chat, so `sender_chat` is set to the message chat (if
+    /// it wasn't set already), and `from` is removed. Set `author_signature` on the message to
+    /// make the channel sign its posts, the bot posts in that channel will be signed too.
+    ///
+    /// # Example
+    /// ```
+    /// let message = teloxide_tests::MockMessageText::new()
+    ///     .chat(teloxide_tests::MockChannelChat::new().build())
+    ///     .author_signature("Admin")
+    ///     .build();
+    /// let channel_post = teloxide_tests::MockChannelPost::new(message.clone());
+    /// assert_eq!(channel_post.message().author_signature(), Some("Admin"));
+    /// assert_eq!(channel_post.message().sender_chat, Some(message.chat));
+    /// assert_eq!(channel_post.message().from, None);
+    /// ```
+    ///
+    pub fn new(mut message: Message) -> Self {
+        message.sender_chat = message.sender_chat.or(Some(message.chat.clone()));
+        message.from = None;
+        Self(message)
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.0
+    }
+}
+
+impl crate::dataset::IntoUpdate for MockChannelPost {
+    /// Converts the channel post into an updates vector
+    ///
+    /// # Example
+    /// ```
+    /// use teloxide_tests::IntoUpdate;
+    /// use teloxide::types::{UpdateId, UpdateKind};
+    /// use std::sync::atomic::AtomicI32;
+    ///
+    /// let message = teloxide_tests::MockMessageText::new()
+    ///     .chat(teloxide_tests::MockChannelChat::new().build())
+    ///     .build();
+    /// let channel_post = teloxide_tests::MockChannelPost::new(message);
+    /// let update = channel_post.clone().into_update(&AtomicI32::new(42))[0].clone();
+    ///
+    /// assert_eq!(update.id, UpdateId(42));
+    /// assert_eq!(update.kind, UpdateKind::ChannelPost(channel_post.message().clone()));
+    /// ```
+    ///
+    fn into_update(self, id: &AtomicI32) -> Vec<Update> {
+        let update = Update {
+            id: UpdateId(id.fetch_add(1, Ordering::Relaxed) as u32),
+            kind: UpdateKind::ChannelPost(self.0),
+        };
+        crate::dataset::validate_update(&update);
+        vec![update]
+    }
+}
+
+/// A photo or a video of [`MockAlbum`]
+#[derive(Clone)]
+pub enum MockAlbumItem {
+    Photo(MockMessagePhoto),
+    Video(MockMessageVideo),
+}
+
+impl From<MockMessagePhoto> for MockAlbumItem {
+    fn from(photo: MockMessagePhoto) -> Self {
+        Self::Photo(photo)
+    }
+}
+
+impl From<MockMessageVideo> for MockAlbumItem {
+    fn from(video: MockMessageVideo) -> Self {
+        Self::Video(video)
+    }
+}
+
+impl MockAlbumItem {
+    fn build(self, id: MessageId, media_group_id: MediaGroupId) -> Message {
+        match self {
+            Self::Photo(mut photo) => {
+                photo.id = id;
+                photo.media_group_id = Some(media_group_id);
+                photo.build()
+            }
+            Self::Video(mut video) => {
+                video.id = id;
+                video.media_group_id = Some(media_group_id);
+                video.build()
+            }
+        }
+    }
+}
+
+#[derive(Changeable, Clone)]
+pub struct MockAlbum {
+    pub media_group_id: MediaGroupId,
+    pub items: Vec<MockAlbumItem>,
+}
+
+impl MockAlbum {
+    pub const MEDIA_GROUP_ID: &'static str = "media_group_id";
+
+    /// Creates a new album (media group) builder. Telegram sends every photo and video of an
+    /// album as a separate message, so the album is converted into one message update per item.
+    /// The messages share the `media_group_id`, and their ids are consecutive, starting with the
+    /// id of the first item. Everything else, like the chat or the caption, is taken from the items
+    /// as they are.
+    ///
+    /// # Example
+    /// ```
+    /// let album = teloxide_tests::MockAlbum::new()
+    ///     .media_group_id("album".into())
+    ///     .item(teloxide_tests::MockMessagePhoto::new().caption("Holidays"))
+    ///     .item(teloxide_tests::MockMessageVideo::new());
+    /// let messages = album.build();
+    ///
+    /// assert_eq!(messages.len(), 2);
+    /// assert_eq!(messages[1].id.0, messages[0].id.0 + 1);
+    /// assert_eq!(messages[1].media_group_id(), Some(&"album".into()));
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self {
+            media_group_id: Self::MEDIA_GROUP_ID.into(),
+            items: vec![],
+        }
+    }
+
+    /// Adds a photo or a video to the end of the album
+    pub fn item(mut self, item: impl Into<MockAlbumItem>) -> Self {
+        self.items.push(item.into());
+        self
+    }
+
+    /// Builds the messages of the album
+    pub fn build(self) -> Vec<Message> {
+        let first_id = match self.items.first() {
+            Some(MockAlbumItem::Photo(photo)) => photo.id.0,
+            Some(MockAlbumItem::Video(video)) => video.id.0,
+            None => return vec![],
+        };
+        self.items
+            .into_iter()
+            .zip(first_id..)
+            .map(|(item, id)| item.build(MessageId(id), self.media_group_id.clone()))
+            .collect()
+    }
+}
+
+impl crate::dataset::IntoUpdate for MockAlbum {
+    /// Converts the album into an updates vector, one update per photo or video
+    ///
+    /// # Example
+    /// ```
+    /// use teloxide_tests::IntoUpdate;
+    /// use teloxide::types::UpdateId;
+    /// use std::sync::atomic::AtomicI32;
+    ///
+    /// let album = teloxide_tests::MockAlbum::new()
+    ///     .item(teloxide_tests::MockMessagePhoto::new())
+    ///     .item(teloxide_tests::MockMessagePhoto::new());
+    /// let updates = album.into_update(&AtomicI32::new(42));
+    ///
+    /// assert_eq!(updates[0].id, UpdateId(42));
+    /// assert_eq!(updates[1].id, UpdateId(43));
+    /// ```
+    ///
+    fn into_update(self, id: &AtomicI32) -> Vec<Update> {
+        self.build()
+            .into_iter()
+            .map(|message| {
+                let update = Update {
+                    id: UpdateId(id.fetch_add(1, Ordering::Relaxed) as u32),
+                    kind: UpdateKind::Message(message),
+                };
+                crate::dataset::validate_update(&update);
+                update
+            })
+            .collect()
     }
 }
 
@@ -284,3 +546,445 @@ impl MockMessageNewChatMembers {
             }))
     }
 }
+
+Message! {
+    #[derive(Changeable, Clone)]
+    pub struct MockMessageLeftChatMember {
+        pub left_chat_member: User,
+    }
+}
+
+impl MockMessageLeftChatMember {
+    /// Creates a new easily changeable left chat member message builder
+    ///
+    /// # Example
+    /// ```
+    /// let message = teloxide_tests::MockMessageLeftChatMember::new()
+    ///     .left_chat_member(teloxide_tests::MockUser::new().id(123).build())
+    ///     .build();
+    /// assert_eq!(message.left_chat_member().unwrap().id.0, 123);
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self::new_message(MockUser::new().build())
+    }
+
+    /// Builds the left chat member message
+    ///
+    /// # Example
+    /// ```
+    /// let mock_message = teloxide_tests::MockMessageLeftChatMember::new();
+    /// let message = mock_message.build();
+    /// assert_eq!(message.left_chat_member().unwrap().id.0 as u64, teloxide_tests::MockUser::ID);
+    /// ```
+    ///
+    pub fn build(self) -> Message {
+        self.clone()
+            .build_message(MessageKind::LeftChatMember(MessageLeftChatMember {
+                left_chat_member: self.left_chat_member,
+            }))
+    }
+}
+
+Message! {
+    #[derive(Changeable, Clone)]
+    pub struct MockMessageNewChatTitle {
+        pub new_chat_title: String,
+    }
+}
+
+impl MockMessageNewChatTitle {
+    pub const NEW_CHAT_TITLE: &'static str = "New title";
+
+    /// Creates a new easily changeable new chat title message builder
+    ///
+    /// # Example
+    /// ```
+    /// let message = teloxide_tests::MockMessageNewChatTitle::new()
+    ///     .new_chat_title("Title")
+    ///     .build();
+    /// assert_eq!(message.new_chat_title(), Some("Title"));
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self::new_message(Self::NEW_CHAT_TITLE.to_string())
+    }
+
+    /// Builds the new chat title message
+    ///
+    /// # Example
+    /// ```
+    /// let mock_message = teloxide_tests::MockMessageNewChatTitle::new();
+    /// let message = mock_message.build();
+    /// assert_eq!(message.new_chat_title(), Some(teloxide_tests::MockMessageNewChatTitle::NEW_CHAT_TITLE));
+    /// ```
+    ///
+    pub fn build(self) -> Message {
+        self.clone()
+            .build_message(MessageKind::NewChatTitle(MessageNewChatTitle {
+                new_chat_title: self.new_chat_title,
+            }))
+    }
+}
+
+Message! {
+    #[derive(Changeable, Clone)]
+    pub struct MockMessagePassportData {
+        pub data: Vec<EncryptedPassportElement>,
+        pub credentials: EncryptedCredentials,
+    }
+}
+
+impl MockMessagePassportData {
+    pub const EMAIL: &'static str = "user@example.com";
+    pub const ELEMENT_HASH: &'static str = "ZWxlbWVudF9oYXNo";
+    pub const CREDENTIALS_DATA: &'static str = "Y3JlZGVudGlhbHNfZGF0YQ==";
+    pub const CREDENTIALS_HASH: &'static str = "Y3JlZGVudGlhbHNfaGFzaA==";
+    pub const CREDENTIALS_SECRET: &'static str = "Y3JlZGVudGlhbHNfc2VjcmV0";
+
+    /// Creates a new easily changable passport data message builder. By default it has
+    /// a single verified email element, and placeholder encrypted credentials.
+    ///
+    /// # Example
+    /// ```
+    /// let message = teloxide_tests::MockMessagePassportData::new()
+    ///     .data(vec![])
+    ///     .build();
+    /// assert!(message.passport_data().unwrap().data.is_empty());
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self::new_message(
+            vec![EncryptedPassportElement {
+                hash: Self::ELEMENT_HASH.to_owned(),
+                kind: EncryptedPassportElementKind::Email(EncryptedPassportElementEmail {
+                    email: Self::EMAIL.to_owned(),
+                }),
+            }],
+            EncryptedCredentials {
+                data: Self::CREDENTIALS_DATA.to_owned(),
+                hash: Self::CREDENTIALS_HASH.to_owned(),
+                secret: Self::CREDENTIALS_SECRET.to_owned(),
+            },
+        )
+    }
+
+    /// Builds the passport data message
+    ///
+    /// # Example
+    /// ```
+    /// let mock_message = teloxide_tests::MockMessagePassportData::new();
+    /// let message = mock_message.build();
+    /// assert_eq!(
+    ///     message.passport_data().unwrap().data[0].hash,
+    ///     teloxide_tests::MockMessagePassportData::ELEMENT_HASH  // ELEMENT_HASH is a default value
+    /// );
+    /// ```
+    ///
+    pub fn build(self) -> Message {
+        self.clone()
+            .build_message(MessageKind::PassportData(MessagePassportData {
+                passport_data: PassportData {
+                    data: self.data,
+                    credentials: self.credentials,
+                },
+            }))
+    }
+}
+
+Message! {
+    #[derive(Changeable, Clone)]
+    pub struct MockMessageProximityAlertTriggered {
+        pub traveler: User,
+        pub watcher: User,
+        pub distance: u32,
+    }
+}
+
+impl MockMessageProximityAlertTriggered {
+    pub const DISTANCE: u32 = 100;
+
+    /// Creates a new easily changable proximity alert triggered message builder
+    ///
+    /// # Example
+    /// ```
+    /// let message = teloxide_tests::MockMessageProximityAlertTriggered::new()
+    ///     .distance(50)
+    ///     .build();
+    /// assert_eq!(message.proximity_alert_triggered().unwrap().distance, 50);
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self::new_message(
+            MockUser::new().build(),
+            MockUser::new().id(MockUser::ID + 1).build(),
+            Self::DISTANCE,
+        )
+    }
+
+    /// Builds the proximity alert triggered message
+    ///
+    /// # Example
+    /// ```
+    /// let mock_message = teloxide_tests::MockMessageProximityAlertTriggered::new();
+    /// let message = mock_message.build();
+    /// assert_eq!(
+    ///     message.proximity_alert_triggered().unwrap().distance,
+    ///     teloxide_tests::MockMessageProximityAlertTriggered::DISTANCE  // DISTANCE is a default value
+    /// );
+    /// ```
+    ///
+    pub fn build(self) -> Message {
+        self.clone()
+            .build_message(MessageKind::ProximityAlertTriggered(
+                MessageProximityAlertTriggered {
+                    proximity_alert_triggered: ProximityAlertTriggered {
+                        traveler: self.traveler,
+                        watcher: self.watcher,
+                        distance: self.distance,
+                    },
+                },
+            ))
+    }
+}
+
+Message! {
+    #[derive(Changeable, Clone)]
+    pub struct MockMessageAutoDeleteTimerChanged {
+        pub message_auto_delete_time: Seconds,
+    }
+}
+
+impl MockMessageAutoDeleteTimerChanged {
+    pub const MESSAGE_AUTO_DELETE_TIME: Seconds = Seconds::from_seconds(86400);
+
+    /// Creates a new easily changable auto-delete timer changed message builder
+    ///
+    /// # Example
+    /// ```
+    /// use teloxide::types::Seconds;
+    ///
+    /// let message = teloxide_tests::MockMessageAutoDeleteTimerChanged::new()
+    ///     .message_auto_delete_time(Seconds::from_seconds(604800))
+    ///     .build();
+    /// assert_eq!(
+    ///     message
+    ///         .message_auto_delete_timer_changed()
+    ///         .unwrap()
+    ///         .message_auto_delete_time,
+    ///     Seconds::from_seconds(604800)
+    /// );
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self::new_message(Self::MESSAGE_AUTO_DELETE_TIME)
+    }
+
+    /// Builds the auto-delete timer changed message
+    ///
+    /// # Example
+    /// ```
+    /// let mock_message = teloxide_tests::MockMessageAutoDeleteTimerChanged::new();
+    /// let message = mock_message.build();
+    /// assert_eq!(
+    ///     message
+    ///         .message_auto_delete_timer_changed()
+    ///         .unwrap()
+    ///         .message_auto_delete_time,
+    ///     teloxide_tests::MockMessageAutoDeleteTimerChanged::MESSAGE_AUTO_DELETE_TIME  // MESSAGE_AUTO_DELETE_TIME is a default value
+    /// );
+    /// ```
+    ///
+    pub fn build(self) -> Message {
+        self.clone()
+            .build_message(MessageKind::MessageAutoDeleteTimerChanged(
+                MessageMessageAutoDeleteTimerChanged {
+                    message_auto_delete_timer_changed: MessageAutoDeleteTimerChanged {
+                        message_auto_delete_time: self.message_auto_delete_time,
+                    },
+                },
+            ))
+    }
+}
+
+Message! {
+    #[derive(Changeable, Clone)]
+    pub struct MockMessageWriteAccessAllowed {
+        pub web_app_name: Option<String>,
+        pub from_request: Option<bool>,
+        pub from_attachment_menu: Option<bool>,
+    }
+}
+
+impl MockMessageWriteAccessAllowed {
+    /// Creates a new easily changable write access allowed message builder. By default
+    /// none of the flags are set, like when the user allowed access by adding the bot
+    /// to the attachment or side menu.
+    ///
+    /// # Example
+    /// ```
+    /// let message = teloxide_tests::MockMessageWriteAccessAllowed::new()
+    ///     .web_app_name("My Mini App")
+    ///     .build();
+    /// assert_eq!(
+    ///     message.write_access_allowed().unwrap().web_app_name,
+    ///     Some("My Mini App".to_owned())
+    /// );
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self::new_message(None, None, None)
+    }
+
+    /// Builds the write access allowed message
+    ///
+    /// # Example
+    /// ```
+    /// let mock_message = teloxide_tests::MockMessageWriteAccessAllowed::new().from_request(true);
+    /// let message = mock_message.build();
+    /// assert_eq!(message.write_access_allowed().unwrap().from_request, Some(true));
+    /// ```
+    ///
+    pub fn build(self) -> Message {
+        self.clone()
+            .build_message(MessageKind::WriteAccessAllowed(MessageWriteAccessAllowed {
+                write_access_allowed: WriteAccessAllowed {
+                    web_app_name: self.web_app_name,
+                    from_request: self.from_request,
+                    from_attachment_menu: self.from_attachment_menu,
+                },
+            }))
+    }
+}
+
+Message! {
+    #[derive(Changeable, Clone)]
+    pub struct MockMessageUsersShared {
+        pub request_id: RequestId,
+        pub users: Vec<SharedUser>,
+    }
+}
+
+impl MockMessageUsersShared {
+    pub const REQUEST_ID: i32 = 1;
+
+    /// Creates a new easily changable users shared message builder
+    ///
+    /// # Example
+    /// ```
+    /// use teloxide::types::{MessageKind, RequestId};
+    ///
+    /// let message = teloxide_tests::MockMessageUsersShared::new()
+    ///     .request_id(RequestId(2))
+    ///     .build();
+    /// let MessageKind::UsersShared(users_shared) = message.kind else {
+    ///     unreachable!()
+    /// };
+    /// assert_eq!(users_shared.users_shared.request_id, RequestId(2));
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self::new_message(
+            RequestId(Self::REQUEST_ID),
+            vec![MockSharedUser::new().build()],
+        )
+    }
+
+    /// Builds the users shared message
+    ///
+    /// # Example
+    /// ```
+    /// use teloxide::types::MessageKind;
+    ///
+    /// let mock_message = teloxide_tests::MockMessageUsersShared::new();
+    /// let message = mock_message.build();
+    /// let MessageKind::UsersShared(users_shared) = message.kind else {
+    ///     unreachable!()
+    /// };
+    /// assert_eq!(
+    ///     users_shared.users_shared.users[0].user_id.0,
+    ///     teloxide_tests::MockSharedUser::USER_ID  // Contains a single MockSharedUser by default
+    /// );
+    /// ```
+    ///
+    pub fn build(self) -> Message {
+        self.clone()
+            .build_message(MessageKind::UsersShared(MessageUsersShared {
+                users_shared: UsersShared {
+                    request_id: self.request_id,
+                    users: self.users,
+                },
+            }))
+    }
+}
+
+Message! {
+    #[derive(Changeable, Clone)]
+    pub struct MockMessageChatShared {
+        pub request_id: RequestId,
+        pub chat_id: ChatId,
+        pub title: Option<String>,
+        pub username: Option<String>,
+        pub photo: Option<Vec<PhotoSize>>,
+    }
+}
+
+impl MockMessageChatShared {
+    pub const REQUEST_ID: i32 = 1;
+    pub const CHAT_ID: i64 = -1001234567890;
+
+    /// Creates a new easily changable chat shared message builder
+    ///
+    /// # Example
+    /// ```
+    /// use teloxide::types::{ChatId, MessageKind};
+    ///
+    /// let message = teloxide_tests::MockMessageChatShared::new()
+    ///     .chat_id(-100123)
+    ///     .build();
+    /// let MessageKind::ChatShared(chat_shared) = message.kind else {
+    ///     unreachable!()
+    /// };
+    /// assert_eq!(chat_shared.chat_shared.chat_id, ChatId(-100123));
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self::new_message(
+            RequestId(Self::REQUEST_ID),
+            ChatId(Self::CHAT_ID),
+            None,
+            None,
+            None,
+        )
+    }
+
+    /// Builds the chat shared message
+    ///
+    /// # Example
+    /// ```
+    /// use teloxide::types::{ChatId, MessageKind};
+    ///
+    /// let mock_message = teloxide_tests::MockMessageChatShared::new();
+    /// let message = mock_message.build();
+    /// let MessageKind::ChatShared(chat_shared) = message.kind else {
+    ///     unreachable!()
+    /// };
+    /// assert_eq!(
+    ///     chat_shared.chat_shared.chat_id,
+    ///     ChatId(teloxide_tests::MockMessageChatShared::CHAT_ID)  // CHAT_ID is a default value
+    /// );
+    /// ```
+    ///
+    pub fn build(self) -> Message {
+        self.clone()
+            .build_message(MessageKind::ChatShared(MessageChatShared {
+                chat_shared: ChatShared {
+                    request_id: self.request_id,
+                    chat_id: self.chat_id,
+                    title: self.title,
+                    username: self.username,
+                    photo: self.photo,
+                },
+            }))
+    }
+}
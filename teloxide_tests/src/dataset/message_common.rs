@@ -36,6 +36,17 @@ macro_rules! MessageCommon {  // Rust was supposed to be used without inheritanc
 
                 $($fpub $field : $type,)*  // Just all of the other fields, nothig too scary here
             }
+            builder {
+                fn reply_to(mut self, message: Message) -> Self {
+                    self.reply_to_message = Some(Box::new(message));
+                    self
+                }
+
+                fn reply_markup(mut self, markup: InlineKeyboardMarkup) -> Self {
+                    self.reply_markup = Some(markup);
+                    self
+                }
+            }
         }
         impl $name {  // Implements common functions
             pub const IS_AUTOMATIC_FORWARD: bool = false;
@@ -838,6 +849,80 @@ impl MockMessageSticker {
         )
     }
 
+    /// Makes the sticker animated, like `.tgs` stickers
+    ///
+    /// # Example
+    /// ```
+    /// let message = teloxide_tests::MockMessageSticker::new().animated().build();
+    /// assert!(message.sticker().unwrap().is_animated());
+    /// ```
+    ///
+    pub fn animated(mut self) -> Self {
+        self.flags = StickerFormatFlags {
+            is_animated: true,
+            is_video: false,
+        };
+        self
+    }
+
+    /// Makes the sticker a video, like `.webm` stickers
+    ///
+    /// # Example
+    /// ```
+    /// let message = teloxide_tests::MockMessageSticker::new().video().build();
+    /// assert!(message.sticker().unwrap().is_video());
+    /// ```
+    ///
+    pub fn video(mut self) -> Self {
+        self.flags = StickerFormatFlags {
+            is_animated: false,
+            is_video: true,
+        };
+        self
+    }
+
+    /// Makes the sticker a custom emoji with this id
+    ///
+    /// # Example
+    /// ```
+    /// let message = teloxide_tests::MockMessageSticker::new()
+    ///     .custom_emoji_id("5368324170671202286")
+    ///     .build();
+    /// assert_eq!(
+    ///     message.sticker().unwrap().custom_emoji_id().unwrap().0,
+    ///     "5368324170671202286"
+    /// );
+    /// ```
+    ///
+    pub fn custom_emoji_id(mut self, custom_emoji_id: impl Into<String>) -> Self {
+        self.kind = StickerKind::CustomEmoji {
+            custom_emoji_id: CustomEmojiId(custom_emoji_id.into()),
+        };
+        self
+    }
+
+    /// Makes the sticker a premium one, with this premium animation
+    ///
+    /// # Example
+    /// ```
+    /// let animation = teloxide::types::FileMeta {
+    ///     id: "premium_animation_id".into(),
+    ///     unique_id: "premium_animation_unique_id".into(),
+    ///     size: 1234,
+    /// };
+    /// let message = teloxide_tests::MockMessageSticker::new()
+    ///     .premium_animation(animation.clone())
+    ///     .build();
+    /// assert_eq!(message.sticker().unwrap().premium_animation(), Some(&animation));
+    /// ```
+    ///
+    pub fn premium_animation(mut self, premium_animation: FileMeta) -> Self {
+        self.kind = StickerKind::Regular {
+            premium_animation: Some(premium_animation),
+        };
+        self
+    }
+
     /// Builds the message sticker
     ///
     /// # Example
@@ -1014,6 +1099,12 @@ impl MockMessageVoice {
 
     /// Creates a new easily changable message voice builder
     ///
+    /// The waveform and the speech transcription of voice messages exist only in the MTProto
+    /// API, the Bot API and teloxide's [`Voice`] don't have them, so neither the builder nor
+    /// `sendVoice` of the fake server can set them. Bots can't branch on them in Telegram either.
+    ///
+    /// [`Voice`]: teloxide::types::Voice
+    ///
     /// # Example
     /// ```
     /// use teloxide::types::Seconds;
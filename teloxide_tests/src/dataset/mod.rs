@@ -1,11 +1,13 @@
 //! A set of mocked structs for testing purposes. Read more in teloxide_tests crate.
 use std::sync::atomic::{AtomicI32, Ordering};
 
+use chrono::{DateTime, Utc};
 use mime::Mime;
 use proc_macros::Changeable;
 use teloxide::types::{
-    ChatPhoto, FileId, FileMeta, FileUniqueId, LinkPreviewOptions, LivePeriod, Location, Me,
-    PhotoSize, Seconds, Update, UpdateId, User, UserId, Video,
+    ChatPhoto, FileId, FileMeta, FileUniqueId, Gift, GiftId, LinkPreviewOptions, LivePeriod,
+    Location, Me, MessageEntity, PhotoSize, Seconds, SharedUser, Sticker, Update, UpdateId,
+    UpdateKind, User, UserId, Video,
 };
 pub mod chat;
 pub mod chat_full_info;
@@ -21,11 +23,15 @@ pub use message_common::*;
 pub use queries::*;
 use teloxide_tests_macros as proc_macros;
 pub use update::*;
+
+use crate::server::{OwnedGift, OwnedGiftRegular};
 #[cfg(test)]
 mod tests;
 
 pub trait IntoUpdate {
-    /// Converts the mocked struct into an update vector, incrementing the id by 1
+    /// Converts the mocked struct into an update vector, incrementing the id by 1. Panics if
+    /// Telegram can't send such an update, like a callback query without a message and an
+    /// `inline_message_id`
     fn into_update(self, id: &AtomicI32) -> Vec<Update>;
 }
 
@@ -47,11 +53,56 @@ where
 // Just to be able to use raw updates anywhere
 impl IntoUpdate for Update {
     fn into_update(mut self, id: &AtomicI32) -> Vec<Update> {
+        validate_update(&self);
         self.id = UpdateId(id.fetch_add(1, Ordering::Relaxed) as u32);
         vec![self]
     }
 }
 
+/// Panics with what is wrong and how to fix it, if Telegram can't send such an update. Otherwise
+/// the mistake shows up much later, deep in the dispatcher or the fake server, or the handlers
+/// just never get the update.
+pub(crate) fn validate_update(update: &Update) {
+    match &update.kind {
+        UpdateKind::Message(message) | UpdateKind::EditedMessage(message)
+            if message.chat.is_channel() =>
+        {
+            panic!(
+                "Message {} is in a channel, but messages of channels are channel posts! Wrap it \
+                 into `MockChannelPost::new`.",
+                message.id
+            );
+        }
+        UpdateKind::ChannelPost(message) | UpdateKind::EditedChannelPost(message)
+            if !message.chat.is_channel() =>
+        {
+            panic!(
+                "Channel post {} isn't in a channel! Set the chat of the message to \
+                 `MockChannelChat::new().build()`, or send it as a regular message.",
+                message.id
+            );
+        }
+        UpdateKind::CallbackQuery(query) => match (&query.message, &query.inline_message_id) {
+            (None, None) => panic!(
+                "Callback query {:?} has neither a message nor an inline_message_id! Set \
+                     `.message(...)` for buttons of messages, or `.inline_message_id(...)` for \
+                     buttons of inline messages.",
+                query.id.0
+            ),
+            (Some(_), Some(_)) => panic!(
+                "Callback query {:?} has both a message and an inline_message_id! Buttons of \
+                     inline messages have no message, so set `.message = None` for them.",
+                query.id.0
+            ),
+            _ => {}
+        },
+        UpdateKind::Error(value) => {
+            panic!("teloxide can't parse the update, check the JSON of it: {value}");
+        }
+        _ => {}
+    }
+}
+
 //
 //  Structs below are just misc mocked structs
 //
@@ -121,6 +172,59 @@ impl MockUser {
     }
 }
 
+#[derive(Changeable, Clone)]
+pub struct MockSharedUser {
+    pub user_id: UserId,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub username: Option<String>,
+    pub photo: Option<Vec<PhotoSize>>,
+}
+
+impl MockSharedUser {
+    pub const USER_ID: u64 = 87654321;
+
+    /// Creates a new easily changable shared user builder. Shared users are the ones that
+    /// the user picked with a `KeyboardButtonRequestUsers` button.
+    ///
+    /// # Examples
+    /// ```
+    /// let shared_user = teloxide_tests::MockSharedUser::new()
+    ///     .first_name("Shared")
+    ///     .build();
+    /// assert_eq!(shared_user.first_name, Some("Shared".to_owned()));
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self {
+            user_id: UserId(Self::USER_ID),
+            first_name: None,
+            last_name: None,
+            username: None,
+            photo: None,
+        }
+    }
+
+    /// Builds the shared user
+    ///
+    /// # Examples
+    /// ```
+    /// let mock_shared_user = teloxide_tests::MockSharedUser::new();
+    /// let shared_user = mock_shared_user.build();
+    /// assert_eq!(shared_user.user_id.0, teloxide_tests::MockSharedUser::USER_ID);  // USER_ID is a default value
+    /// ```
+    ///
+    pub fn build(self) -> SharedUser {
+        SharedUser {
+            user_id: self.user_id,
+            first_name: self.first_name,
+            last_name: self.last_name,
+            username: self.username,
+            photo: self.photo,
+        }
+    }
+}
+
 #[derive(Changeable, Clone)]
 pub struct MockMe {
     pub id: UserId,
@@ -175,6 +279,30 @@ impl MockMe {
         }
     }
 
+    /// Creates a me of a bot that supports inline queries
+    ///
+    /// # Examples
+    /// ```
+    /// let me = teloxide_tests::MockMe::inline_bot().build();
+    /// assert!(me.supports_inline_queries);
+    /// ```
+    ///
+    pub fn inline_bot() -> Self {
+        Self::new().supports_inline_queries(true)
+    }
+
+    /// Creates a me of a bot that can be connected to a Telegram Business account
+    ///
+    /// # Examples
+    /// ```
+    /// let me = teloxide_tests::MockMe::business_bot().build();
+    /// assert!(me.can_connect_to_business);
+    /// ```
+    ///
+    pub fn business_bot() -> Self {
+        Self::new().can_connect_to_business(true)
+    }
+
     /// Builds the me
     ///
     /// # Examples
@@ -500,3 +628,138 @@ impl MockLinkPreviewOptions {
         }
     }
 }
+
+#[derive(Changeable, Clone)]
+pub struct MockGift {
+    pub id: GiftId,
+    pub sticker: Sticker,
+    pub star_count: u32,
+    pub total_count: Option<u32>,
+    pub remaining_count: Option<u32>,
+}
+
+impl MockGift {
+    pub const ID: &'static str = "gift_id";
+    pub const STAR_COUNT: u32 = 15;
+
+    /// Creates a new easily changable gift builder
+    ///
+    /// # Examples
+    /// ```
+    /// let gift = teloxide_tests::MockGift::new()
+    ///     .star_count(50)
+    ///     .build();
+    /// assert_eq!(gift.star_count, 50);
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self {
+            id: Self::ID.into(),
+            sticker: MockMessageSticker::new().build().sticker().unwrap().clone(),
+            star_count: Self::STAR_COUNT,
+            total_count: None,
+            remaining_count: None,
+        }
+    }
+
+    /// Builds the gift
+    ///
+    /// # Examples
+    /// ```
+    /// let mock_gift = teloxide_tests::MockGift::new();
+    /// let gift = mock_gift.build();
+    /// assert_eq!(gift.star_count, teloxide_tests::MockGift::STAR_COUNT); // STAR_COUNT is a default value
+    /// ```
+    ///
+    pub fn build(self) -> Gift {
+        Gift {
+            id: self.id,
+            sticker: self.sticker,
+            star_count: self.star_count,
+            total_count: self.total_count,
+            remaining_count: self.remaining_count,
+        }
+    }
+}
+
+#[derive(Changeable, Clone)]
+pub struct MockOwnedGift {
+    pub gift: Gift,
+    pub owned_gift_id: Option<String>,
+    pub sender_user: Option<User>,
+    pub send_date: DateTime<Utc>,
+    pub text: Option<String>,
+    pub entities: Option<Vec<MessageEntity>>,
+    pub is_private: bool,
+    pub is_saved: bool,
+    pub can_be_upgraded: bool,
+    pub was_refunded: bool,
+    pub convert_star_count: Option<u32>,
+    pub prepaid_upgrade_star_count: Option<u32>,
+}
+
+impl MockOwnedGift {
+    pub const OWNED_GIFT_ID: &'static str = "owned_gift_id";
+
+    /// Creates a new easily changable owned gift builder. Owned gifts are the ones that are
+    /// returned by `getBusinessAccountGifts`.
+    ///
+    /// # Examples
+    /// ```
+    /// let owned_gift = teloxide_tests::MockOwnedGift::new()
+    ///     .is_saved(true)
+    ///     .build();
+    /// let teloxide_tests::server::OwnedGift::Regular(regular) = owned_gift else {
+    ///     unreachable!()
+    /// };
+    /// assert!(regular.is_saved);
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self {
+            gift: MockGift::new().build(),
+            owned_gift_id: Some(Self::OWNED_GIFT_ID.to_string()),
+            sender_user: Some(MockUser::new().build()),
+            send_date: Utc::now(),
+            text: None,
+            entities: None,
+            is_private: false,
+            is_saved: false,
+            can_be_upgraded: false,
+            was_refunded: false,
+            convert_star_count: None,
+            prepaid_upgrade_star_count: None,
+        }
+    }
+
+    /// Builds the owned gift
+    ///
+    /// # Examples
+    /// ```
+    /// let mock_owned_gift = teloxide_tests::MockOwnedGift::new();
+    /// let teloxide_tests::server::OwnedGift::Regular(regular) = mock_owned_gift.build() else {
+    ///     unreachable!()
+    /// };
+    /// assert_eq!(
+    ///     regular.owned_gift_id.as_deref(),
+    ///     Some(teloxide_tests::MockOwnedGift::OWNED_GIFT_ID) // OWNED_GIFT_ID is a default value
+    /// );
+    /// ```
+    ///
+    pub fn build(self) -> OwnedGift {
+        OwnedGift::Regular(OwnedGiftRegular {
+            gift: self.gift,
+            owned_gift_id: self.owned_gift_id,
+            sender_user: self.sender_user,
+            send_date: self.send_date,
+            text: self.text,
+            entities: self.entities,
+            is_private: self.is_private,
+            is_saved: self.is_saved,
+            can_be_upgraded: self.can_be_upgraded,
+            was_refunded: self.was_refunded,
+            convert_star_count: self.convert_star_count,
+            prepaid_upgrade_star_count: self.prepaid_upgrade_star_count,
+        })
+    }
+}
@@ -121,9 +121,95 @@ impl crate::dataset::IntoUpdate for MockCallbackQuery {
     /// ```
     ///
     fn into_update(self, id: &AtomicI32) -> Vec<Update> {
-        vec![Update {
+        let update = Update {
             id: UpdateId(id.fetch_add(1, Ordering::Relaxed) as u32),
             kind: UpdateKind::CallbackQuery(self.build()),
+        };
+        crate::dataset::validate_update(&update);
+        vec![update]
+    }
+}
+
+#[derive(Changeable, Clone)]
+pub struct MockInlineQuery {
+    pub id: InlineQueryId,
+    pub from: User,
+    pub location: Option<Location>,
+    pub query: String,
+    pub offset: String,
+    pub chat_type: Option<ChatType>,
+}
+
+impl MockInlineQuery {
+    pub const ID: &'static str = "id";
+    pub const QUERY: &'static str = "";
+    pub const OFFSET: &'static str = "";
+
+    /// Creates a new easily changable inline query builder
+    ///
+    /// # Examples
+    /// ```
+    /// let inline_query = teloxide_tests::MockInlineQuery::new()
+    ///     .query("cats")
+    ///     .build();
+    /// assert_eq!(inline_query.query, "cats");
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self {
+            id: Self::ID.into(),
+            from: MockUser::new().build(),
+            location: None,
+            query: Self::QUERY.to_string(),
+            offset: Self::OFFSET.to_string(),
+            chat_type: None,
+        }
+    }
+
+    /// Builds the inline query
+    ///
+    /// # Example
+    /// ```
+    /// let mock_inline_query = teloxide_tests::MockInlineQuery::new();
+    /// let inline_query = mock_inline_query.build();
+    /// assert_eq!(
+    ///     inline_query.id,
+    ///     teloxide_tests::MockInlineQuery::ID.into()
+    /// );  // ID is a default value
+    /// ```
+    ///
+    pub fn build(self) -> InlineQuery {
+        InlineQuery {
+            id: self.id,
+            from: self.from,
+            location: self.location,
+            query: self.query,
+            offset: self.offset,
+            chat_type: self.chat_type,
+        }
+    }
+}
+
+impl crate::dataset::IntoUpdate for MockInlineQuery {
+    /// Converts the MockInlineQuery into an updates vector
+    ///
+    /// # Example
+    /// ```
+    /// use teloxide_tests::IntoUpdate;
+    /// use teloxide::types::{UpdateId, UpdateKind::InlineQuery};
+    /// use std::sync::atomic::AtomicI32;
+    ///
+    /// let mock_inline_query = teloxide_tests::MockInlineQuery::new();
+    /// let update = mock_inline_query.clone().into_update(&AtomicI32::new(42))[0].clone();
+    ///
+    /// assert_eq!(update.id, UpdateId(42));
+    /// assert_eq!(update.kind, InlineQuery(mock_inline_query.build()));
+    /// ```
+    ///
+    fn into_update(self, id: &AtomicI32) -> Vec<Update> {
+        vec![Update {
+            id: UpdateId(id.fetch_add(1, Ordering::Relaxed) as u32),
+            kind: UpdateKind::InlineQuery(self.build()),
         }]
     }
 }
@@ -1,6 +1,9 @@
 use teloxide::{
     dispatching::dialogue::GetChatId,
-    types::{ChatId, MessageEntity, MessageId, UpdateId, UpdateKind, UserId},
+    types::{
+        ChatId, InlineKeyboardButton, InlineKeyboardMarkup, Message, MessageEntity, MessageId,
+        UpdateId, UpdateKind, UserId,
+    },
 };
 use update::MockUpdatePoll;
 
@@ -210,6 +213,70 @@ fn test_message_common_audio() {
     assert_eq!(message_object.media_group_id(), Some(&"123".into()));
 }
 
+#[test]
+fn test_album() {
+    let album = MockAlbum::new()
+        .media_group_id("123".into())
+        .item(MockMessagePhoto::new().id(5).caption("caption"))
+        .item(MockMessageVideo::new())
+        .item(MockMessagePhoto::new());
+
+    let updates = album.into_update(&AtomicI32::new(1));
+    let messages: Vec<_> = updates
+        .into_iter()
+        .map(|update| match update.kind {
+            UpdateKind::Message(message) => message,
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(messages.len(), 3);
+    assert_eq!(
+        messages.iter().map(|m| m.id.0).collect::<Vec<_>>(),
+        vec![5, 6, 7]
+    );
+    assert!(messages
+        .iter()
+        .all(|m| m.media_group_id() == Some(&"123".into())));
+    assert_eq!(messages[0].caption(), Some("caption"));
+    assert!(messages[1].video().is_some());
+}
+
+fn customize<T: MockMessageBuilder>(message: T) -> Message {
+    let user = MockUser::new().id(42).build();
+    message
+        .id(10)
+        .chat(MockGroupChat::new().build())
+        .from(user)
+        .build()
+}
+
+#[test]
+fn test_message_builder() {
+    let text = customize(MockMessageText::new());
+    let sticker = customize(MockMessageSticker::new());
+    let members = customize(MockMessageNewChatMembers::new());
+
+    for message in [&text, &sticker, &members] {
+        assert_eq!(message.id, MessageId(10));
+        assert_eq!(message.chat.id.0, MockGroupChat::ID);
+        assert_eq!(message.from.as_ref().unwrap().id, UserId(42));
+    }
+    assert!(sticker.sticker().is_some());
+
+    let reply =
+        MockMessageBuilder::build(MockMessagePhoto::new().reply_to(text.clone()).reply_markup(
+            InlineKeyboardMarkup::new([[InlineKeyboardButton::callback("button", "data")]]),
+        ));
+    assert_eq!(reply.reply_to_message().unwrap().id, text.id);
+    assert!(reply.reply_markup().is_some());
+}
+
+#[test]
+#[should_panic]
+fn test_service_message_builder_reply() {
+    MockMessageNewChatMembers::new().reply_to(MockMessageText::new().build());
+}
+
 #[test]
 fn test_message_common_contact() {
     let message = MockMessageContact::new()
@@ -332,6 +399,22 @@ fn test_message_common_sticker() {
     );
 }
 
+#[test]
+fn test_message_sticker_kinds() {
+    let message = MockMessageSticker::new()
+        .video()
+        .custom_emoji_id("123")
+        .emoji("👍");
+
+    let message_object = message.build();
+    let sticker = message_object.sticker().unwrap();
+    assert!(sticker.is_video());
+    assert!(!sticker.is_animated());
+    assert!(sticker.is_custom_emoji());
+    assert_eq!(sticker.custom_emoji_id().unwrap().0, "123");
+    assert_eq!(sticker.emoji.as_deref(), Some("👍"));
+}
+
 #[test]
 fn test_message_common_video() {
     let message = MockMessageVideo::new();
@@ -401,6 +484,41 @@ fn test_callback_query() {
     assert_eq!(query_object.from.first_name, MockUser::FIRST_NAME);
 }
 
+#[test]
+fn test_inline_callback_query() {
+    let mut query = MockCallbackQuery::new().inline_message_id("inline_id");
+    query.message = None;
+    let updates = query.into_update(&AtomicI32::new(1));
+    let UpdateKind::CallbackQuery(query_object) = &updates[0].kind else {
+        unreachable!()
+    };
+    assert_eq!(query_object.inline_message_id.as_deref(), Some("inline_id"));
+}
+
+#[test]
+#[should_panic(expected = "has both a message and an inline_message_id")]
+fn test_callback_query_with_message_and_inline_message_id() {
+    MockCallbackQuery::new()
+        .inline_message_id("inline_id")
+        .into_update(&AtomicI32::new(1));
+}
+
+#[test]
+#[should_panic(expected = "has neither a message nor an inline_message_id")]
+fn test_callback_query_without_message() {
+    let mut query = MockCallbackQuery::new();
+    query.message = None;
+    query.into_update(&AtomicI32::new(1));
+}
+
+#[test]
+#[should_panic(expected = "Wrap it into `MockChannelPost::new`")]
+fn test_message_in_channel() {
+    MockMessageText::new()
+        .chat(MockChannelChat::new().build())
+        .into_update(&AtomicI32::new(1));
+}
+
 //
 //
 //
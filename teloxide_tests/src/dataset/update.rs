@@ -2,11 +2,16 @@ use std::sync::atomic::Ordering;
 
 use chrono::{DateTime, Utc};
 use teloxide::types::{
-    MessageEntity, Poll, PollId, PollOption, PollType, Seconds, Update, UpdateId, UpdateKind,
+    Administrator, Chat, ChatInviteLink, ChatKind, ChatMember, ChatMemberKind, ChatMemberUpdated,
+    Member, Message, MessageEntity, MessageId, Poll, PollId, PollOption, PollType, Seconds, Update,
+    UpdateId, UpdateKind, User,
 };
 use teloxide_tests_macros::Changeable;
 
-use super::{IntoUpdate, MockMessagePoll};
+use super::{
+    IntoUpdate, MockGroupChat, MockMe, MockMessageLeftChatMember, MockMessageNewChatMembers,
+    MockMessageNewChatTitle, MockMessagePoll, MockUser,
+};
 
 #[derive(Changeable, Clone)]
 pub struct MockUpdatePoll {
@@ -80,3 +85,330 @@ impl IntoUpdate for MockUpdatePoll {
         }]
     }
 }
+
+#[derive(Changeable, Clone)]
+pub struct MockBotAddedToGroup {
+    pub chat: Chat,
+    pub from: User,
+    pub bot: User,
+    pub date: DateTime<Utc>,
+    pub message_id: MessageId,
+    pub promotion: Option<Administrator>,
+}
+
+impl MockBotAddedToGroup {
+    /// Creates a new builder of the updates, that Telegram sends when the bot is added to a
+    /// group: the `my_chat_member` update with the bot becoming a member, the `new_chat_members`
+    /// service message and, if the bot is promoted right away, another `my_chat_member` update
+    /// with the bot becoming an administrator.
+    ///
+    /// The bot is `MockMe` by default, so if the bot is built with a different me, it has to be
+    /// set with `.bot(...)`.
+    ///
+    /// # Example
+    /// ```
+    /// use teloxide_tests::IntoUpdate;
+    /// use teloxide::types::UpdateKind;
+    /// use std::sync::atomic::AtomicI32;
+    ///
+    /// let scenario = teloxide_tests::MockBotAddedToGroup::new().promoted();
+    /// let updates = scenario.into_update(&AtomicI32::new(1));
+    ///
+    /// assert_eq!(updates.len(), 3);
+    /// assert!(matches!(updates[0].kind, UpdateKind::MyChatMember(_)));
+    /// assert!(matches!(updates[1].kind, UpdateKind::Message(_)));
+    /// assert!(matches!(updates[2].kind, UpdateKind::MyChatMember(_)));
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self {
+            chat: MockGroupChat::new().build(),
+            from: MockUser::new().build(),
+            bot: MockMe::new().build().user,
+            date: Utc::now(),
+            message_id: MessageId(MockMessageNewChatMembers::ID),
+            promotion: None,
+        }
+    }
+
+    /// Promotes the bot to an administrator, that can manage the chat, delete messages, restrict
+    /// members, invite users and pin messages. Other rights can be set with `.promotion(...)`.
+    pub fn promoted(self) -> Self {
+        self.promotion(default_administrator())
+    }
+
+    fn my_chat_member(&self, old: ChatMemberKind, new: ChatMemberKind) -> UpdateKind {
+        UpdateKind::MyChatMember(chat_member_updated(
+            &self.chat, &self.from, self.date, &self.bot, old, new,
+        ))
+    }
+}
+
+impl IntoUpdate for MockBotAddedToGroup {
+    fn into_update(self, id: &std::sync::atomic::AtomicI32) -> Vec<Update> {
+        let member = ChatMemberKind::Member(Member { until_date: None });
+        let mut kinds = vec![self.my_chat_member(ChatMemberKind::Left, member.clone())];
+
+        let mut message = MockMessageNewChatMembers::new()
+            .id(self.message_id.0)
+            .chat(self.chat.clone())
+            .date(self.date)
+            .new_chat_members(vec![self.bot.clone()]);
+        message.from = Some(self.from.clone());
+        kinds.push(UpdateKind::Message(message.build()));
+
+        if let Some(promotion) = self.promotion.clone() {
+            kinds.push(self.my_chat_member(member, ChatMemberKind::Administrator(promotion)));
+        }
+
+        kinds
+            .into_iter()
+            .map(|kind| Update {
+                id: UpdateId(id.fetch_add(1, Ordering::Relaxed) as u32),
+                kind,
+            })
+            .collect()
+    }
+}
+
+/// An event of `MockGroupScenario`
+#[derive(Clone, Debug)]
+pub enum MockGroupEvent {
+    UserJoined {
+        user: User,
+        invite_link: Option<ChatInviteLink>,
+    },
+    UserLeft {
+        user: User,
+    },
+    UserPromoted {
+        user: User,
+        rights: Administrator,
+    },
+    TitleChanged {
+        title: String,
+    },
+}
+
+#[derive(Changeable, Clone)]
+pub struct MockGroupScenario {
+    pub chat: Chat,
+    pub admin: User,
+    pub date: DateTime<Utc>,
+    pub first_message_id: MessageId,
+    pub events: Vec<MockGroupEvent>,
+}
+
+impl MockGroupScenario {
+    /// Creates a new builder of the updates for a sequence of group events. Every event becomes
+    /// the updates Telegram sends for it:
+    ///
+    /// - A user joining: the `chat_member` update and the `new_chat_members` service message
+    /// - A user leaving: the `chat_member` update and the `left_chat_member` service message
+    /// - A user promoted by the `admin`: the `chat_member` update
+    /// - The title changed by the `admin`: the `new_chat_title` service message, the chat of the
+    ///   following events has the new title
+    ///
+    /// The service messages have consecutive ids, starting with `first_message_id`.
+    ///
+    /// # Example
+    /// ```
+    /// use teloxide_tests::{IntoUpdate, MockGroupScenario, MockUser};
+    /// use teloxide::types::UpdateKind;
+    /// use std::sync::atomic::AtomicI32;
+    ///
+    /// let scenario = MockGroupScenario::new()
+    ///     .user_joined(MockUser::new().id(1))
+    ///     .user_promoted(MockUser::new().id(1))
+    ///     .title_changed("Renamed")
+    ///     .user_left(MockUser::new().id(1));
+    /// let updates = scenario.into_update(&AtomicI32::new(1));
+    ///
+    /// assert_eq!(updates.len(), 6);
+    /// let UpdateKind::Message(message) = &updates[3].kind else { panic!() };
+    /// assert_eq!(message.new_chat_title(), Some("Renamed"));
+    /// assert_eq!(message.id.0, 2);
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self {
+            chat: MockGroupChat::new().build(),
+            admin: MockUser::new()
+                .id(MockUser::ID + 1)
+                .first_name("Admin")
+                .build(),
+            date: Utc::now(),
+            first_message_id: MessageId(MockMessageNewChatMembers::ID),
+            events: vec![],
+        }
+    }
+
+    /// Adds the event to the end of the scenario
+    pub fn event(mut self, event: MockGroupEvent) -> Self {
+        self.events.push(event);
+        self
+    }
+
+    /// The user joins the group by themselves
+    pub fn user_joined(self, user: MockUser) -> Self {
+        self.event(MockGroupEvent::UserJoined {
+            user: user.build(),
+            invite_link: None,
+        })
+    }
+
+    /// The user joins the group with the invite link
+    pub fn user_joined_via_link(self, user: MockUser, invite_link: ChatInviteLink) -> Self {
+        self.event(MockGroupEvent::UserJoined {
+            user: user.build(),
+            invite_link: Some(invite_link),
+        })
+    }
+
+    /// The user leaves the group
+    pub fn user_left(self, user: MockUser) -> Self {
+        self.event(MockGroupEvent::UserLeft { user: user.build() })
+    }
+
+    /// The admin promotes the user with the same rights as `MockBotAddedToGroup::promoted`.
+    /// Other rights can be set with `.event(MockGroupEvent::UserPromoted { .. })`.
+    pub fn user_promoted(self, user: MockUser) -> Self {
+        self.event(MockGroupEvent::UserPromoted {
+            user: user.build(),
+            rights: default_administrator(),
+        })
+    }
+
+    /// The admin changes the title of the group
+    pub fn title_changed(self, title: impl Into<String>) -> Self {
+        self.event(MockGroupEvent::TitleChanged {
+            title: title.into(),
+        })
+    }
+}
+
+impl IntoUpdate for MockGroupScenario {
+    fn into_update(self, id: &std::sync::atomic::AtomicI32) -> Vec<Update> {
+        let mut chat = self.chat.clone();
+        let mut message_id = self.first_message_id.0;
+        let mut service_message = |mut message: Message, chat: &Chat, from: &User| {
+            message.id = MessageId(message_id);
+            message.chat = chat.clone();
+            message.date = self.date;
+            message.from = Some(from.clone());
+            message_id += 1;
+            UpdateKind::Message(message)
+        };
+        let member = ChatMemberKind::Member(Member { until_date: None });
+
+        let mut kinds = vec![];
+        for event in self.events {
+            match event {
+                MockGroupEvent::UserJoined { user, invite_link } => {
+                    let mut updated = chat_member_updated(
+                        &chat,
+                        &user,
+                        self.date,
+                        &user,
+                        ChatMemberKind::Left,
+                        member.clone(),
+                    );
+                    updated.invite_link = invite_link;
+                    kinds.push(UpdateKind::ChatMember(updated));
+                    let message = MockMessageNewChatMembers::new()
+                        .new_chat_members(vec![user.clone()])
+                        .build();
+                    kinds.push(service_message(message, &chat, &user));
+                }
+                MockGroupEvent::UserLeft { user } => {
+                    kinds.push(UpdateKind::ChatMember(chat_member_updated(
+                        &chat,
+                        &user,
+                        self.date,
+                        &user,
+                        member.clone(),
+                        ChatMemberKind::Left,
+                    )));
+                    let message = MockMessageLeftChatMember::new()
+                        .left_chat_member(user.clone())
+                        .build();
+                    kinds.push(service_message(message, &chat, &user));
+                }
+                MockGroupEvent::UserPromoted { user, rights } => {
+                    kinds.push(UpdateKind::ChatMember(chat_member_updated(
+                        &chat,
+                        &self.admin,
+                        self.date,
+                        &user,
+                        member.clone(),
+                        ChatMemberKind::Administrator(rights),
+                    )));
+                }
+                MockGroupEvent::TitleChanged { title } => {
+                    if let ChatKind::Public(public) = &mut chat.kind {
+                        public.title = Some(title.clone());
+                    }
+                    let message = MockMessageNewChatTitle::new().new_chat_title(title).build();
+                    kinds.push(service_message(message, &chat, &self.admin));
+                }
+            }
+        }
+
+        kinds
+            .into_iter()
+            .map(|kind| Update {
+                id: UpdateId(id.fetch_add(1, Ordering::Relaxed) as u32),
+                kind,
+            })
+            .collect()
+    }
+}
+
+fn chat_member_updated(
+    chat: &Chat,
+    from: &User,
+    date: DateTime<Utc>,
+    user: &User,
+    old: ChatMemberKind,
+    new: ChatMemberKind,
+) -> ChatMemberUpdated {
+    ChatMemberUpdated {
+        chat: chat.clone(),
+        from: from.clone(),
+        date,
+        old_chat_member: ChatMember {
+            user: user.clone(),
+            kind: old,
+        },
+        new_chat_member: ChatMember {
+            user: user.clone(),
+            kind: new,
+        },
+        invite_link: None,
+        via_join_request: false,
+        via_chat_folder_invite_link: false,
+    }
+}
+
+/// Rights to manage the chat, delete messages, restrict members, invite users and pin messages
+fn default_administrator() -> Administrator {
+    Administrator {
+        custom_title: None,
+        is_anonymous: false,
+        can_be_edited: false,
+        can_manage_chat: true,
+        can_change_info: false,
+        can_post_messages: false,
+        can_edit_messages: false,
+        can_delete_messages: true,
+        can_post_stories: false,
+        can_edit_stories: false,
+        can_delete_stories: false,
+        can_manage_video_chats: false,
+        can_invite_users: true,
+        can_restrict_members: true,
+        can_pin_messages: true,
+        can_manage_topics: false,
+        can_promote_members: false,
+    }
+}
@@ -0,0 +1,287 @@
+//! Expectations of the requests the bot sends, that are declared before the dispatch and
+//! verified after it
+use std::{
+    fmt::Write as _,
+    sync::{Arc, Mutex},
+};
+
+#[doc(hidden)]
+pub use serde_json::json;
+use serde_json::Value;
+
+use crate::{server::BotRequest, state::State};
+
+/// Creates a [`JsonContains`] matcher for [`Expectation::with`], using the `serde_json::json!`
+/// syntax. The request body matches if it has all of the given fields, objects in it are matched
+/// the same way
+///
+/// # Example
+/// ```
+/// use teloxide_tests::json_contains;
+///
+/// let matcher = json_contains!({"chat_id": 123});
+/// assert!(matcher.matches(&serde_json::json!({"chat_id": 123, "text": "Hi"})));
+/// assert!(!matcher.matches(&serde_json::json!({"chat_id": 124, "text": "Hi"})));
+/// ```
+///
+#[macro_export]
+macro_rules! json_contains {
+    ($($json:tt)+) => {
+        $crate::expectations::JsonContains($crate::expectations::json!($($json)+))
+    };
+}
+
+/// Matches request bodies that contain this JSON, see [`json_contains!`]
+///
+/// [`json_contains!`]: crate::json_contains
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonContains(pub Value);
+
+impl JsonContains {
+    /// Returns true if `body` contains all of the fields of the matcher
+    pub fn matches(&self, body: &Value) -> bool {
+        json_contains(body, &self.0)
+    }
+}
+
+fn json_contains(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => expected.iter().all(|(key, value)| {
+            actual
+                .get(key)
+                .is_some_and(|actual_value| json_contains(actual_value, value))
+        }),
+        (Value::Array(actual), Value::Array(expected)) => {
+            actual.len() == expected.len()
+                && actual
+                    .iter()
+                    .zip(expected)
+                    .all(|(actual, expected)| json_contains(actual, expected))
+        }
+        _ => actual == expected,
+    }
+}
+
+/// An expected request, created with [`MockBot::expect`]
+///
+/// [`MockBot::expect`]: crate::MockBot::expect
+#[derive(Clone, Debug)]
+pub struct Expectation {
+    method: String,
+    times: Option<usize>,
+    matchers: Vec<JsonContains>,
+    // Only the requests after the expectation was made are checked
+    first_request: usize,
+}
+
+impl Expectation {
+    /// Expects the request to be sent exactly `times` times. By default it is expected at least
+    /// once
+    pub fn times(&mut self, times: usize) -> &mut Self {
+        self.times = Some(times);
+        self
+    }
+
+    /// Only counts the requests that have a body matching `matcher`. Can be called multiple
+    /// times, then all of the matchers must match
+    pub fn with(&mut self, matcher: JsonContains) -> &mut Self {
+        self.matchers.push(matcher);
+        self
+    }
+
+    fn matches(&self, request: &BotRequest) -> bool {
+        request.method.eq_ignore_ascii_case(&self.method)
+            && self
+                .matchers
+                .iter()
+                .all(|matcher| matcher.matches(&request.body))
+    }
+
+    fn describe(&self) -> String {
+        let mut description = self.method.clone();
+        for matcher in &self.matchers {
+            write!(description, " with {}", matcher.0).unwrap();
+        }
+        description
+    }
+}
+
+/// All of the expectations of a bot. They are verified when the bot is dropped, if they weren't
+/// verified before
+pub(crate) struct Expectations {
+    state: Arc<Mutex<State>>,
+    expectations: Vec<Expectation>,
+    // In strict mode, the index of the first request that has to be asserted
+    strict_from: Option<usize>,
+}
+
+impl Expectations {
+    pub(crate) fn new(state: Arc<Mutex<State>>) -> Self {
+        Self {
+            state,
+            expectations: vec![],
+            strict_from: None,
+        }
+    }
+
+    /// Forgets the expectations without verifying them, for when the requests were reset
+    pub(crate) fn reset(&mut self) {
+        self.expectations.clear();
+        if self.strict_from.is_some() {
+            self.strict_from = Some(0);
+        }
+    }
+
+    pub(crate) fn strict(&mut self) {
+        self.strict_from = Some(self.state.lock().unwrap().requests.len());
+    }
+
+    /// Marks the first request that matches and wasn't asserted yet as asserted. Returns false if
+    /// there is no such request
+    pub(crate) fn assert_request(&self, method: &str, matcher: &JsonContains) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let index = state.requests.iter().enumerate().position(|(i, request)| {
+            !state.asserted_requests.contains(&i)
+                && request.method.eq_ignore_ascii_case(method)
+                && matcher.matches(&request.body)
+        });
+        match index {
+            Some(index) => state.asserted_requests.insert(index),
+            None => false,
+        }
+    }
+
+    /// Lists every request, for the panic messages
+    pub(crate) fn describe_requests(&self) -> String {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .map(|request| format!("  {} {}", request.method, request.body))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Marks the last request with this method as asserted
+    pub(crate) fn assert_last_request(&self, method: &str) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(index) = state
+            .requests
+            .iter()
+            .rposition(|request| request.method.eq_ignore_ascii_case(method))
+        {
+            state.asserted_requests.insert(index);
+        }
+    }
+
+    pub(crate) fn add(&mut self, method: &str) -> &mut Expectation {
+        let first_request = self.state.lock().unwrap().requests.len();
+        self.expectations.push(Expectation {
+            method: method.to_string(),
+            times: None,
+            matchers: vec![],
+            first_request,
+        });
+        self.expectations.last_mut().unwrap()
+    }
+
+    /// Checks all of the expectations and forgets them, panicking with a report if any of them
+    /// weren't met. In strict mode, also reports the requests no assertion checked
+    pub(crate) fn verify(&mut self) {
+        let mut report = self.verify_expectations();
+        report.push_str(&self.verify_strict());
+        if !report.is_empty() {
+            panic!("{}", report.trim_end());
+        }
+    }
+
+    fn verify_expectations(&mut self) -> String {
+        let expectations = std::mem::take(&mut self.expectations);
+        let Some(first_request) = expectations.iter().map(|e| e.first_request).min() else {
+            return String::new();
+        };
+        let mut state = self.state.lock().unwrap();
+        let requests = state.requests[first_request..].to_vec();
+
+        let mut unmet = String::new();
+        for (index, expectation) in expectations.iter().enumerate() {
+            let matching: Vec<usize> = requests
+                .iter()
+                .enumerate()
+                .map(|(i, request)| (first_request + i, request))
+                .filter(|(i, request)| {
+                    *i >= expectation.first_request && expectation.matches(request)
+                })
+                .map(|(i, _)| i)
+                .collect();
+            let calls = matching.len();
+            state.asserted_requests.extend(matching);
+            let met = match expectation.times {
+                Some(times) => calls == times,
+                None => calls > 0,
+            };
+            if !met {
+                let expected = match expectation.times {
+                    Some(times) => format!("{times} times"),
+                    None => "at least once".to_string(),
+                };
+                writeln!(
+                    unmet,
+                    "  #{index} {}: expected {expected}, was called {calls} times",
+                    expectation.describe()
+                )
+                .unwrap();
+            }
+        }
+        if unmet.is_empty() {
+            return String::new();
+        }
+
+        // Requests to the expected methods that didn't match any expectation
+        let mut unexpected = String::new();
+        for request in &requests {
+            let expected_method = expectations
+                .iter()
+                .any(|e| request.method.eq_ignore_ascii_case(&e.method));
+            if expected_method && !expectations.iter().any(|e| e.matches(request)) {
+                writeln!(unexpected, "  {} {}", request.method, request.body).unwrap();
+            }
+        }
+
+        let mut report = format!("Expectations were not met:\n{unmet}");
+        if !unexpected.is_empty() {
+            write!(report, "Unexpected calls:\n{unexpected}").unwrap();
+        }
+        report
+    }
+
+    fn verify_strict(&mut self) -> String {
+        let Some(strict_from) = self.strict_from else {
+            return String::new();
+        };
+        let state = self.state.lock().unwrap();
+        // Every request is reported only once
+        self.strict_from = Some(state.requests.len());
+
+        let mut unasserted = String::new();
+        for (i, request) in state.requests.iter().enumerate().skip(strict_from) {
+            // Queries like getMe don't change anything, and the dispatcher sends some on its own
+            if !state.asserted_requests.contains(&i) && !request.method.starts_with("get") {
+                writeln!(unasserted, "  {} {}", request.method, request.body).unwrap();
+            }
+        }
+        if unasserted.is_empty() {
+            return String::new();
+        }
+        format!("Unexpected bot requests, no assertion checked them:\n{unasserted}")
+    }
+}
+
+impl Drop for Expectations {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            self.verify();
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Diagnostics for the panics of the handlers. The dispatcher only says that a worker panicked,
+//! so the panic hook remembers the message, the location and the backtrace of the first panic
+//! of a handler, with the update that it was handling.
+use std::{
+    backtrace::Backtrace,
+    fmt,
+    panic::{self, PanicHookInfo},
+    sync::{Arc, Mutex, MutexGuard, Once, PoisonError},
+};
+
+use lazy_static::lazy_static;
+use teloxide::{
+    dispatching::{DpHandlerDescription, UpdateHandler},
+    dptree::{self, di::DependencyMap, HandlerDescription, HandlerSignature},
+    types::Update,
+};
+
+lazy_static! {
+    /// The first panic of a handler in the current dispatch. Only one bot dispatches at a time,
+    /// so there is only one
+    static ref HANDLER_PANIC: Mutex<Option<HandlerPanic>> = Mutex::new(None);
+}
+
+tokio::task_local! {
+    /// The update the handler tree of the current task is handling
+    static HANDLED_UPDATE: Arc<Update>;
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+/// A panic of a handler, with the update it was handling
+pub(crate) struct HandlerPanic {
+    message: String,
+    location: String,
+    update: Arc<Update>,
+    backtrace: Backtrace,
+}
+
+impl fmt::Display for HandlerPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The handler panicked at {}: {}\n\nThe update it was handling:\n{:#?}\n\nBacktrace:\n{}",
+            self.location, self.message, self.update, self.backtrace
+        )
+    }
+}
+
+/// Returns the handler tree, that remembers the update it is handling, so that a panic can be
+/// traced back to it
+pub(crate) fn track_updates<Err>(tree: UpdateHandler<Err>) -> UpdateHandler<Err>
+where
+    Err: Send + Sync + 'static,
+{
+    let tracker = dptree::from_fn_with_description(
+        DpHandlerDescription::entry(),
+        |deps: DependencyMap, cont| async move {
+            let update = deps.get::<Update>();
+            HANDLED_UPDATE.scope(update, cont(deps)).await
+        },
+        HandlerSignature::Entry,
+    );
+    tracker.chain(tree)
+}
+
+/// Forgets the panic of the previous dispatch, and installs the panic hook if it isn't installed
+/// yet. The previous hook is still called, so the panic is printed as usual.
+pub(crate) fn start_recording() {
+    INSTALL_HOOK.call_once(|| {
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            record(info);
+            previous_hook(info);
+        }));
+    });
+    *lock(&HANDLER_PANIC) = None;
+}
+
+/// Returns the first panic of a handler in the current dispatch, if there was one
+pub(crate) fn take() -> Option<HandlerPanic> {
+    lock(&HANDLER_PANIC).take()
+}
+
+fn record(info: &PanicHookInfo) {
+    // Panics outside of the tasks that handle updates aren't the handler's
+    let Ok(update) = HANDLED_UPDATE.try_with(Arc::clone) else {
+        return;
+    };
+    let mut handler_panic = lock(&HANDLER_PANIC);
+    if handler_panic.is_some() {
+        return;
+    }
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".to_string());
+    *handler_panic = Some(HandlerPanic {
+        message,
+        location: info.location().map(ToString::to_string).unwrap_or_default(),
+        update,
+        backtrace: Backtrace::force_capture(),
+    });
+}
+
+// A panic while the lock is held must not hide the panics that come after it
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
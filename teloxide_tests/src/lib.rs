@@ -57,11 +57,13 @@
 //! ## Supported Endpoints
 //!
 //! - /AnswerCallbackQuery
+//! - /AnswerInlineQuery
 //! - /DeleteMessage
 //! - /DeleteMessages
 //! - /EditMessageText
 //! - /EditMessageReplyMarkup
 //! - /EditMessageCaption
+//! - /EditMessageLiveLocation
 //! - /GetFile
 //! - /SendMessage
 //! - /SendDocument
@@ -90,11 +92,31 @@
 //! - /RestrictChatMember
 //! - /SetMessageReaction
 //! - /SetMyCommands
+//! - /DeleteMyCommands
+//! - /GetMyCommands
+//! - /SetPassportDataErrors
 //! - /GetMe
+//! - /ReadBusinessMessage
+//! - /DeleteBusinessMessages
+//! - /SetBusinessAccountName
+//! - /SetBusinessAccountBio
+//! - /SetBusinessAccountProfilePhoto
+//! - /GetBusinessAccountStarBalance
+//! - /GetBusinessAccountGifts
+//! - /ConvertGiftToStars
+//! - /UpgradeGift
+//! - /TransferGift
+//! - /GetChat
+//! - /GetChatMember
+//! - /GetChatAdministrators
+//! - /GetCustomEmojiStickers
+//! - /SetChatMenuButton
+//! - /GetChatMenuButton
 //!
 //! More endpoints will be added as time goes on!
 //!
-//! (/GetUpdates and /GetWebhookInfo exist, but they are dummies)
+//! (/GetWebhookInfo exists, but it is a dummy. /GetUpdates returns only the updates of
+//! [`MockEnvironment`], for testing complete bots that poll the fake server)
 //!
 //! And also fake file downloading!
 //!
@@ -161,16 +183,35 @@
 #![allow(clippy::needless_return)]
 #![allow(clippy::bool_assert_comparison)]
 
+pub mod bench;
+pub mod coverage;
 mod dataset;
+pub mod expectations;
+pub(crate) mod handler_panics;
 pub(crate) mod listener;
+pub mod matchers;
+pub mod media_metadata;
 pub mod mock_bot;
+pub mod mock_bot_builder;
+pub mod mock_environment;
+pub mod mock_server;
 pub mod server;
 pub(crate) mod state;
+pub mod storage_chaos;
 #[cfg(test)]
 mod tests;
+pub(crate) mod transcript;
 pub(crate) mod utils;
 
+pub use bench::BenchReport;
+pub use coverage::RouteCoverage;
 pub use dataset::*;
-pub use mock_bot::MockBot;
+pub use matchers::TextMatcher;
+pub use media_metadata::MediaMetadata;
+pub use mock_bot::{CommandMenu, DefaultMockBot, MockBot, StateStorageError, UpdateDelivery};
+pub use mock_bot_builder::MockBotBuilder;
+pub use mock_environment::MockEnvironment;
+pub use mock_server::MockServer;
 pub use server::Responses;
+pub use storage_chaos::StorageChaos;
 use teloxide_tests_macros as proc_macros;
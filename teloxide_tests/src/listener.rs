@@ -1,6 +1,7 @@
 use std::{
+    future::Future,
     pin::Pin,
-    sync::Mutex,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
     thread::sleep,
     time::Duration,
@@ -14,20 +15,45 @@ use teloxide::{
     Bot, RequestError,
 };
 
+use crate::{state::State, transcript::TranscriptEntry};
+
 // It isn't really a listener, it just takes the updates and feeds them one by one to the
 // dispather, until there is no more.
 pub(crate) struct InsertingListener {
     pub updates: Vec<Update>,
+    /// How long to wait before feeding every update
+    pub delays: Vec<Duration>,
+    /// Where the fed updates are written down for the transcript
+    pub state: Arc<Mutex<State>>,
 }
 
 pub(crate) struct InsertingListenerStream {
-    updates: Mutex<Vec<Update>>,
+    updates: Mutex<Vec<(Duration, Update)>>,
+    delay: Option<Pin<Box<tokio::time::Sleep>>>,
+    state: Arc<Mutex<State>>,
 }
 
 impl Stream for InsertingListenerStream {
     type Item = Result<Update, RequestError>;
 
-    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // Waits for the delay of the next update, if it has one
+        let next_delay = self
+            .updates
+            .lock()
+            .unwrap()
+            .first()
+            .map(|(delay, _)| *delay);
+        if let Some(delay) = next_delay.filter(|delay| !delay.is_zero()) {
+            let sleep = self
+                .delay
+                .get_or_insert_with(|| Box::pin(tokio::time::sleep(delay)));
+            if sleep.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            self.delay = None;
+        }
+
         if self.updates.lock().unwrap().len() == 0 {
             // A small wait to make sure the state is setteled in?..
             // No idea, but it fixes a bug with test_erased_state...
@@ -37,7 +63,12 @@ impl Stream for InsertingListenerStream {
             return Poll::Ready(None);
         }
         // Returns updates one by one
-        let update = self.updates.lock().unwrap().remove(0);
+        let (_, update) = self.updates.lock().unwrap().remove(0);
+        self.state
+            .lock()
+            .unwrap()
+            .transcript
+            .push(TranscriptEntry::Update(update.clone()));
         Poll::Ready(Some(Ok(update)))
     }
 }
@@ -60,8 +91,15 @@ impl<'a> AsUpdateStream<'a> for InsertingListener {
     type Stream = InsertingListenerStream;
 
     fn as_stream(&'a mut self) -> Self::Stream {
+        let delays = self
+            .delays
+            .iter()
+            .copied()
+            .chain(std::iter::repeat(Duration::ZERO));
         InsertingListenerStream {
-            updates: self.updates.clone().into(),
+            updates: delays.zip(self.updates.clone()).collect::<Vec<_>>().into(),
+            delay: None,
+            state: self.state.clone(),
         }
     }
 }
@@ -0,0 +1,81 @@
+//! Matchers for the text assertions of [`MockBot`], so dynamic content, like timestamps or ids,
+//! can be checked without comparing exact strings
+//!
+//! [`MockBot`]: crate::MockBot
+pub use regex::Regex;
+
+/// Something the text or caption of a message can be checked against. It's implemented for
+/// strings (exact match), [`Regex`] (the text has a match) and closures that take the text and
+/// return `bool`
+///
+/// # Example
+/// ```
+/// use teloxide_tests::matchers::{Regex, TextMatcher};
+///
+/// assert!("Hello!".matches_text("Hello!"));
+/// assert!((&"Hello!".to_string()).matches_text("Hello!"));
+/// assert!(Regex::new(r"^Your id is \d+$").unwrap().matches_text("Your id is 42"));
+/// assert!((|text: &str| text.ends_with('!')).matches_text("Hello!"));
+/// ```
+///
+pub trait TextMatcher {
+    /// Returns true if the text matches
+    fn matches_text(&self, text: &str) -> bool;
+
+    /// Describes what is expected, to be shown when the text doesn't match
+    fn expected(&self) -> String;
+}
+
+impl TextMatcher for &str {
+    fn matches_text(&self, text: &str) -> bool {
+        *self == text
+    }
+
+    fn expected(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+impl TextMatcher for String {
+    fn matches_text(&self, text: &str) -> bool {
+        self == text
+    }
+
+    fn expected(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+// Generic parameters get no deref coercion, so `&String` needs its own impl
+impl TextMatcher for &String {
+    fn matches_text(&self, text: &str) -> bool {
+        *self == text
+    }
+
+    fn expected(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+impl TextMatcher for Regex {
+    fn matches_text(&self, text: &str) -> bool {
+        self.is_match(text)
+    }
+
+    fn expected(&self) -> String {
+        format!("text matching /{}/", self.as_str())
+    }
+}
+
+impl<F> TextMatcher for F
+where
+    F: Fn(&str) -> bool,
+{
+    fn matches_text(&self, text: &str) -> bool {
+        self(text)
+    }
+
+    fn expected(&self) -> String {
+        "text matching the predicate".to_string()
+    }
+}
@@ -0,0 +1,54 @@
+//! Realistic durations and dimensions of the sent media, see [`MockBot::probe_media`]
+//!
+//! [`MockBot::probe_media`]: crate::MockBot::probe_media
+use std::sync::Arc;
+
+use teloxide::types::Seconds;
+
+/// The duration and the dimensions of a media file the bot sent, that the fake server uses
+/// instead of its defaults, unless the bot set them in the request itself
+///
+/// # Example
+/// ```
+/// use teloxide::types::Seconds;
+///
+/// let metadata = teloxide_tests::MediaMetadata::new()
+///     .duration(Seconds::from_seconds(42))
+///     .width(1920)
+///     .height(1080);
+/// assert_eq!(metadata.width, Some(1920));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MediaMetadata {
+    /// Duration of a video, animation, video note, audio or voice
+    pub duration: Option<Seconds>,
+    /// Width of a video, animation or photo, or the diameter of a video note
+    pub width: Option<u32>,
+    /// Height of a video, animation or photo
+    pub height: Option<u32>,
+}
+
+impl MediaMetadata {
+    /// Creates metadata that changes nothing, the defaults of the fake server are used
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn duration(mut self, duration: Seconds) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn width(mut self, width: u32) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn height(mut self, height: u32) -> Self {
+        self.height = Some(height);
+        self
+    }
+}
+
+/// Returns the metadata of a sent media file by its file name
+pub(crate) type MediaProbe = Arc<dyn Fn(&str) -> Option<MediaMetadata> + Send + Sync>;
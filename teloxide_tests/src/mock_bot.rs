@@ -1,46 +1,222 @@
 //! Mock bot that sends requests to the fake server
 use std::{
+    collections::{HashMap, HashSet},
     env,
     fmt::Debug,
     hash::Hash,
     mem::discriminant,
     panic,
-    sync::{atomic::AtomicI32, Arc, Mutex, MutexGuard, PoisonError},
+    sync::{atomic::AtomicI32, Arc, Condvar, Mutex, PoisonError},
+    time::Duration,
 };
 
-use gag::Gag;
+use chrono::{DateTime, TimeDelta, Utc};
+use futures_util::future::BoxFuture;
 use lazy_static::lazy_static;
+use serde_json::Value;
 use teloxide::{
     dispatching::{
-        dialogue::{ErasedStorage, GetChatId, InMemStorage, Storage},
+        dialogue::{ErasedStorage, InMemStorage, Storage},
         UpdateHandler,
     },
+    dptree::HandlerSignature,
     error_handlers::ErrorHandler,
     prelude::*,
-    types::{MaybeInaccessibleMessage, Me, UpdateKind},
+    types::{
+        BotCommand, BotCommandScope, ButtonRequest, ChatAction, ChatFullInfo, ChatMember,
+        ChatMemberKind, ChatPermissions, ChatType, CustomEmojiId, FileId, InlineKeyboardButtonKind,
+        InlineKeyboardMarkup, MaybeAnonymousUser, Me, MenuButton, MessageEntityKind,
+        MessageReactionUpdated, ReactionType, ReplyMarkup, SharedUser, Sticker, UpdateId,
+        UpdateKind, User,
+    },
+    ApiError,
 };
 
 // Needed for trait bound stuff
 pub use crate::utils::DistributionKey;
 use crate::{
-    dataset::{IntoUpdate, MockMe},
+    bench::{BenchReport, LatencyRecorder},
+    coverage::{CoverageDump, RouteCoverage},
+    dataset::{
+        IntoUpdate, MockCallbackQuery, MockInlineQuery, MockMe, MockMessageChatShared,
+        MockMessageText, MockMessageUsersShared, MockOwnedGift, MockUser,
+    },
+    expectations::{Expectation, Expectations, JsonContains},
+    handler_panics,
     listener::InsertingListener,
     server,
-    server::ServerManager,
+    server::{effects::effect_emoji, ServerManager},
     state::State,
+    storage_chaos::{ChaosStorage, ChaosStorageMarker},
+    transcript,
     utils::{assert_eqn, default_distribution_function, find_chat_id},
+    MediaMetadata, StorageChaos, TextMatcher,
 };
 
 lazy_static! {
-    static ref BOT_LOCK: Mutex<()> = Mutex::new(());
+    static ref BOT_LOCK: (Mutex<bool>, Condvar) = (Mutex::new(false), Condvar::new());
+}
+
+// Unlike a `MutexGuard`, this is `Send`, so the bot can be moved between threads, like in test
+// fixtures
+pub(crate) struct BotLockGuard;
+
+impl BotLockGuard {
+    pub(crate) fn acquire() -> Self {
+        let (locked, released) = &*BOT_LOCK;
+        // If the lock is poisoned, we don't care, some other bot panicked and can't do anything
+        let mut locked = locked.lock().unwrap_or_else(PoisonError::into_inner);
+        while *locked {
+            locked = released
+                .wait(locked)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        *locked = true;
+        Self
+    }
+}
+
+impl Drop for BotLockGuard {
+    fn drop(&mut self) {
+        let (locked, released) = &*BOT_LOCK;
+        *locked.lock().unwrap_or_else(PoisonError::into_inner) = false;
+        released.notify_one();
+    }
 }
 
+// Everything the dispatcher needs, except for the bot
+pub(crate) struct Dispatch<Err, Key> {
+    handler_tree: UpdateHandler<Err>,
+    dependencies: DependencyMap,
+    distribution_f: fn(&Update) -> Option<Key>,
+    error_handler: Arc<dyn ErrorHandler<Err> + Send + Sync>,
+    listener: InsertingListener,
+}
+
+impl<Err, Key> Dispatch<Err, Key>
+where
+    Err: Debug + Send + Sync + 'static,
+    Key: Hash + Eq + Clone + Send + 'static,
+{
+    async fn run<R>(self, bot: R)
+    where
+        R: Requester + Clone + Send + Sync + 'static,
+    {
+        Dispatcher::builder(bot, handler_panics::track_updates(self.handler_tree))
+            .dependencies(self.dependencies)
+            .distribution_function(self.distribution_f)
+            .error_handler(self.error_handler)
+            .build()
+            .dispatch_with_listener(self.listener, LoggingErrorHandler::new())
+            .await;
+    }
+}
+
+// Wraps the bot connected to the fake server into the requester the handlers use, and runs the
+// dispatcher with it. It erases the requester type, so `MockBot` doesn't need another generic
+pub(crate) type RequesterFn<Err, Key> =
+    Arc<dyn Fn(Bot, Dispatch<Err, Key>) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Why the state of the dialogue can't be set, see [`MockBot::try_set_state`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateStorageError {
+    /// There is no `InMemStorage` or `ErasedStorage` in the dependencies
+    NoStorage,
+    /// The storages that the handler tree requires are for the dialogues of other types
+    WrongStateType {
+        /// The type of the state that was given
+        provided: &'static str,
+        /// The types of the dialogues of the storages that the handler tree requires
+        expected: Vec<String>,
+    },
+    /// The storage failed to update the dialogue
+    Storage(String),
+}
+
+impl std::fmt::Display for StateStorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoStorage => f.write_str(
+                "No storage was detected! Did you add it to \
+                 bot.dependencies(deps![get_bot_storage().await]); ?",
+            ),
+            Self::WrongStateType { provided, expected } => write!(
+                f,
+                "The state is `{provided}`, but the storages are for `{}`! Did you specify the \
+                 type ::<State> ?",
+                expected.join("`, `")
+            ),
+            Self::Storage(error) => write!(f, "Failed to update dialogue: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for StateStorageError {}
+
+/// How the updates of a dispatch get to the bot, see [`MockBot::set_update_delivery`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UpdateDelivery {
+    /// Every update is delivered once, in the order they were set
+    #[default]
+    InOrder,
+    /// Every update is delivered twice in a row with the same `update_id`, like Telegram
+    /// retrying a webhook that didn't answer in time
+    Duplicated,
+    /// The updates are delivered from the last to the first, so their `update_id`s decrease,
+    /// like updates that arrive late
+    Reversed,
+}
+
+pub(crate) type RequestHook = Arc<dyn Fn(&str, &Value) + Send + Sync>;
+
+/// [`MockBot`] with the error type and distribution key that fit most bots, for storing the bot
+/// in test fixtures and harnesses without spelling out the generics
+pub type DefaultMockBot = MockBot<Box<dyn std::error::Error + Send + Sync>, DistributionKey>;
+
+/// The expected commands of a scope and a language code in [`MockBot::assert_commands`], as
+/// `(command, description)` pairs
+pub type CommandMenu<'a> = (BotCommandScope, Option<&'a str>, Vec<(&'a str, &'a str)>);
+
 const DEFAULT_STACK_SIZE: usize = 8 * 1024 * 1024;
+// Auto responses that go on for longer than that are most likely stuck in a loop
+const MAX_AUTO_RESPONSES: usize = 100;
+// Paginated messages with more pages than that most likely go in circles
+const MAX_PAGES: usize = 100;
+
+/// Prints the responses if a check after the dispatch fails, so it's visible what the bot did
+struct ResponsesDump(server::Responses);
+
+impl Drop for ResponsesDump {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            eprintln!("Requests of the failed dispatch:\n{}", self.0);
+        }
+    }
+}
+
+/// Puts back the fields of the bot that `bench` replaced, even if a handler panics
+struct BenchGuard<'a, Err, Key> {
+    bot: &'a mut MockBot<Err, Key>,
+    updates: Vec<Update>,
+    handler_tree: Option<UpdateHandler<Err>>,
+    dependencies: DependencyMap,
+}
+
+impl<Err, Key> Drop for BenchGuard<'_, Err, Key> {
+    fn drop(&mut self) {
+        self.bot.updates = std::mem::take(&mut self.updates);
+        if let Some(handler_tree) = self.handler_tree.take() {
+            self.bot.handler_tree = handler_tree;
+        }
+        self.bot.dependencies = std::mem::take(&mut self.dependencies);
+    }
+}
 
 /// A mocked bot that sends requests to the fake server
 /// Please check the [`new`] function docs and [github examples](https://github.com/LasterAlex/teloxide_tests/tree/master/examples) for more information.
 ///
-/// If you are having troubles with generics while trying to store `MockBot`, just do this:
+/// If you are having troubles with generics while trying to store `MockBot`, use
+/// [`DefaultMockBot`], which is
 ///
 /// `MockBot<Box<dyn std::error::Error + Send + Sync>, teloxide_tests::mock_bot::DistributionKey>`
 ///
@@ -61,10 +237,23 @@ pub struct MockBot<Err, Key> {
 
     distribution_f: fn(&Update) -> Option<Key>,
     error_handler: Arc<dyn ErrorHandler<Err> + Send + Sync>,
+    auto_responses: Vec<(String, String)>,
+    expectations: Expectations,
+    update_interval: Duration,
+    update_delays: Vec<Duration>,
+    update_delivery: UpdateDelivery,
+    requester_f: Option<RequesterFn<Err, Key>>,
+    request_hooks: Vec<RequestHook>,
+    // Whether the dispatches leave the env variables of the process alone
+    isolate_env: bool,
+    // Whether the updates are the reactions queued since the last dispatch
+    reactions_queued: bool,
 
     current_update_id: AtomicI32,
     state: Arc<Mutex<State>>,
-    _bot_lock: Option<MutexGuard<'static, ()>>,
+    // Before the lock, so the coverage file is written by one bot at a time
+    _coverage_dump: CoverageDump,
+    _bot_lock: BotLockGuard,
 }
 
 impl<Err> MockBot<Err, DistributionKey>
@@ -133,8 +322,7 @@ where
         let current_update_id = AtomicI32::new(42);
         let state = Arc::new(Mutex::new(State::default()));
 
-        // If the lock is poisoned, we don't care, some other bot panicked and can't do anything
-        let lock = Some(BOT_LOCK.lock().unwrap_or_else(PoisonError::into_inner));
+        let lock = BotLockGuard::acquire();
 
         Self {
             bot,
@@ -145,6 +333,16 @@ where
             stack_size: DEFAULT_STACK_SIZE,
             error_handler: LoggingErrorHandler::new(),
             distribution_f: default_distribution_function,
+            auto_responses: vec![],
+            expectations: Expectations::new(state.clone()),
+            update_interval: Duration::ZERO,
+            update_delays: vec![],
+            update_delivery: UpdateDelivery::InOrder,
+            requester_f: None,
+            request_hooks: vec![],
+            isolate_env: false,
+            reactions_queued: false,
+            _coverage_dump: CoverageDump(state.clone()),
             _bot_lock: lock,
             current_update_id,
             state,
@@ -183,6 +381,16 @@ where
             stack_size,
             error_handler,
             distribution_f: _,
+            auto_responses,
+            expectations,
+            update_interval,
+            update_delays,
+            update_delivery,
+            requester_f: _,
+            request_hooks,
+            isolate_env,
+            reactions_queued,
+            _coverage_dump,
             _bot_lock,
             current_update_id,
             state,
@@ -197,6 +405,16 @@ where
             stack_size,
             error_handler,
             distribution_f: f,
+            auto_responses,
+            expectations,
+            update_interval,
+            update_delays,
+            update_delivery,
+            requester_f: None,
+            request_hooks,
+            isolate_env,
+            reactions_queued,
+            _coverage_dump,
             _bot_lock,
             current_update_id,
             state,
@@ -211,15 +429,1477 @@ where
         self.dependencies = deps;
     }
 
+    /// Forgets everything that happened to the bot, so it can be reused like a new one: the
+    /// messages, files, requests and responses of the fake server, the mock clock, expectations,
+    /// auto responses, update delays and the update delivery. The star balances go back to the
+    /// ones that were set.
+    ///
+    /// The handler tree, updates, dependencies, `me`, the error handler and the request hooks
+    /// stay, and so do the settings of the fake server, like [`validate_like_telegram`],
+    /// [`set_lenient_replies`], [`set_flood_limit`], [`check_response_fidelity`],
+    /// [`probe_media`], [`localize_errors`], [`storage_chaos`] and [`accumulate_responses`]. So a
+    /// configured bot can be shared through a test fixture. Note that storages in the
+    /// dependencies keep their state.
+    ///
+    /// [`validate_like_telegram`]: crate::MockBot::validate_like_telegram
+    /// [`set_lenient_replies`]: crate::MockBot::set_lenient_replies
+    /// [`set_flood_limit`]: crate::MockBot::set_flood_limit
+    /// [`check_response_fidelity`]: crate::MockBot::check_response_fidelity
+    /// [`probe_media`]: crate::MockBot::probe_media
+    /// [`localize_errors`]: crate::MockBot::localize_errors
+    /// [`storage_chaos`]: crate::MockBot::storage_chaos
+    /// [`accumulate_responses`]: crate::MockBot::accumulate_responses
+    pub fn reset(&mut self) {
+        self.state.lock().unwrap().reset_to_config();
+        self.expectations.reset();
+        self.auto_responses.clear();
+        self.update_interval = Duration::ZERO;
+        self.update_delays.clear();
+        self.update_delivery = UpdateDelivery::InOrder;
+    }
+
     /// Sets the bot parameters, like supports_inline_queries, first_name, etc.
     pub fn me(&mut self, me: MockMe) {
         self.me = me.build();
     }
 
-    /// Sets the updates. Useful for reusing the same mocked bot instance in different tests
-    /// Reminder: You can pass in `vec![MockMessagePhoto]` or something else!
-    pub fn update<T: IntoUpdate>(&mut self, update: T) {
-        self.updates = update.into_update(&self.current_update_id);
+    /// Seeds the generator of file ids, media group ids and the other random values of the fake
+    /// server, so that they are the same on every run, for snapshots and golden files. [`reset`]
+    /// makes them random again.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, types::InputFile};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         bot.send_photo(message.chat.id, InputFile::memory("photo")).await?;
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/photo"), handler_tree);
+    /// bot.seed_rng(42);
+    /// bot.dispatch().await;
+    /// let first = bot.get_responses().sent_messages[0].photo().unwrap()[0].file.id.clone();
+    ///
+    /// bot.reset();
+    /// bot.seed_rng(42);
+    /// bot.dispatch().await;
+    /// let second = bot.get_responses().sent_messages[0].photo().unwrap()[0].file.id.clone();
+    /// assert_eq!(first, second);
+    /// # }
+    /// ```
+    ///
+    /// [`reset`]: crate::MockBot::reset
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.state.lock().unwrap().ids.seed(seed);
+    }
+
+    /// Sets the value of the next dice the bot sends, instead of a random one. Every call adds a
+    /// value to the queue, so a few dice in a row can be set up. The value must fit the emoji
+    /// of the dice: 1 to 6 for 🎲, 🎯 and 🎳, 1 to 5 for 🏀 and ⚽, and 1 to 64 for 🎰,
+    /// otherwise `sendDice` fails.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         let dice = bot.send_dice(message.chat.id).await?;
+    ///         if dice.dice().unwrap().value == 6 {
+    ///             bot.send_message(message.chat.id, "You won!").await?;
+    ///         }
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/roll"), handler_tree);
+    /// bot.set_next_dice_value(6);
+    /// bot.dispatch_and_check_last_text("You won!").await;
+    /// # }
+    /// ```
+    pub fn set_next_dice_value(&mut self, value: u8) {
+        self.state.lock().unwrap().dice_values.push_back(value);
+    }
+
+    /// Moves the mock clock of the fake server forward. Messages the bot sends afterwards are
+    /// dated with the mock time, live locations whose `live_period` has passed expire, so
+    /// `editMessageLiveLocation` fails for them like it does in Telegram, and chat members whose
+    /// restriction or ban has ended are unrestricted or unbanned.
+    pub fn advance_time(&mut self, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.clock_offset += TimeDelta::from_std(duration).expect("The duration is too long!");
+        state.expire_live_locations();
+        state.lift_expired_restrictions();
+    }
+
+    /// Turns on a debug mode, where every response of the fake server is decoded into the
+    /// teloxide type the bot would decode it into and encoded again. If that fails, or anything
+    /// is lost or changed on the way, like fields teloxide doesn't know about, the dispatch
+    /// panics with the diff. It catches the fake server drifting from what teloxide expects,
+    /// before it shows up as a confusing decode error in a handler.
+    pub fn check_response_fidelity(&mut self) {
+        self.state.lock().unwrap().config.check_fidelity = true;
+    }
+
+    /// Sets how the fake server finds out the duration and the dimensions of the media files the
+    /// bot sends, like videos, animations, video notes, audios and voices, by their file names.
+    /// The metadata the bot sets in the request itself is used first, then the one the probe
+    /// returns, and the defaults of the fake server for everything else. The dimensions of
+    /// photos are only checked against the Bot API limits with [`validate_like_telegram`].
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, types::{InputFile, Seconds}};
+    /// # use teloxide_tests::{MediaMetadata, MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         let video = InputFile::memory("video").file_name("trailer.mp4");
+    ///         bot.send_video(message.chat.id, video).await?;
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/trailer"), handler_tree);
+    /// bot.probe_media(|file_name| match file_name {
+    ///     "trailer.mp4" => Some(
+    ///         MediaMetadata::new()
+    ///             .duration(Seconds::from_seconds(95))
+    ///             .width(1920)
+    ///             .height(1080),
+    ///     ),
+    ///     _ => None,
+    /// });
+    /// bot.dispatch().await;
+    /// let video = bot.get_responses().sent_messages_video[0].message.video().unwrap().clone();
+    /// assert_eq!(video.width, 1920);
+    /// assert_eq!(video.duration, Seconds::from_seconds(95));
+    /// # }
+    /// ```
+    ///
+    /// [`validate_like_telegram`]: crate::MockBot::validate_like_telegram
+    pub fn probe_media<F>(&mut self, probe: F)
+    where
+        F: Fn(&str) -> Option<MediaMetadata> + Send + Sync + 'static,
+    {
+        self.state.lock().unwrap().config.media_probe = Some(Arc::new(probe));
+    }
+
+    /// Makes the fake server rewrite the descriptions of the errors it returns with `localize`,
+    /// like a Bot API server that words its errors differently. teloxide only recognizes the
+    /// canonical descriptions, so the bot gets `ApiError::Unknown` with the rewritten
+    /// description instead, to test how it handles errors it doesn't know. Without this the
+    /// canonical descriptions are returned.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, types::MessageId};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         let edit = bot.edit_message_text(message.chat.id, MessageId(404), "Edited");
+    ///         if let Err(error) = edit.await {
+    ///             bot.send_message(message.chat.id, error.to_string()).await?;
+    ///         }
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/edit"), handler_tree);
+    /// bot.localize_errors(|description| description.replace("Bad Request", "Ungültige Anfrage"));
+    /// bot.dispatch().await;
+    /// let error = bot.get_responses().sent_messages[0].text().unwrap().to_string();
+    /// assert!(error.contains("Ungültige Anfrage"));
+    /// # }
+    /// ```
+    pub fn localize_errors<F>(&mut self, localize: F)
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.state.lock().unwrap().config.error_localizer = Some(Arc::new(localize));
+    }
+
+    /// Registers a hook that is called with the Bot API method and the body of every request the
+    /// bot sends, like [`BotRequest`], for invariants that every test of a suite should keep.
+    /// The hooks are called in the order they were registered, after every dispatch, so they
+    /// can panic like any assertion.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         bot.send_message(message.chat.id, "Welcome! — Acme").await?;
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+    /// bot.on_request(|method, body| {
+    ///     if method == "sendMessage" {
+    ///         let text = body["text"].as_str().unwrap();
+    ///         assert!(text.ends_with("— Acme"), "No brand footer in {text:?}");
+    ///     }
+    /// });
+    /// bot.dispatch().await;
+    /// # }
+    /// ```
+    ///
+    /// [`BotRequest`]: crate::server::BotRequest
+    pub fn on_request<F>(&mut self, hook: F)
+    where
+        F: Fn(&str, &Value) + Send + Sync + 'static,
+    {
+        self.request_hooks.push(Arc::new(hook));
+    }
+
+    /// Returns true if the live location in `message` has expired and can't be edited anymore
+    pub fn is_live_location_expired(&self, message: &Message) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .expired_live_locations
+            .contains(&message.id)
+    }
+
+    /// Sets the amount of Telegram Stars owned by the connected business account, that is
+    /// returned by `getBusinessAccountStarBalance`
+    pub fn business_star_balance(&mut self, star_count: i64) {
+        let mut state = self.state.lock().unwrap();
+        state.config.business_star_balance = star_count;
+        state.business_star_balance = star_count;
+    }
+
+    /// Limits how many messages the bot can send in a second, by the mock clock. Over the limit,
+    /// the fake server answers with `429 Too Many Requests`, unless the request sets
+    /// `allow_paid_broadcast`. Then every message over the limit costs 0.1 stars, that are
+    /// withdrawn from the balance set with [`bot_star_balance`], and the request fails with
+    /// `BALANCE_TOO_LOW` if there aren't enough stars. There is no limit by default.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         for _ in 0..31 {
+    ///             bot.send_message(message.chat.id, "News")
+    ///                 .allow_paid_broadcast(true)
+    ///                 .await?;
+    ///         }
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/broadcast"), handler_tree);
+    /// bot.set_flood_limit(30);
+    /// bot.bot_star_balance(10);
+    /// bot.dispatch().await;
+    /// // The 31st message cost 0.1 stars
+    /// assert_eq!(bot.get_bot_star_balance().amount, 9);
+    /// # }
+    /// ```
+    ///
+    /// [`bot_star_balance`]: crate::MockBot::bot_star_balance
+    pub fn set_flood_limit(&mut self, messages_per_second: usize) {
+        self.state.lock().unwrap().config.flood_limit = Some(messages_per_second);
+    }
+
+    /// Sets the amount of Telegram Stars owned by the bot, that paid broadcasts are paid from
+    pub fn bot_star_balance(&mut self, star_count: i64) {
+        let mut state = self.state.lock().unwrap();
+        state.config.bot_nanostar_balance = star_count * 1_000_000_000;
+        state.bot_nanostar_balance = star_count * 1_000_000_000;
+    }
+
+    /// Returns the amount of Telegram Stars owned by the bot, after the paid broadcasts
+    pub fn get_bot_star_balance(&self) -> server::StarAmount {
+        let nanostars = self.state.lock().unwrap().bot_nanostar_balance;
+        let nanostar_amount = (nanostars % 1_000_000_000) as i32;
+        server::StarAmount {
+            amount: nanostars / 1_000_000_000,
+            nanostar_amount: (nanostar_amount != 0).then_some(nanostar_amount),
+        }
+    }
+
+    /// Adds a gift to the gifts owned by the connected business account, that are returned by
+    /// `getBusinessAccountGifts`
+    pub fn business_gift(&mut self, gift: MockOwnedGift) {
+        self.state.lock().unwrap().business_gifts.push(gift.build());
+    }
+
+    /// Sets the updates. Useful for reusing the same mocked bot instance in different tests
+    /// Reminder: You can pass in `vec![MockMessagePhoto]` or something else!
+    pub fn update<T: IntoUpdate>(&mut self, update: T) {
+        self.updates = update.into_update(&self.current_update_id);
+        self.reactions_queued = false;
+    }
+
+    /// Makes the `user` react to the stored message with the `message_id` with the `emoji`,
+    /// replacing their previous reaction, and queues the `MessageReactionUpdated` update for the
+    /// next dispatch. Consecutive calls queue the reactions one after another, the first call
+    /// after a dispatch or [`update`] replaces the updates of the bot.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, types::MessageReactionUpdated};
+    /// # use teloxide_tests::{MockBot, MockMessageText, MockUser};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message_reaction_updated().endpoint(
+    ///         |reaction: MessageReactionUpdated, bot: Bot| async move {
+    ///             bot.send_message(reaction.chat.id, "Thanks!").await?;
+    ///             Ok(())
+    ///         },
+    ///     );
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/poll"), handler_tree);
+    /// let message = bot.seed_messages(vec![MockMessageText::new().text("Do you like trains?")]);
+    /// bot.react_to_message(message[0].id.0, "👍", MockUser::new().id(1).build());
+    /// bot.react_to_message(message[0].id.0, "👍", MockUser::new().id(2).build());
+    /// bot.dispatch().await;
+    /// assert_eq!(bot.get_responses().sent_messages.len(), 2);
+    /// # }
+    /// ```
+    ///
+    /// [`update`]: crate::MockBot::update
+    pub fn react_to_message(&mut self, message_id: i32, emoji: &str, user: User) {
+        let mut state = self.state.lock().unwrap();
+        let Some(message) = state.messages.get_message(message_id) else {
+            panic!("There is no message with id {message_id} to react to!");
+        };
+        let new_reaction = vec![ReactionType::Emoji {
+            emoji: emoji.to_string(),
+        }];
+        let old_reaction = state
+            .message_reactions
+            .entry(message.id)
+            .or_default()
+            .insert(user.id, new_reaction.clone())
+            .unwrap_or_default();
+        let update = Update {
+            id: UpdateId(0),
+            kind: UpdateKind::MessageReaction(MessageReactionUpdated {
+                chat: message.chat,
+                message_id: message.id,
+                actor: MaybeAnonymousUser::User(user),
+                date: state.now(),
+                old_reaction,
+                new_reaction,
+            }),
+        };
+        drop(state);
+
+        if !self.reactions_queued {
+            self.updates.clear();
+            self.reactions_queued = true;
+        }
+        self.updates
+            .extend(update.into_update(&self.current_update_id));
+    }
+
+    /// Adds messages to the fake server without dispatching them, so that handlers can reply to,
+    /// edit, forward or delete messages that were sent before the test. Returns the stored
+    /// messages, the ones without an explicit id get the next free one.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, types::MessageId};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         bot.edit_message_text(message.chat.id, MessageId(100), "Edited")
+    ///             .await?;
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/edit_old"), handler_tree);
+    /// bot.seed_messages(vec![
+    ///     MockMessageText::new().text("Old message").id(100),
+    ///     MockMessageText::new().text("Another one").id(101),
+    /// ]);
+    /// bot.dispatch().await;
+    /// let edited = &bot.get_responses().edited_messages_text[0].message;
+    /// assert_eq!((edited.id.0, edited.text()), (100, Some("Edited")));
+    /// # }
+    /// ```
+    pub fn seed_messages<T: IntoUpdate>(&mut self, messages: T) -> Vec<Message> {
+        let mut updates = messages.into_update(&self.current_update_id);
+        self.insert_updates(&mut updates);
+        updates
+            .into_iter()
+            .filter_map(|update| match update.kind {
+                UpdateKind::Message(message) | UpdateKind::ChannelPost(message) => Some(message),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Adds `user` to the chat with the `chat_id`, replacing their old membership. The members
+    /// are returned by `getChatMember`, and the owner and administrators by
+    /// `getChatAdministrators`.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, types::{ChatMemberKind, Owner}};
+    /// # use teloxide_tests::{MockBot, MockGroupChat, MockMessageText, MockUser};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         let admins = bot.get_chat_administrators(message.chat.id).await?;
+    ///         let owner = admins.iter().find(|admin| admin.is_owner()).unwrap();
+    ///         bot.send_message(message.chat.id, format!("Owner: {}", owner.user.id))
+    ///             .await?;
+    ///         Ok(())
+    ///     });
+    /// let message = MockMessageText::new().text("/admins").chat(MockGroupChat::new().build());
+    /// let mut bot = MockBot::new(message, handler_tree);
+    /// bot.add_chat_member(
+    ///     MockGroupChat::ID,
+    ///     MockUser::new().id(1234),
+    ///     ChatMemberKind::Owner(Owner {
+    ///         custom_title: None,
+    ///         is_anonymous: false,
+    ///     }),
+    /// );
+    /// bot.dispatch_and_check_last_text("Owner: 1234").await;
+    /// # }
+    /// ```
+    pub fn add_chat_member(&mut self, chat_id: i64, user: MockUser, kind: ChatMemberKind) {
+        let member = ChatMember {
+            user: user.build(),
+            kind,
+        };
+        self.state
+            .lock()
+            .unwrap()
+            .add_chat_member(ChatId(chat_id), member);
+    }
+
+    /// Adds a file to the fake server, as if it was uploaded before the test. Handlers can get it
+    /// with `get_file` and download `contents` from its `path`, or send it again with
+    /// `InputFile::file_id(file_id)`. Replaces the file that already has this id.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, net::Download, prelude::*, types::FileId};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         let file = bot.get_file(FileId("report_file_id".into())).await?;
+    ///         let mut contents = vec![];
+    ///         bot.download_file(&file.path, &mut contents).await?;
+    ///         bot.send_message(message.chat.id, String::from_utf8(contents)?)
+    ///             .await?;
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/send_report"), handler_tree);
+    /// bot.seed_file("report_file_id", b"Report contents".to_vec(), "documents/report.txt");
+    /// bot.dispatch_and_check_last_text("Report contents").await;
+    /// # }
+    /// ```
+    pub fn seed_file(&mut self, file_id: &str, contents: Vec<u8>, path: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .seed_file(file_id, contents, path);
+    }
+
+    /// Sets the `file_path` that `getFile` returns for the file with the `file_id`, and the file is
+    /// downloaded from. Works for the files of the updates too, that the fake server doesn't know
+    /// before the dispatch.
+    pub fn set_file_path(&mut self, file_id: &str, path: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .file_paths
+            .insert(FileId(file_id.to_string()), path.to_string());
+    }
+
+    /// Makes `getFile` fail with the `error` for the file with the `file_id`, so that the error
+    /// handling of downloads can be tested. Usually it is `ApiError::WrongFileId`, or
+    /// `ApiError::Unknown` with `"Bad Request: file is too big"`, that Telegram returns for files
+    /// larger than 20 MB.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, ApiError};
+    /// # use teloxide_tests::{MockBot, MockMessageDocument};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         let document = message.document().unwrap();
+    ///         if bot.get_file(document.file.id.clone()).await.is_err() {
+    ///             bot.send_message(message.chat.id, "The file is too big!")
+    ///                 .await?;
+    ///         }
+    ///         Ok(())
+    ///     });
+    /// let message = MockMessageDocument::new().file_id("huge_file_id".into());
+    /// let mut bot = MockBot::new(message, handler_tree);
+    /// let too_big = ApiError::Unknown("Bad Request: file is too big".to_string());
+    /// bot.set_get_file_error("huge_file_id", too_big);
+    /// bot.dispatch_and_check_last_text("The file is too big!").await;
+    /// # }
+    /// ```
+    pub fn set_get_file_error(&mut self, file_id: &str, error: ApiError) {
+        self.state
+            .lock()
+            .unwrap()
+            .get_file_errors
+            .insert(FileId(file_id.to_string()), error);
+    }
+
+    /// Pins `message` in its chat, as if it was pinned before the test. `getChat` returns the
+    /// most recent pinned message, and `unpinChatMessage` unpins it like in Telegram.
+    pub fn set_pinned_message(&mut self, message: &Message) {
+        let mut state = self.state.lock().unwrap();
+        let pinned_messages = &mut state
+            .chat_metadata
+            .entry(message.chat.id)
+            .or_default()
+            .pinned_messages;
+        pinned_messages.retain(|pinned| pinned.id != message.id);
+        pinned_messages.push(message.clone());
+    }
+
+    /// Turns the lenient mode for replies on or off. By default, replying to a message that the
+    /// fake server doesn't know fails, unless `allow_sending_without_reply` is set. In the lenient
+    /// mode, a placeholder text message with that id is created in the chat instead, so the reply
+    /// goes through. Useful when porting tests that reply to made up message ids.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide::types::{MessageId, ReplyParameters};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         bot.send_message(message.chat.id, "Still here")
+    ///             .reply_parameters(ReplyParameters::new(MessageId(42)))
+    ///             .await?;
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/reply_to_old"), handler_tree);
+    /// bot.set_lenient_replies(true);
+    /// bot.dispatch().await;
+    /// let reply = &bot.get_responses().sent_messages[0];
+    /// assert_eq!(reply.reply_to_message().unwrap().id, MessageId(42));
+    /// # }
+    /// ```
+    pub fn set_lenient_replies(&mut self, lenient: bool) {
+        self.state.lock().unwrap().config.lenient_replies = lenient;
+    }
+
+    /// Returns the menu button of the private chat with the `chat_id`, like `getChatMenuButton`
+    /// does. Chats without a menu button of their own and `None` give the default menu button.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, types::{MenuButton, WebAppInfo}};
+    /// # use teloxide_tests::{MockBot, MockMessageText, MockUser};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         let web_app = WebAppInfo {
+    ///             url: "https://example.com".parse()?,
+    ///         };
+    ///         bot.set_chat_menu_button()
+    ///             .chat_id(message.chat.id)
+    ///             .menu_button(MenuButton::WebApp {
+    ///                 text: "Shop".to_string(),
+    ///                 web_app,
+    ///             })
+    ///             .await?;
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/setup"), handler_tree);
+    /// bot.dispatch().await;
+    /// assert_eq!(bot.menu_button(None), MenuButton::Commands);
+    /// assert!(matches!(
+    ///     bot.menu_button(Some(MockUser::ID as i64)),
+    ///     MenuButton::WebApp { .. }
+    /// ));
+    /// # }
+    /// ```
+    pub fn menu_button(&self, chat_id: Option<i64>) -> MenuButton {
+        self.state.lock().unwrap().menu_button(chat_id.map(ChatId))
+    }
+
+    /// Returns the commands of the bot for the scope and the language code, like `getMyCommands`
+    /// does. `None` is the default scope, and commands that were never set are an empty list.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, types::BotCommand};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|bot: Bot| async move {
+    ///         bot.set_my_commands(vec![BotCommand::new("start", "Bot starten")])
+    ///             .language_code("de")
+    ///             .await?;
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/setup"), handler_tree);
+    /// bot.dispatch().await;
+    /// assert_eq!(bot.commands(None, Some("de"))[0].command, "start");
+    /// assert!(bot.commands(None, None).is_empty());
+    /// # }
+    /// ```
+    pub fn commands(
+        &self,
+        scope: Option<BotCommandScope>,
+        language_code: Option<&str>,
+    ) -> Vec<BotCommand> {
+        self.state
+            .lock()
+            .unwrap()
+            .my_commands(scope, language_code.map(str::to_string))
+    }
+
+    /// Checks the commands of the bot in every scope and language, after all the
+    /// `setMyCommands` and `deleteMyCommands` calls so far. The expected commands are
+    /// `(command, description)` pairs, and the scopes and languages that aren't listed must have
+    /// no commands.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide::types::{BotCommand, BotCommandScope};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|bot: Bot| async move {
+    ///         bot.set_my_commands(vec![BotCommand::new("start", "Start the bot")])
+    ///             .await?;
+    ///         let group_commands = vec![
+    ///             BotCommand::new("start", "Bot starten"),
+    ///             BotCommand::new("stats", "Statistik"),
+    ///         ];
+    ///         bot.set_my_commands(group_commands)
+    ///             .scope(BotCommandScope::AllGroupChats)
+    ///             .language_code("de")
+    ///             .await?;
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/setup"), handler_tree);
+    /// bot.dispatch().await;
+    /// bot.assert_commands(vec![
+    ///     (BotCommandScope::Default, None, vec![("start", "Start the bot")]),
+    ///     (
+    ///         BotCommandScope::AllGroupChats,
+    ///         Some("de"),
+    ///         vec![("start", "Bot starten"), ("stats", "Statistik")],
+    ///     ),
+    /// ]);
+    /// # }
+    /// ```
+    pub fn assert_commands(&self, expected: Vec<CommandMenu>) {
+        let expected: HashMap<(BotCommandScope, Option<String>), Vec<BotCommand>> = expected
+            .into_iter()
+            .filter(|(_, _, commands)| !commands.is_empty())
+            .map(|(scope, language_code, commands)| {
+                let commands = commands
+                    .into_iter()
+                    .map(|(command, description)| BotCommand::new(command, description))
+                    .collect();
+                ((scope, language_code.map(str::to_string)), commands)
+            })
+            .collect();
+        let actual = self.state.lock().unwrap().my_commands.clone();
+
+        let mut differences: Vec<String> = actual
+            .keys()
+            .chain(expected.keys())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .filter(|key| actual.get(*key) != expected.get(*key))
+            .map(|(scope, language_code)| {
+                let key = (scope.clone(), language_code.clone());
+                let texts = |commands: Option<&Vec<BotCommand>>| -> Vec<(String, String)> {
+                    commands
+                        .into_iter()
+                        .flatten()
+                        .map(|command| (command.command.clone(), command.description.clone()))
+                        .collect()
+                };
+                format!(
+                    "{scope:?} with {language_code:?} language:\n   actual: {:?}\n expected: {:?}",
+                    texts(actual.get(&key)),
+                    texts(expected.get(&key)),
+                )
+            })
+            .collect();
+        if !differences.is_empty() {
+            differences.sort();
+            panic!(
+                "The commands of the bot are not equal!\n{}",
+                differences.join("\n")
+            );
+        }
+    }
+
+    /// Adds the sticker of a custom emoji, that `getCustomEmojiStickers` returns. The custom
+    /// emojis of the messages are added automatically, with a default sticker.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, types::CustomEmojiId};
+    /// # use teloxide_tests::{MockBot, MockMessageSticker, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         let emoji_id = CustomEmojiId("5368324170671202286".to_string());
+    ///         let stickers = bot.get_custom_emoji_stickers(vec![emoji_id]).await?;
+    ///         bot.send_message(message.chat.id, stickers[0].emoji.clone().unwrap())
+    ///             .await?;
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/emoji"), handler_tree);
+    /// let message = MockMessageSticker::new()
+    ///     .custom_emoji_id("5368324170671202286")
+    ///     .emoji("👍")
+    ///     .build();
+    /// bot.add_custom_emoji_sticker(message.sticker().unwrap().clone());
+    /// bot.dispatch_and_check_last_text("👍").await;
+    /// # }
+    /// ```
+    pub fn add_custom_emoji_sticker(&mut self, sticker: Sticker) {
+        let Some(custom_emoji_id) = sticker.custom_emoji_id().cloned() else {
+            panic!("The sticker is not a custom emoji! Use MockMessageSticker::custom_emoji_id");
+        };
+        self.state
+            .lock()
+            .unwrap()
+            .custom_emoji_stickers
+            .insert(custom_emoji_id, sticker);
+    }
+
+    /// Sets the full info of a chat that `getChat` returns, built with one of the
+    /// `MockChatFullInfo*` builders. Without it, the info is made from the chat of the messages.
+    /// Its pinned message replaces the pinned messages of the chat, and the description, the
+    /// permissions and the other extras set with the other methods are still applied on top of it.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockChatFullInfoPrivate, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         let chat = bot.get_chat(message.chat.id).await?;
+    ///         bot.send_message(message.chat.id, chat.bio().unwrap()).await?;
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/profile"), handler_tree);
+    /// bot.set_chat_full_info(MockChatFullInfoPrivate::new().bio("I like trains").build());
+    /// bot.dispatch_and_check_last_text("I like trains").await;
+    /// # }
+    /// ```
+    pub fn set_chat_full_info(&mut self, full_info: ChatFullInfo) {
+        let mut lock = self.state.lock().unwrap();
+        let metadata = lock.chat_metadata.entry(full_info.id).or_default();
+        // The pinned message of the info replaces the pins, so that unpinning works as usual
+        metadata.pinned_messages = full_info
+            .pinned_message
+            .clone()
+            .into_iter()
+            .map(|m| *m)
+            .collect();
+        metadata.full_info = Some(full_info);
+    }
+
+    /// Sets the description of the chat with the `chat_id`, that `getChat` returns
+    pub fn set_chat_description(&mut self, chat_id: i64, description: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .chat_metadata
+            .entry(ChatId(chat_id))
+            .or_default()
+            .description = Some(description.to_string());
+    }
+
+    /// Sets the default permissions of the group with the `chat_id`, that `getChat` returns
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, types::ChatPermissions};
+    /// # use teloxide_tests::{MockBot, MockGroupChat, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         let chat = bot.get_chat(message.chat.id).await?;
+    ///         let can_send_polls = chat.permissions().unwrap().can_send_polls();
+    ///         let rules = format!("{} Polls: {can_send_polls}", chat.description().unwrap());
+    ///         bot.send_message(message.chat.id, rules).await?;
+    ///         Ok(())
+    ///     });
+    /// let chat = MockGroupChat::new().build();
+    /// let mut bot = MockBot::new(MockMessageText::new().chat(chat.clone()), handler_tree);
+    /// bot.set_chat_description(chat.id.0, "The rules are simple.");
+    /// bot.set_chat_permissions(chat.id.0, ChatPermissions::SEND_MESSAGES);
+    /// bot.dispatch_and_check_last_text("The rules are simple. Polls: false").await;
+    /// # }
+    /// ```
+    pub fn set_chat_permissions(&mut self, chat_id: i64, permissions: ChatPermissions) {
+        self.state
+            .lock()
+            .unwrap()
+            .chat_metadata
+            .entry(ChatId(chat_id))
+            .or_default()
+            .permissions = Some(permissions);
+    }
+
+    /// Sets the emoji status of the chat with the `chat_id`, that `getChat` returns. The status
+    /// expires at `expiration_date`, or never if it is `None`.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         let chat = bot.get_chat(message.chat.id).await?;
+    ///         let status = chat.emoji_status_custom_emoji_id.unwrap();
+    ///         bot.send_message(message.chat.id, status.0).await?;
+    ///         Ok(())
+    ///     });
+    /// let message = MockMessageText::new().text("/greet");
+    /// let chat_id = message.clone().build().chat.id.0;
+    /// let mut bot = MockBot::new(message, handler_tree);
+    /// bot.set_chat_emoji_status(chat_id, "5368324170671202286", None);
+    /// bot.dispatch_and_check_last_text("5368324170671202286").await;
+    /// # }
+    /// ```
+    pub fn set_chat_emoji_status(
+        &mut self,
+        chat_id: i64,
+        custom_emoji_id: &str,
+        expiration_date: Option<DateTime<Utc>>,
+    ) {
+        self.state
+            .lock()
+            .unwrap()
+            .chat_metadata
+            .entry(ChatId(chat_id))
+            .or_default()
+            .emoji_status = Some((CustomEmojiId(custom_emoji_id.to_string()), expiration_date));
+    }
+
+    /// Sets the profile accent color of the chat with the `chat_id`, that `getChat` returns
+    pub fn set_chat_profile_accent_color(&mut self, chat_id: i64, profile_accent_color_id: u8) {
+        self.state
+            .lock()
+            .unwrap()
+            .chat_metadata
+            .entry(ChatId(chat_id))
+            .or_default()
+            .profile_accent_color_id = Some(profile_accent_color_id);
+    }
+
+    /// Sets whether the user of the private chat with the `chat_id` doesn't allow voice and video
+    /// note messages, that `getChat` returns. Has no effect on other chats.
+    pub fn set_restricted_voice_and_video_messages(&mut self, chat_id: i64, restricted: bool) {
+        self.state
+            .lock()
+            .unwrap()
+            .chat_metadata
+            .entry(ChatId(chat_id))
+            .or_default()
+            .has_restricted_voice_and_video_messages = Some(restricted);
+    }
+
+    /// Sets how long to wait between the updates of a dispatch, so that debouncing or handlers
+    /// that aggregate updates in a time window can be tested. The updates are sent all at once by
+    /// default.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::{Duration, Instant};
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         bot.send_message(message.chat.id, message.text().unwrap()).await?;
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(
+    ///     vec![
+    ///         MockMessageText::new().text("first"),
+    ///         MockMessageText::new().text("second"),
+    ///     ],
+    ///     handler_tree,
+    /// );
+    /// bot.set_update_interval(Duration::from_millis(500));
+    /// let started = Instant::now();
+    /// bot.dispatch().await;
+    /// assert!(started.elapsed() >= Duration::from_millis(500));
+    /// # }
+    /// ```
+    pub fn set_update_interval(&mut self, interval: Duration) {
+        self.update_interval = interval;
+    }
+
+    /// Sets how long to wait before every update of a dispatch, the first delay is for the
+    /// first update. Updates that have no delay here wait for the [`set_update_interval`]
+    /// interval.
+    ///
+    /// [`set_update_interval`]: crate::MockBot::set_update_interval
+    pub fn set_update_delays(&mut self, delays: Vec<Duration>) {
+        self.update_delays = delays;
+    }
+
+    /// Sets how the updates of the following dispatches are delivered, to test the
+    /// deduplication and idempotency that bots behind a webhook need. Messages in the updates
+    /// are still stored once. The updates of auto responses are always delivered in order.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::{collections::HashSet, sync::{Arc, Mutex}};
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMessageText, UpdateDelivery};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let seen = Arc::new(Mutex::new(HashSet::new()));
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(move |update: Update, message: Message, bot: Bot| {
+    ///         let first_time = seen.lock().unwrap().insert(update.id);
+    ///         async move {
+    ///             if first_time {
+    ///                 bot.send_message(message.chat.id, "Order placed").await?;
+    ///             }
+    ///             Ok(())
+    ///         }
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/buy"), handler_tree);
+    /// bot.set_update_delivery(UpdateDelivery::Duplicated);
+    /// bot.dispatch().await;
+    /// // The order should be placed once, even though the update came twice
+    /// assert_eq!(bot.get_responses().sent_messages.len(), 1);
+    /// # }
+    /// ```
+    pub fn set_update_delivery(&mut self, delivery: UpdateDelivery) {
+        self.update_delivery = delivery;
+    }
+
+    /// Makes the dispatches leave `TELOXIDE_TOKEN` and `TELOXIDE_API_URL` alone, because the env
+    /// is global to the process, and other code reading it in parallel races with the dispatch.
+    /// The handlers still get the bot connected to the fake server from the dispatcher, so they
+    /// should take `bot: Bot` (or its `api_url()`) instead of calling `Bot::from_env()`.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    ///         let from_env = std::env::var("TELOXIDE_API_URL").is_ok();
+    ///         bot.send_message(message.chat.id, format!("From env: {from_env}"))
+    ///             .await?;
+    ///         Ok(())
+    ///     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+    /// bot.isolate_env();
+    /// // The handlers use the `Bot` they get, `Bot::from_env()` isn't connected to the fake server
+    /// bot.dispatch_and_check_last_text("From env: false").await;
+    /// # }
+    /// ```
+    pub fn isolate_env(&mut self) {
+        self.isolate_env = true;
+    }
+
+    /// Simulates the user picking `users` with the `request_users` button of the reply keyboard
+    /// `message` was sent with. The `users_shared` message with the `request_id` of that button
+    /// becomes the update for the next dispatch.
+    ///
+    /// # Panics
+    /// If `message` wasn't sent with a reply keyboard that has a `request_users` button
+    pub fn share_users(&mut self, message: &Message, users: Vec<SharedUser>) {
+        let request_id = self
+            .find_button_requests(message)
+            .into_iter()
+            .find_map(|request| match request {
+                ButtonRequest::RequestUsers(request_users) => Some(request_users.request_id),
+                _ => None,
+            })
+            .expect("The message doesn't have a request_users keyboard button!");
+
+        self.update(
+            MockMessageUsersShared::new()
+                .chat(message.chat.clone())
+                .request_id(request_id)
+                .users(users),
+        );
+    }
+
+    /// Simulates the user picking the chat with `chat_id` with the `request_chat` button of the
+    /// reply keyboard `message` was sent with. The `chat_shared` message with the `request_id` of
+    /// that button becomes the update for the next dispatch.
+    ///
+    /// # Panics
+    /// If `message` wasn't sent with a reply keyboard that has a `request_chat` button
+    pub fn share_chat(&mut self, message: &Message, chat_id: i64) {
+        let request_id = self
+            .find_button_requests(message)
+            .into_iter()
+            .find_map(|request| match request {
+                ButtonRequest::RequestChat(request_chat) => Some(request_chat.request_id),
+                _ => None,
+            })
+            .expect("The message doesn't have a request_chat keyboard button!");
+
+        self.update(
+            MockMessageChatShared::new()
+                .chat(message.chat.clone())
+                .request_id(request_id)
+                .chat_id(chat_id),
+        );
+    }
+
+    /// Simulates the user tapping the inline keyboard button of `message` that has `button` as
+    /// its text or callback data. The callback query, with the message as it is now stored in the
+    /// fake server, becomes the update for the next dispatch.
+    ///
+    /// # Panics
+    /// If the message doesn't exist, or it has no callback button with that text or data
+    pub fn click_inline_button(&mut self, message: &Message, button: &str) {
+        let message = self
+            .state
+            .lock()
+            .unwrap()
+            .messages
+            .get_message(message.id.0)
+            .expect("The message doesn't exist!");
+        let data = message
+            .reply_markup()
+            .into_iter()
+            .flat_map(|markup| markup.inline_keyboard.iter().flatten())
+            .find_map(|inline_button| match &inline_button.kind {
+                InlineKeyboardButtonKind::CallbackData(data)
+                    if inline_button.text == button || data == button =>
+                {
+                    Some(data.clone())
+                }
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("The message has no \"{button}\" callback button!"));
+
+        self.update(MockCallbackQuery::new().message(message).data(data));
+    }
+
+    /// Returns every inline keyboard `message` had, from the one it was sent with to the one of
+    /// its last edit, across all of the dispatches
+    pub fn keyboard_history(&self, message: &Message) -> Vec<InlineKeyboardMarkup> {
+        self.state
+            .lock()
+            .unwrap()
+            .messages
+            .keyboard_history(message.id.0)
+    }
+
+    /// Walks the pages of a paginated `message` by clicking its `button`, like "Next ▶️", and
+    /// dispatching the callback query, until the button is gone or clicking it doesn't change the
+    /// message anymore. Returns the message as it was on every page, starting with the current
+    /// one, so the content of each page can be checked.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # fn page(number: u32) -> (String, InlineKeyboardMarkup) {
+    /// #     let first = (number - 1) * 10 + 1;
+    /// #     let text = format!("Items {first}-{}", (first + 9).min(25));
+    /// #     let next = (number * 10 < 25)
+    /// #         .then(|| InlineKeyboardButton::callback("Next", (number + 1).to_string()));
+    /// #     (text, InlineKeyboardMarkup::new([next]))
+    /// # }
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let send_first = |message: Message, bot: Bot| async move {
+    /// #     let (text, keyboard) = page(1);
+    /// #     bot.send_message(message.chat.id, text).reply_markup(keyboard).await?;
+    /// #     Ok(())
+    /// # };
+    /// # let turn_page = |query: CallbackQuery, bot: Bot| async move {
+    /// #     let (text, keyboard) = page(query.data.unwrap().parse()?);
+    /// #     let message = query.message.unwrap();
+    /// #     bot.edit_message_text(message.chat().id, message.id(), text)
+    /// #         .reply_markup(keyboard)
+    /// #         .await?;
+    /// #     Ok(())
+    /// # };
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     dptree::entry()
+    /// #         .branch(Update::filter_message().endpoint(send_first))
+    /// #         .branch(Update::filter_callback_query().endpoint(turn_page));
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/list"), handler_tree);
+    /// bot.dispatch().await;
+    /// let message = bot.get_responses().sent_messages.pop().unwrap();
+    ///
+    /// let pages = bot.walk_pages(&message, "Next").await;
+    /// let texts: Vec<_> = pages.iter().map(|page| page.text().unwrap()).collect();
+    /// assert_eq!(texts, vec!["Items 1-10", "Items 11-20", "Items 21-25"]);
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    /// If the message doesn't exist, or it has more than 100 pages, which most likely means the
+    /// pages go in circles
+    pub async fn walk_pages(&mut self, message: &Message, button: &str) -> Vec<Message> {
+        let get_page = |bot: &Self| {
+            bot.state
+                .lock()
+                .unwrap()
+                .messages
+                .get_message(message.id.0)
+                .expect("The message doesn't exist!")
+        };
+        let has_button = |page: &Message| {
+            page.reply_markup()
+                .into_iter()
+                .flat_map(|markup| markup.inline_keyboard.iter().flatten())
+                .any(|inline_button| match &inline_button.kind {
+                    InlineKeyboardButtonKind::CallbackData(data) => {
+                        inline_button.text == button || data == button
+                    }
+                    _ => false,
+                })
+        };
+
+        let mut pages = vec![get_page(self)];
+        while has_button(pages.last().unwrap()) {
+            if pages.len() > MAX_PAGES {
+                panic!("The message has more than {MAX_PAGES} pages, they probably go in circles!");
+            }
+            let current = pages.last().unwrap().clone();
+            self.click_inline_button(&current, button);
+            self.dispatch().await;
+
+            let page = get_page(self);
+            if page.text() == current.text()
+                && page.caption() == current.caption()
+                && page.reply_markup() == current.reply_markup()
+            {
+                break;
+            }
+            pages.push(page);
+        }
+        pages
+    }
+
+    /// Simulates the user tapping the `switch_inline_query`, `switch_inline_query_current_chat`
+    /// or `switch_inline_query_chosen_chat` button of `message` that has `button` as its text. The
+    /// inline query that the user would then send becomes the update for the next dispatch.
+    ///
+    /// The query is sent from the chat of the message for `switch_inline_query_current_chat`
+    /// buttons. For the other buttons the user has to pick a chat, so the first allowed chat type
+    /// is used, which is a private chat for plain `switch_inline_query` buttons.
+    ///
+    /// # Panics
+    /// If the message doesn't exist, or it has no switch inline query button with that text
+    pub fn switch_inline_query(&mut self, message: &Message, button: &str) {
+        let message = self
+            .state
+            .lock()
+            .unwrap()
+            .messages
+            .get_message(message.id.0)
+            .expect("The message doesn't exist!");
+        let (query, chat_type) = message
+            .reply_markup()
+            .into_iter()
+            .flat_map(|markup| markup.inline_keyboard.iter().flatten())
+            .filter(|inline_button| inline_button.text == button)
+            .find_map(|inline_button| match &inline_button.kind {
+                InlineKeyboardButtonKind::SwitchInlineQuery(query) => {
+                    Some((query.clone(), ChatType::Private))
+                }
+                InlineKeyboardButtonKind::SwitchInlineQueryCurrentChat(query) => {
+                    let chat_type = match &message.chat {
+                        chat if chat.is_private() => ChatType::Sender,
+                        chat if chat.is_group() => ChatType::Group,
+                        chat if chat.is_supergroup() => ChatType::Supergroup,
+                        _ => ChatType::Channel,
+                    };
+                    Some((query.clone(), chat_type))
+                }
+                InlineKeyboardButtonKind::SwitchInlineQueryChosenChat(chosen_chat) => {
+                    let chat_type = if chosen_chat.allow_user_chats || chosen_chat.allow_bot_chats {
+                        ChatType::Private
+                    } else if chosen_chat.allow_group_chats {
+                        ChatType::Group
+                    } else {
+                        ChatType::Channel
+                    };
+                    Some((chosen_chat.query.clone().unwrap_or_default(), chat_type))
+                }
+                _ => None,
+            })
+            .unwrap_or_else(|| {
+                panic!("The message has no \"{button}\" switch inline query button!")
+            });
+
+        self.update(MockInlineQuery::new().query(query).chat_type(chat_type));
+    }
+
+    /// Simulates the user pressing the `button` of the reply keyboard of the last message that
+    /// was sent with one. Just like in a real chat, the default user sends a text message with the
+    /// button text, which becomes the update for the next dispatch.
+    ///
+    /// # Panics
+    /// If no message was sent with a reply keyboard, or the keyboard has no such button
+    pub fn press_reply_button(&mut self, button: &str) {
+        let state = self.state.lock().unwrap();
+        let (message_id, keyboard) = state
+            .reply_markups
+            .iter()
+            .filter_map(|(message_id, markup)| match markup {
+                ReplyMarkup::Keyboard(keyboard) => Some((*message_id, keyboard)),
+                _ => None,
+            })
+            .max_by_key(|(message_id, _)| message_id.0)
+            .expect("No messages with a reply keyboard were sent!");
+        if !keyboard
+            .keyboard
+            .iter()
+            .flatten()
+            .any(|keyboard_button| keyboard_button.text == button)
+        {
+            panic!("The reply keyboard has no \"{button}\" button!");
+        }
+        let chat = state
+            .messages
+            .get_message(message_id.0)
+            .expect("The message with the reply keyboard doesn't exist!")
+            .chat;
+        drop(state);
+
+        self.update(MockMessageText::new().chat(chat).text(button));
+    }
+
+    fn find_button_requests(&self, message: &Message) -> Vec<ButtonRequest> {
+        match self.state.lock().unwrap().reply_markups.get(&message.id) {
+            Some(ReplyMarkup::Keyboard(keyboard)) => keyboard
+                .keyboard
+                .iter()
+                .flatten()
+                .filter_map(|button| button.request.clone())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Makes the user reply with `reply` every time the bot sends a message with `trigger` in its
+    /// text or caption. `dispatch` keeps sending these replies until the bot sends nothing that
+    /// triggers a reply, so whole conversations can be tested with one dispatch.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::sync::{Arc, Mutex};
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let answers = Arc::new(Mutex::new(vec![]));
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(move |message: Message, bot: Bot| {
+    /// #         let text = message.text().unwrap().to_string();
+    /// #         let reply = if text == "/start" {
+    /// #             "What is your name?".to_string()
+    /// #         } else {
+    /// #             let mut answers = answers.lock().unwrap();
+    /// #             answers.push(text);
+    /// #             match answers.as_slice() {
+    /// #                 [_] => "What is your age?".to_string(),
+    /// #                 [name, age, ..] => format!("Nice to meet you, {name} ({age})!"),
+    /// #                 [] => unreachable!(),
+    /// #             }
+    /// #         };
+    /// #         async move {
+    /// #             bot.send_message(message.chat.id, reply).await?;
+    /// #             Ok(())
+    /// #         }
+    /// #     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+    /// bot.auto_respond("your name?", "Alice");
+    /// bot.auto_respond("your age?", "25");
+    /// bot.dispatch_and_check_last_text("Nice to meet you, Alice (25)!").await;
+    /// # }
+    /// ```
+    pub fn auto_respond(&mut self, trigger: &str, reply: &str) {
+        self.auto_responses
+            .push((trigger.to_string(), reply.to_string()));
+    }
+
+    /// Expects the bot to send a request with the Bot API `method`, like `sendMessage`. By
+    /// default the request is expected at least once, which can be changed with
+    /// [`Expectation::times`], and filtered by the body with [`Expectation::with`].
+    ///
+    /// The expectations are checked by [`verify_expectations`], or when the bot is dropped, with
+    /// a report of the unmet expectations and the requests that didn't match them.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{json_contains, MockBot, MockMessageText, MockUser};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    /// #         bot.send_message(message.chat.id, "Welcome!").await?;
+    /// #         bot.send_message(message.chat.id, "Pick an option").await?;
+    /// #         Ok(())
+    /// #     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+    /// bot.expect("sendMessage")
+    ///     .times(2)
+    ///     .with(json_contains!({"chat_id": MockUser::ID}));
+    /// bot.dispatch().await;
+    /// bot.verify_expectations();
+    /// # }
+    /// ```
+    ///
+    /// [`Expectation::times`]: crate::expectations::Expectation::times
+    /// [`Expectation::with`]: crate::expectations::Expectation::with
+    /// [`verify_expectations`]: crate::MockBot::verify_expectations
+    pub fn expect(&mut self, method: &str) -> &mut Expectation {
+        self.expectations.add(method)
+    }
+
+    /// Checks the expectations made with [`expect`], panicking with a report if any of them
+    /// weren't met. Checked expectations are forgotten.
+    ///
+    /// [`expect`]: crate::MockBot::expect
+    pub fn verify_expectations(&mut self) {
+        self.expectations.verify();
+    }
+
+    /// Makes every request sent from now on require an assertion. Requests that weren't checked
+    /// by [`expect`], [`assert_request`] or the `dispatch_and_check_last_text*` functions (they
+    /// check the request that sent the last message) are reported as unexpected by
+    /// [`verify_expectations`], or when the bot is dropped. Catches extra messages that a change
+    /// to the bot introduced by accident. Queries like `getMe` or `getChat` don't need to be
+    /// asserted.
+    ///
+    /// [`expect`]: crate::MockBot::expect
+    /// [`assert_request`]: crate::MockBot::assert_request
+    /// [`verify_expectations`]: crate::MockBot::verify_expectations
+    pub fn strict(&mut self) {
+        self.expectations.strict();
+    }
+
+    /// Makes the fake server reject what Telegram rejects: message effects that aren't in
+    /// [`MESSAGE_EFFECTS`], texts with a `parse_mode` that Telegram can't parse, callback answers
+    /// with a `url` that isn't a `t.me` link to the bot unless the query came from a game,
+    /// uploads over 50 MB, `getFile` of files over 20 MB, photos over 10 MB or with dimensions
+    /// from [`probe_media`] that Telegram doesn't accept, copies of quizzes whose correct answer
+    /// the bot doesn't know, and reactions that aren't in the available reactions of the chat info
+    /// from [`set_chat_full_info`]. These checks are off by default, because a fake server that is
+    /// stricter than needed fails tests that would pass in Telegram, e.g. when Telegram adds new
+    /// effects. The setting stays after [`reset`].
+    ///
+    /// [`MESSAGE_EFFECTS`]: crate::server::effects::MESSAGE_EFFECTS
+    /// [`probe_media`]: crate::MockBot::probe_media
+    /// [`set_chat_full_info`]: crate::MockBot::set_chat_full_info
+    /// [`reset`]: crate::MockBot::reset
+    pub fn validate_like_telegram(&mut self) {
+        self.state.lock().unwrap().config.strict_validation = true;
+    }
+
+    /// Checks that the bot sent a request with the Bot API `method` and a body matching
+    /// `matcher`, that wasn't checked by this function before. Use `json_contains!({})` to match
+    /// any body.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, types::InputFile};
+    /// # use teloxide_tests::{json_contains, MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    /// #         bot.send_message(message.chat.id, "Hello!").await?;
+    /// #         bot.send_sticker(message.chat.id, InputFile::file_id("sticker".into()))
+    /// #             .await?;
+    /// #         Ok(())
+    /// #     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+    /// bot.strict();
+    /// bot.dispatch().await;
+    /// bot.assert_request("sendMessage", json_contains!({"text": "Hello!"}));
+    /// bot.assert_request("sendSticker", json_contains!({}));
+    /// # }
+    /// ```
+    pub fn assert_request(&self, method: &str, matcher: JsonContains) {
+        if !self.expectations.assert_request(method, &matcher) {
+            panic!(
+                "No {method} request with {} was sent! The requests are:\n{}",
+                matcher.0,
+                self.expectations.describe_requests()
+            );
+        }
+    }
+
+    /// Makes the handlers get the bot wrapped into adaptors, like in production. `f` gets the
+    /// `Bot` connected to the fake server on every dispatch, and returns the requester that is
+    /// given to the dispatcher, so the handlers should take that type instead of `Bot`.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{adaptors::DefaultParseMode, dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide::types::ParseMode;
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    ///     Update::filter_message().endpoint(
+    ///         |message: Message, bot: DefaultParseMode<Bot>| async move {
+    ///             bot.send_message(message.chat.id, "<b>Hello!</b>").await?;
+    ///             Ok(())
+    ///         },
+    ///     );
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+    /// bot.requester(|bot| bot.parse_mode(ParseMode::Html));
+    /// bot.dispatch().await;
+    /// let request = &bot.get_responses().sent_messages_text[0].bot_request;
+    /// assert_eq!(request.parse_mode, Some(ParseMode::Html));
+    /// # }
+    /// ```
+    pub fn requester<R, F>(&mut self, f: F)
+    where
+        F: Fn(Bot) -> R + Send + Sync + 'static,
+        R: Requester + Clone + Send + Sync + 'static,
+    {
+        self.requester_f = Some(Arc::new(move |bot, dispatch| {
+            let requester = f(bot);
+            Box::pin(dispatch.run(requester))
+        }));
     }
 
     /// Sets the error_handler for Dispather
@@ -229,69 +1909,70 @@ where
 
     /// Just inserts the updates into the state, returning them
     fn insert_updates(&self, updates: &mut [Update]) {
-        for update in updates.iter_mut() {
-            match update.kind.clone() {
-                UpdateKind::Message(mut message) => {
-                    // Add the message to the list of messages, so the bot can interact with it
-                    self.state.lock().unwrap().add_message(&mut message);
-                    update.kind = UpdateKind::Message(message.clone());
-                }
-                UpdateKind::EditedMessage(mut message) => {
-                    self.state.lock().unwrap().edit_message(&mut message);
-                    update.kind = UpdateKind::EditedMessage(message.clone());
-                }
-                UpdateKind::CallbackQuery(mut callback) => {
-                    if let Some(MaybeInaccessibleMessage::Regular(ref mut message)) =
-                        callback.message
-                    {
-                        self.state.lock().unwrap().add_message(message);
-                    }
-                    update.kind = UpdateKind::CallbackQuery(callback.clone());
-                }
-                _ => {}
-            }
-        }
+        self.state.lock().unwrap().insert_updates(updates);
     }
 
-    async fn run_updates(&self, bot: Bot, updates: Vec<Update>) {
+    async fn run_updates(&self, bot: Bot, updates: Vec<Update>, delays: Vec<Duration>) {
         let handler_tree = self.handler_tree.clone();
         let deps = self.dependencies.clone();
         let stack_size = self.stack_size;
         let distribution_f = self.distribution_f.clone();
         let error_handler = self.error_handler.clone();
+        let requester_f = self.requester_f.clone();
+        let state = self.state.clone();
 
-        tokio::task::spawn_blocking(move || {
+        handler_panics::start_recording();
+        let result = tokio::task::spawn_blocking(move || {
             let runtime = tokio::runtime::Builder::new_multi_thread()
                 .thread_stack_size(stack_size) // Not needed, but just in case
                 .enable_all()
                 .build()
                 .unwrap();
+            let dispatch = Dispatch {
+                handler_tree,
+                dependencies: deps,
+                distribution_f,
+                error_handler,
+                listener: InsertingListener {
+                    updates,
+                    delays,
+                    state,
+                },
+            };
             runtime.block_on(async {
-                Dispatcher::builder(bot.clone(), handler_tree.clone())
-                    .dependencies(deps)
-                    .distribution_function(distribution_f)
-                    .error_handler(error_handler)
-                    .build()
-                    .dispatch_with_listener(
-                        InsertingListener { updates },
-                        LoggingErrorHandler::new(),
-                    )
-                    .await;
+                match requester_f {
+                    // Adaptors like `Throttle` need the runtime to be created
+                    Some(requester_f) => requester_f(bot, dispatch).await,
+                    None => dispatch.run(bot).await,
+                }
             });
         })
-        .await
-        .expect("Dispatcher panicked!");
+        .await;
+        if let Err(error) = result {
+            // The panic of a handler says more than the panic of the dispatcher it caused
+            match handler_panics::take() {
+                Some(handler_panic) => panic!("{handler_panic}"),
+                None => panic!("Dispatcher panicked!: {error:?}"),
+            }
+        }
     }
 
     /// Actually dispatches the bot, calling the update through the handler tree.
     /// All the requests made through the bot will be stored in `responses`, and can be retrieved
     /// with `get_responses`. All the responses are unique to that dispatch, and will be erased for
-    /// every new dispatch.
+    /// every new dispatch, unless [`accumulate_responses`] is on. If a handler panics, this panics too, with the message, the location
+    /// and the backtrace of that panic, and the update the handler was handling.
     ///
     /// This method overrides env variables `TELOXIDE_TOKEN` and `TELOXIDE_API_URL`, so anyone can
-    /// call `Bot::from_env()` and get an actual bot that is connected to the fake server
+    /// call `Bot::from_env()` and get an actual bot that is connected to the fake server, unless
+    /// [`isolate_env`] is on
+    ///
+    /// [`isolate_env`]: crate::MockBot::isolate_env
+    /// [`accumulate_responses`]: crate::MockBot::accumulate_responses
     pub async fn dispatch(&mut self) {
         self.state.lock().unwrap().reset();
+        let requests_before = self.state.lock().unwrap().requests.len();
+        self.reactions_queued = false;
 
         let server = ServerManager::start(self.me.clone(), self.state.clone())
             .await
@@ -299,16 +1980,244 @@ where
 
         let mut updates = self.updates.clone();
         self.insert_updates(&mut updates);
+        match self.update_delivery {
+            UpdateDelivery::InOrder => {}
+            UpdateDelivery::Duplicated => {
+                updates = updates
+                    .into_iter()
+                    .flat_map(|update| [update.clone(), update])
+                    .collect();
+            }
+            UpdateDelivery::Reversed => updates.reverse(),
+        }
 
         let api_url = reqwest::Url::parse(&format!("http://127.0.0.1:{}", server.port)).unwrap();
         let bot = self.bot.clone().set_api_url(api_url.clone());
 
-        env::set_var("TELOXIDE_TOKEN", bot.token());
-        env::set_var("TELOXIDE_API_URL", api_url.to_string());
+        if !self.isolate_env {
+            env::set_var("TELOXIDE_TOKEN", bot.token());
+            env::set_var("TELOXIDE_API_URL", api_url.to_string());
+        }
+
+        let mut delays = (0..updates.len())
+            .map(|i| match self.update_delays.get(i) {
+                Some(delay) => *delay,
+                None if i == 0 => Duration::ZERO,
+                None => self.update_interval,
+            })
+            .collect();
+        let mut auto_responses_sent = 0;
+        loop {
+            let already_sent = self.get_responses().sent_messages.len();
+            self.run_updates(bot.clone(), updates, std::mem::take(&mut delays))
+                .await;
 
-        self.run_updates(bot, updates).await;
+            let Some(auto_response) = self.find_auto_response(already_sent) else {
+                break;
+            };
+            auto_responses_sent += 1;
+            if auto_responses_sent > MAX_AUTO_RESPONSES {
+                server.stop().await.unwrap();
+                panic!("More than {MAX_AUTO_RESPONSES} auto responses were sent, the conversation is probably stuck in a loop!");
+            }
+            updates = auto_response.into_update(&self.current_update_id);
+            self.insert_updates(&mut updates);
+        }
 
         server.stop().await.unwrap();
+        let fidelity_errors = std::mem::take(&mut self.state.lock().unwrap().fidelity_errors);
+        if !fidelity_errors.is_empty() {
+            panic!(
+                "Some responses of the fake server changed after decoding them into teloxide \
+                 types:\n{}",
+                fidelity_errors.join("\n")
+            );
+        }
+
+        let requests = self.state.lock().unwrap().requests[requests_before..].to_vec();
+        for request in &requests {
+            for hook in &self.request_hooks {
+                hook(&request.method, &request.body);
+            }
+        }
+    }
+
+    /// Dispatches the updates like [`dispatch`], but as the bot `me`, for deployments where the
+    /// same handler tree runs for several bot identities. The bot goes back to its own `me`
+    /// after the dispatch.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMe, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    /// #         bot.send_message(message.chat.id, "Welcome to the shop!").await?;
+    /// #         Ok(())
+    /// #     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+    /// bot.dispatch_as(MockMe::new().username("shop_eu_bot")).await;
+    /// let message = bot.get_responses().sent_messages.pop().unwrap();
+    /// assert_eq!(message.from.unwrap().username.as_deref(), Some("shop_eu_bot"));
+    /// # }
+    /// ```
+    ///
+    /// [`dispatch`]: crate::MockBot::dispatch
+    pub async fn dispatch_as(&mut self, me: MockMe) {
+        let own_me = std::mem::replace(&mut self.me, me.build());
+        self.dispatch().await;
+        self.me = own_me;
+    }
+
+    /// Dispatches the updates like [`dispatch`], as if `duration` had passed on the mock clock,
+    /// without moving the clock itself. The messages the bot sends during the dispatch are dated
+    /// `duration` later, so "the bot edited the message three days later" scenarios don't need
+    /// the `.date()` of every mock to be changed.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::Duration;
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    /// #         bot.send_message(message.chat.id, "Don't forget the milk").await?;
+    /// #         Ok(())
+    /// #     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/remind"), handler_tree);
+    /// bot.dispatch().await;
+    /// let sent_at = bot.get_responses().sent_messages[0].date;
+    ///
+    /// bot.dispatch_after(Duration::from_secs(3 * 24 * 60 * 60)).await;
+    /// let later = bot.get_responses().sent_messages[0].date;
+    /// assert!(later - sent_at >= chrono::TimeDelta::days(3));
+    /// # }
+    /// ```
+    ///
+    /// [`dispatch`]: crate::MockBot::dispatch
+    pub async fn dispatch_after(&mut self, duration: Duration) {
+        self.state.lock().unwrap().dispatch_offset =
+            TimeDelta::from_std(duration).expect("The duration is too long!");
+        self.dispatch().await;
+        self.state.lock().unwrap().dispatch_offset = TimeDelta::zero();
+    }
+
+    /// Dispatches `n` updates, made by repeating the updates of the bot with new update ids, and
+    /// measures how long the handler tree takes to handle every one of them. The report has the
+    /// latency percentiles for every kind of update, like `message` or `callback_query`, of every
+    /// branch wrapped into [`timed`], and the total throughput. The updates are handled against
+    /// the fake server, just like in [`dispatch`], so the latencies include the requests the
+    /// handlers make.
+    ///
+    /// `n` counts the updates in [`updates`], not the mocks they were made of: an album of three
+    /// photos is three updates, so use a multiple of `bot.updates.len()` to not cut it. The
+    /// report can have more updates than `n` with the [`Duplicated`] update delivery.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    /// #         bot.send_message(message.chat.id, "Hello!").await?;
+    /// #         Ok(())
+    /// #     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+    /// let report = bot.bench(100).await;
+    /// println!("{report}");
+    /// assert_eq!(report.latencies["message"].count, 100);
+    /// assert!(report.latencies["message"].p99 <= report.latencies["message"].max);
+    /// # }
+    /// ```
+    ///
+    /// [`dispatch`]: crate::MockBot::dispatch
+    /// [`timed`]: crate::bench::timed
+    /// [`updates`]: crate::MockBot::updates
+    /// [`Duplicated`]: crate::UpdateDelivery::Duplicated
+    pub async fn bench(&mut self, n: usize) -> BenchReport {
+        assert!(
+            !self.updates.is_empty(),
+            "There are no updates to bench with!"
+        );
+        let updates = self
+            .updates
+            .iter()
+            .cycle()
+            .take(n)
+            .flat_map(|update| update.clone().into_update(&self.current_update_id))
+            .collect();
+        let recorder = LatencyRecorder::default();
+        let timed_tree = recorder.wrap(self.handler_tree.clone());
+        let mut dependencies = self.dependencies.clone();
+        // For the `timed` branches
+        dependencies.insert(recorder.clone());
+
+        let guard = BenchGuard {
+            updates: std::mem::replace(&mut self.updates, updates),
+            handler_tree: Some(std::mem::replace(&mut self.handler_tree, timed_tree)),
+            dependencies: std::mem::replace(&mut self.dependencies, dependencies),
+            bot: self,
+        };
+        guard.bot.dispatch().await;
+        drop(guard);
+
+        recorder.report()
+    }
+
+    /// Returns the user reply to the last message sent after `already_sent` messages, that
+    /// triggers an auto response
+    fn find_auto_response(&self, already_sent: usize) -> Option<MockMessageText> {
+        let responses = self.get_responses();
+        responses.sent_messages[already_sent..]
+            .iter()
+            .rev()
+            .find_map(|message| {
+                let text = message.text().or(message.caption())?;
+                self.auto_responses
+                    .iter()
+                    .find(|(trigger, _)| text.contains(trigger.as_str()))
+                    .map(|(_, reply)| {
+                        MockMessageText::new()
+                            .chat(message.chat.clone())
+                            .text(reply)
+                    })
+            })
+    }
+
+    /// Returns which Bot API methods the bot called in all of the dispatches so far, including the
+    /// ones before [`reset`], and which of the methods the fake server implements it never
+    /// called. Set the `TELOXIDE_TESTS_COVERAGE` env variable to a path, to get the coverage of
+    /// the whole test run in that file.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    /// #         bot.send_message(message.chat.id, "Hello!").await?;
+    /// #         Ok(())
+    /// #     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+    /// bot.dispatch().await;
+    /// let coverage = bot.route_coverage();
+    /// println!("{coverage}");
+    /// assert!(coverage.unsupported().is_empty());
+    /// assert!(!coverage.uncalled().contains(&"sendMessage".to_string()));
+    /// # }
+    /// ```
+    ///
+    /// [`reset`]: crate::MockBot::reset
+    pub fn route_coverage(&self) -> RouteCoverage {
+        self.state.lock().unwrap().coverage.clone()
     }
 
     /// Returns the responses stored in `responses`
@@ -317,7 +2226,389 @@ where
         self.state.lock().unwrap().responses.clone()
     }
 
-    async fn get_potential_storages<S>(
+    /// Forgets the responses of the dispatches so far, like a new dispatch does. Useful with
+    /// [`accumulate_responses`], to check only what comes after the set-up dispatches.
+    ///
+    /// [`accumulate_responses`]: crate::MockBot::accumulate_responses
+    pub fn clear_responses(&mut self) {
+        self.state.lock().unwrap().clear_responses();
+    }
+
+    /// Makes the dispatches add to the responses of the previous ones, instead of starting with
+    /// empty responses, so a test can check a whole conversation of several dispatches at once.
+    /// The sequence numbers keep counting across the dispatches. The responses are only forgotten
+    /// with [`clear_responses`] or [`reset`] then, and the setting itself stays after [`reset`].
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    /// #         bot.send_message(message.chat.id, "Got it").await?;
+    /// #         Ok(())
+    /// #     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+    /// bot.accumulate_responses(true);
+    /// bot.dispatch().await;
+    /// bot.update(MockMessageText::new().text("/help"));
+    /// bot.dispatch().await;
+    /// assert_eq!(bot.get_responses().sent_messages.len(), 2);
+    /// # }
+    /// ```
+    ///
+    /// [`clear_responses`]: crate::MockBot::clear_responses
+    /// [`reset`]: crate::MockBot::reset
+    pub fn accumulate_responses(&mut self, accumulate: bool) {
+        self.state.lock().unwrap().config.accumulate_responses = accumulate;
+    }
+
+    /// Returns the messages that the fake server knows about and that match `predicate`, as they
+    /// are now, ordered by their ids. Unlike [`Responses::find_messages`], it has the messages of
+    /// all of the dispatches, including the messages of the updates.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, types::LabeledPrice};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    /// #         let price = LabeledPrice { label: "Stars".into(), amount: 1 };
+    /// #         bot.send_invoice(message.chat.id, "Book", "A book", "book", "XTR", vec![price])
+    /// #             .await?;
+    /// #         Ok(())
+    /// #     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/buy"), handler_tree);
+    /// bot.dispatch().await;
+    /// let invoices = bot.find_messages(|message| message.invoice().is_some());
+    /// assert_eq!(invoices.len(), 1);
+    /// # }
+    /// ```
+    ///
+    /// [`Responses::find_messages`]: crate::Responses::find_messages
+    pub fn find_messages(&self, predicate: impl Fn(&Message) -> bool) -> Vec<Message> {
+        self.state.lock().unwrap().messages.find_messages(predicate)
+    }
+
+    /// Returns the messages in the chat with `chat_id`, like in [`find_messages`]
+    ///
+    /// [`find_messages`]: crate::MockBot::find_messages
+    pub fn messages_in_chat(&self, chat_id: i64) -> Vec<Message> {
+        self.find_messages(|message| message.chat.id.0 == chat_id)
+    }
+
+    /// Returns a readable transcript of the conversation, with the updates the bot got and the
+    /// requests it sent in the order they happened, across all of the dispatches since the bot
+    /// was created or [`reset`]. The `getMe` calls of the dispatcher are left out.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    /// #         let sent = bot.send_message(message.chat.id, "Hello!").await?;
+    /// #         bot.edit_message_text(sent.chat.id, sent.id, "Hello again!").await?;
+    /// #         Ok(())
+    /// #     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+    /// bot.dispatch().await;
+    /// assert_eq!(bot.transcript(), "User: /start\nBot: Hello!\nBot edited msg 2: Hello again!");
+    /// # }
+    /// ```
+    ///
+    /// [`reset`]: crate::MockBot::reset
+    pub fn transcript(&self) -> String {
+        transcript::render(&self.state.lock().unwrap().transcript)
+    }
+
+    /// Returns the reply markup `message` was sent with. Unlike `message.reply_markup()`, this
+    /// also has reply keyboards, keyboard removals and force replies
+    pub fn get_reply_markup(&self, message: &Message) -> Option<ReplyMarkup> {
+        self.state
+            .lock()
+            .unwrap()
+            .reply_markups
+            .get(&message.id)
+            .cloned()
+    }
+
+    fn last_reply_markup(&self) -> ReplyMarkup {
+        let responses = self.get_responses();
+        let message = responses
+            .sent_messages
+            .last()
+            .expect("No sent messages were detected!");
+        self.get_reply_markup(message)
+            .expect("The last sent message has no reply markup!")
+    }
+
+    /// Checks that `message` has an entity of `kind` that covers exactly `text`, so the UTF-16
+    /// offsets and lengths don't have to be counted by hand. Caption entities are checked for
+    /// media messages
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide::types::{MessageEntity, MessageEntityKind};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    /// #         bot.send_message(message.chat.id, "👋 Hello, world!")
+    /// #             .entities(vec![MessageEntity::bold(3, 5)])
+    /// #             .await?;
+    /// #         Ok(())
+    /// #     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+    /// bot.dispatch().await;
+    /// // The bot sent "👋 Hello, world!" with bold "Hello"
+    /// let message = bot.get_responses().sent_messages.pop().unwrap();
+    /// bot.assert_entity(&message, MessageEntityKind::Bold, "Hello");
+    /// # }
+    /// ```
+    pub fn assert_entity(&self, message: &Message, kind: MessageEntityKind, text: &str) {
+        let entities = message
+            .parse_entities()
+            .or_else(|| message.parse_caption_entities())
+            .unwrap_or_default();
+        if entities
+            .iter()
+            .any(|entity| *entity.kind() == kind && entity.text() == text)
+        {
+            return;
+        }
+
+        let found: Vec<_> = entities
+            .iter()
+            .map(|entity| (entity.kind(), entity.text()))
+            .collect();
+        panic!(
+            "The message has no {kind:?} entity with \"{text}\" text! The entities are: {found:?}"
+        );
+    }
+
+    /// Same as `assert_entity`, but checks the last sent message
+    pub fn assert_last_entity(&self, kind: MessageEntityKind, text: &str) {
+        let responses = self.get_responses();
+        let message = responses
+            .sent_messages
+            .last()
+            .expect("No sent messages were detected!");
+        self.assert_entity(message, kind, text);
+    }
+
+    /// Checks that the last sent message has a reply keyboard with this layout of button texts
+    pub fn assert_last_reply_keyboard(&self, layout: Vec<Vec<&str>>) {
+        let ReplyMarkup::Keyboard(keyboard) = self.last_reply_markup() else {
+            panic!("The last sent message has no reply keyboard!");
+        };
+        let texts: Vec<Vec<&str>> = keyboard
+            .keyboard
+            .iter()
+            .map(|row| row.iter().map(|button| button.text.as_str()).collect())
+            .collect();
+        assert_eqn!(texts, layout, "Keyboard layouts are not equal!");
+    }
+
+    /// Checks the input field placeholder of the reply keyboard or force reply of the last sent
+    /// message. Pass in an empty string if you want the placeholder to be empty
+    pub fn assert_last_input_field_placeholder(&self, placeholder: &str) {
+        let actual = match self.last_reply_markup() {
+            ReplyMarkup::Keyboard(keyboard) => keyboard.input_field_placeholder,
+            ReplyMarkup::ForceReply(force_reply) => {
+                force_reply.input_field_placeholder.unwrap_or_default()
+            }
+            _ => panic!("The last sent message has no reply keyboard or force reply!"),
+        };
+        assert_eqn!(
+            actual,
+            placeholder,
+            "Input field placeholders are not equal!"
+        );
+    }
+
+    /// Checks the `one_time_keyboard` flag of the reply keyboard of the last sent message
+    pub fn assert_last_one_time_keyboard(&self, one_time_keyboard: bool) {
+        let ReplyMarkup::Keyboard(keyboard) = self.last_reply_markup() else {
+            panic!("The last sent message has no reply keyboard!");
+        };
+        assert_eqn!(
+            keyboard.one_time_keyboard,
+            one_time_keyboard,
+            "One time keyboard flags are not equal!"
+        );
+    }
+
+    /// Checks the `selective` flag of the reply keyboard, keyboard removal or force reply of the
+    /// last sent message
+    pub fn assert_last_selective(&self, selective: bool) {
+        let actual = match self.last_reply_markup() {
+            ReplyMarkup::Keyboard(keyboard) => keyboard.selective,
+            ReplyMarkup::KeyboardRemove(keyboard_remove) => keyboard_remove.selective,
+            ReplyMarkup::ForceReply(force_reply) => force_reply.selective,
+            ReplyMarkup::InlineKeyboard(_) => {
+                panic!("Inline keyboards don't have the selective flag!")
+            }
+        };
+        assert_eqn!(actual, selective, "Selective flags are not equal!");
+    }
+
+    fn last_callback_answer(&self) -> server::AnswerCallbackQueryBody {
+        self.get_responses()
+            .answered_callback_queries
+            .pop()
+            .expect("No callback query answers were detected!")
+    }
+
+    /// Checks the text of the last callback query answer. An answer without a text is checked as
+    /// an empty string
+    pub fn assert_last_callback_answer_text(&self, text: impl TextMatcher) {
+        let actual = self.last_callback_answer().text.unwrap_or_default();
+        if !text.matches_text(&actual) {
+            panic!(
+                "assertion `actual == expected` failed: Callback answer texts are not equal!
+   actual: {actual:?}
+ expected: {}",
+                text.expected()
+            );
+        }
+    }
+
+    /// Checks if the last callback query answer is shown as an alert (`true`) or as a
+    /// notification at the top of the chat (`false`, the default)
+    pub fn assert_last_callback_answer_alert(&self, show_alert: bool) {
+        assert_eqn!(
+            self.last_callback_answer().show_alert.unwrap_or(false),
+            show_alert,
+            "Callback answer alert flags are not equal!"
+        );
+    }
+
+    /// Checks the url the last callback query answer opens, `None` if it doesn't open any
+    pub fn assert_last_callback_answer_url(&self, url: Option<&str>) {
+        assert_eqn!(
+            self.last_callback_answer().url.as_deref(),
+            url,
+            "Callback answer urls are not equal!"
+        );
+    }
+
+    /// Checks how long the last callback query answer may be cached, in seconds. An answer
+    /// without a `cache_time` is checked as 0, like Telegram does
+    pub fn assert_last_callback_answer_cache_time(&self, cache_time: i32) {
+        assert_eqn!(
+            self.last_callback_answer().cache_time.unwrap_or(0),
+            cache_time,
+            "Callback answer cache times are not equal!"
+        );
+    }
+
+    /// Checks the message effect of the last sent message, either by its id or by its emoji for
+    /// the effects in [`MESSAGE_EFFECTS`]. `None` checks that the message has no effect.
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, types::EffectId};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    /// #         bot.send_message(message.chat.id, "Congratulations!")
+    /// #             .message_effect_id(EffectId::from("5046509860389126442".to_string()))
+    /// #             .await?;
+    /// #         Ok(())
+    /// #     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/celebrate"), handler_tree);
+    /// bot.dispatch().await;
+    /// bot.assert_last_message_effect(Some("🎉"));
+    /// # }
+    /// ```
+    ///
+    /// [`MESSAGE_EFFECTS`]: crate::server::effects::MESSAGE_EFFECTS
+    pub fn assert_last_message_effect(&self, effect: Option<&str>) {
+        let message = self
+            .get_responses()
+            .sent_messages
+            .pop()
+            .expect("No sent messages were detected!");
+        let effect_id = message.effect_id();
+        let matches = match (effect_id, effect) {
+            (Some(effect_id), Some(effect)) => {
+                effect_id.0 == effect || effect_emoji(effect_id) == Some(effect)
+            }
+            (None, None) => true,
+            _ => false,
+        };
+        if !matches {
+            panic!(
+                "assertion `actual == expected` failed: Message effects are not equal!
+   actual: {:?}
+ expected: {effect:?}",
+                effect_id.map(|effect_id| effect_emoji(effect_id).unwrap_or(&effect_id.0))
+            );
+        }
+    }
+
+    /// Checks that the bot sent the `action` chat action at most `duration` after the dispatch
+    /// started, by the mock clock. Useful for bots that must show a typing indicator before a long
+    /// operation.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::Duration;
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*, types::ChatAction};
+    /// # use teloxide_tests::{MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    /// #         bot.send_chat_action(message.chat.id, ChatAction::Typing).await?;
+    /// #         bot.send_message(message.chat.id, "Here is the report").await?;
+    /// #         Ok(())
+    /// #     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/report"), handler_tree);
+    /// bot.dispatch().await;
+    /// bot.assert_chat_action_sent_within(ChatAction::Typing, Duration::from_secs(5));
+    /// # }
+    /// ```
+    pub fn assert_chat_action_sent_within(&self, action: ChatAction, duration: Duration) {
+        let action = match serde_json::to_value(action) {
+            Ok(serde_json::Value::String(action)) => action,
+            _ => unreachable!("Chat actions are serialized as strings"),
+        };
+        let state = self.state.lock().unwrap();
+        let Some(first_sent_at) = state
+            .responses
+            .sent_chat_actions
+            .iter()
+            .filter(|sent| sent.bot_request.action == action)
+            .map(|sent| sent.date)
+            .min()
+        else {
+            panic!("No \"{action}\" chat actions were sent!");
+        };
+        let elapsed = (first_sent_at - state.dispatch_started_at)
+            .to_std()
+            .unwrap_or_default();
+        if elapsed > duration {
+            panic!(
+                "The \"{action}\" chat action was sent {elapsed:?} after the update, expected \
+                 within {duration:?}"
+            );
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn get_potential_storages<S>(
         &self,
     ) -> (
         Option<Arc<Arc<InMemStorage<S>>>>,
@@ -326,36 +2617,52 @@ where
     where
         S: Send + 'static + Clone,
     {
-        let default_panic = panic::take_hook();
-        let in_mem_storage: Option<Arc<Arc<InMemStorage<S>>>>;
-        let erased_storage: Option<Arc<Arc<ErasedStorage<S>>>>;
         // No trace storage cuz who uses it
-        let dependencies = Arc::new(self.dependencies.clone());
-        // Get dependencies into Arc cuz otherwise it complaints about &self being moved
+        (self.dependencies.try_get(), self.dependencies.try_get())
+    }
 
-        panic::set_hook(Box::new(|_| {
-            // Do nothing to ignore the panic
-        }));
-        let print_gag = Gag::stderr().unwrap(); // Otherwise the panic will be printed
-        in_mem_storage = std::thread::spawn(move || {
-            // Try to convert one of dptrees fields into an InMemStorage
-            dependencies.get()
-        })
-        .join()
-        .ok();
-
-        let dependencies = Arc::new(self.dependencies.clone());
-        // Dependencies were moved to a prev. thread, so create a new one
-        erased_storage = std::thread::spawn(move || {
-            // The same for ErasedStorage
-            dependencies.get()
-        })
-        .join()
-        .ok();
+    // Why there is no storage of the dialogues of type `S` in the dependencies
+    fn missing_storage_error<S>(&self) -> StateStorageError {
+        let provided = std::any::type_name::<S>();
+        let expected = self.required_storage_state_types();
+        // If the handler tree wants a storage for `S`, it just wasn't added to the dependencies
+        if expected.is_empty() || expected.iter().any(|state| state == provided) {
+            StateStorageError::NoStorage
+        } else {
+            StateStorageError::WrongStateType { provided, expected }
+        }
+    }
 
-        panic::set_hook(default_panic); // Restore the default panic hook
-        drop(print_gag);
-        (in_mem_storage, erased_storage)
+    // The states of the storages that the handler tree requires, like `State` for
+    // `dialogue::enter::<Update, InMemStorage<State>, State, _>()`
+    fn required_storage_state_types(&self) -> Vec<String> {
+        let HandlerSignature::Other { obligations, .. } = self.handler_tree.sig() else {
+            return vec![];
+        };
+        let mut states: Vec<String> = obligations
+            .keys()
+            .filter_map(|ty| {
+                // `ErasedStorage<S>` is an alias, so it is named as `dyn Storage<S, Error = ..>`
+                let (_, rest) = ["InMemStorage<", "storage::Storage<"]
+                    .iter()
+                    .find_map(|storage| ty.name.split_once(storage))?;
+                // The state is the first generic argument, that may have generics itself
+                let mut depth = 0;
+                let end = rest.find(|c| {
+                    match c {
+                        '<' => depth += 1,
+                        '>' | ',' if depth == 0 => return true,
+                        '>' => depth -= 1,
+                        _ => {}
+                    }
+                    false
+                })?;
+                Some(rest[..end].to_string())
+            })
+            .collect();
+        states.sort();
+        states.dedup();
+        states
     }
 
     /// Sets the state of the dialogue, if the storage exists in dependencies
@@ -425,35 +2732,144 @@ where
     where
         S: Send + 'static + Clone,
     {
-        let (in_mem_storage, erased_storage) = self.get_potential_storages().await;
-        let first_update = self.updates.first().expect("No updates were detected!");
-        let chat_id = match first_update.chat_id() {
-            Some(chat_id) => chat_id,
-            None => match find_chat_id(serde_json::to_value(first_update).unwrap()) {
-                Some(id) => ChatId(id),
-                None => {
-                    log::error!("No chat id was detected in the update! Did you send an update without a chat identifier? Like MockCallbackQuery without an attached message?");
-                    panic!("No chat id was detected!");
-                }
-            },
-        };
+        if let Err(error) = self.try_set_state(state).await {
+            log::error!("{error}");
+            panic!("{error}");
+        }
+    }
+
+    /// Same as [`set_state`], but returns the error instead of panicking, e.g. if the storage
+    /// that the handler tree requires is for the dialogues of another type
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::{dialogue::{self, InMemStorage}, UpdateHandler}, prelude::*};
+    /// # use teloxide_tests::{MockBot, MockMessageText, StateStorageError};
+    /// # #[derive(Clone, Default)]
+    /// # struct State;
+    /// # #[derive(Clone)]
+    /// # struct OtherState;
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     dialogue::enter::<Update, InMemStorage<State>, State, _>()
+    /// #         .endpoint(|| async { Ok(()) });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+    /// bot.dependencies(teloxide::dptree::deps![InMemStorage::<State>::new()]);
+    /// let error = bot.try_set_state(OtherState).await.unwrap_err();
+    /// assert!(matches!(error, StateStorageError::WrongStateType { .. }));
+    /// # }
+    /// ```
+    ///
+    /// [`set_state`]: crate::MockBot::set_state
+    pub async fn try_set_state<S>(&self, state: S) -> Result<(), StateStorageError>
+    where
+        S: Send + 'static + Clone,
+    {
+        let (in_mem_storage, erased_storage) = self.get_potential_storages();
+        let chat_id = self.dialogue_chat_id();
+        let _paused = self.pause_storage_chaos();
         if let Some(storage) = in_mem_storage {
             // If memory storage exists
             (*storage)
                 .clone()
                 .update_dialogue(chat_id, state)
                 .await
-                .expect("Failed to update dialogue");
+                .map_err(|error| StateStorageError::Storage(error.to_string()))
         } else if let Some(storage) = erased_storage {
             // If erased storage exists
             (*storage)
                 .clone()
                 .update_dialogue(chat_id, state)
                 .await
-                .expect("Failed to update dialogue");
+                .map_err(|error| StateStorageError::Storage(error.to_string()))
         } else {
-            log::error!("No storage was detected! Did you add it to bot.dependencies(deps![get_bot_storage().await]); ? Did you specify the type ::<State> ?");
-            panic!("No storage was detected! Did you add it to bot.dependencies(deps![get_bot_storage().await]); ? Did you specify the type ::<State> ?");
+            Err(self.missing_storage_error::<S>())
+        }
+    }
+
+    /// Makes the dialogue storage of the handlers slow or failing, so it can be tested how the
+    /// bot behaves when Redis or Sqlite is slow or down. The first call wraps the `ErasedStorage`
+    /// of the dialogues of type `S` in the dependencies, later calls only change the chaos.
+    /// `set_state` and `get_state` aren't affected. The chaos stays after [`reset`], call this
+    /// with `StorageChaos::new()` to make the storage healthy again.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use teloxide::dispatching::dialogue::{ErasedStorage, InMemStorage, Storage};
+    /// # use teloxide::dispatching::UpdateHandler;
+    /// # use teloxide::prelude::*;
+    /// # use teloxide_tests::{MockBot, MockMessageText, StorageChaos};
+    /// # #[derive(Clone, Default)]
+    /// # enum State { #[default] Start }
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(
+    /// #         |message: Message, bot: Bot, storage: Arc<ErasedStorage<State>>| async move {
+    /// #             let dialogue = Dialogue::new(storage, message.chat.id);
+    /// #             let text = match dialogue.get().await {
+    /// #                 Ok(_) => "Hello!",
+    /// #                 Err(_) => "Sorry, try again later",
+    /// #             };
+    /// #             bot.send_message(message.chat.id, text).await?;
+    /// #             Ok(())
+    /// #         },
+    /// #     );
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/start"), handler_tree);
+    /// bot.dependencies(teloxide::dptree::deps![InMemStorage::<State>::new().erase()]);
+    /// bot.storage_chaos::<State>(StorageChaos::new().fail_get(true)).await;
+    /// bot.dispatch_and_check_last_text("Sorry, try again later").await;
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    /// If there is no `ErasedStorage` of the dialogues of type `S` in the dependencies
+    ///
+    /// [`reset`]: crate::MockBot::reset
+    pub async fn storage_chaos<S>(&mut self, chaos: StorageChaos)
+    where
+        S: Send + 'static + Clone,
+    {
+        if self
+            .dependencies
+            .try_get::<ChaosStorageMarker<S>>()
+            .is_none()
+        {
+            let (_, erased_storage) = self.get_potential_storages::<S>();
+            let Some(storage) = erased_storage else {
+                panic!("No ErasedStorage was detected! Only the storages made with .erase() can be wrapped");
+            };
+            let chaos_storage: Arc<ErasedStorage<S>> =
+                Arc::new(ChaosStorage::new((*storage).clone(), self.state.clone()));
+            self.dependencies.insert(chaos_storage);
+            self.dependencies.insert(ChaosStorageMarker::<S>::new());
+        }
+        self.state.lock().unwrap().config.storage_chaos = chaos;
+    }
+
+    // Turns off the storage chaos until the guard is dropped, so the state helpers always work
+    fn pause_storage_chaos(&self) -> impl Drop + '_ {
+        struct Guard<'a>(&'a Mutex<State>, StorageChaos);
+        impl Drop for Guard<'_> {
+            fn drop(&mut self) {
+                self.0.lock().unwrap().config.storage_chaos = std::mem::take(&mut self.1);
+            }
+        }
+        let chaos = std::mem::take(&mut self.state.lock().unwrap().config.storage_chaos);
+        Guard(&self.state, chaos)
+    }
+
+    // The chat of the dialogue that the first update belongs to
+    fn dialogue_chat_id(&self) -> ChatId {
+        let first_update = self.updates.first().expect("No updates were detected!");
+        match find_chat_id(first_update) {
+            Some(chat_id) => chat_id,
+            None => {
+                log::error!("No chat id was detected in the update! Did you send an update without a chat or a user, like a poll?");
+                panic!("No chat id was detected!");
+            }
         }
     }
 
@@ -485,17 +2901,9 @@ where
     where
         S: Send + 'static + Clone,
     {
-        let (in_mem_storage, erased_storage) = self.get_potential_storages().await;
-        let first_update = self.updates.first().expect("No updates were detected!");
-        let chat_id = match first_update.chat_id() {
-            Some(chat_id) => chat_id,
-            None => match find_chat_id(serde_json::to_value(first_update).unwrap()) {
-                Some(id) => ChatId(id),
-                None => {
-                    panic!("No chat id was detected!");
-                }
-            },
-        };
+        let (in_mem_storage, erased_storage) = self.get_potential_storages();
+        let chat_id = self.dialogue_chat_id();
+        let _paused = self.pause_storage_chaos();
         if let Some(storage) = in_mem_storage {
             // If memory storage exists
             (*storage)
@@ -513,8 +2921,9 @@ where
                 .ok()
                 .flatten()
         } else {
-            log::error!("No storage was detected! Did you add it to bot.dependencies(deps![get_bot_storage().await]); ? Did you specify the type ::<State> ?");
-            panic!("No storage was detected! Did you add it to bot.dependencies(deps![get_bot_storage().await]); ? Did you specify the type ::<State> ?");
+            let error = self.missing_storage_error::<S>();
+            log::error!("{error}");
+            panic!("{error}");
         }
     }
 
@@ -524,48 +2933,48 @@ where
 
     /// Dispatches and checks the last sent message text or caption. Pass in an empty string if you
     /// want the text or caption to be None
-    pub async fn dispatch_and_check_last_text(&mut self, text_or_caption: &str) {
+    ///
+    /// Instead of a string, a [`Regex`] or a closure can be passed in to check dynamic text
+    ///
+    /// # Example
+    /// ```
+    /// # use teloxide::{dispatching::UpdateHandler, prelude::*};
+    /// # use teloxide_tests::{matchers::Regex, MockBot, MockMessageText};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+    /// #     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+    /// #         let id = message.from.unwrap().id;
+    /// #         bot.send_message(message.chat.id, format!("Your id is {id}")).await?;
+    /// #         Ok(())
+    /// #     });
+    /// let mut bot = MockBot::new(MockMessageText::new().text("/id"), handler_tree);
+    /// bot.dispatch_and_check_last_text(Regex::new(r"^Your id is \d+$").unwrap()).await;
+    /// bot.dispatch_and_check_last_text(|text: &str| text.starts_with("Your id")).await;
+    /// # }
+    /// ```
+    ///
+    /// [`Regex`]: crate::matchers::Regex
+    pub async fn dispatch_and_check_last_text(&mut self, text_or_caption: impl TextMatcher) {
         self.dispatch().await;
+        let _dump = ResponsesDump(self.get_responses());
 
-        let responses = self.get_responses();
-        let message = responses
-            .sent_messages
-            .last()
-            .expect("No sent messages were detected!");
-
-        if let Some(text) = message.text() {
-            assert_eqn!(text, text_or_caption, "Texts are not equal!");
-        } else if let Some(caption) = message.caption() {
-            assert_eqn!(caption, text_or_caption, "Captions are not equal!");
-        } else if !text_or_caption.is_empty() {
-            panic!("Message has no text or caption!");
-        }
+        self.assert_last_text(text_or_caption);
     }
 
     /// Same as `dispatch_and_check_last_text`, but also checks the state. You need to derive
     /// PartialEq, Clone and Debug for the state like in `set_state` example
     pub async fn dispatch_and_check_last_text_and_state<S>(
         &mut self,
-        text_or_caption: &str,
+        text_or_caption: impl TextMatcher,
         state: S,
     ) where
         S: Send + Default + 'static + Clone + std::fmt::Debug + PartialEq,
     {
         self.dispatch().await;
+        let _dump = ResponsesDump(self.get_responses());
 
-        let responses = self.get_responses();
-        let message = responses
-            .sent_messages
-            .last()
-            .expect("No sent messages were detected!");
-
-        if let Some(text) = message.text() {
-            assert_eqn!(text, text_or_caption, "Texts are not equal!");
-        } else if let Some(caption) = message.caption() {
-            assert_eqn!(caption, text_or_caption, "Captions are not equal!");
-        } else if !text_or_caption.is_empty() {
-            panic!("Message has no text or caption!");
-        }
+        self.assert_last_text(text_or_caption);
 
         self.assert_state(state).await;
     }
@@ -575,30 +2984,49 @@ where
     /// For example, `State::Start { some_field: "value" }` and `State::Start { some_field: "other value" }` are the same in this function
     pub async fn dispatch_and_check_last_text_and_state_discriminant<S>(
         &mut self,
-        text_or_caption: &str,
+        text_or_caption: impl TextMatcher,
         state: S,
     ) where
         S: Send + PartialEq + Debug + Default + 'static + Clone,
     {
         self.dispatch().await;
+        let _dump = ResponsesDump(self.get_responses());
+
+        self.assert_last_text(text_or_caption);
+
+        let got_state: S = self.get_state().await;
+        if discriminant(&got_state) != discriminant(&state) {
+            assert_eqn!(got_state, state, "State variants are not equal!")
+        }
+    }
 
+    /// Checks the text or caption of the last sent message. A message without either is checked
+    /// as an empty string
+    fn assert_last_text(&self, text_or_caption: impl TextMatcher) {
         let responses = self.get_responses();
         let message = responses
             .sent_messages
             .last()
             .expect("No sent messages were detected!");
+        self.expectations
+            .assert_last_request(server::sent_message_method(message));
 
-        if let Some(text) = message.text() {
-            assert_eqn!(text, text_or_caption, "Texts are not equal!");
+        let (actual, what) = if let Some(text) = message.text() {
+            (text, "Texts")
         } else if let Some(caption) = message.caption() {
-            assert_eqn!(caption, text_or_caption, "Captions are not equal!");
-        } else if !text_or_caption.is_empty() {
+            (caption, "Captions")
+        } else if text_or_caption.matches_text("") {
+            return;
+        } else {
             panic!("Message has no text or caption!");
-        }
-
-        let got_state: S = self.get_state().await;
-        if discriminant(&got_state) != discriminant(&state) {
-            assert_eqn!(got_state, state, "State variants are not equal!")
+        };
+        if !text_or_caption.matches_text(actual) {
+            panic!(
+                "assertion `actual == expected` failed: {what} are not equal!
+   actual: {actual:?}
+ expected: {}",
+                text_or_caption.expected()
+            );
         }
     }
 
@@ -608,6 +3036,7 @@ where
         S: Send + Default + 'static + Clone + std::fmt::Debug + PartialEq,
     {
         self.dispatch().await;
+        let _dump = ResponsesDump(self.get_responses());
         self.assert_state(state).await;
     }
 
@@ -617,6 +3046,7 @@ where
         S: Send + Debug + PartialEq + Default + 'static + Clone,
     {
         self.dispatch().await;
+        let _dump = ResponsesDump(self.get_responses());
         let got_state: S = self.get_state().await;
         if discriminant(&got_state) != discriminant(&state) {
             assert_eqn!(got_state, state, "State variants are not equal!")
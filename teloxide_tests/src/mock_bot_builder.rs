@@ -0,0 +1,204 @@
+//! A builder for [`MockBot`], for the cases where setting up the bot with `new` and setters is
+//! inconvenient, like in shared test fixtures
+use std::{
+    fmt::Debug,
+    hash::Hash,
+    sync::{atomic::AtomicI32, Arc},
+};
+
+use teloxide::{
+    dispatching::UpdateHandler, dptree::di::DependencyMap, error_handlers::ErrorHandler,
+    types::Update,
+};
+
+use crate::{dataset::IntoUpdate, mock_bot::DistributionKey, MockBot, MockMe};
+
+/// The handler tree of [`MockBotBuilder`] that wasn't set yet. A builder with it can't be built.
+pub struct NoHandlerTree;
+
+type IntoUpdateFn = Box<dyn FnOnce(&AtomicI32) -> Vec<Update>>;
+
+// Updates are converted when the bot is created, so that they get the ids of that bot
+struct DeferredUpdates(IntoUpdateFn);
+
+impl IntoUpdate for DeferredUpdates {
+    fn into_update(self, id: &AtomicI32) -> Vec<Update> {
+        (self.0)(id)
+    }
+}
+
+/// A builder of [`MockBot`], created by [`MockBot::builder`]. The handler tree is required, and
+/// [`build`] doesn't exist until it is set, so forgetting it is a compile error:
+///
+/// ```compile_fail
+/// # use teloxide_tests::MockBot;
+/// let bot = MockBot::<Box<dyn std::error::Error + Send + Sync>, _>::builder().build();
+/// ```
+///
+/// # Example
+/// ```
+/// # use teloxide::{dispatching::UpdateHandler, dptree::deps, prelude::*};
+/// # use teloxide_tests::{MockBot, MockMe, MockMessageText};
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let handler_tree: UpdateHandler<Box<dyn std::error::Error + Send + Sync>> =
+/// #     Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+/// #         bot.send_message(message.chat.id, "Hello!").await?;
+/// #         Ok(())
+/// #     });
+/// let mut bot = MockBot::builder()
+///     .handler_tree(handler_tree)
+///     .update(MockMessageText::new().text("/start"))
+///     .me(MockMe::new().first_name("Test bot"))
+///     .dependencies(deps![])
+///     .build();
+/// bot.dispatch().await;
+/// let message = bot.get_responses().sent_messages.pop().unwrap();
+/// assert_eq!(message.from.unwrap().first_name, "Test bot");
+/// # }
+/// ```
+///
+/// [`build`]: crate::mock_bot_builder::MockBotBuilder::build
+pub struct MockBotBuilder<Err, Key, Tree> {
+    handler_tree: Tree,
+    updates: DeferredUpdates,
+    me: Option<MockMe>,
+    dependencies: Option<DependencyMap>,
+    error_handler: Option<Arc<dyn ErrorHandler<Err> + Send + Sync>>,
+    distribution_f: fn(&Update) -> Option<Key>,
+    stack_size: Option<usize>,
+    isolate_env: bool,
+}
+
+impl<Err> MockBot<Err, DistributionKey>
+where
+    Err: Debug + Send + Sync + 'static,
+{
+    /// Creates a [`MockBotBuilder`], an alternative to [`new`] and the setters
+    ///
+    /// [`new`]: crate::MockBot::new
+    pub fn builder() -> MockBotBuilder<Err, DistributionKey, NoHandlerTree> {
+        MockBotBuilder {
+            handler_tree: NoHandlerTree,
+            updates: DeferredUpdates(Box::new(|_| vec![])),
+            me: None,
+            dependencies: None,
+            error_handler: None,
+            distribution_f: crate::utils::default_distribution_function,
+            stack_size: None,
+            isolate_env: false,
+        }
+    }
+}
+
+impl<Err, Key, Tree> MockBotBuilder<Err, Key, Tree>
+where
+    Err: Debug + Send + Sync + 'static,
+    Key: Hash + Eq + Clone + Send + 'static,
+{
+    /// Sets the handler tree, the same as in `dptree::entry()`. Required.
+    pub fn handler_tree(
+        self,
+        handler_tree: UpdateHandler<Err>,
+    ) -> MockBotBuilder<Err, Key, UpdateHandler<Err>> {
+        MockBotBuilder {
+            handler_tree,
+            updates: self.updates,
+            me: self.me,
+            dependencies: self.dependencies,
+            error_handler: self.error_handler,
+            distribution_f: self.distribution_f,
+            stack_size: self.stack_size,
+            isolate_env: self.isolate_env,
+        }
+    }
+
+    /// Sets the updates for the first dispatch, like in [`MockBot::new`]. There are no updates
+    /// by default.
+    pub fn update<T: IntoUpdate + 'static>(mut self, update: T) -> Self {
+        self.updates = DeferredUpdates(Box::new(move |id| update.into_update(id)));
+        self
+    }
+
+    /// Sets the bot parameters, like in [`MockBot::me`]
+    pub fn me(mut self, me: MockMe) -> Self {
+        self.me = Some(me);
+        self
+    }
+
+    /// Sets the dependencies of the dptree, like in [`MockBot::dependencies`]
+    pub fn dependencies(mut self, deps: DependencyMap) -> Self {
+        self.dependencies = Some(deps);
+        self
+    }
+
+    /// Sets the error handler of the dispatcher, like in [`MockBot::error_handler`]
+    pub fn error_handler(mut self, handler: Arc<dyn ErrorHandler<Err> + Send + Sync>) -> Self {
+        self.error_handler = Some(handler);
+        self
+    }
+
+    /// Sets the stack size of the runtime for running updates
+    pub fn stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = Some(stack_size);
+        self
+    }
+
+    /// Makes the dispatches leave the env variables alone, like [`MockBot::isolate_env`]
+    pub fn isolate_env(mut self) -> Self {
+        self.isolate_env = true;
+        self
+    }
+
+    /// Sets the distribution function of the dispatcher, like in
+    /// [`MockBot::new_with_distribution_function`]
+    pub fn distribution_function<NewKey>(
+        self,
+        f: fn(&Update) -> Option<NewKey>,
+    ) -> MockBotBuilder<Err, NewKey, Tree>
+    where
+        NewKey: Hash + Eq + Clone + Send + 'static,
+    {
+        MockBotBuilder {
+            handler_tree: self.handler_tree,
+            updates: self.updates,
+            me: self.me,
+            dependencies: self.dependencies,
+            error_handler: self.error_handler,
+            distribution_f: f,
+            stack_size: self.stack_size,
+            isolate_env: self.isolate_env,
+        }
+    }
+}
+
+impl<Err, Key> MockBotBuilder<Err, Key, UpdateHandler<Err>>
+where
+    Err: Debug + Send + Sync + 'static,
+    Key: Hash + Eq + Clone + Send + 'static,
+{
+    /// Creates the bot. Like [`MockBot::new`], it waits for other bots to be dropped.
+    pub fn build(self) -> MockBot<Err, Key> {
+        let mut bot = MockBot::new_with_distribution_function(
+            self.updates,
+            self.handler_tree,
+            self.distribution_f,
+        );
+        if let Some(me) = self.me {
+            bot.me(me);
+        }
+        if let Some(deps) = self.dependencies {
+            bot.dependencies(deps);
+        }
+        if let Some(handler) = self.error_handler {
+            bot.error_handler(handler);
+        }
+        if let Some(stack_size) = self.stack_size {
+            bot.stack_size = stack_size;
+        }
+        if self.isolate_env {
+            bot.isolate_env();
+        }
+        bot
+    }
+}
@@ -0,0 +1,176 @@
+//! The fake server running on its own, for integration tests of complete bots
+use std::{
+    future::Future,
+    io,
+    process::{Child, Command},
+    time::Duration,
+};
+
+use teloxide::types::Message;
+use tokio::task::JoinHandle;
+
+use crate::{
+    dataset::{IntoUpdate, MockMe},
+    expectations::{Expectation, JsonContains},
+    server, MockServer,
+};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+// How often the waiting methods check the responses
+const CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The fake server without a handler tree, for testing complete bot binaries or tasks, that get
+/// updates with `getUpdates` like a real bot does, for example with `Dispatcher::dispatch` or
+/// `teloxide::repl`.
+///
+/// Starting it exports `TELOXIDE_TOKEN` and `TELOXIDE_API_URL`, so `Bot::from_env()` is
+/// connected to the fake server, in this process and in the processes it spawns. The updates sent
+/// with [`send_update`] are returned by `getUpdates`. Just like [`MockBot`], only one environment
+/// or bot can exist at a time.
+///
+/// When the environment is dropped, the spawned processes are killed and the tasks are aborted.
+///
+/// # Example
+/// ```no_run
+/// use std::process::Command;
+///
+/// use teloxide_tests::{json_contains, MockEnvironment, MockMessageText};
+///
+/// #[tokio::test]
+/// async fn test_bot_binary() {
+///     let mut env = MockEnvironment::start().await;
+///     env.spawn_process(&mut Command::new(env!("CARGO_BIN_EXE_my_bot")))
+///         .unwrap();
+///
+///     env.send_update(MockMessageText::new().text("/start"));
+///     let sent_messages = env.wait_for_sent_messages(1).await;
+///     assert_eq!(sent_messages[0].text(), Some("Hello!"));
+///     env.assert_request("sendMessage", json_contains!({"text": "Hello!"}));
+/// }
+/// ```
+///
+/// [`send_update`]: crate::MockEnvironment::send_update
+/// [`MockBot`]: crate::MockBot
+pub struct MockEnvironment {
+    /// The fake server the bot is connected to
+    pub server: MockServer,
+    /// How long the `wait_*` methods wait before panicking. 10 seconds by default.
+    pub timeout: Duration,
+
+    bots: SpawnedBots,
+}
+
+// The processes and tasks of the bots, that are stopped when they are dropped
+#[derive(Default)]
+struct SpawnedBots {
+    processes: Vec<Child>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Drop for SpawnedBots {
+    fn drop(&mut self) {
+        for process in self.processes.iter_mut() {
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+        for task in self.tasks.iter() {
+            task.abort();
+        }
+    }
+}
+
+impl MockEnvironment {
+    /// Starts the fake server with the default [`MockMe`]
+    pub async fn start() -> Self {
+        Self::start_with_me(MockMe::new()).await
+    }
+
+    /// Starts the fake server, with `me` being the bot that `getMe` returns
+    pub async fn start_with_me(me: MockMe) -> Self {
+        Self {
+            server: MockServer::start_with_me(me).await,
+            timeout: DEFAULT_TIMEOUT,
+            bots: SpawnedBots::default(),
+        }
+    }
+
+    /// Spawns the bot process, with `TELOXIDE_TOKEN` and `TELOXIDE_API_URL` of the fake server.
+    /// It is killed when the environment is stopped or dropped.
+    pub fn spawn_process(&mut self, command: &mut Command) -> io::Result<()> {
+        let bot = &self.server.bot;
+        let child = command
+            .env("TELOXIDE_TOKEN", bot.token())
+            .env("TELOXIDE_API_URL", bot.api_url().to_string())
+            .spawn()?;
+        self.bots.processes.push(child);
+        Ok(())
+    }
+
+    /// Spawns the bot as a task, like the `main` of the bot. It is aborted when the environment is
+    /// stopped or dropped.
+    pub fn spawn<F>(&mut self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.bots.tasks.push(tokio::spawn(task));
+    }
+
+    /// Queues the updates, so that the bot gets them with its next `getUpdates`
+    pub fn send_update<T: IntoUpdate>(&self, update: T) {
+        let mut updates = update.into_update(&self.server.current_update_id);
+        let mut state = self.server.state.lock().unwrap();
+        state.insert_updates(&mut updates);
+        state.pending_updates.extend(updates);
+    }
+
+    /// Returns the responses to all the requests the bot has sent so far
+    pub fn get_responses(&self) -> server::Responses {
+        self.server.get_responses()
+    }
+
+    /// Waits until the condition on the responses is true, panicking after the timeout
+    pub async fn wait_until(&self, condition: impl Fn(&server::Responses) -> bool) {
+        let mut waited = Duration::ZERO;
+        while !condition(&self.server.state.lock().unwrap().responses) {
+            if waited >= self.timeout {
+                panic!(
+                    "The condition wasn't met in {:?}! The requests are:\n{}",
+                    self.timeout,
+                    self.server.expectations.describe_requests()
+                );
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            waited += CHECK_INTERVAL;
+        }
+    }
+
+    /// Waits until the bot has sent at least `count` messages, and returns all of them
+    pub async fn wait_for_sent_messages(&self, count: usize) -> Vec<Message> {
+        self.wait_until(|responses| responses.sent_messages.len() >= count)
+            .await;
+        self.get_responses().sent_messages
+    }
+
+    /// Expects the bot to send a request with this method, like [`MockBot::expect`]. The
+    /// expectations are verified when the environment is dropped.
+    ///
+    /// [`MockBot::expect`]: crate::MockBot::expect
+    pub fn expect(&mut self, method: &str) -> &mut Expectation {
+        self.server.expect(method)
+    }
+
+    /// Asserts that the bot has sent a request with this method, which contains `matcher`, like
+    /// [`MockBot::assert_request`]
+    ///
+    /// [`MockBot::assert_request`]: crate::MockBot::assert_request
+    pub fn assert_request(&self, method: &str, matcher: JsonContains) {
+        self.server.assert_request(method, matcher);
+    }
+
+    /// Kills the processes, aborts the tasks and stops the fake server
+    pub async fn stop(self) {
+        let MockEnvironment { server, bots, .. } = self;
+        drop(bots);
+        server.stop().await;
+    }
+}
@@ -0,0 +1,184 @@
+//! The fake server on its own, for testing raw `Bot` calls without a dispatcher
+use std::{
+    env,
+    sync::{atomic::AtomicI32, Arc, Mutex},
+};
+
+use teloxide::{
+    prelude::*,
+    types::{Me, UpdateKind},
+};
+
+use crate::{
+    dataset::{IntoUpdate, MockMe},
+    expectations::{Expectation, Expectations, JsonContains},
+    mock_bot::BotLockGuard,
+    server::{self, BotRequest, ServerManager},
+    state::State,
+};
+
+/// The fake server without a handler tree, for projects that call the bot API with
+/// `teloxide-core` directly. The requests of [`bot`] go to the fake server, and they are stored
+/// in the responses, like with [`MockBot`].
+///
+/// Starting it exports `TELOXIDE_TOKEN` and `TELOXIDE_API_URL`, so `Bot::from_env()` is
+/// connected to the fake server too, unless it is started with [`start_isolated`]. Just like [`MockBot`], only one server or bot can exist at a
+/// time.
+///
+/// # Example
+/// ```no_run
+/// use teloxide::prelude::*;
+/// use teloxide_tests::{json_contains, MockServer, MockUser};
+///
+/// #[tokio::test]
+/// async fn test_notification() {
+///     let server = MockServer::start().await;
+///     server
+///         .bot
+///         .send_message(ChatId(MockUser::ID as i64), "Hello!")
+///         .await
+///         .unwrap();
+///
+///     let sent_messages = server.get_responses().sent_messages;
+///     assert_eq!(sent_messages[0].text(), Some("Hello!"));
+///     server.assert_request("sendMessage", json_contains!({"text": "Hello!"}));
+///     server.stop().await;
+/// }
+/// ```
+///
+/// [`bot`]: crate::MockServer::bot
+/// [`start_isolated`]: crate::MockServer::start_isolated
+/// [`MockBot`]: crate::MockBot
+pub struct MockServer {
+    /// The bot connected to the fake server
+    pub bot: Bot,
+    /// Bot parameters, that `getMe` returns
+    pub me: Me,
+
+    pub(crate) expectations: Expectations,
+    pub(crate) current_update_id: AtomicI32,
+    pub(crate) state: Arc<Mutex<State>>,
+    server: Option<ServerManager>,
+    _bot_lock: BotLockGuard,
+}
+
+impl MockServer {
+    /// Starts the fake server with the default [`MockMe`]
+    pub async fn start() -> Self {
+        Self::start_with_me(MockMe::new()).await
+    }
+
+    /// Starts the fake server, with `me` being the bot that `getMe` returns
+    pub async fn start_with_me(me: MockMe) -> Self {
+        Self::start_server(me, true).await
+    }
+
+    /// Starts the fake server like [`start_with_me`], but leaves `TELOXIDE_TOKEN` and
+    /// `TELOXIDE_API_URL` alone, so nothing else reading the env races with it. Only [`bot`] is
+    /// connected to the fake server then, and its `api_url()` is the url of the server.
+    ///
+    /// [`start_with_me`]: crate::MockServer::start_with_me
+    /// [`bot`]: crate::MockServer::bot
+    pub async fn start_isolated(me: MockMe) -> Self {
+        Self::start_server(me, false).await
+    }
+
+    async fn start_server(me: MockMe, export_env: bool) -> Self {
+        let _ = pretty_env_logger::try_init();
+        let lock = BotLockGuard::acquire();
+
+        let me = me.build();
+        let state = Arc::new(Mutex::new(State::default()));
+        let server = ServerManager::start(me.clone(), state.clone())
+            .await
+            .unwrap();
+
+        let api_url = reqwest::Url::parse(&format!("http://127.0.0.1:{}", server.port)).unwrap();
+        let bot = Bot::new("1234567890:QWERTYUIOPASDFGHJKLZXCVBNMQWERTYUIO").set_api_url(api_url);
+        if export_env {
+            env::set_var("TELOXIDE_TOKEN", bot.token());
+            env::set_var("TELOXIDE_API_URL", bot.api_url().to_string());
+        }
+
+        Self {
+            bot,
+            me,
+            expectations: Expectations::new(state.clone()),
+            current_update_id: AtomicI32::new(42),
+            state,
+            server: Some(server),
+            _bot_lock: lock,
+        }
+    }
+
+    /// Adds messages to the fake server, so that the bot can reply to, edit, forward or delete
+    /// them, like [`MockBot::seed_messages`]. Returns the stored messages.
+    ///
+    /// [`MockBot::seed_messages`]: crate::MockBot::seed_messages
+    pub fn seed_messages<T: IntoUpdate>(&self, messages: T) -> Vec<Message> {
+        let mut updates = messages.into_update(&self.current_update_id);
+        self.state.lock().unwrap().insert_updates(&mut updates);
+        updates
+            .into_iter()
+            .filter_map(|update| match update.kind {
+                UpdateKind::Message(message) | UpdateKind::ChannelPost(message) => Some(message),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Seeds the generator of file ids and the other random values, like
+    /// [`MockBot::seed_rng`]
+    ///
+    /// [`MockBot::seed_rng`]: crate::MockBot::seed_rng
+    pub fn seed_rng(&self, seed: u64) {
+        self.state.lock().unwrap().ids.seed(seed);
+    }
+
+    /// Returns the responses to all the requests the bot has sent so far
+    pub fn get_responses(&self) -> server::Responses {
+        self.state.lock().unwrap().responses.clone()
+    }
+
+    /// Returns every request the bot has sent so far, in order
+    pub fn get_requests(&self) -> Vec<BotRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Expects the bot to send a request with this method, like [`MockBot::expect`]. The
+    /// expectations are verified when the server is dropped.
+    ///
+    /// [`MockBot::expect`]: crate::MockBot::expect
+    pub fn expect(&mut self, method: &str) -> &mut Expectation {
+        self.expectations.add(method)
+    }
+
+    /// Asserts that the bot has sent a request with this method, which contains `matcher`, like
+    /// [`MockBot::assert_request`]
+    ///
+    /// [`MockBot::assert_request`]: crate::MockBot::assert_request
+    pub fn assert_request(&self, method: &str, matcher: JsonContains) {
+        if !self.expectations.assert_request(method, &matcher) {
+            panic!(
+                "No {method} request with {} was sent! The requests are:\n{}",
+                matcher.0,
+                self.expectations.describe_requests()
+            );
+        }
+    }
+
+    /// Stops the fake server
+    pub async fn stop(mut self) {
+        if let Some(server) = self.server.take() {
+            server.stop().await.unwrap();
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(server) = &self.server {
+            server.cancel();
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! The message effects that Telegram knows about
+use teloxide::types::EffectId;
+
+/// The ids of the message effects that are available to everyone, with their emojis
+pub const MESSAGE_EFFECTS: [(&str, &str); 6] = [
+    ("5104841245755180586", "🔥"),
+    ("5107584321108051014", "👍"),
+    ("5104858069142078462", "👎"),
+    ("5044134455711629726", "❤️"),
+    ("5046509860389126442", "🎉"),
+    ("5046589136895476101", "💩"),
+];
+
+/// Returns the emoji of the message effect, or `None` if the effect is unknown
+///
+/// # Example
+/// ```
+/// use teloxide::types::EffectId;
+/// use teloxide_tests::server::effects::effect_emoji;
+///
+/// assert_eq!(effect_emoji(&EffectId::from("5104841245755180586")), Some("🔥"));
+/// assert_eq!(effect_emoji(&EffectId::from("123")), None);
+/// ```
+pub fn effect_emoji(effect_id: &EffectId) -> Option<&'static str> {
+    MESSAGE_EFFECTS
+        .iter()
+        .find(|(id, _)| *id == effect_id.0)
+        .map(|(_, emoji)| *emoji)
+}
@@ -0,0 +1,91 @@
+//! Checks that the responses of the fake server decode into teloxide types without losing or
+//! changing anything, see [`MockBot::check_response_fidelity`]
+//!
+//! [`MockBot::check_response_fidelity`]: crate::MockBot::check_response_fidelity
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// Attached to every successful response, checks it when the fidelity mode is on
+pub(crate) struct FidelityCheck(Box<dyn Fn() -> Option<String>>);
+
+impl FidelityCheck {
+    pub(crate) fn new<T>(result: Value) -> Self
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        Self(Box::new(move || round_trip_diff::<T>(&result)))
+    }
+
+    /// Returns what changed in the response after the round trip, if anything did
+    pub(crate) fn run(&self) -> Option<String> {
+        (self.0)()
+    }
+}
+
+fn round_trip_diff<T>(result: &Value) -> Option<String>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let decoded = match serde_json::from_value::<T>(result.clone()) {
+        Ok(decoded) => decoded,
+        Err(err) => return Some(format!("  result: can't be decoded: {err}")),
+    };
+    let round_tripped = serde_json::to_value(decoded).expect("Decoded values can be serialized");
+    let mut diff = vec![];
+    json_diff("result", result, &round_tripped, &mut diff);
+    (!diff.is_empty()).then(|| diff.join("\n"))
+}
+
+fn json_diff(path: &str, sent: &Value, decoded: &Value, diff: &mut Vec<String>) {
+    match (sent, decoded) {
+        (Value::Object(sent), Value::Object(decoded)) => {
+            for (key, sent_value) in sent {
+                let field_path = format!("{path}.{key}");
+                match decoded.get(key) {
+                    Some(decoded_value) => json_diff(&field_path, sent_value, decoded_value, diff),
+                    // Teloxide skips `None` fields, so a `null` that went away is the same
+                    None if sent_value.is_null() => {}
+                    None => diff.push(format!("- {field_path}: {sent_value}")),
+                }
+            }
+            for (key, decoded_value) in decoded {
+                if !sent.contains_key(key) && !decoded_value.is_null() {
+                    diff.push(format!("+ {path}.{key}: {decoded_value}"));
+                }
+            }
+        }
+        (Value::Array(sent), Value::Array(decoded)) if sent.len() == decoded.len() => {
+            for (i, (sent, decoded)) in sent.iter().zip(decoded).enumerate() {
+                json_diff(&format!("{path}[{i}]"), sent, decoded, diff);
+            }
+        }
+        _ if sent != decoded => {
+            diff.push(format!("- {path}: {sent}"));
+            diff.push(format!("+ {path}: {decoded}"));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use teloxide::types::MessageId;
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip_diff() {
+        assert_eq!(
+            round_trip_diff::<MessageId>(&json!({"message_id": 1})),
+            None
+        );
+        assert_eq!(
+            round_trip_diff::<MessageId>(&json!({"message_id": 1, "extra": true})),
+            Some("- result.extra: true".to_string())
+        );
+        assert!(round_trip_diff::<MessageId>(&json!({"message_id": "1"}))
+            .unwrap()
+            .starts_with("  result: can't be decoded"));
+    }
+}
@@ -0,0 +1,291 @@
+//! Finds the markup errors Telegram reports as `can't parse entities`, when validating like
+//! Telegram
+use teloxide::types::ParseMode;
+
+/// The tags Telegram supports in the HTML parse mode
+const HTML_TAGS: [&str; 18] = [
+    "b",
+    "strong",
+    "i",
+    "em",
+    "u",
+    "ins",
+    "s",
+    "strike",
+    "del",
+    "span",
+    "tg-spoiler",
+    "a",
+    "tg-emoji",
+    "code",
+    "pre",
+    "blockquote",
+    "br",
+    "tg-time",
+];
+
+/// The characters that must be escaped with `\` in the MarkdownV2 parse mode, outside of the
+/// entities that use them
+const MARKDOWN_V2_RESERVED: &str = "_*[]()~`>#+-=|{}.!";
+
+/// Returns why Telegram can't parse `text` in `parse_mode`, like `Unsupported start tag "foo"
+/// at byte offset 0`, or `None` if it can
+pub(crate) fn markup_error(text: &str, parse_mode: ParseMode) -> Option<String> {
+    match parse_mode {
+        ParseMode::Html => html_error(text),
+        ParseMode::MarkdownV2 => markdown_v2_error(text),
+        #[allow(deprecated)]
+        ParseMode::Markdown => markdown_error(text),
+    }
+}
+
+fn html_error(text: &str) -> Option<String> {
+    let mut open_tags: Vec<(String, usize)> = vec![];
+    let mut rest = text.char_indices().peekable();
+    while let Some((offset, c)) = rest.next() {
+        if c != '<' {
+            continue;
+        }
+        let Some(tag_length) = text[offset..].find('>') else {
+            return Some(format!("Unclosed start tag at byte offset {offset}"));
+        };
+        let tag = &text[offset + 1..offset + tag_length];
+        // Skips the tag, the parser goes on after its `>`
+        while rest.next_if(|(i, _)| *i <= offset + tag_length).is_some() {}
+
+        if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim().to_lowercase();
+            match open_tags.pop() {
+                Some((open, _)) if open == name => {}
+                Some((open, _)) => {
+                    return Some(format!(
+                        "Unmatched end tag at byte offset {offset}, expected \"</{open}>\", \
+                         found \"</{name}>\""
+                    ))
+                }
+                None => return Some(format!("Unexpected end tag at byte offset {offset}")),
+            }
+            continue;
+        }
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        if !HTML_TAGS.contains(&name.as_str()) {
+            return Some(format!(
+                "Unsupported start tag \"{name}\" at byte offset {offset}"
+            ));
+        }
+        if name != "br" && !tag.ends_with('/') {
+            open_tags.push((name, offset));
+        }
+    }
+    open_tags
+        .pop()
+        .map(|(name, _)| format!("Can't find end tag corresponding to start tag \"{name}\""))
+}
+
+fn markdown_v2_error(text: &str) -> Option<String> {
+    // The open entities with the offsets of their starts
+    let mut open: Vec<(&str, usize)> = vec![];
+    let mut in_link_url = false;
+    let mut line_start = true;
+    let mut chars = text.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        let at_line_start = std::mem::replace(&mut line_start, c == '\n');
+        let code = open
+            .last()
+            .filter(|(kind, _)| matches!(*kind, "Code" | "Pre"))
+            .map(|(kind, _)| *kind);
+        if c == '\\' {
+            if chars.next().is_none() {
+                return Some(
+                    "Character '\\' is reserved and must be escaped with the preceding '\\'"
+                        .to_string(),
+                );
+            }
+            continue;
+        }
+        if in_link_url {
+            in_link_url = c != ')';
+            continue;
+        }
+        if let Some(code) = code {
+            // Only the end of the code matters inside of it
+            let is_end = match code {
+                "Pre" => text[offset..].starts_with("```"),
+                _ => c == '`',
+            };
+            if is_end {
+                if code == "Pre" {
+                    chars.next();
+                    chars.next();
+                }
+                open.pop();
+            }
+            continue;
+        }
+        let kind = match c {
+            '*' => "Bold",
+            '_' if chars.next_if(|(_, next)| *next == '_').is_some() => "Underline",
+            '_' => "Italic",
+            '~' => "Strikethrough",
+            '|' if chars.next_if(|(_, next)| *next == '|').is_some() => "Spoiler",
+            '`' if text[offset..].starts_with("```") => {
+                chars.next();
+                chars.next();
+                open.push(("Pre", offset));
+                continue;
+            }
+            '`' => {
+                open.push(("Code", offset));
+                continue;
+            }
+            '[' => {
+                open.push(("TextUrl", offset));
+                continue;
+            }
+            '!' if text[offset..].starts_with("![") => {
+                chars.next();
+                open.push(("CustomEmoji", offset));
+                continue;
+            }
+            ']' if open
+                .last()
+                .is_some_and(|(kind, _)| matches!(*kind, "TextUrl" | "CustomEmoji")) =>
+            {
+                if chars.next_if(|(_, next)| *next == '(').is_none() {
+                    return Some(
+                        "Character ']' is reserved and must be escaped with the preceding '\\'"
+                            .to_string(),
+                    );
+                }
+                open.pop();
+                in_link_url = true;
+                continue;
+            }
+            '>' if at_line_start => continue,
+            c if MARKDOWN_V2_RESERVED.contains(c) => {
+                return Some(format!(
+                    "Character '{c}' is reserved and must be escaped with the preceding '\\'"
+                ));
+            }
+            _ => continue,
+        };
+        match open.iter().rposition(|(open_kind, _)| *open_kind == kind) {
+            Some(position) => {
+                open.remove(position);
+            }
+            None => open.push((kind, offset)),
+        }
+    }
+    if in_link_url {
+        return Some("Can't find end of a url".to_string());
+    }
+    open.first()
+        .map(|(kind, offset)| format!("Can't find end of {kind} entity at byte offset {offset}"))
+}
+
+fn markdown_error(text: &str) -> Option<String> {
+    let mut chars = text.char_indices();
+    while let Some((offset, c)) = chars.next() {
+        let end = match c {
+            '*' | '_' => c.to_string(),
+            '`' if text[offset..].starts_with("```") => {
+                chars.nth(1);
+                "```".to_string()
+            }
+            '`' => "`".to_string(),
+            '[' => "](".to_string(),
+            _ => continue,
+        };
+        let start = offset + if end == "```" { 3 } else { 1 };
+        let Some(length) = text[start..].find(&end) else {
+            return Some(format!(
+                "Can't find end of the entity starting at byte offset {offset}"
+            ));
+        };
+        let mut end_offset = start + length + end.len();
+        if c == '[' {
+            let Some(url_length) = text[end_offset..].find(')') else {
+                return Some(format!(
+                    "Can't find end of the entity starting at byte offset {offset}"
+                ));
+            };
+            end_offset += url_length + 1;
+        }
+        // The entities of the legacy Markdown can't be nested, so their content is skipped
+        while chars.as_str().len() > text.len() - end_offset {
+            chars.next();
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_errors() {
+        assert_eq!(
+            markup_error("<b>bold</b> <a href=\"t.me\">link</a>", ParseMode::Html),
+            None
+        );
+        assert_eq!(
+            markup_error("<foo>text</foo>", ParseMode::Html),
+            Some("Unsupported start tag \"foo\" at byte offset 0".to_string())
+        );
+        assert_eq!(
+            markup_error("<b>bold <i>both</b></i>", ParseMode::Html),
+            Some(
+                "Unmatched end tag at byte offset 15, expected \"</i>\", found \"</b>\""
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            markup_error("<b>bold", ParseMode::Html),
+            Some("Can't find end tag corresponding to start tag \"b\"".to_string())
+        );
+        assert_eq!(
+            markup_error("1 < 2", ParseMode::Html),
+            Some("Unclosed start tag at byte offset 2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_markdown_v2_errors() {
+        assert_eq!(
+            markup_error(
+                "*bold* _italic_ __underline__ ||spoiler|| `a.b` [link](https://t.me/a_b) 1\\.5",
+                ParseMode::MarkdownV2
+            ),
+            None
+        );
+        assert_eq!(markup_error("> quote", ParseMode::MarkdownV2), None);
+        assert_eq!(
+            markup_error("Price: 1.5", ParseMode::MarkdownV2),
+            Some(
+                "Character '.' is reserved and must be escaped with the preceding '\\'".to_string()
+            )
+        );
+        assert_eq!(
+            markup_error("*bold", ParseMode::MarkdownV2),
+            Some("Can't find end of Bold entity at byte offset 0".to_string())
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_markdown_errors() {
+        assert_eq!(
+            markup_error("*bold* [link](https://t.me/a_b) 1.5", ParseMode::Markdown),
+            None
+        );
+        assert_eq!(
+            markup_error("snake_case", ParseMode::Markdown),
+            Some("Can't find end of the entity starting at byte offset 5".to_string())
+        );
+    }
+}
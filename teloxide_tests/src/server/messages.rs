@@ -1,15 +1,32 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use serde::Serialize;
-use teloxide::types::{Message, ReplyMarkup};
+use teloxide::types::{InlineKeyboardMarkup, Message, ReplyMarkup};
 
 #[derive(Default)]
 pub struct Messages {
     pub messages: Vec<Message>,
     last_message_id: i32,
+    /// Every inline keyboard the messages had, in the order they were set
+    keyboards: HashMap<i32, Vec<InlineKeyboardMarkup>>,
 }
 
 impl Messages {
+    /// Returns all of the inline keyboards the message had, from the one it was sent with to the
+    /// one of its last edit
+    pub fn keyboard_history(&self, message_id: i32) -> Vec<InlineKeyboardMarkup> {
+        self.keyboards.get(&message_id).cloned().unwrap_or_default()
+    }
+
+    fn record_keyboard(&mut self, message: &Message) {
+        if let Some(keyboard) = message.reply_markup() {
+            self.keyboards
+                .entry(message.id.0)
+                .or_default()
+                .push(keyboard.clone());
+        }
+    }
+
     pub fn max_message_id(&self) -> i32 {
         self.last_message_id
     }
@@ -19,6 +36,7 @@ impl Messages {
 
         self.messages.retain(|m| m.id != message.id); // Remove the old message
         self.messages.push(message.clone()); // Add the new message
+        self.record_keyboard(&message);
         Some(message) // Profit!
     }
 
@@ -40,6 +58,9 @@ impl Messages {
 
         self.messages.retain(|m| m.id.0 != message_id); // Remove the old message
         self.messages.push(new_message.clone()); // Add the new message
+        if field == "reply_markup" {
+            self.record_keyboard(&new_message);
+        }
         Some(new_message) // Profit!
     }
 
@@ -62,11 +83,24 @@ impl Messages {
     }
 
     pub fn add_message(&mut self, message: Message) -> Message {
+        self.record_keyboard(&message);
         self.messages.push(message.clone());
         self.last_message_id += 1;
         message
     }
 
+    /// Returns the messages that match `predicate`, ordered by their ids
+    pub fn find_messages(&self, predicate: impl Fn(&Message) -> bool) -> Vec<Message> {
+        let mut messages: Vec<_> = self
+            .messages
+            .iter()
+            .filter(|message| predicate(message))
+            .cloned()
+            .collect();
+        messages.sort_by_key(|message| message.id.0);
+        messages
+    }
+
     pub fn get_message(&self, message_id: i32) -> Option<Message> {
         self.messages.iter().find(|m| m.id.0 == message_id).cloned()
     }
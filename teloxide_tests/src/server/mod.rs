@@ -1,5 +1,5 @@
 //! A fake telegram bot API for testing purposes. Read more in teloxide_tests crate.
-mod routes;
+pub(crate) mod routes;
 use std::{
     error::Error,
     io,
@@ -8,33 +8,61 @@ use std::{
 };
 
 use actix_web::{
+    middleware::from_fn,
     web::{self, get, post, scope, Data, ServiceConfig},
     App, HttpResponse, HttpServer, Responder,
 };
+use requests::record_request;
+pub use requests::BotRequest;
 pub use responses::*;
+pub use routes::{
+    answer_callback_query::AnswerCallbackQueryBody,
+    copy_message::CopyMessageBody,
+    delete_message::DeleteMessageBody,
+    delete_messages::DeleteMessagesBody,
+    edit_message_caption::EditMessageCaptionBody,
+    edit_message_live_location::EditMessageLiveLocationBody,
+    edit_message_reply_markup::EditMessageReplyMarkupBody,
+    edit_message_text::EditMessageTextBody,
+    forward_message::ForwardMessageBody,
+    get_business_account_gifts::{
+        OwnedGift, OwnedGiftRegular, OwnedGiftUnique, OwnedGifts, UniqueGift, UniqueGiftBackdrop,
+        UniqueGiftBackdropColors, UniqueGiftModel, UniqueGiftSymbol,
+    },
+    get_business_account_star_balance::StarAmount,
+    send_animation::SendMessageAnimationBody,
+    send_audio::SendMessageAudioBody,
+    send_contact::SendMessageContactBody,
+    send_dice::SendMessageDiceBody,
+    send_document::SendMessageDocumentBody,
+    send_invoice::SendMessageInvoiceBody,
+    send_location::SendMessageLocationBody,
+    send_media_group::SendMediaGroupBody,
+    send_message::SendMessageTextBody,
+    send_photo::SendMessagePhotoBody,
+    send_poll::SendMessagePollBody,
+    send_sticker::SendMessageStickerBody,
+    send_venue::SendMessageVenueBody,
+    send_video::SendMessageVideoBody,
+    send_video_note::SendMessageVideoNoteBody,
+    set_business_account_profile_photo::InputProfilePhoto,
+};
 use routes::{
-    answer_callback_query::*, ban_chat_member::*, copy_message::*, delete_message::*,
-    delete_messages::*, download_file::download_file, edit_message_caption::*,
-    edit_message_reply_markup::*, edit_message_text::*, forward_message::*, get_file::*, get_me::*,
-    get_updates::*, get_webhook_info::*, pin_chat_message::*, restrict_chat_member::*,
+    answer_callback_query::*, answer_inline_query::*, ban_chat_member::*, convert_gift_to_stars::*,
+    copy_message::*, delete_business_messages::*, delete_message::*, delete_messages::*,
+    delete_my_commands::*, download_file::download_file, edit_message_caption::*,
+    edit_message_live_location::*, edit_message_reply_markup::*, edit_message_text::*,
+    forward_message::*, get_business_account_gifts::*, get_business_account_star_balance::*,
+    get_chat::*, get_chat_administrators::*, get_chat_member::*, get_chat_menu_button::*,
+    get_custom_emoji_stickers::*, get_file::*, get_me::*, get_my_commands::*, get_updates::*,
+    get_webhook_info::*, pin_chat_message::*, read_business_message::*, restrict_chat_member::*,
     send_animation::*, send_audio::*, send_chat_action::*, send_contact::*, send_dice::*,
     send_document::*, send_invoice::*, send_location::*, send_media_group::*, send_message::*,
     send_photo::*, send_poll::*, send_sticker::*, send_venue::*, send_video::*, send_video_note::*,
-    send_voice::*, set_message_reaction::*, set_my_commands::*, unban_chat_member::*,
-    unpin_all_chat_messages::*, unpin_chat_message::*,
-};
-pub use routes::{
-    copy_message::CopyMessageBody, delete_message::DeleteMessageBody,
-    delete_messages::DeleteMessagesBody, edit_message_caption::EditMessageCaptionBody,
-    edit_message_reply_markup::EditMessageReplyMarkupBody, edit_message_text::EditMessageTextBody,
-    forward_message::ForwardMessageBody, send_animation::SendMessageAnimationBody,
-    send_audio::SendMessageAudioBody, send_contact::SendMessageContactBody,
-    send_dice::SendMessageDiceBody, send_document::SendMessageDocumentBody,
-    send_invoice::SendMessageInvoiceBody, send_location::SendMessageLocationBody,
-    send_media_group::SendMediaGroupBody, send_message::SendMessageTextBody,
-    send_photo::SendMessagePhotoBody, send_poll::SendMessagePollBody,
-    send_sticker::SendMessageStickerBody, send_venue::SendMessageVenueBody,
-    send_video::SendMessageVideoBody, send_video_note::SendMessageVideoNoteBody,
+    send_voice::*, set_business_account_bio::*, set_business_account_name::*,
+    set_business_account_profile_photo::*, set_chat_menu_button::*, set_message_reaction::*,
+    set_my_commands::*, set_passport_data_errors::*, transfer_gift::*, unban_chat_member::*,
+    unpin_all_chat_messages::*, unpin_chat_message::*, upgrade_gift::*,
 };
 use teloxide::types::Me;
 use tokio::{
@@ -45,7 +73,11 @@ use tokio_util::sync::CancellationToken;
 
 use crate::state::State;
 
+pub mod effects;
+pub(crate) mod fidelity;
+pub(crate) mod markup;
 pub mod messages;
+pub mod requests;
 pub mod responses;
 
 pub(crate) struct ServerManager {
@@ -80,6 +112,11 @@ impl ServerManager {
         })
     }
 
+    /// Stops the server without waiting for it, for when `stop` can't be awaited
+    pub(crate) fn cancel(&self) {
+        self.cancel_token.cancel();
+    }
+
     pub(crate) async fn stop(self) -> Result<(), JoinError> {
         self.cancel_token.cancel();
         self.server.await
@@ -114,6 +151,9 @@ fn create_server(
         App::new()
             .app_data(Data::new(me.clone()))
             .app_data(Data::from(state.clone()))
+            // The uploads over the Bot API limit get to the routes, that reject them only when
+            // validating like Telegram, instead of failing on the small default limit of actix
+            .app_data(web::PayloadConfig::new(usize::MAX))
             .configure(set_routes)
     })
     .listen(listener)?
@@ -122,51 +162,86 @@ fn create_server(
 
 fn set_routes(cfg: &mut ServiceConfig) {
     cfg.route("/file/bot{token}/{file_name}", get().to(download_file))
-        .service(scope("/bot{token}").configure(set_bot_routes));
+        .service(
+            scope("/bot{token}")
+                .wrap(from_fn(record_request))
+                .configure(set_bot_routes),
+        );
+}
+
+// Makes both the routes and the list of the methods they implement, so they can't drift apart
+macro_rules! bot_routes {
+    ($($method:ident => $route:expr,)*) => {
+        /// The Bot API methods the fake server implements, as teloxide sends them, like
+        /// `SendMessage`
+        pub(crate) const BOT_METHODS: &[&str] = &[$(stringify!($method)),*];
+
+        fn set_bot_routes(cfg: &mut ServiceConfig) {
+            $(cfg.route(concat!("/", stringify!($method)), post().to($route));)*
+            cfg.route("/{unknown_endpoint}", post().to(unknown_endpoint));
+        }
+    };
 }
 
-fn set_bot_routes(cfg: &mut ServiceConfig) {
-    cfg.route("/GetFile", post().to(get_file))
-        .route("/SendMessage", post().to(send_message))
-        .route("/GetWebhookInfo", post().to(get_webhook_info))
-        .route("/GetMe", post().to(get_me))
-        .route("/GetUpdates", post().to(get_updates))
-        .route("/SendPhoto", post().to(send_photo))
-        .route("/SendVideo", post().to(send_video))
-        .route("/SendVoice", post().to(send_voice))
-        .route("/SendAudio", post().to(send_audio))
-        .route("/SendVideoNote", post().to(send_video_note))
-        .route("/SendDocument", post().to(send_document))
-        .route("/SendAnimation", post().to(send_animation))
-        .route("/SendLocation", post().to(send_location))
-        .route("/SendVenue", post().to(send_venue))
-        .route("/SendContact", post().to(send_contact))
-        .route("/SendSticker", post().to(send_sticker))
-        .route("/SendChatAction", post().to(send_chat_action))
-        .route("/SendDice", post().to(send_dice))
-        .route("/SendPoll", post().to(send_poll))
-        .route("/SendMediaGroup", post().to(send_media_group))
-        .route("/SendInvoice", post().to(send_invoice))
-        .route("/EditMessageText", post().to(edit_message_text))
-        .route("/EditMessageCaption", post().to(edit_message_caption))
-        .route(
-            "/EditMessageReplyMarkup",
-            post().to(edit_message_reply_markup),
-        )
-        .route("/DeleteMessage", post().to(delete_message))
-        .route("/DeleteMessages", post().to(delete_messages))
-        .route("/ForwardMessage", post().to(forward_message))
-        .route("/CopyMessage", post().to(copy_message))
-        .route("/AnswerCallbackQuery", post().to(answer_callback_query))
-        .route("/PinChatMessage", post().to(pin_chat_message))
-        .route("/UnpinChatMessage", post().to(unpin_chat_message))
-        .route("/UnpinAllChatMessages", post().to(unpin_all_chat_messages))
-        .route("/BanChatMember", post().to(ban_chat_member))
-        .route("/UnbanChatMember", post().to(unban_chat_member))
-        .route("/RestrictChatMember", post().to(restrict_chat_member))
-        .route("/SetMessageReaction", post().to(set_message_reaction))
-        .route("/SetMyCommands", post().to(set_my_commands))
-        .route("/{unknown_endpoint}", post().to(unknown_endpoint));
+bot_routes! {
+    GetFile => get_file,
+    SendMessage => send_message,
+    GetWebhookInfo => get_webhook_info,
+    GetMe => get_me,
+    GetUpdates => get_updates,
+    SendPhoto => send_photo,
+    SendVideo => send_video,
+    SendVoice => send_voice,
+    SendAudio => send_audio,
+    SendVideoNote => send_video_note,
+    SendDocument => send_document,
+    SendAnimation => send_animation,
+    SendLocation => send_location,
+    SendVenue => send_venue,
+    SendContact => send_contact,
+    SendSticker => send_sticker,
+    SendChatAction => send_chat_action,
+    SendDice => send_dice,
+    SendPoll => send_poll,
+    SendMediaGroup => send_media_group,
+    SendInvoice => send_invoice,
+    EditMessageText => edit_message_text,
+    EditMessageCaption => edit_message_caption,
+    EditMessageLiveLocation => edit_message_live_location,
+    EditMessageReplyMarkup => edit_message_reply_markup,
+    DeleteMessage => delete_message,
+    DeleteMessages => delete_messages,
+    ForwardMessage => forward_message,
+    CopyMessage => copy_message,
+    AnswerCallbackQuery => answer_callback_query,
+    AnswerInlineQuery => answer_inline_query,
+    PinChatMessage => pin_chat_message,
+    UnpinChatMessage => unpin_chat_message,
+    UnpinAllChatMessages => unpin_all_chat_messages,
+    BanChatMember => ban_chat_member,
+    UnbanChatMember => unban_chat_member,
+    RestrictChatMember => restrict_chat_member,
+    SetMessageReaction => set_message_reaction,
+    SetMyCommands => set_my_commands,
+    DeleteMyCommands => delete_my_commands,
+    GetMyCommands => get_my_commands,
+    SetPassportDataErrors => set_passport_data_errors,
+    ReadBusinessMessage => read_business_message,
+    DeleteBusinessMessages => delete_business_messages,
+    SetBusinessAccountName => set_business_account_name,
+    SetBusinessAccountBio => set_business_account_bio,
+    SetBusinessAccountProfilePhoto => set_business_account_profile_photo,
+    GetBusinessAccountStarBalance => get_business_account_star_balance,
+    GetBusinessAccountGifts => get_business_account_gifts,
+    ConvertGiftToStars => convert_gift_to_stars,
+    GetChat => get_chat,
+    GetChatMember => get_chat_member,
+    GetChatAdministrators => get_chat_administrators,
+    UpgradeGift => upgrade_gift,
+    TransferGift => transfer_gift,
+    GetCustomEmojiStickers => get_custom_emoji_stickers,
+    SetChatMenuButton => set_chat_menu_button,
+    GetChatMenuButton => get_chat_menu_button,
 }
 
 async fn unknown_endpoint(path: web::Path<(String, String)>) -> impl Responder {
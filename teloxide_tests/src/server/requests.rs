@@ -0,0 +1,132 @@
+use std::sync::{Arc, Mutex};
+
+use actix_web::{
+    body::{self, EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    error::ErrorInternalServerError,
+    http::header::CONTENT_TYPE,
+    middleware::Next,
+    web::{Bytes, Data},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{fidelity::FidelityCheck, routes::get_raw_multipart_fields};
+use crate::{state::State, transcript::TranscriptEntry};
+
+/// Rewrites the descriptions of the errors the fake server returns, see
+/// `MockBot::localize_errors`
+pub(crate) type ErrorLocalizer = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// A request the bot sent to the fake server
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BotRequest {
+    /// The Bot API method, like `sendMessage`
+    pub method: String,
+    /// The parameters of the request. For multipart requests every field is parsed as JSON if
+    /// it can be, and files are replaced with their names
+    pub body: Value,
+}
+
+/// Teloxide sends methods like `/SendMessage`, but the Bot API docs call it `sendMessage`
+pub(crate) fn method_name(route: &str) -> String {
+    let mut chars = route.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Remembers every request to the bot API, before it gets to its route
+pub(crate) async fn record_request(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody, Bytes>>, Error> {
+    let method = method_name(req.path().rsplit('/').next().unwrap_or_default());
+
+    let bytes = req.extract::<Bytes>().await?;
+    let is_multipart = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("multipart/form-data"));
+    let body = if is_multipart {
+        let stream = futures_util::stream::once({
+            let bytes = bytes.clone();
+            async move { Ok(bytes) }
+        });
+        let mut multipart = actix_multipart::Multipart::new(req.headers(), stream);
+        let (fields, attachments) = get_raw_multipart_fields(&mut multipart).await;
+
+        let mut body = serde_json::Map::new();
+        for (name, value) in fields {
+            let value = serde_json::from_str(&value).unwrap_or(Value::String(value));
+            body.insert(name, value);
+        }
+        for attachment in attachments.into_values() {
+            body.insert(attachment.raw_name, Value::String(attachment.file_name));
+        }
+        Value::Object(body)
+    } else {
+        serde_json::from_slice(&bytes).unwrap_or(Value::Object(serde_json::Map::new()))
+    };
+    req.set_payload(bytes.into());
+
+    let state = req.app_data::<Data<Mutex<State>>>().cloned();
+    if let Some(state) = &state {
+        let request = BotRequest {
+            method: method.clone(),
+            body,
+        };
+        let mut state = state.lock().unwrap();
+        state
+            .transcript
+            .push(TranscriptEntry::Request(request.clone()));
+        state.coverage.record(&method);
+        state.requests.push(request);
+    }
+
+    let res = next.call(req).await?;
+    let mut error_localizer = None;
+    if let Some(state) = state {
+        let mut state = state.lock().unwrap();
+        if state.config.check_fidelity {
+            let diff = res
+                .response()
+                .extensions()
+                .get::<FidelityCheck>()
+                .and_then(FidelityCheck::run);
+            if let Some(diff) = diff {
+                state.fidelity_errors.push(format!("{method}:\n{diff}"));
+            }
+        }
+        error_localizer = state.config.error_localizer.clone();
+    }
+    match error_localizer {
+        Some(localize) if !res.status().is_success() => localize_error(res, &*localize).await,
+        _ => Ok(res.map_into_left_body()),
+    }
+}
+
+/// Replaces the description of an error response with the localized one. Responses that aren't
+/// JSON, like the plain text errors of actix, are left as they are.
+async fn localize_error<B: MessageBody>(
+    res: ServiceResponse<B>,
+    localize: &(dyn Fn(&str) -> String + Send + Sync),
+) -> Result<ServiceResponse<EitherBody<B, Bytes>>, Error> {
+    let (req, res) = res.into_parts();
+    let (res, res_body) = res.into_parts();
+    let bytes = body::to_bytes(res_body)
+        .await
+        .map_err(|error| ErrorInternalServerError(error.into()))?;
+    let localized = serde_json::from_slice::<Value>(&bytes)
+        .ok()
+        .and_then(|mut value| {
+            let description = value.get("description")?.as_str()?;
+            value["description"] = Value::String(localize(description));
+            serde_json::to_vec(&value).ok()
+        });
+    let res = res.set_body(localized.map(Bytes::from).unwrap_or(bytes));
+    Ok(ServiceResponse::new(req, res).map_into_right_body())
+}
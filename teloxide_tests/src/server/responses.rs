@@ -1,150 +1,272 @@
-use teloxide::types::{Message, MessageId};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use teloxide::{
+    types::{
+        CopyTextButton, LoginUrl, Me, MediaKind, Message, MessageEntity, MessageId, MessageKind,
+    },
+    utils::render::Renderer,
+};
 
 use super::routes::{
-    answer_callback_query::*, ban_chat_member::*, copy_message::*, delete_message::*,
-    edit_message_caption::*, edit_message_reply_markup::*, edit_message_text::*,
-    forward_message::*, pin_chat_message::*, restrict_chat_member::*, send_animation::*,
+    answer_callback_query::*, answer_inline_query::*, ban_chat_member::*, convert_gift_to_stars::*,
+    copy_message::*, delete_business_messages::*, delete_message::*, delete_my_commands::*,
+    edit_message_caption::*, edit_message_live_location::*, edit_message_reply_markup::*,
+    edit_message_text::*, forward_message::*, get_business_account_gifts::*,
+    get_business_account_star_balance::*, get_chat::*, get_chat_administrators::*,
+    get_chat_member::*, get_chat_menu_button::*, get_custom_emoji_stickers::*, get_my_commands::*,
+    pin_chat_message::*, read_business_message::*, restrict_chat_member::*, send_animation::*,
     send_audio::*, send_chat_action::*, send_contact::*, send_dice::*, send_document::*,
     send_invoice::*, send_location::*, send_media_group::*, send_message::*, send_photo::*,
     send_poll::*, send_sticker::*, send_venue::*, send_video::*, send_video_note::*, send_voice::*,
-    set_message_reaction::*, set_my_commands::*, unban_chat_member::*, unpin_all_chat_messages::*,
-    unpin_chat_message::*,
+    set_business_account_bio::*, set_business_account_name::*,
+    set_business_account_profile_photo::*, set_chat_menu_button::*, set_message_reaction::*,
+    set_my_commands::*, set_passport_data_errors::*, transfer_gift::*, unban_chat_member::*,
+    unpin_all_chat_messages::*, unpin_chat_message::*, upgrade_gift::*, BodyChatId,
 };
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessageText {
     // For better syntax, this is a struct, not a tuple
     pub message: Message,
     pub bot_request: SendMessageTextBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessagePhoto {
     pub message: Message,
     pub bot_request: SendMessagePhotoBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessageVideo {
     pub message: Message,
     pub bot_request: SendMessageVideoBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessageAudio {
     pub message: Message,
     pub bot_request: SendMessageAudioBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessageVoice {
     pub message: Message,
     pub bot_request: SendMessageVoiceBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessageVideoNote {
     pub message: Message,
     pub bot_request: SendMessageVideoNoteBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessageDocument {
     pub message: Message,
     pub bot_request: SendMessageDocumentBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessageAnimation {
     pub message: Message,
     pub bot_request: SendMessageAnimationBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessageLocation {
     pub message: Message,
     pub bot_request: SendMessageLocationBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessageVenue {
     pub message: Message,
     pub bot_request: SendMessageVenueBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessageContact {
     pub message: Message,
     pub bot_request: SendMessageContactBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessageDice {
     pub message: Message,
     pub bot_request: SendMessageDiceBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessagePoll {
     pub message: Message,
     pub bot_request: SendMessagePollBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessageSticker {
     pub message: Message,
     pub bot_request: SendMessageStickerBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMediaGroup {
     pub messages: Vec<Message>,
     pub bot_request: SendMediaGroupBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SentMessageInvoice {
     pub message: Message,
     pub bot_request: SendMessageInvoiceBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EditedMessageText {
     pub message: Message,
     pub bot_request: EditMessageTextBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EditedMessageCaption {
     pub message: Message,
     pub bot_request: EditMessageCaptionBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EditedMessageLiveLocation {
+    pub message: Message,
+    pub bot_request: EditMessageLiveLocationBody,
+    #[serde(default)]
+    pub seq: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DeletedMessage {
     pub message: Message,
     pub bot_request: DeleteMessageBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EditedMessageReplyMarkup {
     pub message: Message,
     pub bot_request: EditMessageReplyMarkupBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ForwardedMessage {
     pub message: Message,
     pub bot_request: ForwardMessageBody,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CopiedMessage {
     pub message_id: MessageId,
     pub bot_request: CopyMessageBody,
+    #[serde(default)]
+    pub seq: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SentCopyTextButton {
+    pub message: Message,
+    pub text: String,
+    pub copy_text: CopyTextButton,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SentLoginUrlButton {
+    pub message: Message,
+    pub text: String,
+    pub login_url: LoginUrl,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SentChatAction {
+    /// Has the chat, the action and the `message_thread_id` and `business_connection_id` it was
+    /// sent to
+    pub bot_request: SendChatActionBody,
+    /// When the action was sent, by the mock clock
+    pub date: DateTime<Utc>,
+    #[serde(default)]
+    pub seq: u64,
 }
 
-#[derive(Clone, Debug, Default)]
+/// What happened to one of the messages the bot tried to delete
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeleteOutcome {
+    pub chat_id: BodyChatId,
+    pub message_id: i32,
+    /// Whether the message was deleted. `deleteMessage` fails for the messages that aren't in the
+    /// chat, and `deleteMessages` skips them.
+    pub deleted: bool,
+}
+
+/// A field of a multipart request, like `sendPhoto`, that the fake server didn't put into the
+/// body of the request, because it doesn't know the field or failed to parse it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DroppedField {
+    /// The Bot API method of the request, like `sendPhoto`
+    pub method: String,
+    pub field: String,
+    /// The raw value of the field, as the bot sent it
+    pub value: String,
+    /// The error of parsing the field, or `None` if the body has no such field
+    pub error: Option<String>,
+}
+
+/// Every `Sent*`, `Edited*`, `Deleted*`, `Forwarded*` and `Copied*` record has a `seq` field,
+/// numbered from 1 in the order the requests of the dispatch were handled across all of the
+/// lists, so the order of e.g. an edit and a following send can be checked.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Responses {
     /// All of the sent messages, including text, photo, audio, etc.
     /// Be warned, editing or deleting messages do not affect this list!
@@ -240,16 +362,37 @@ pub struct Responses {
     /// has the request that was sent to the fake server
     pub edited_messages_caption: Vec<EditedMessageCaption>,
 
+    /// This has only live location messages edited by the bot.
+    /// The `.message` field has the new edited message, and `.bot_request`
+    /// has the request that was sent to the fake server
+    pub edited_messages_live_location: Vec<EditedMessageLiveLocation>,
+
     /// This has only messages whos reply markup was edited by the bot.
     /// The `.message` field has the new edited message, and `.bot_request`
     /// has the request that was sent to the fake server
     pub edited_messages_reply_markup: Vec<EditedMessageReplyMarkup>,
 
+    /// This has all copy text buttons the bot has sent or edited messages with.
+    /// The `.message` field has the message with the button, `.text` has the button
+    /// text and `.copy_text` has the text that the button copies
+    pub copy_text_buttons: Vec<SentCopyTextButton>,
+
+    /// This has all login url buttons the bot has sent or edited messages with.
+    /// The `.message` field has the message with the button, `.text` has the button
+    /// text and `.login_url` has the login url of the button
+    pub login_url_buttons: Vec<SentLoginUrlButton>,
+
     /// This has only messages which were deleted by the bot.
     /// The `.message` field has the deleted message, and `.bot_request`
     /// has the request that was sent to the fake server
     pub deleted_messages: Vec<DeletedMessage>,
 
+    /// Every message id the bot tried to delete with `deleteMessage` or `deleteMessages`, in the
+    /// order of the requests, and whether it was deleted. Unlike `deleted_messages`, it has the
+    /// ids that weren't found too.
+    #[serde(default)]
+    pub delete_outcomes: Vec<DeleteOutcome>,
+
     /// This has only the requests that were sent to the fake server to forward messages.
     /// The `.message` field has the forwarded message, and `.bot_request`
     /// has the request that was sent to the fake server
@@ -265,6 +408,11 @@ pub struct Responses {
     /// `.message` field.
     pub answered_callback_queries: Vec<AnswerCallbackQueryBody>,
 
+    /// This has only the requests that were sent to the fake server to answer inline queries.
+    /// Telegram doesn't return anything, because there isn't anything to return, so there is no
+    /// `.message` field.
+    pub answered_inline_queries: Vec<AnswerInlineQueryBody>,
+
     /// This has only the requests that were sent to the fake server to pin messages.
     /// Telegram doesn't return anything, because there isn't anything to return, so there is no
     /// `.message` field.
@@ -298,15 +446,378 @@ pub struct Responses {
     /// This has only the requests that were sent to the fake server to send chat actions.
     /// Telegram doesn't return anything, because there isn't anything to return, so there is no
     /// `.message` field.
-    pub sent_chat_actions: Vec<SendChatActionBody>,
+    pub sent_chat_actions: Vec<SentChatAction>,
 
     /// This has only the requests that were sent to the fake server to set message reactions.
     /// Telegram doesn't return anything, because there isn't anything to return, so there is no
     /// `.message` field.
     pub set_message_reaction: Vec<SetMessageReactionBody>,
 
-    /// This has only the requests that were sent to the fake server to set message reactions.
+    /// This has only the requests that were sent to the fake server to set the commands of the
+    /// bot. Telegram doesn't return anything, because there isn't anything to return, so there is
+    /// no `.message` field.
+    pub set_my_commands: Vec<SetMyCommandsBody>,
+
+    /// This has only the requests that were sent to the fake server to delete the commands of the
+    /// bot. Telegram doesn't return anything, because there isn't anything to return, so there is
+    /// no `.message` field.
+    pub deleted_my_commands: Vec<DeleteMyCommandsBody>,
+
+    /// This has only the requests that were sent to the fake server to get the commands of the
+    /// bot. The returned commands are the ones that were set, so there is no `.message` field.
+    pub fetched_my_commands: Vec<GetMyCommandsBody>,
+
+    /// This has only the requests that were sent to the fake server to set passport data errors.
     /// Telegram doesn't return anything, because there isn't anything to return, so there is no
     /// `.message` field.
-    pub set_my_commands: Vec<SetMyCommandsBody>,
+    pub set_passport_data_errors: Vec<SetPassportDataErrorsBody>,
+
+    /// This has only the requests that were sent to the fake server to mark business messages
+    /// as read. Telegram doesn't return anything, because there isn't anything to return, so
+    /// there is no `.message` field.
+    pub read_business_messages: Vec<ReadBusinessMessageBody>,
+
+    /// This has only the requests that were sent to the fake server to delete business messages.
+    /// Telegram doesn't return anything, because there isn't anything to return, so there is no
+    /// `.message` field.
+    pub deleted_business_messages: Vec<DeleteBusinessMessagesBody>,
+
+    /// This has only the requests that were sent to the fake server to change the name of a
+    /// business account. Telegram doesn't return anything, because there isn't anything to
+    /// return, so there is no `.message` field.
+    pub set_business_account_names: Vec<SetBusinessAccountNameBody>,
+
+    /// This has only the requests that were sent to the fake server to change the bio of a
+    /// business account. Telegram doesn't return anything, because there isn't anything to
+    /// return, so there is no `.message` field.
+    pub set_business_account_bios: Vec<SetBusinessAccountBioBody>,
+
+    /// This has only the requests that were sent to the fake server to change the profile photo
+    /// of a business account. Telegram doesn't return anything, because there isn't anything to
+    /// return, so there is no `.message` field.
+    pub set_business_account_profile_photos: Vec<SetBusinessAccountProfilePhotoBody>,
+
+    /// This has only the requests that were sent to the fake server to get the star balance of a
+    /// business account. The returned balance is the one set with
+    /// `MockBot::business_star_balance`, so there is no `.message` field.
+    pub fetched_business_account_star_balances: Vec<GetBusinessAccountStarBalanceBody>,
+
+    /// This has only the requests that were sent to the fake server to get the gifts of a
+    /// business account. The returned gifts are the ones added with
+    /// `MockBot::business_gift`, so there is no `.message` field.
+    pub fetched_business_account_gifts: Vec<GetBusinessAccountGiftsBody>,
+
+    /// This has only the requests that were sent to the fake server to convert gifts to stars.
+    /// The stars are added to the balance set with `MockBot::business_star_balance`, and the
+    /// gift is removed, so there is no `.message` field.
+    pub converted_gifts_to_stars: Vec<ConvertGiftToStarsBody>,
+
+    /// This has only the requests that were sent to the fake server to upgrade gifts to unique
+    /// ones. The upgraded gift replaces the regular one in the business account gifts, so there
+    /// is no `.message` field.
+    pub upgraded_gifts: Vec<UpgradeGiftBody>,
+
+    /// This has only the requests that were sent to the fake server to transfer unique gifts.
+    /// The gift is removed from the business account gifts, so there is no `.message` field.
+    pub transferred_gifts: Vec<TransferGiftBody>,
+
+    /// This has only the requests that were sent to the fake server to get a chat member. The
+    /// returned member is the one added with `MockBot::add_chat_member`, so there is no
+    /// `.message` field.
+    pub fetched_chat_members: Vec<GetChatMemberBody>,
+
+    /// This has only the requests that were sent to the fake server to get the administrators of
+    /// a chat. The returned administrators are the owner and administrators added with
+    /// `MockBot::add_chat_member`, so there is no `.message` field.
+    pub fetched_chat_administrators: Vec<GetChatAdministratorsBody>,
+
+    /// This has only the requests that were sent to the fake server to get the full info of a
+    /// chat. The returned info is the one set with `MockBot::set_chat_full_info`, or made from
+    /// the chat, so there is no `.message` field.
+    pub fetched_chats: Vec<GetChatBody>,
+
+    /// This has only the requests that were sent to the fake server to get the stickers of custom
+    /// emojis. The returned stickers are the ones added with `MockBot::add_custom_emoji_sticker`
+    /// or seen in the messages, so there is no `.message` field.
+    pub fetched_custom_emoji_stickers: Vec<GetCustomEmojiStickersBody>,
+
+    /// This has only the requests that were sent to the fake server to set the menu button of a
+    /// private chat, or the default one if the `chat_id` is `None`. Telegram doesn't return
+    /// anything, because there isn't anything to return, so there is no `.message` field.
+    pub set_chat_menu_buttons: Vec<SetChatMenuButtonBody>,
+
+    /// This has only the requests that were sent to the fake server to get the menu button of a
+    /// private chat, or the default one if the `chat_id` is `None`. The returned button is the
+    /// one that was set, so there is no `.message` field.
+    pub fetched_chat_menu_buttons: Vec<GetChatMenuButtonBody>,
+
+    /// Every `getMe` call that reached the fake server, with the returned bot. `getMe` has no
+    /// parameters, so this is mostly for counting the calls, e.g. to check that `CacheMe` works.
+    /// The calls aren't in the summary, because command filters make them all the time.
+    pub fetched_me: Vec<Me>,
+
+    /// The fields of the multipart requests, like `sendPhoto` or `sendDocument`, that the fake
+    /// server dropped, because it doesn't know them or failed to parse them. It is a debug aid:
+    /// a field here is one that the bot sent, but the `.bot_request` of the response doesn't
+    /// have, e.g. a new Bot API field teloxide sends that the fake server doesn't support yet.
+    #[serde(default)]
+    pub dropped_fields: Vec<DroppedField>,
+}
+
+impl Responses {
+    /// Writes the responses to a JSON file, so they can be inspected by other tools. Every field
+    /// of `Responses` is a key of the JSON object, and the messages and requests in it are
+    /// serialized just like they are in the Bot API.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teloxide_tests::Responses::default();
+    /// let path = std::env::temp_dir().join("teloxide_tests_write_json_example.json");
+    /// responses.write_json(&path).unwrap();
+    ///
+    /// let read_responses = teloxide_tests::Responses::read_json(&path).unwrap();
+    /// assert!(read_responses.sent_messages.is_empty());
+    /// ```
+    ///
+    pub fn write_json(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// Reads the responses from a JSON file written by `write_json`
+    pub fn read_json(path: impl AsRef<Path>) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Renders the text or caption of the last sent message back to HTML, using its entities.
+    /// Comparing it with a readable string is easier than comparing the entities.
+    ///
+    /// # Panics
+    /// If no messages were sent
+    pub fn last_sent_message_as_html(&self) -> String {
+        let (text, entities) = self.last_sent_text_and_entities();
+        Renderer::new(text, entities).as_html()
+    }
+
+    /// Renders the text or caption of the last sent message back to MarkdownV2, using its
+    /// entities. Comparing it with a readable string is easier than comparing the entities.
+    ///
+    /// # Panics
+    /// If no messages were sent
+    pub fn last_sent_message_as_markdown_v2(&self) -> String {
+        let (text, entities) = self.last_sent_text_and_entities();
+        Renderer::new(text, entities).as_markdown()
+    }
+
+    /// Returns the messages the bot sent, forwarded or copied, and then the messages as they
+    /// became after the edits, that match `predicate`. Saves iterating over `sent_messages` and
+    /// every `edited_messages_*` list by hand.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teloxide_tests::Responses::default();
+    /// let invoices = responses
+    ///     .find_messages(|message| message.text().is_some_and(|text| text.contains("invoice")));
+    /// assert!(invoices.is_empty());
+    /// ```
+    ///
+    pub fn find_messages(&self, predicate: impl Fn(&Message) -> bool) -> Vec<&Message> {
+        self.sent_messages
+            .iter()
+            .chain(
+                self.edited_messages_text
+                    .iter()
+                    .map(|edited| &edited.message),
+            )
+            .chain(
+                self.edited_messages_caption
+                    .iter()
+                    .map(|edited| &edited.message),
+            )
+            .chain(
+                self.edited_messages_live_location
+                    .iter()
+                    .map(|edited| &edited.message),
+            )
+            .chain(
+                self.edited_messages_reply_markup
+                    .iter()
+                    .map(|edited| &edited.message),
+            )
+            .filter(|message| predicate(message))
+            .collect()
+    }
+
+    /// Returns the messages in the chat with `chat_id`, like in [`find_messages`]
+    ///
+    /// [`find_messages`]: crate::Responses::find_messages
+    pub fn messages_in_chat(&self, chat_id: i64) -> Vec<&Message> {
+        self.find_messages(|message| message.chat.id.0 == chat_id)
+    }
+
+    fn last_sent_text_and_entities(&self) -> (&str, &[MessageEntity]) {
+        let message = self
+            .sent_messages
+            .last()
+            .expect("No sent messages were detected!");
+        match message.text() {
+            Some(text) => (text, message.entities().unwrap_or_default()),
+            None => (
+                message.caption().unwrap_or_default(),
+                message.caption_entities().unwrap_or_default(),
+            ),
+        }
+    }
+}
+
+/// Requests are cut to this many characters in the summary
+const SUMMARY_TEXT_LENGTH: usize = 60;
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= SUMMARY_TEXT_LENGTH {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(SUMMARY_TEXT_LENGTH).collect();
+    format!("{truncated}...")
+}
+
+pub(crate) fn sent_message_method(message: &Message) -> &'static str {
+    if message.forward_origin().is_some() {
+        return "forwardMessage";
+    }
+    match &message.kind {
+        MessageKind::Dice(_) => "sendDice",
+        MessageKind::Invoice(_) => "sendInvoice",
+        MessageKind::Common(common) => match common.media_kind {
+            MediaKind::Animation(_) => "sendAnimation",
+            MediaKind::Audio(_) => "sendAudio",
+            MediaKind::Contact(_) => "sendContact",
+            MediaKind::Document(_) => "sendDocument",
+            MediaKind::Location(_) => "sendLocation",
+            MediaKind::Photo(_) => "sendPhoto",
+            MediaKind::Poll(_) => "sendPoll",
+            MediaKind::Sticker(_) => "sendSticker",
+            MediaKind::Venue(_) => "sendVenue",
+            MediaKind::Video(_) => "sendVideo",
+            MediaKind::VideoNote(_) => "sendVideoNote",
+            MediaKind::Voice(_) => "sendVoice",
+            _ => "sendMessage",
+        },
+        _ => "sendMessage",
+    }
+}
+
+fn message_line(method: &str, message: &Message) -> String {
+    match message.text().or(message.caption()) {
+        Some(text) => format!("{method} chat={}: {:?}", message.chat.id, truncate(text)),
+        None => format!("{method} chat={}", message.chat.id),
+    }
+}
+
+fn request_line(method: &str, bot_request: &impl std::fmt::Debug) -> String {
+    format!("{method}: {}", truncate(&format!("{bot_request:?}")))
+}
+
+impl Responses {
+    /// Returns a compact summary of the requests the bot sent, one line per request with the
+    /// method, the chat and the (truncated) text. Requests are grouped by their kind, sent
+    /// messages first. `Display` of `Responses` shows the same summary.
+    ///
+    /// # Example
+    /// ```
+    /// let responses = teloxide_tests::Responses::default();
+    /// assert_eq!(responses.summary(), "No requests were sent");
+    /// ```
+    ///
+    pub fn summary(&self) -> String {
+        let mut lines = vec![];
+        lines.extend(
+            self.sent_messages
+                .iter()
+                .map(|message| message_line(sent_message_method(message), message)),
+        );
+        lines.extend(self.sent_media_group.iter().map(|media_group| {
+            format!(
+                "sendMediaGroup chat={}: {} messages",
+                media_group.bot_request.chat_id.id(),
+                media_group.messages.len()
+            )
+        }));
+        macro_rules! edited_lines {
+            ($($method:literal => $field:ident),* $(,)?) => {$(
+                lines.extend(
+                    self.$field
+                        .iter()
+                        .map(|edited| message_line($method, &edited.message)),
+                );
+            )*};
+        }
+        edited_lines!(
+            "editMessageText" => edited_messages_text,
+            "editMessageCaption" => edited_messages_caption,
+            "editMessageLiveLocation" => edited_messages_live_location,
+            "editMessageReplyMarkup" => edited_messages_reply_markup,
+            "deleteMessage" => deleted_messages,
+        );
+        macro_rules! request_lines {
+            ($($method:literal => $field:ident),* $(,)?) => {$(
+                lines.extend(
+                    self.$field
+                        .iter()
+                        .map(|bot_request| request_line($method, bot_request)),
+                );
+            )*};
+        }
+        request_lines!(
+            "answerCallbackQuery" => answered_callback_queries,
+            "answerInlineQuery" => answered_inline_queries,
+            "pinChatMessage" => pinned_chat_messages,
+            "unpinChatMessage" => unpinned_chat_messages,
+            "unpinAllChatMessages" => unpinned_all_chat_messages,
+            "banChatMember" => banned_chat_members,
+            "unbanChatMember" => unbanned_chat_members,
+            "restrictChatMember" => restricted_chat_members,
+        );
+        lines.extend(
+            self.sent_chat_actions
+                .iter()
+                .map(|sent| request_line("sendChatAction", &sent.bot_request)),
+        );
+        request_lines!(
+            "setMessageReaction" => set_message_reaction,
+            "setMyCommands" => set_my_commands,
+            "deleteMyCommands" => deleted_my_commands,
+            "getMyCommands" => fetched_my_commands,
+            "setPassportDataErrors" => set_passport_data_errors,
+            "readBusinessMessage" => read_business_messages,
+            "deleteBusinessMessages" => deleted_business_messages,
+            "setBusinessAccountName" => set_business_account_names,
+            "setBusinessAccountBio" => set_business_account_bios,
+            "setBusinessAccountProfilePhoto" => set_business_account_profile_photos,
+            "getBusinessAccountStarBalance" => fetched_business_account_star_balances,
+            "getBusinessAccountGifts" => fetched_business_account_gifts,
+            "convertGiftToStars" => converted_gifts_to_stars,
+            "upgradeGift" => upgraded_gifts,
+            "transferGift" => transferred_gifts,
+            "getChatMember" => fetched_chat_members,
+            "getChatAdministrators" => fetched_chat_administrators,
+            "getChat" => fetched_chats,
+            "getCustomEmojiStickers" => fetched_custom_emoji_stickers,
+            "setChatMenuButton" => set_chat_menu_buttons,
+            "getChatMenuButton" => fetched_chat_menu_buttons,
+        );
+
+        if lines.is_empty() {
+            return "No requests were sent".to_string();
+        }
+        lines.join("\n")
+    }
+}
+
+impl std::fmt::Display for Responses {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.summary())
+    }
 }
@@ -1,12 +1,13 @@
 use std::sync::Mutex;
 
-use actix_web::{web, Responder};
-use serde::Deserialize;
+use actix_web::{error::ErrorBadRequest, web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::Me;
 
 use super::make_telegram_result;
 use crate::state::State;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AnswerCallbackQueryBody {
     pub callback_query_id: String,
     pub text: Option<String>,
@@ -17,9 +18,25 @@ pub struct AnswerCallbackQueryBody {
 
 pub async fn answer_callback_query(
     state: web::Data<Mutex<State>>,
+    me: web::Data<Me>,
     body: web::Json<AnswerCallbackQueryBody>,
 ) -> impl Responder {
     let mut lock = state.lock().unwrap();
+    if let (true, Some(url)) = (lock.config.strict_validation, &body.url) {
+        // Only the queries of game buttons can open any url, the others can only open the bot
+        let is_game = lock
+            .callback_queries
+            .get(&body.callback_query_id)
+            .is_some_and(|query| query.game_short_name.is_some());
+        let bot_link = format!("t.me/{}?start=", me.username());
+        let is_bot_link = url
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .starts_with(&bot_link);
+        if !is_game && !is_bot_link {
+            return ErrorBadRequest("Bad Request: URL_INVALID").into();
+        }
+    }
     lock.responses
         .answered_callback_queries
         .push(body.into_inner());
@@ -0,0 +1,29 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::{InlineQueryResult, InlineQueryResultsButton};
+
+use super::make_telegram_result;
+use crate::state::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnswerInlineQueryBody {
+    pub inline_query_id: String,
+    pub results: Vec<InlineQueryResult>,
+    pub cache_time: Option<u32>,
+    pub is_personal: Option<bool>,
+    pub next_offset: Option<String>,
+    pub button: Option<InlineQueryResultsButton>,
+}
+
+pub async fn answer_inline_query(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<AnswerInlineQueryBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    lock.responses
+        .answered_inline_queries
+        .push(body.into_inner());
+    make_telegram_result(true)
+}
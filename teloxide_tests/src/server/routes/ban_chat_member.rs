@@ -1,12 +1,14 @@
 use std::sync::Mutex;
 
 use actix_web::{web, Responder};
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use teloxide::types::{Banned, ChatId, ChatMember, ChatMemberKind};
 
 use super::BodyChatId;
-use crate::{server::routes::make_telegram_result, state::State};
+use crate::{server::routes::make_telegram_result, state::State, MockUser};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BanChatMemberBody {
     pub chat_id: BodyChatId,
     pub user_id: u64,
@@ -14,6 +16,14 @@ pub struct BanChatMemberBody {
     pub revoke_messages: Option<bool>,
 }
 
+impl BanChatMemberBody {
+    /// The date the ban ends at, the `until_date` field has it as the raw unix time
+    pub fn until_date(&self) -> Option<DateTime<Utc>> {
+        self.until_date
+            .and_then(|until_date| DateTime::from_timestamp(until_date, 0))
+    }
+}
+
 pub async fn ban_chat_member(
     state: web::Data<Mutex<State>>,
     body: web::Json<BanChatMemberBody>,
@@ -30,6 +40,18 @@ pub async fn ban_chat_member(
             }
         }
     }
+    let user = match lock.get_chat_member(chat_id, body.user_id) {
+        Some(member) => member.user,
+        None => MockUser::new().id(body.user_id).build(),
+    };
+    let until_date = lock.until_date(body.until_date());
+    lock.add_chat_member(
+        ChatId(chat_id),
+        ChatMember {
+            user,
+            kind: ChatMemberKind::Banned(Banned { until_date }),
+        },
+    );
     lock.responses.banned_chat_members.push(body.into_inner());
 
     make_telegram_result(true)
@@ -0,0 +1,38 @@
+use std::sync::Mutex;
+
+use actix_web::{error::ErrorBadRequest, web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::BusinessConnectionId;
+
+use super::make_telegram_result;
+use crate::{server::OwnedGift, state::State};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConvertGiftToStarsBody {
+    pub business_connection_id: BusinessConnectionId,
+    pub owned_gift_id: String,
+}
+
+pub async fn convert_gift_to_stars(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<ConvertGiftToStarsBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    let Some(index) = lock.find_business_gift(&body.owned_gift_id) else {
+        return ErrorBadRequest("Bad Request: gift not found").into();
+    };
+    let OwnedGift::Regular(regular) = &lock.business_gifts[index] else {
+        return ErrorBadRequest("Bad Request: unique gifts can't be converted to stars").into();
+    };
+    let Some(convert_star_count) = regular.convert_star_count else {
+        return ErrorBadRequest("Bad Request: STARGIFT_CONVERT_TOO_OLD").into();
+    };
+
+    lock.business_gifts.remove(index);
+    lock.business_star_balance += convert_star_count as i64;
+    lock.responses
+        .converted_gifts_to_stars
+        .push(body.into_inner());
+
+    make_telegram_result(true)
+}
@@ -1,20 +1,25 @@
 use std::sync::Mutex;
 
-use actix_web::{error::ErrorBadRequest, web, Responder};
-use serde::Deserialize;
-use serde_json::json;
-use teloxide::types::{
-    Me, MediaAnimation, MediaAudio, MediaDocument, MediaKind, MediaPhoto, MediaVideo, MediaVoice,
-    MessageEntity, MessageId, MessageKind, ParseMode, ReplyMarkup,
+use actix_web::{error::ErrorBadRequest, web, Responder, ResponseError};
+use serde::{Deserialize, Serialize};
+use teloxide::{
+    types::{
+        Me, MediaAnimation, MediaAudio, MediaDocument, MediaKind, MediaPhoto, MediaVideo,
+        MediaVoice, MessageEntity, MessageId, MessageKind, ParseMode, PollType, ReplyMarkup,
+    },
+    ApiError,
 };
 
-use super::{make_telegram_result, BodyChatId};
+use super::{
+    check_flood_limit, check_markup, check_reply_markup, make_telegram_result, BodyChatId,
+    BotApiError,
+};
 use crate::{
     server::{routes::check_if_message_exists, CopiedMessage},
     state::State,
 };
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CopyMessageBody {
     pub chat_id: BodyChatId,
     pub message_thread_id: Option<i64>,
@@ -26,6 +31,7 @@ pub struct CopyMessageBody {
     pub show_caption_above_media: Option<bool>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
+    pub allow_paid_broadcast: Option<bool>,
     pub reply_markup: Option<ReplyMarkup>,
 }
 
@@ -38,12 +44,24 @@ pub async fn copy_message(
     let chat = body.chat_id.chat();
     check_if_message_exists!(lock, body.message_id);
     let mut message = lock.messages.get_message(body.message_id).unwrap();
+    // A quiz can be copied only if the bot knows its correct answer, otherwise the bot has to
+    // send it again with the options
+    let unknown_quiz = message
+        .poll()
+        .is_some_and(|poll| poll.poll_type == PollType::Quiz && poll.correct_option_id.is_none());
+    if lock.config.strict_validation && unknown_quiz {
+        let error = ApiError::Unknown("Bad Request: message can't be copied".to_string());
+        return BotApiError::new(error).error_response();
+    }
     message.chat = chat;
     message.from = Some(me.user.clone());
+    message.sender_chat = None;
 
     // FIXME: Use show_caption_above_media
     if let MessageKind::Common(ref mut common) = message.kind {
+        // Copies don't link to the original message in any way
         common.forward_origin = None;
+        common.is_automatic_forward = false;
         common.external_reply = None;
         match common.media_kind {
             MediaKind::Animation(MediaAnimation {
@@ -87,18 +105,26 @@ pub async fn copy_message(
         common.has_protected_content = body.protect_content.unwrap_or(false);
     }
 
+    check_reply_markup!(body.reply_markup);
+    check_markup!(
+        lock,
+        body.caption.as_deref().unwrap_or_default(),
+        body.parse_mode
+    );
+    check_flood_limit!(lock, body.allow_paid_broadcast);
     let last_id = lock.messages.max_message_id();
     message.id = MessageId(last_id + 1);
     message.chat = body.chat_id.chat();
-    let message = lock.messages.add_message(message);
+    let message = lock.add_sent_message(message, &me);
+    lock.add_reply_markup(&message, body.reply_markup.clone());
 
     lock.responses.sent_messages.push(message.clone());
+    let seq = lock.next_seq();
     lock.responses.copied_messages.push(CopiedMessage {
         message_id: message.id,
         bot_request: body.into_inner(),
+        seq,
     });
 
-    make_telegram_result(json!({
-        "message_id": message.id.0
-    }))
+    make_telegram_result(message.id)
 }
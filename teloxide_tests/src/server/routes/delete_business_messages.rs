@@ -0,0 +1,27 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::BusinessConnectionId;
+
+use super::make_telegram_result;
+use crate::state::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeleteBusinessMessagesBody {
+    pub business_connection_id: BusinessConnectionId,
+    pub message_ids: Vec<i32>,
+}
+
+pub async fn delete_business_messages(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<DeleteBusinessMessagesBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    let bot_request = body.into_inner();
+    // Just like deleteMessages, messages that are not found are skipped
+    lock.messages.delete_messages(&bot_request.message_ids);
+    lock.responses.deleted_business_messages.push(bot_request);
+
+    make_telegram_result(true)
+}
@@ -1,30 +1,59 @@
 use std::sync::Mutex;
 
-use actix_web::{error::ErrorBadRequest, web, Responder};
-use serde::Deserialize;
+use actix_web::{web, Responder, ResponseError};
+use serde::{Deserialize, Serialize};
+use teloxide::{types::Message, ApiError};
 
-use super::{check_if_message_exists, BodyChatId};
+use super::{BodyChatId, BotApiError};
 use crate::{
-    server::{routes::make_telegram_result, DeletedMessage},
+    server::{routes::make_telegram_result, DeleteOutcome, DeletedMessage},
     state::State,
 };
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DeleteMessageBody {
     pub chat_id: BodyChatId,
     pub message_id: i32,
 }
 
+/// Deletes the message if it is in the chat, recording the outcome either way
+pub(crate) fn delete_chat_message(
+    state: &mut State,
+    chat_id: &BodyChatId,
+    message_id: i32,
+) -> Option<Message> {
+    let in_chat = state
+        .messages
+        .get_message(message_id)
+        .is_some_and(|message| {
+            // Chats with a username can't be checked, their id is made up
+            matches!(chat_id, BodyChatId::Text(_)) || message.chat.id.0 == chat_id.id()
+        });
+    let deleted_message = in_chat
+        .then(|| state.messages.delete_message(message_id))
+        .flatten();
+    state.responses.delete_outcomes.push(DeleteOutcome {
+        chat_id: chat_id.clone(),
+        message_id,
+        deleted: deleted_message.is_some(),
+    });
+    deleted_message
+}
+
 pub async fn delete_message(
     state: web::Data<Mutex<State>>,
     body: web::Json<DeleteMessageBody>,
 ) -> impl Responder {
     let mut lock = state.lock().unwrap();
-    check_if_message_exists!(lock, body.message_id);
-    let deleted_message = lock.messages.delete_message(body.message_id).unwrap();
+    let Some(deleted_message) = delete_chat_message(&mut lock, &body.chat_id, body.message_id)
+    else {
+        return BotApiError::new(ApiError::MessageToDeleteNotFound).error_response();
+    };
+    let seq = lock.next_seq();
     lock.responses.deleted_messages.push(DeletedMessage {
-        message: deleted_message.clone(),
+        message: deleted_message,
         bot_request: body.into_inner(),
+        seq,
     });
 
     make_telegram_result(true)
@@ -1,18 +1,21 @@
 use std::sync::Mutex;
 
 use actix_web::{web, Responder};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use super::BodyChatId;
 use crate::{
     server::{
-        routes::{delete_message::DeleteMessageBody, make_telegram_result},
+        routes::{
+            delete_message::{delete_chat_message, DeleteMessageBody},
+            make_telegram_result,
+        },
         DeletedMessage,
     },
     state::State,
 };
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DeleteMessagesBody {
     pub chat_id: BodyChatId,
     pub message_ids: Vec<i32>,
@@ -24,23 +27,21 @@ pub async fn delete_messages(
 ) -> impl Responder {
     let mut lock = state.lock().unwrap();
     let bot_request = body.into_inner();
-    // deleteMessages skips messages that are not found, no error is returned.
-    let mut deleted_messages = lock
-        .messages
-        .delete_messages(&bot_request.message_ids)
-        .into_iter()
-        .map(|m| DeletedMessage {
-            message: m.clone(),
+    // Unlike deleteMessage, the messages that can't be found are skipped without an error
+    for &message_id in &bot_request.message_ids {
+        let Some(message) = delete_chat_message(&mut lock, &bot_request.chat_id, message_id) else {
+            continue;
+        };
+        let seq = lock.next_seq();
+        lock.responses.deleted_messages.push(DeletedMessage {
             bot_request: DeleteMessageBody {
                 chat_id: bot_request.chat_id.clone(),
-                message_id: m.id.0,
+                message_id,
             },
-        })
-        .collect();
-
-    lock.responses
-        .deleted_messages
-        .append(&mut deleted_messages);
+            message,
+            seq,
+        });
+    }
 
     make_telegram_result(true)
 }
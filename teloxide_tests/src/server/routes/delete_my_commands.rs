@@ -0,0 +1,25 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::BotCommandScope;
+
+use super::make_telegram_result;
+use crate::state::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeleteMyCommandsBody {
+    pub scope: Option<BotCommandScope>,
+    pub language_code: Option<String>,
+}
+
+pub async fn delete_my_commands(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<DeleteMyCommandsBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    lock.set_my_commands(body.scope.clone(), body.language_code.clone(), vec![]);
+    lock.responses.deleted_my_commands.push(body.into_inner());
+
+    make_telegram_result(true)
+}
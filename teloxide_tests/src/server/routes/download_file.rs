@@ -13,21 +13,17 @@ pub async fn download_file(
     path: web::Path<(String, String)>,
     state: web::Data<Mutex<State>>,
 ) -> HttpResponse {
-    if state
-        .lock()
-        .unwrap()
-        .files
-        .clone()
-        .into_iter()
-        .find(|f| f.path == path.1)
-        .is_none()
-    {
+    let lock = state.lock().unwrap();
+    if lock.files.iter().all(|f| f.path != path.1) {
         return ErrorBadRequest("No such file found").into();
     }
 
-    let stream = once(ok::<_, Error>(Bytes::copy_from_slice(
-        "Hello, world!".as_bytes(),
-    )));
+    // Files seeded with `MockBot::seed_file` have contents, the rest are all the same
+    let contents = match lock.file_contents.get(&path.1) {
+        Some(contents) => Bytes::copy_from_slice(contents),
+        None => Bytes::copy_from_slice("Hello, world!".as_bytes()),
+    };
+    let stream = once(ok::<_, Error>(contents));
 
     HttpResponse::Ok().streaming(stream)
 }
@@ -1,16 +1,16 @@
 use std::sync::Mutex;
 
 use actix_web::{error::ErrorBadRequest, web, Responder};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use teloxide::types::{BusinessConnectionId, MessageEntity, ParseMode, ReplyMarkup};
 
-use super::{check_if_message_exists, BodyChatId};
+use super::{check_if_message_exists, check_markup, BodyChatId};
 use crate::{
     server::{routes::make_telegram_result, EditedMessageCaption},
     state::State,
 };
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EditMessageCaptionBody {
     pub chat_id: Option<BodyChatId>,
     pub message_id: Option<i32>,
@@ -27,6 +27,7 @@ pub async fn edit_message_caption(
     state: web::Data<Mutex<State>>,
     body: web::Json<EditMessageCaptionBody>,
 ) -> impl Responder {
+    check_markup!(state.lock().unwrap(), body.caption, body.parse_mode);
     match (
         body.chat_id.clone(),
         body.message_id,
@@ -47,17 +48,20 @@ pub async fn edit_message_caption(
                 "show_caption_above_media",
                 body.show_caption_above_media.unwrap_or(false),
             );
+            lock.mark_edited(message_id);
 
             let message = lock
                 .messages
                 .edit_message_reply_markup(message_id, body.reply_markup.clone())
                 .unwrap();
 
+            let seq = lock.next_seq();
             lock.responses
                 .edited_messages_caption
                 .push(EditedMessageCaption {
                     message: message.clone(),
                     bot_request: body.into_inner(),
+                    seq,
                 });
 
             make_telegram_result(message)
@@ -0,0 +1,91 @@
+use std::sync::Mutex;
+
+use actix_web::{error::ErrorBadRequest, web, Responder, ResponseError};
+use serde::{Deserialize, Serialize};
+use teloxide::{
+    types::{BusinessConnectionId, LivePeriod, ReplyMarkup},
+    ApiError,
+};
+
+use super::{check_reply_markup, BodyChatId, BotApiError};
+use crate::{
+    server::{routes::make_telegram_result, EditedMessageLiveLocation},
+    state::State,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EditMessageLiveLocationBody {
+    pub chat_id: Option<BodyChatId>,
+    pub message_id: Option<i32>,
+    pub inline_message_id: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub live_period: Option<LivePeriod>,
+    pub horizontal_accuracy: Option<f64>,
+    pub heading: Option<u16>,
+    pub proximity_alert_radius: Option<u32>,
+    pub reply_markup: Option<ReplyMarkup>,
+    pub business_connection_id: Option<BusinessConnectionId>,
+}
+
+pub async fn edit_message_live_location(
+    body: web::Json<EditMessageLiveLocationBody>,
+    state: web::Data<Mutex<State>>,
+) -> impl Responder {
+    match (
+        body.chat_id.clone(),
+        body.message_id,
+        body.inline_message_id.clone(),
+    ) {
+        (Some(_), Some(message_id), None) => {
+            let mut lock = state.lock().unwrap();
+            let Some(old_message) = lock.messages.get_message(message_id) else {
+                return BotApiError::new(ApiError::MessageToEditNotFound).error_response();
+            };
+            let Some(mut location) = old_message
+                .location()
+                .filter(|location| location.live_period.is_some())
+                .cloned()
+            else {
+                return BotApiError::new(ApiError::MessageCantBeEdited).error_response();
+            };
+            check_reply_markup!(body.reply_markup);
+
+            lock.expire_live_locations();
+            if lock.expired_live_locations.contains(&old_message.id) {
+                return BotApiError::new(ApiError::MessageCantBeEdited).error_response();
+            }
+
+            location.latitude = body.latitude;
+            location.longitude = body.longitude;
+            location.horizontal_accuracy = body.horizontal_accuracy;
+            location.heading = body.heading;
+            location.proximity_alert_radius = body.proximity_alert_radius;
+            if let Some(live_period) = body.live_period {
+                location.live_period = Some(live_period);
+            }
+
+            lock.messages
+                .edit_message_field(message_id, "location", location);
+            lock.mark_edited(message_id);
+            let message = lock
+                .messages
+                .edit_message_reply_markup(message_id, body.reply_markup.clone())
+                .unwrap();
+
+            let seq = lock.next_seq();
+            lock.responses
+                .edited_messages_live_location
+                .push(EditedMessageLiveLocation {
+                    message: message.clone(),
+                    bot_request: body.into_inner(),
+                    seq,
+                });
+
+            make_telegram_result(message)
+        }
+        // No implementation for inline messages yet, so just return success
+        (None, None, Some(_)) => make_telegram_result(true),
+        _ => ErrorBadRequest("No message_id or inline_message_id were provided").into(),
+    }
+}
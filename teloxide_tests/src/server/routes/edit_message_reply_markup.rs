@@ -1,19 +1,19 @@
 use std::sync::Mutex;
 
 use actix_web::{error::ErrorBadRequest, web, Responder};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use teloxide::types::{BusinessConnectionId, ReplyMarkup};
 
 use super::BodyChatId;
 use crate::{
     server::{
-        routes::{check_if_message_exists, make_telegram_result},
+        routes::{check_if_message_exists, check_reply_markup, make_telegram_result},
         EditedMessageReplyMarkup,
     },
     state::State,
 };
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EditMessageReplyMarkupBody {
     pub chat_id: Option<BodyChatId>,
     pub message_id: Option<i32>,
@@ -34,6 +34,7 @@ pub async fn edit_message_reply_markup(
         (Some(_), Some(message_id), None) => {
             let mut lock = state.lock().unwrap();
             check_if_message_exists!(lock, message_id);
+            check_reply_markup!(body.reply_markup);
 
             let message = match body.reply_markup.clone() {
                 Some(reply_markup) => lock
@@ -46,11 +47,16 @@ pub async fn edit_message_reply_markup(
                     .unwrap(),
             };
 
+            if let Some(reply_markup) = &body.reply_markup {
+                lock.record_special_buttons(&message, reply_markup);
+            }
+            let seq = lock.next_seq();
             lock.responses
                 .edited_messages_reply_markup
                 .push(EditedMessageReplyMarkup {
                     message: message.clone(),
                     bot_request: body.into_inner(),
+                    seq,
                 });
 
             make_telegram_result(message)
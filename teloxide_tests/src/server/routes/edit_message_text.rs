@@ -1,19 +1,19 @@
 use std::sync::Mutex;
 
 use actix_web::{error::ErrorBadRequest, web, Responder, ResponseError};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use teloxide::{
     types::{BusinessConnectionId, LinkPreviewOptions, MessageEntity, ParseMode, ReplyMarkup},
     ApiError,
 };
 
-use super::{BodyChatId, BotApiError};
+use super::{check_entities, check_markup, BodyChatId, BotApiError};
 use crate::{
     server::{routes::make_telegram_result, EditedMessageText},
     state::State,
 };
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EditMessageTextBody {
     pub chat_id: Option<BodyChatId>,
     pub message_id: Option<i32>,
@@ -30,6 +30,8 @@ pub async fn edit_message_text(
     body: web::Json<EditMessageTextBody>,
     state: web::Data<Mutex<State>>,
 ) -> impl Responder {
+    check_entities!(body.text, body.entities);
+    check_markup!(state.lock().unwrap(), body.text, body.parse_mode);
     match (
         body.chat_id.clone(),
         body.message_id,
@@ -44,7 +46,12 @@ pub async fn edit_message_text(
             let old_reply_markup = old_message
                 .reply_markup()
                 .map(|kb| ReplyMarkup::InlineKeyboard(kb.clone()));
-            if old_message.text() == Some(&body.text) && old_reply_markup == body.reply_markup {
+            let old_entities = old_message.entities().unwrap_or_default();
+            let new_entities = body.entities.as_deref().unwrap_or_default();
+            if old_message.text() == Some(&body.text)
+                && old_entities == new_entities
+                && old_reply_markup == body.reply_markup
+            {
                 return BotApiError::new(ApiError::MessageNotModified).error_response();
             }
 
@@ -53,16 +60,24 @@ pub async fn edit_message_text(
             lock.messages.edit_message_field(
                 message_id,
                 "entities",
-                body.entities.clone().unwrap_or(vec![]),
+                body.entities.clone().unwrap_or_default(),
             );
+            lock.messages.edit_message_field(
+                message_id,
+                "link_preview_options",
+                body.link_preview_options.clone(),
+            );
+            lock.mark_edited(message_id);
             let message = lock
                 .messages
                 .edit_message_reply_markup(message_id, body.reply_markup.clone())
                 .unwrap();
 
+            let seq = lock.next_seq();
             lock.responses.edited_messages_text.push(EditedMessageText {
                 message: message.clone(),
                 bot_request: body.into_inner(),
+                seq,
             });
 
             make_telegram_result(message)
@@ -1,8 +1,8 @@
 use std::sync::Mutex;
 
 use actix_web::{error::ErrorBadRequest, web, Responder};
-use serde::Deserialize;
-use teloxide::types::{Me, MessageId, MessageKind, MessageOrigin};
+use serde::{Deserialize, Serialize};
+use teloxide::types::{Me, MediaKind, MediaPoll, MessageId, MessageKind, MessageOrigin};
 
 use super::{make_telegram_result, BodyChatId};
 use crate::{
@@ -10,7 +10,7 @@ use crate::{
     state::State,
 };
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ForwardMessageBody {
     pub chat_id: BodyChatId,
     pub from_chat_id: BodyChatId,
@@ -35,45 +35,61 @@ pub async fn forward_message(
     }
 
     let message_clone = message.clone();
+    let author_signature = message.author_signature().map(ToOwned::to_owned);
     if let MessageKind::Common(ref mut common) = message.kind {
-        common.forward_origin = Some(if message.chat.is_channel() {
-            MessageOrigin::Channel {
-                date: message_clone.date,
-                chat: message_clone.chat,
-                message_id: message_clone.id,
-                author_signature: None,
-            }
-        } else if let Some(sender_chat) = &message.sender_chat {
-            MessageOrigin::Chat {
-                date: message_clone.date,
-                sender_chat: sender_chat.clone(),
-                author_signature: None,
-            }
-        } else if let Some(user) = &message.from {
-            MessageOrigin::User {
-                date: message_clone.date,
-                sender_user: user.clone(),
-            }
-        } else {
-            // This is probably unreachable.
-            MessageOrigin::HiddenUser {
-                date: message_clone.date,
-                sender_user_name: "Unknown user".to_string(),
-            }
-        });
+        // A forwarded message that is forwarded again keeps its original origin
+        common.forward_origin =
+            common
+                .forward_origin
+                .take()
+                .or(Some(if message.chat.is_channel() {
+                    MessageOrigin::Channel {
+                        date: message_clone.date,
+                        chat: message_clone.chat,
+                        message_id: message_clone.id,
+                        author_signature,
+                    }
+                } else if let Some(sender_chat) = &message.sender_chat {
+                    MessageOrigin::Chat {
+                        date: message_clone.date,
+                        sender_chat: sender_chat.clone(),
+                        author_signature,
+                    }
+                } else if let Some(user) = &message.from {
+                    MessageOrigin::User {
+                        date: message_clone.date,
+                        sender_user: user.clone(),
+                    }
+                } else {
+                    // This is probably unreachable.
+                    MessageOrigin::HiddenUser {
+                        date: message_clone.date,
+                        sender_user_name: "Unknown user".to_string(),
+                    }
+                }));
+        common.is_automatic_forward = false;
         common.has_protected_content = body.protect_content.unwrap_or(false);
+        // Forwarded quizzes don't show the correct answer until they are closed
+        if let MediaKind::Poll(MediaPoll { ref mut poll }) = common.media_kind {
+            if !poll.is_closed {
+                poll.correct_option_id = None;
+            }
+        }
     }
 
     let last_id = lock.messages.max_message_id();
     message.id = MessageId(last_id + 1);
     message.chat = body.chat_id.chat();
     message.from = Some(me.user.clone());
-    let message = lock.messages.add_message(message);
+    message.sender_chat = None;
+    let message = lock.add_sent_message(message, &me);
 
     lock.responses.sent_messages.push(message.clone());
+    let seq = lock.next_seq();
     lock.responses.forwarded_messages.push(ForwardedMessage {
         message: message.clone(),
         bot_request: body.into_inner(),
+        seq,
     });
 
     make_telegram_result(message)
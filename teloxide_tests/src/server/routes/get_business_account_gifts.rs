@@ -0,0 +1,195 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use teloxide::types::{BusinessConnectionId, Gift, MessageEntity, Sticker, User};
+
+use super::make_telegram_result;
+use crate::state::State;
+
+/// Describes a gift received and owned by a user or a chat.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
+pub enum OwnedGift {
+    Regular(OwnedGiftRegular),
+    Unique(OwnedGiftUnique),
+}
+
+impl OwnedGift {
+    /// Returns the unique identifier of the gift for the bot
+    pub fn owned_gift_id(&self) -> Option<&str> {
+        match self {
+            OwnedGift::Regular(regular) => regular.owned_gift_id.as_deref(),
+            OwnedGift::Unique(unique) => unique.owned_gift_id.as_deref(),
+        }
+    }
+}
+
+/// Describes a regular gift owned by a user or a chat.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OwnedGiftRegular {
+    pub gift: Gift,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owned_gift_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender_user: Option<User>,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub send_date: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entities: Option<Vec<MessageEntity>>,
+    #[serde(default)]
+    pub is_private: bool,
+    #[serde(default)]
+    pub is_saved: bool,
+    #[serde(default)]
+    pub can_be_upgraded: bool,
+    #[serde(default)]
+    pub was_refunded: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub convert_star_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prepaid_upgrade_star_count: Option<u32>,
+}
+
+/// Describes a unique gift received and owned by a user or a chat.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OwnedGiftUnique {
+    pub gift: UniqueGift,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owned_gift_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender_user: Option<User>,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub send_date: DateTime<Utc>,
+    #[serde(default)]
+    pub is_saved: bool,
+    #[serde(default)]
+    pub can_be_transferred: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transfer_star_count: Option<u32>,
+}
+
+/// Describes a unique gift that was upgraded from a regular gift.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UniqueGift {
+    pub base_name: String,
+    pub name: String,
+    pub number: u32,
+    pub model: UniqueGiftModel,
+    pub symbol: UniqueGiftSymbol,
+    pub backdrop: UniqueGiftBackdrop,
+}
+
+/// Describes the model of a unique gift.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UniqueGiftModel {
+    pub name: String,
+    pub sticker: Sticker,
+    pub rarity_per_mille: u16,
+}
+
+/// Describes the symbol shown on the pattern of a unique gift.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UniqueGiftSymbol {
+    pub name: String,
+    pub sticker: Sticker,
+    pub rarity_per_mille: u16,
+}
+
+/// Describes the backdrop of a unique gift.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UniqueGiftBackdrop {
+    pub name: String,
+    pub colors: UniqueGiftBackdropColors,
+    pub rarity_per_mille: u16,
+}
+
+/// Describes the colors of the backdrop of a unique gift, in RGB24 format.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UniqueGiftBackdropColors {
+    pub center_color: u32,
+    pub edge_color: u32,
+    pub symbol_color: u32,
+    pub text_color: u32,
+}
+
+/// Contains the list of gifts received and owned by a user or a chat.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OwnedGifts {
+    pub total_count: u32,
+    pub gifts: Vec<OwnedGift>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetBusinessAccountGiftsBody {
+    pub business_connection_id: BusinessConnectionId,
+    pub exclude_unsaved: Option<bool>,
+    pub exclude_saved: Option<bool>,
+    pub exclude_unlimited: Option<bool>,
+    pub exclude_limited: Option<bool>,
+    pub exclude_unique: Option<bool>,
+    pub sort_by_price: Option<bool>,
+    pub offset: Option<String>,
+    pub limit: Option<u32>,
+}
+
+pub async fn get_business_account_gifts(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<GetBusinessAccountGiftsBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    let bot_request = body.into_inner();
+
+    let mut gifts: Vec<OwnedGift> = lock
+        .business_gifts
+        .iter()
+        .filter(|owned_gift| match owned_gift {
+            OwnedGift::Regular(regular) => {
+                !(bot_request.exclude_unsaved.unwrap_or(false) && !regular.is_saved
+                    || bot_request.exclude_saved.unwrap_or(false) && regular.is_saved
+                    || bot_request.exclude_unlimited.unwrap_or(false) && !regular.gift.is_limited()
+                    || bot_request.exclude_limited.unwrap_or(false) && regular.gift.is_limited())
+            }
+            OwnedGift::Unique(unique) => {
+                !(bot_request.exclude_unique.unwrap_or(false)
+                    || bot_request.exclude_unsaved.unwrap_or(false) && !unique.is_saved
+                    || bot_request.exclude_saved.unwrap_or(false) && unique.is_saved)
+            }
+        })
+        .cloned()
+        .collect();
+    if bot_request.sort_by_price.unwrap_or(false) {
+        gifts.sort_by_key(|owned_gift| match owned_gift {
+            OwnedGift::Regular(regular) => std::cmp::Reverse(regular.gift.star_count),
+            // Unique gifts don't have a fixed price
+            OwnedGift::Unique(_) => std::cmp::Reverse(0),
+        });
+    }
+
+    let total_count = gifts.len() as u32;
+    let offset: usize = bot_request
+        .offset
+        .as_deref()
+        .and_then(|offset| offset.parse().ok())
+        .unwrap_or(0);
+    let limit = bot_request.limit.unwrap_or(100) as usize;
+    let gifts: Vec<OwnedGift> = gifts.into_iter().skip(offset).take(limit).collect();
+    let next_offset =
+        (offset + gifts.len() < total_count as usize).then(|| (offset + gifts.len()).to_string());
+
+    lock.responses
+        .fetched_business_account_gifts
+        .push(bot_request);
+
+    make_telegram_result(OwnedGifts {
+        total_count,
+        gifts,
+        next_offset,
+    })
+}
@@ -0,0 +1,37 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::BusinessConnectionId;
+
+use super::make_telegram_result;
+use crate::state::State;
+
+/// Describes an amount of Telegram Stars.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct StarAmount {
+    pub amount: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nanostar_amount: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetBusinessAccountStarBalanceBody {
+    pub business_connection_id: BusinessConnectionId,
+}
+
+pub async fn get_business_account_star_balance(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<GetBusinessAccountStarBalanceBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    let star_amount = StarAmount {
+        amount: lock.business_star_balance,
+        nanostar_amount: None,
+    };
+    lock.responses
+        .fetched_business_account_star_balances
+        .push(body.into_inner());
+
+    make_telegram_result(star_amount)
+}
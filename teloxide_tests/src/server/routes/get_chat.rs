@@ -0,0 +1,116 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::{
+    Chat, ChatFullInfo, ChatFullInfoKind, ChatFullInfoPublicKind, ChatId, ChatKind, PublicChatKind,
+};
+
+use super::{make_telegram_result, BodyChatId};
+use crate::{
+    state::{ChatMetadata, State},
+    MockChatFullInfoChannel, MockChatFullInfoGroup, MockChatFullInfoPrivate,
+    MockChatFullInfoSupergroup,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetChatBody {
+    pub chat_id: BodyChatId,
+}
+
+pub async fn get_chat(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<GetChatBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    let chat_id = body.chat_id.id();
+    let metadata = lock
+        .chat_metadata
+        .get(&ChatId(chat_id))
+        .cloned()
+        .unwrap_or_default();
+    let full_info = match &metadata.full_info {
+        Some(full_info) => full_info.clone(),
+        None => {
+            // The chat of the messages is the most accurate, it has the title, username, etc.
+            let chat = lock
+                .messages
+                .messages
+                .iter()
+                .rev()
+                .find(|message| message.chat.id.0 == chat_id)
+                .map(|message| message.chat.clone())
+                .unwrap_or_else(|| body.chat_id.chat());
+            chat_full_info(chat)
+        }
+    };
+    lock.responses.fetched_chats.push(body.into_inner());
+
+    make_telegram_result(with_metadata(full_info, metadata))
+}
+
+fn chat_full_info(chat: Chat) -> ChatFullInfo {
+    match chat.kind {
+        ChatKind::Private(private) => {
+            let mut full_info = MockChatFullInfoPrivate::new().id(chat.id.0);
+            full_info.username = private.username;
+            full_info.first_name = private.first_name;
+            full_info.last_name = private.last_name;
+            full_info.build()
+        }
+        ChatKind::Public(public) => match public.kind {
+            PublicChatKind::Channel(channel) => {
+                let mut full_info = MockChatFullInfoChannel::new().id(chat.id.0);
+                full_info.title = public.title;
+                full_info.username = channel.username;
+                full_info.build()
+            }
+            PublicChatKind::Group => {
+                let mut full_info = MockChatFullInfoGroup::new().id(chat.id.0);
+                full_info.title = public.title;
+                full_info.build()
+            }
+            PublicChatKind::Supergroup(supergroup) => {
+                let mut full_info = MockChatFullInfoSupergroup::new()
+                    .id(chat.id.0)
+                    .is_forum(supergroup.is_forum);
+                full_info.title = public.title;
+                full_info.username = supergroup.username;
+                full_info.build()
+            }
+        },
+    }
+}
+
+// The seeded metadata and the pins are newer than the full info
+fn with_metadata(mut full_info: ChatFullInfo, metadata: ChatMetadata) -> ChatFullInfo {
+    full_info.pinned_message = metadata.pinned_messages.last().cloned().map(Box::new);
+    if let Some((custom_emoji_id, expiration_date)) = metadata.emoji_status {
+        full_info.emoji_status_custom_emoji_id = Some(custom_emoji_id);
+        full_info.emoji_status_expiration_date = expiration_date;
+    }
+    if metadata.profile_accent_color_id.is_some() {
+        full_info.profile_accent_color_id = metadata.profile_accent_color_id;
+    }
+    if let (ChatFullInfoKind::Private(private), Some(restricted)) = (
+        &mut full_info.kind,
+        metadata.has_restricted_voice_and_video_messages,
+    ) {
+        private.has_restricted_voice_and_video_messages = restricted;
+    }
+    if let ChatFullInfoKind::Public(public) = &mut full_info.kind {
+        if metadata.description.is_some() {
+            public.description = metadata.description;
+        }
+        match &mut public.kind {
+            ChatFullInfoPublicKind::Group(group) if metadata.permissions.is_some() => {
+                group.permissions = metadata.permissions;
+            }
+            ChatFullInfoPublicKind::Supergroup(supergroup) if metadata.permissions.is_some() => {
+                supergroup.permissions = metadata.permissions;
+            }
+            _ => {}
+        }
+    }
+    full_info
+}
@@ -0,0 +1,33 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::{ChatId, ChatMember};
+
+use super::{make_telegram_result, BodyChatId};
+use crate::state::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetChatAdministratorsBody {
+    pub chat_id: BodyChatId,
+}
+
+pub async fn get_chat_administrators(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<GetChatAdministratorsBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    let administrators: Vec<ChatMember> = lock
+        .chat_members
+        .get(&ChatId(body.chat_id.id()))
+        .into_iter()
+        .flatten()
+        .filter(|member| member.is_privileged())
+        .cloned()
+        .collect();
+    lock.responses
+        .fetched_chat_administrators
+        .push(body.into_inner());
+
+    make_telegram_result(administrators)
+}
@@ -0,0 +1,28 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder, ResponseError};
+use serde::{Deserialize, Serialize};
+use teloxide::ApiError;
+
+use super::{make_telegram_result, BodyChatId, BotApiError};
+use crate::state::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetChatMemberBody {
+    pub chat_id: BodyChatId,
+    pub user_id: u64,
+}
+
+pub async fn get_chat_member(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<GetChatMemberBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    lock.lift_expired_restrictions();
+    let Some(member) = lock.get_chat_member(body.chat_id.id(), body.user_id) else {
+        return BotApiError::new(ApiError::UserNotFound).error_response();
+    };
+    lock.responses.fetched_chat_members.push(body.into_inner());
+
+    make_telegram_result(member)
+}
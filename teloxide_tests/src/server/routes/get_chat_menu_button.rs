@@ -0,0 +1,26 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::ChatId;
+
+use super::{make_telegram_result, BodyChatId};
+use crate::state::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetChatMenuButtonBody {
+    pub chat_id: Option<BodyChatId>,
+}
+
+pub async fn get_chat_menu_button(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<GetChatMenuButtonBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    let menu_button = lock.menu_button(body.chat_id.as_ref().map(|chat_id| ChatId(chat_id.id())));
+    lock.responses
+        .fetched_chat_menu_buttons
+        .push(body.into_inner());
+
+    make_telegram_result(menu_button)
+}
@@ -0,0 +1,34 @@
+use std::sync::Mutex;
+
+use actix_web::{error::ErrorBadRequest, web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::{CustomEmojiId, Sticker};
+
+use super::make_telegram_result;
+use crate::state::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetCustomEmojiStickersBody {
+    pub custom_emoji_ids: Vec<CustomEmojiId>,
+}
+
+pub async fn get_custom_emoji_stickers(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<GetCustomEmojiStickersBody>,
+) -> impl Responder {
+    if body.custom_emoji_ids.len() > 200 {
+        return ErrorBadRequest("Too many custom emoji identifiers specified").into();
+    }
+    let mut lock = state.lock().unwrap();
+    // Like in Telegram, the unknown custom emojis are just skipped
+    let stickers: Vec<Sticker> = body
+        .custom_emoji_ids
+        .iter()
+        .filter_map(|id| lock.custom_emoji_stickers.get(id).cloned())
+        .collect();
+    lock.responses
+        .fetched_custom_emoji_stickers
+        .push(body.into_inner());
+
+    make_telegram_result(stickers)
+}
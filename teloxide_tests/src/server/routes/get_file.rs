@@ -1,10 +1,10 @@
 use std::sync::Mutex;
 
-use actix_web::{error::ErrorBadRequest, web, Responder};
+use actix_web::{error::ErrorBadRequest, web, Responder, ResponseError};
 use serde::Deserialize;
-use teloxide::types::FileId;
+use teloxide::{types::FileId, ApiError};
 
-use super::make_telegram_result;
+use super::{make_telegram_result, BotApiError, MAX_DOWNLOAD_SIZE};
 use crate::state::State;
 
 #[derive(Deserialize)]
@@ -16,9 +16,22 @@ pub async fn get_file(
     query: web::Json<GetFileQuery>,
     state: web::Data<Mutex<State>>,
 ) -> impl Responder {
-    let lock = state.lock().unwrap();
-    let Some(file) = lock.files.iter().find(|f| f.id == query.file_id) else {
+    let mut lock = state.lock().unwrap();
+    if let Some(error) = lock.get_file_errors.get(&query.file_id) {
+        return BotApiError::new(error.clone()).error_response();
+    }
+    let path = lock.file_paths.get(&query.file_id).cloned();
+    let strict_validation = lock.config.strict_validation;
+    let Some(file) = lock.files.iter_mut().find(|f| f.id == query.file_id) else {
         return ErrorBadRequest("File not found").into();
     };
-    make_telegram_result(file)
+    if strict_validation && file.size > MAX_DOWNLOAD_SIZE {
+        let too_big = ApiError::Unknown("Bad Request: file is too big".to_string());
+        return BotApiError::new(too_big).error_response();
+    }
+    // The file is moved, so that it is downloaded from the new path
+    if let Some(path) = path {
+        file.path = path;
+    }
+    make_telegram_result(file.clone())
 }
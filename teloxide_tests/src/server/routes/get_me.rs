@@ -1,8 +1,14 @@
+use std::sync::Mutex;
+
 use actix_web::{web, Responder};
 use teloxide::types::Me;
 
 use super::make_telegram_result;
+use crate::state::State;
+
+pub async fn get_me(me: web::Data<Me>, state: web::Data<Mutex<State>>) -> impl Responder {
+    let me = me.get_ref().clone();
+    state.lock().unwrap().responses.fetched_me.push(me.clone());
 
-pub async fn get_me(me: web::Data<Me>) -> impl Responder {
     make_telegram_result(me)
 }
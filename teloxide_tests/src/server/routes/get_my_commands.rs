@@ -0,0 +1,25 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::BotCommandScope;
+
+use super::make_telegram_result;
+use crate::state::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetMyCommandsBody {
+    pub scope: Option<BotCommandScope>,
+    pub language_code: Option<String>,
+}
+
+pub async fn get_my_commands(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<GetMyCommandsBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    let commands = lock.my_commands(body.scope.clone(), body.language_code.clone());
+    lock.responses.fetched_my_commands.push(body.into_inner());
+
+    make_telegram_result(commands)
+}
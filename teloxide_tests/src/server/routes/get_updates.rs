@@ -1,8 +1,47 @@
-use actix_web::Responder;
-use serde_json::json;
+use std::{sync::Mutex, time::Duration};
+
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::Seconds;
 
 use super::make_telegram_result;
+use crate::state::State;
+
+// How often a long polling request checks for new updates
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetUpdatesBody {
+    pub offset: Option<i32>,
+    pub limit: Option<u8>,
+    pub timeout: Option<Seconds>,
+}
 
-pub async fn get_updates() -> impl Responder {
-    make_telegram_result(json!([]))
+// Without queued updates, like with `MockBot`, it always returns an empty list
+pub async fn get_updates(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<GetUpdatesBody>,
+) -> impl Responder {
+    let timeout = body
+        .timeout
+        .map(|timeout| timeout.duration())
+        .unwrap_or_default();
+    let mut waited = Duration::ZERO;
+    loop {
+        {
+            let mut lock = state.lock().unwrap();
+            // Like in telegram, the offset confirms the updates before it
+            if let Some(offset) = body.offset {
+                lock.pending_updates
+                    .retain(|update| update.id.0 as i64 >= offset as i64);
+            }
+            if !lock.pending_updates.is_empty() || waited >= timeout {
+                let limit = body.limit.unwrap_or(100) as usize;
+                let updates: Vec<_> = lock.pending_updates.iter().take(limit).cloned().collect();
+                return make_telegram_result(updates);
+            }
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+        waited += POLL_INTERVAL;
+    }
 }
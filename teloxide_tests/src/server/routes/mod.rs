@@ -1,32 +1,58 @@
 use std::{collections::HashMap, str::from_utf8};
 
-use actix_web::{error::ResponseError, http::header::ContentType, HttpResponse};
+use actix_web::{
+    error::{ErrorBadRequest, ResponseError},
+    http::header::ContentType,
+    HttpResponse,
+};
+use chrono::TimeDelta;
 use futures_util::{stream::StreamExt as _, TryStreamExt};
-use rand::distr::{Alphanumeric, SampleString};
-use serde::{Deserialize, Serialize};
+use mime::Mime;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
 use teloxide::{
-    types::{Chat, MessageEntity, ParseMode, Seconds},
+    types::{
+        Chat, EffectId, InlineKeyboardButtonKind, Message, MessageEntity, MessageEntityKind,
+        ParseMode, ReplyMarkup, ReplyParameters, Seconds,
+    },
     ApiError,
 };
 
-use crate::dataset::{MockPrivateChat, MockSupergroupChat};
+use crate::{
+    dataset::{MockMessageText, MockPrivateChat, MockSupergroupChat},
+    server::{effects::effect_emoji, fidelity::FidelityCheck, markup::markup_error, DroppedField},
+    state::State,
+};
 
 pub mod answer_callback_query;
+pub mod answer_inline_query;
 pub mod ban_chat_member;
+pub mod convert_gift_to_stars;
 pub mod copy_message;
+pub mod delete_business_messages;
 pub mod delete_message;
 pub mod delete_messages;
+pub mod delete_my_commands;
 pub mod download_file;
 pub mod edit_message_caption;
+pub mod edit_message_live_location;
 pub mod edit_message_reply_markup;
 pub mod edit_message_text;
 pub mod forward_message;
+pub mod get_business_account_gifts;
+pub mod get_business_account_star_balance;
+pub mod get_chat;
+pub mod get_chat_administrators;
+pub mod get_chat_member;
+pub mod get_chat_menu_button;
+pub mod get_custom_emoji_stickers;
 pub mod get_file;
 pub mod get_me;
+pub mod get_my_commands;
 pub mod get_updates;
 pub mod get_webhook_info;
 pub mod pin_chat_message;
+pub mod read_business_message;
 pub mod restrict_chat_member;
 pub mod send_animation;
 pub mod send_audio;
@@ -45,15 +71,22 @@ pub mod send_venue;
 pub mod send_video;
 pub mod send_video_note;
 pub mod send_voice;
+pub mod set_business_account_bio;
+pub mod set_business_account_name;
+pub mod set_business_account_profile_photo;
+pub mod set_chat_menu_button;
 pub mod set_message_reaction;
 pub mod set_my_commands;
+pub mod set_passport_data_errors;
+pub mod transfer_gift;
 pub mod unban_chat_member;
 pub mod unpin_all_chat_messages;
 pub mod unpin_chat_message;
+pub mod upgrade_gift;
 
 /// Telegram accepts both `i64` and `String` for chat_id,
 /// so it is a wrapper for both
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum BodyChatId {
     Text(String),
@@ -80,7 +113,7 @@ impl BodyChatId {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum MediaGroupInputMedia {
     InputMediaAudio(MediaGroupInputMediaAudio),
@@ -89,7 +122,7 @@ pub enum MediaGroupInputMedia {
     InputMediaVideo(MediaGroupInputMediaVideo),
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MediaGroupInputMediaAudio {
     pub r#type: String,
     pub file_name: String,
@@ -100,9 +133,10 @@ pub struct MediaGroupInputMediaAudio {
     pub duration: Option<Seconds>,
     pub performer: Option<String>,
     pub title: Option<String>,
+    pub thumbnail: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MediaGroupInputMediaDocument {
     pub r#type: String,
     pub file_name: String,
@@ -111,9 +145,10 @@ pub struct MediaGroupInputMediaDocument {
     pub parse_mode: Option<ParseMode>,
     pub caption_entities: Option<Vec<MessageEntity>>,
     pub disable_content_type_detection: Option<bool>,
+    pub thumbnail: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MediaGroupInputMediaPhoto {
     pub r#type: String,
     pub file_name: String,
@@ -125,7 +160,7 @@ pub struct MediaGroupInputMediaPhoto {
     pub has_spoiler: Option<bool>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MediaGroupInputMediaVideo {
     pub r#type: String,
     pub file_name: String,
@@ -139,6 +174,7 @@ pub struct MediaGroupInputMediaVideo {
     pub duration: Option<Seconds>,
     pub supports_streaming: Option<bool>,
     pub has_spoiler: Option<bool>,
+    pub thumbnail: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -154,10 +190,128 @@ pub enum FileType {
     Animation,
 }
 
+impl FileType {
+    /// The name of the request field with the file
+    fn field_name(&self) -> &'static str {
+        match self {
+            FileType::Photo => "photo",
+            FileType::Video => "video",
+            FileType::Audio => "audio",
+            FileType::Document => "document",
+            FileType::Sticker => "sticker",
+            FileType::Voice => "voice",
+            FileType::VideoNote => "video_note",
+            FileType::Animation => "animation",
+        }
+    }
+
+    /// The file name of a file sent by its id or url
+    fn default_file_name(&self) -> &'static str {
+        match self {
+            FileType::Photo => "no_name.jpg",
+            FileType::Video => "no_name.mp4",
+            FileType::Audio => "no_name.mp3",
+            FileType::Document => "no_name.txt",
+            FileType::Sticker => "no_name.png",
+            FileType::Voice => "no_name.mp3",
+            FileType::VideoNote => "no_name.mp4",
+            FileType::Animation => "no_name.gif",
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Attachment {
     pub raw_name: String,
     pub file_name: String,
     pub file_data: String,
+    /// The content type the bot set for the file, if any
+    pub mime_type: Option<Mime>,
+    /// Size of the file in bytes, the data of binary files is a placeholder
+    pub size: usize,
+}
+
+/// Returns the attachment that the field refers to with `attach://<name>`
+fn find_attachment<'a>(
+    fields: &HashMap<String, String>,
+    attachments: &'a HashMap<String, Attachment>,
+    field: &str,
+) -> Option<&'a Attachment> {
+    let name = fields.get(field)?.strip_prefix("attach://")?;
+    attachments
+        .values()
+        .find(|attachment| attachment.raw_name == name)
+}
+
+/// Returns the attachment in the part named after the field. Raw requests can attach the files
+/// like this, without referring to them with `attach://<name>`
+fn raw_attachment<'a>(
+    attachments: &'a HashMap<String, Attachment>,
+    field: &str,
+) -> Option<&'a Attachment> {
+    attachments
+        .values()
+        .find(|attachment| attachment.raw_name == field)
+}
+
+/// Returns the file name and the data of the sent file. It is either the attached file, or the
+/// file id or url in the field itself.
+pub fn sent_file(
+    fields: &HashMap<String, String>,
+    attachments: &HashMap<String, Attachment>,
+    file_type: FileType,
+) -> Option<(String, String)> {
+    let attachment = sent_attachment(fields, attachments, file_type)?;
+    Some((attachment.file_name, attachment.file_data))
+}
+
+/// Returns the file the bot sent, attached or by its file id or url
+pub fn sent_attachment(
+    fields: &HashMap<String, String>,
+    attachments: &HashMap<String, Attachment>,
+    file_type: FileType,
+) -> Option<Attachment> {
+    let field = file_type.field_name();
+    let attachment = find_attachment(fields, attachments, field)
+        .or_else(|| raw_attachment(attachments, field))
+        .or_else(|| {
+            // Or attach it under another name, but the thumbnail is never the file itself
+            match fields.get(field) {
+                Some(_) => None,
+                None => attachments
+                    .values()
+                    .find(|attachment| attachment.raw_name != "thumbnail"),
+            }
+        });
+    match attachment {
+        Some(attachment) => Some(attachment.clone()),
+        None => Some(Attachment {
+            raw_name: field.to_string(),
+            file_name: file_type.default_file_name().to_string(),
+            file_data: fields.get(field)?.clone(),
+            mime_type: None,
+            size: 0,
+        }),
+    }
+}
+
+/// Returns the thumbnail the bot attached, or sent by its file id or url
+pub fn sent_thumbnail(
+    fields: &HashMap<String, String>,
+    attachments: &HashMap<String, Attachment>,
+) -> Option<Attachment> {
+    let field = "thumbnail";
+    match find_attachment(fields, attachments, field).or_else(|| raw_attachment(attachments, field))
+    {
+        Some(attachment) => Some(attachment.clone()),
+        None => Some(Attachment {
+            raw_name: field.to_string(),
+            file_name: "thumbnail.jpg".to_string(),
+            file_data: fields.get(field)?.clone(),
+            mime_type: None,
+            size: 0,
+        }),
+    }
 }
 
 pub trait SerializeRawFields {
@@ -168,6 +322,33 @@ pub trait SerializeRawFields {
     ) -> Option<Self>
     where
         Self: Sized;
+
+    /// Returns the request fields that `serialize_raw_fields` doesn't put into the body, with the
+    /// error if the body has such field, but it failed to parse
+    fn dropped_raw_fields(
+        fields: &HashMap<String, String>,
+        file_type: FileType,
+    ) -> Vec<(String, Option<String>)>;
+}
+
+/// Records the request fields that the body of `method` dropped, see
+/// [`Responses::dropped_fields`]
+///
+/// [`Responses::dropped_fields`]: crate::Responses::dropped_fields
+pub(crate) fn record_dropped_fields<T: SerializeRawFields>(
+    state: &mut State,
+    method: &str,
+    fields: &HashMap<String, String>,
+    file_type: FileType,
+) {
+    for (field, error) in T::dropped_raw_fields(fields, file_type) {
+        state.responses.dropped_fields.push(DroppedField {
+            method: method.to_string(),
+            value: fields[&field].clone(),
+            field,
+            error,
+        });
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -189,7 +370,11 @@ impl BotApiError {
 
 impl std::fmt::Display for BotApiError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.error.fmt(f)
+        match &self.error {
+            // The text of unknown errors is the description itself, so teloxide parses it back
+            ApiError::Unknown(description) => f.write_str(description),
+            error => error.fmt(f),
+        }
     }
 }
 
@@ -201,7 +386,7 @@ impl ResponseError for BotApiError {
     fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
         let response = TelegramResponse {
             ok: false,
-            description: self.error.to_string(),
+            description: self.to_string(),
         };
         HttpResponse::build(self.status_code())
             .insert_header(ContentType::json())
@@ -220,16 +405,325 @@ macro_rules! check_if_message_exists {
 
 pub(crate) use check_if_message_exists;
 
+/// Returns the message the bot replies to. If there is no such message, the bot sends the
+/// message without a reply if `allow_sending_without_reply` is set, like in Telegram, or replies
+/// to a placeholder message in the lenient mode. Otherwise the request fails.
+pub(crate) fn find_reply_to_message(
+    state: &mut State,
+    reply_parameters: Option<&ReplyParameters>,
+    chat: &Chat,
+) -> Result<Option<Box<Message>>, actix_web::Error> {
+    let Some(reply_parameters) = reply_parameters else {
+        return Ok(None);
+    };
+    let message_id = reply_parameters.message_id;
+    if let Some(message) = state.messages.get_message(message_id.0) {
+        return Ok(Some(Box::new(message)));
+    }
+    if reply_parameters.allow_sending_without_reply == Some(true) {
+        return Ok(None);
+    }
+    if !state.config.lenient_replies {
+        return Err(ErrorBadRequest("Message not found"));
+    }
+    let placeholder = MockMessageText::new()
+        .id(message_id.0)
+        .chat(chat.clone())
+        .build();
+    // The placeholder keeps its id, so it doesn't count as a new message
+    state.messages.messages.push(placeholder.clone());
+    Ok(Some(Box::new(placeholder)))
+}
+
+macro_rules! reply_to_message {
+    ($lock:expr, $reply_parameters:expr, $chat:expr) => {
+        match $crate::server::routes::find_reply_to_message(
+            &mut $lock,
+            $reply_parameters.as_ref(),
+            &$chat,
+        ) {
+            Ok(reply_to_message) => reply_to_message,
+            Err(error) => return error.into(),
+        }
+    };
+}
+
+pub(crate) use reply_to_message;
+
+/// Checks the inline keyboard buttons the same way Telegram does
+pub(crate) fn validate_reply_markup(
+    reply_markup: &Option<ReplyMarkup>,
+) -> Result<(), actix_web::Error> {
+    let Some(ReplyMarkup::InlineKeyboard(markup)) = reply_markup else {
+        return Ok(());
+    };
+    for button in markup.inline_keyboard.iter().flatten() {
+        match &button.kind {
+            InlineKeyboardButtonKind::CopyText(copy_text) => {
+                if copy_text.text.is_empty() || copy_text.text.chars().count() > 256 {
+                    return Err(ErrorBadRequest(
+                        "Bad Request: can't parse inline keyboard button: copy text must be 1-256 \
+                         characters long",
+                    ));
+                }
+            }
+            InlineKeyboardButtonKind::LoginUrl(login_url) => {
+                if login_url.url.scheme() != "https" {
+                    return Err(BotApiError::new(ApiError::ButtonUrlInvalid).into());
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+macro_rules! check_reply_markup {
+    ($reply_markup:expr) => {
+        if let Err(error) = $crate::server::routes::validate_reply_markup(&$reply_markup) {
+            return error.into();
+        }
+    };
+}
+
+pub(crate) use check_reply_markup;
+
+/// Checks the custom emoji entities the same way Telegram does
+pub(crate) fn validate_entities(
+    text: &str,
+    entities: &Option<Vec<MessageEntity>>,
+) -> Result<(), actix_web::Error> {
+    let cant_parse = |reason: &str| {
+        BotApiError::new(ApiError::CantParseEntities(format!(
+            "Bad Request: can't parse entities: {reason}"
+        )))
+        .into()
+    };
+    // Entity offsets are in UTF-16 code units
+    let text_length = text.encode_utf16().count();
+    for entity in entities.iter().flatten() {
+        let MessageEntityKind::CustomEmoji { custom_emoji_id } = &entity.kind else {
+            continue;
+        };
+        if custom_emoji_id.0.is_empty() || !custom_emoji_id.0.chars().all(|c| c.is_ascii_digit()) {
+            return Err(cant_parse("invalid custom emoji identifier specified"));
+        }
+        if entity.length == 0 || entity.offset + entity.length > text_length {
+            return Err(cant_parse("custom emoji entity must be inside of the text"));
+        }
+    }
+    Ok(())
+}
+
+macro_rules! check_entities {
+    ($text:expr, $entities:expr) => {
+        if let Err(error) = $crate::server::routes::validate_entities(&$text, &$entities) {
+            return error.into();
+        }
+    };
+}
+
+pub(crate) use check_entities;
+
+/// Checks that the message effect is one of the known effects. It is only done when validating
+/// like Telegram, because Telegram adds new effects from time to time.
+pub(crate) fn validate_effect_id(
+    state: &State,
+    effect_id: &Option<EffectId>,
+) -> Result<(), actix_web::Error> {
+    match effect_id {
+        Some(effect_id) if state.config.strict_validation && effect_emoji(effect_id).is_none() => {
+            Err(ErrorBadRequest("Bad Request: EFFECT_ID_INVALID"))
+        }
+        _ => Ok(()),
+    }
+}
+
+macro_rules! check_effect_id {
+    ($lock:expr, $effect_id:expr) => {
+        if let Err(error) = $crate::server::routes::validate_effect_id(&$lock, &$effect_id) {
+            return error.into();
+        }
+    };
+}
+
+pub(crate) use check_effect_id;
+
+/// Checks that Telegram can parse the markup of the text. It is only done when validating like
+/// Telegram, because the markup isn't turned into entities, and a text with `<` or `.` can be
+/// meant literally.
+pub(crate) fn validate_markup(
+    state: &State,
+    text: &str,
+    parse_mode: &Option<ParseMode>,
+) -> Result<(), actix_web::Error> {
+    match parse_mode.and_then(|parse_mode| markup_error(text, parse_mode)) {
+        Some(reason) if state.config.strict_validation => Err(BotApiError::new(
+            ApiError::CantParseEntities(format!("Bad Request: can't parse entities: {reason}")),
+        )
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+macro_rules! check_markup {
+    ($lock:expr, $text:expr, $parse_mode:expr) => {
+        if let Err(error) = $crate::server::routes::validate_markup(&$lock, &$text, &$parse_mode) {
+            return error.into();
+        }
+    };
+}
+
+pub(crate) use check_markup;
+
+/// Bot API limit of the size of photos
+pub(crate) const MAX_PHOTO_SIZE: usize = 10 * 1024 * 1024;
+/// Bot API limit of the sum of the width and the height of photos
+pub(crate) const MAX_PHOTO_DIMENSIONS_SUM: u32 = 10000;
+/// Bot API limit of the ratio of the longer side of photos to the shorter one
+pub(crate) const MAX_PHOTO_ASPECT_RATIO: u32 = 20;
+
+/// Checks that a sent photo fits into the Bot API limits when validating like Telegram. The
+/// dimensions are only known from `MockBot::probe_media`, and photos sent by file ids have no
+/// size, so they are checked only if they are known.
+pub(crate) fn validate_photo(state: &State, photo: &Attachment) -> Result<(), actix_web::Error> {
+    if !state.config.strict_validation {
+        return Ok(());
+    }
+    let metadata = state.media_metadata(&photo.file_name);
+    let invalid_dimensions = match (metadata.width, metadata.height) {
+        (Some(width), Some(height)) => {
+            let (shorter, longer) = (width.min(height), width.max(height));
+            width + height > MAX_PHOTO_DIMENSIONS_SUM
+                || longer > shorter.saturating_mul(MAX_PHOTO_ASPECT_RATIO)
+        }
+        _ => false,
+    };
+    if invalid_dimensions || photo.size > MAX_PHOTO_SIZE {
+        return Err(BotApiError::new(ApiError::Unknown(
+            "Bad Request: PHOTO_INVALID_DIMENSIONS".to_string(),
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+macro_rules! check_photo {
+    ($lock:expr, $photo:expr) => {
+        if let Err(error) = $crate::server::routes::validate_photo(&$lock, &$photo) {
+            return error.into();
+        }
+    };
+}
+
+pub(crate) use check_photo;
+
+/// How much a message over the flood limit costs with `allow_paid_broadcast`, 0.1 stars
+pub(crate) const PAID_BROADCAST_MESSAGE_NANOSTARS: i64 = 100_000_000;
+
+/// Counts the messages against the flood limit. Over the limit, the messages are paid for from the
+/// star balance of the bot if `allow_paid_broadcast` is set, like in Telegram, otherwise the
+/// request fails with `429 Too Many Requests`.
+pub(crate) fn validate_flood_limit(
+    state: &mut State,
+    allow_paid_broadcast: Option<bool>,
+    message_count: usize,
+) -> Result<(), HttpResponse> {
+    let Some(flood_limit) = state.config.flood_limit else {
+        return Ok(());
+    };
+    let now = state.now();
+    state
+        .recent_message_times
+        .retain(|sent_at| now - *sent_at < TimeDelta::seconds(1));
+    // Only the messages of this request that don't fit into the limit are paid for
+    let over_limit = (state.recent_message_times.len() + message_count)
+        .saturating_sub(flood_limit)
+        .min(message_count);
+    if over_limit > 0 {
+        if allow_paid_broadcast != Some(true) {
+            return Err(HttpResponse::TooManyRequests().json(json!({
+                "ok": false,
+                "error_code": 429,
+                "description": "Too Many Requests: retry after 1",
+                "parameters": {"retry_after": 1},
+            })));
+        }
+        let price = over_limit as i64 * PAID_BROADCAST_MESSAGE_NANOSTARS;
+        if state.bot_nanostar_balance < price {
+            return Err(ErrorBadRequest("Bad Request: BALANCE_TOO_LOW").into());
+        }
+        state.bot_nanostar_balance -= price;
+    }
+    state
+        .recent_message_times
+        .extend(std::iter::repeat_n(now, message_count));
+    Ok(())
+}
+
+/// Bot API limit of the files that bots upload
+pub(crate) const MAX_UPLOAD_SIZE: usize = 50 * 1024 * 1024;
+/// Bot API limit of the files that bots can download with `getFile`
+pub(crate) const MAX_DOWNLOAD_SIZE: u32 = 20 * 1024 * 1024;
+
+/// Checks that the uploaded files fit into the Bot API limit when validating like Telegram,
+/// otherwise the request fails with `413 Request Entity Too Large`, like in Telegram
+pub(crate) fn validate_upload_size(
+    state: &State,
+    attachments: &HashMap<String, Attachment>,
+) -> Result<(), HttpResponse> {
+    if state.config.strict_validation
+        && attachments
+            .values()
+            .any(|attachment| attachment.size > MAX_UPLOAD_SIZE)
+    {
+        return Err(HttpResponse::PayloadTooLarge().json(json!({
+            "ok": false,
+            "error_code": 413,
+            "description": "Request Entity Too Large",
+        })));
+    }
+    Ok(())
+}
+
+macro_rules! check_upload_size {
+    ($lock:expr, $attachments:expr) => {
+        if let Err(response) = $crate::server::routes::validate_upload_size(&$lock, &$attachments) {
+            return response;
+        }
+    };
+}
+
+pub(crate) use check_upload_size;
+
+macro_rules! check_flood_limit {
+    ($lock:expr, $allow_paid_broadcast:expr) => {
+        $crate::server::routes::check_flood_limit!($lock, $allow_paid_broadcast, 1)
+    };
+    ($lock:expr, $allow_paid_broadcast:expr, $message_count:expr) => {
+        if let Err(response) = $crate::server::routes::validate_flood_limit(
+            &mut $lock,
+            $allow_paid_broadcast,
+            $message_count,
+        ) {
+            return response;
+        }
+    };
+}
+
+pub(crate) use check_flood_limit;
+
 pub async fn get_raw_multipart_fields(
     payload: &mut actix_multipart::Multipart,
 ) -> (HashMap<String, String>, HashMap<String, Attachment>) {
     let mut raw_fields: HashMap<String, Vec<u8>> = HashMap::new();
-    let mut raw_attachments: HashMap<String, (String, Vec<u8>)> = HashMap::new();
+    let mut raw_attachments: HashMap<String, (String, Vec<u8>, Option<Mime>)> = HashMap::new();
 
     while let Ok(Some(mut field)) = payload.try_next().await {
         let content_disposition = field.content_disposition().unwrap();
         let name = content_disposition.get_name().unwrap().to_string();
         let filename = content_disposition.get_filename().map(|s| s.to_string());
+        let mime_type = field.content_type().cloned();
 
         let mut field_data = Vec::new();
         while let Some(chunk) = field.next().await {
@@ -240,22 +734,24 @@ pub async fn get_raw_multipart_fields(
         if let Some(fname) = filename {
             // Treat raw_fields with filenames as raw_attachments
             let mut attachment_key = fname.clone();
-            if raw_attachments.contains_key(&fname) {
-                // If two files have the same name, add a random string to the filename
+            // If two files have the same name, add a number to the filename
+            let mut copy_number = 1;
+            while raw_attachments.contains_key(&attachment_key) {
                 attachment_key = fname
                     .split('.')
                     .enumerate()
                     .map(|(i, s)| {
                         if i == 0 {
-                            format!("{s}{}", Alphanumeric.sample_string(&mut rand::rng(), 5))
+                            format!("{s}{copy_number}")
                         } else {
                             s.to_string()
                         }
                     })
                     .collect::<Vec<String>>()
                     .join(".");
+                copy_number += 1;
             }
-            raw_attachments.insert(attachment_key, (name, field_data));
+            raw_attachments.insert(attachment_key, (name, field_data, mime_type));
         } else {
             raw_fields.insert(name, field_data);
         }
@@ -281,6 +777,8 @@ pub async fn get_raw_multipart_fields(
                 file_data: from_utf8(&data.1)
                     .unwrap_or("error_getting_data")
                     .to_string(),
+                mime_type: data.2,
+                size: data.1.len(),
             },
         );
     }
@@ -290,13 +788,18 @@ pub async fn get_raw_multipart_fields(
 
 pub fn make_telegram_result<T>(result: T) -> HttpResponse
 where
-    T: Serialize,
+    T: Serialize + DeserializeOwned,
 {
-    HttpResponse::Ok().body(
+    let result = serde_json::to_value(result).unwrap();
+    let mut response = HttpResponse::Ok().body(
         json!({
             "ok": true,
             "result": result,
         })
         .to_string(),
-    )
+    );
+    response
+        .extensions_mut()
+        .insert(FidelityCheck::new::<T>(result));
+    response
 }
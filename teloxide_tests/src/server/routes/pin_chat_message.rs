@@ -1,13 +1,13 @@
 use std::sync::Mutex;
 
 use actix_web::{error::ErrorBadRequest, web, Responder};
-use serde::Deserialize;
-use teloxide::types::BusinessConnectionId;
+use serde::{Deserialize, Serialize};
+use teloxide::types::{BusinessConnectionId, ChatId};
 
 use super::{check_if_message_exists, BodyChatId};
 use crate::{server::routes::make_telegram_result, state::State};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PinChatMessageBody {
     pub chat_id: BodyChatId,
     pub message_id: i32,
@@ -21,6 +21,14 @@ pub async fn pin_chat_message(
 ) -> impl Responder {
     let mut lock = state.lock().unwrap();
     check_if_message_exists!(lock, body.message_id);
+    let message = lock.messages.get_message(body.message_id).unwrap();
+    let pinned_messages = &mut lock
+        .chat_metadata
+        .entry(ChatId(body.chat_id.id()))
+        .or_default()
+        .pinned_messages;
+    pinned_messages.retain(|pinned| pinned.id != message.id);
+    pinned_messages.push(message);
     lock.responses.pinned_chat_messages.push(body.into_inner());
     make_telegram_result(true)
 }
@@ -0,0 +1,27 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::BusinessConnectionId;
+
+use super::make_telegram_result;
+use crate::state::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReadBusinessMessageBody {
+    pub business_connection_id: BusinessConnectionId,
+    pub chat_id: i64,
+    pub message_id: i32,
+}
+
+pub async fn read_business_message(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<ReadBusinessMessageBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    lock.responses
+        .read_business_messages
+        .push(body.into_inner());
+
+    make_telegram_result(true)
+}
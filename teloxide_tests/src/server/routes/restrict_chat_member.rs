@@ -1,13 +1,14 @@
 use std::sync::Mutex;
 
 use actix_web::{web, Responder};
-use serde::Deserialize;
-use teloxide::types::ChatPermissions;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use teloxide::types::{ChatId, ChatMember, ChatMemberKind, ChatPermissions, Member, Restricted};
 
 use super::BodyChatId;
-use crate::{server::routes::make_telegram_result, state::State};
+use crate::{server::routes::make_telegram_result, state::State, MockUser};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RestrictChatMemberBody {
     pub chat_id: BodyChatId,
     pub user_id: u64,
@@ -16,12 +17,67 @@ pub struct RestrictChatMemberBody {
     pub until_date: Option<i64>,
 }
 
+impl RestrictChatMemberBody {
+    /// The date the restriction ends at, the `until_date` field has it as the raw unix time
+    pub fn until_date(&self) -> Option<DateTime<Utc>> {
+        self.until_date
+            .and_then(|until_date| DateTime::from_timestamp(until_date, 0))
+    }
+}
+
 pub async fn restrict_chat_member(
     state: web::Data<Mutex<State>>,
     body: web::Json<RestrictChatMemberBody>,
 ) -> impl Responder {
-    // Idk what to verify here
     let mut lock = state.lock().unwrap();
+    let chat_id = body.chat_id.id();
+    let mut permissions = body.permissions.clone();
+    if !body.use_independent_chat_permissions.unwrap_or(false) {
+        // Without independent permissions, the broader ones imply the ones they are built on
+        if permissions.intersects(
+            ChatPermissions::SEND_OTHER_MESSAGES | ChatPermissions::ADD_WEB_PAGE_PREVIEWS,
+        ) {
+            permissions |= ChatPermissions::SEND_MESSAGES | ChatPermissions::SEND_MEDIA_MESSAGES;
+        }
+        if permissions.contains(ChatPermissions::SEND_POLLS) {
+            permissions |= ChatPermissions::SEND_MESSAGES;
+        }
+    }
+
+    let old_member = lock.get_chat_member(chat_id, body.user_id);
+    let is_member = !matches!(
+        old_member.as_ref().map(|member| &member.kind),
+        Some(ChatMemberKind::Left | ChatMemberKind::Banned(_))
+    );
+    let user = match old_member {
+        Some(member) => member.user,
+        None => MockUser::new().id(body.user_id).build(),
+    };
+    let kind = if permissions.is_all() {
+        // A restriction that allows everything is no restriction at all
+        ChatMemberKind::Member(Member { until_date: None })
+    } else {
+        ChatMemberKind::Restricted(Restricted {
+            until_date: lock.until_date(body.until_date()),
+            is_member,
+            can_send_messages: permissions.can_send_messages(),
+            can_send_audios: permissions.can_send_audios(),
+            can_send_documents: permissions.can_send_documents(),
+            can_send_photos: permissions.can_send_photos(),
+            can_send_videos: permissions.can_send_videos(),
+            can_send_video_notes: permissions.can_send_video_notes(),
+            can_send_voice_notes: permissions.can_send_voice_notes(),
+            can_send_other_messages: permissions.can_send_other_messages(),
+            can_add_web_page_previews: permissions.can_add_web_page_previews(),
+            can_change_info: permissions.can_change_info(),
+            can_invite_users: permissions.can_invite_users(),
+            can_pin_messages: permissions.can_pin_messages(),
+            can_manage_topics: permissions.can_manage_topics(),
+            can_send_polls: permissions.can_send_polls(),
+        })
+    };
+    lock.add_chat_member(ChatId(chat_id), ChatMember { user, kind });
+
     lock.responses
         .restricted_chat_members
         .push(body.into_inner());
@@ -1,20 +1,24 @@
 use std::{collections::HashMap, str::FromStr, sync::Mutex};
 
 use actix_multipart::Multipart;
-use actix_web::{error::ErrorBadRequest, web, Responder};
+use actix_web::{web, Responder};
 use mime::Mime;
-use rand::distr::{Alphanumeric, SampleString};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use teloxide::types::{
-    BusinessConnectionId, EffectId, FileId, FileUniqueId, Me, MessageEntity, ParseMode,
-    ReplyMarkup, ReplyParameters, Seconds,
+    BusinessConnectionId, EffectId, FileMeta, Me, MessageEntity, ParseMode, ReplyMarkup,
+    ReplyParameters, Seconds,
 };
 
-use super::{get_raw_multipart_fields, make_telegram_result, BodyChatId};
+use super::{
+    check_effect_id, check_flood_limit, check_markup, check_reply_markup, check_upload_size,
+    get_raw_multipart_fields, make_telegram_result, BodyChatId,
+};
 use crate::{
     proc_macros::SerializeRawFields,
     server::{
-        routes::{check_if_message_exists, Attachment, FileType, SerializeRawFields},
+        routes::{
+            record_dropped_fields, reply_to_message, Attachment, FileType, SerializeRawFields,
+        },
         SentMessageAudio,
     },
     state::State,
@@ -28,8 +32,10 @@ pub async fn send_audio(
 ) -> impl Responder {
     let (fields, attachments) = get_raw_multipart_fields(&mut payload).await;
     let mut lock = state.lock().unwrap();
+    check_upload_size!(lock, attachments);
     let body =
         SendMessageAudioBody::serialize_raw_fields(&fields, &attachments, FileType::Audio).unwrap();
+    record_dropped_fields::<SendMessageAudioBody>(&mut lock, "sendAudio", &fields, FileType::Audio);
     let chat = body.chat_id.chat();
 
     let mut message = MockMessageAudio::new().chat(chat.clone());
@@ -40,47 +46,58 @@ pub async fn send_audio(
     message.effect_id = body.message_effect_id.clone();
     message.business_connection_id = body.business_connection_id.clone();
 
-    if let Some(reply_parameters) = &body.reply_parameters {
-        check_if_message_exists!(lock, reply_parameters.message_id.0);
-        let reply_to_message = lock
-            .messages
-            .get_message(reply_parameters.message_id.0)
-            .unwrap();
-        message.reply_to_message = Some(Box::new(reply_to_message.clone()));
-    }
+    message.reply_to_message = reply_to_message!(lock, body.reply_parameters, message.chat);
     if let Some(ReplyMarkup::InlineKeyboard(markup)) = body.reply_markup.clone() {
         message.reply_markup = Some(markup);
     }
 
-    let file_id = FileId(Alphanumeric.sample_string(&mut rand::rng(), 16));
-    let file_unique_id = FileUniqueId(Alphanumeric.sample_string(&mut rand::rng(), 8));
+    let FileMeta {
+        id: file_id,
+        unique_id: file_unique_id,
+        size: file_size,
+    } = lock.file_meta(&body.file_data);
+    let metadata = lock.media_metadata(&body.file_name);
 
     message.file_id = file_id;
     message.file_unique_id = file_unique_id;
     message.performer = body.performer.clone();
     message.title = body.title.clone();
-    message.duration = body.duration.unwrap_or(Seconds::from_seconds(0));
-    message.file_size = body.file_data.bytes().len() as u32;
+    message.duration = body
+        .duration
+        .or(metadata.duration)
+        .unwrap_or(Seconds::from_seconds(0));
+    message.file_size = file_size;
     message.mime_type = Some(Mime::from_str("audio/mp3").unwrap());
     message.file_name = Some(body.file_name.clone());
 
+    check_reply_markup!(body.reply_markup);
+    check_effect_id!(lock, body.message_effect_id);
+    check_markup!(
+        lock,
+        body.caption.as_deref().unwrap_or_default(),
+        body.parse_mode
+    );
+    check_flood_limit!(lock, body.allow_paid_broadcast);
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build(), &me);
+    lock.add_reply_markup(&message, body.reply_markup.clone());
 
     lock.files.push(teloxide::types::File {
         meta: message.audio().unwrap().file.clone(),
         path: body.file_name.to_owned(),
     });
     lock.responses.sent_messages.push(message.clone());
+    let seq = lock.next_seq();
     lock.responses.sent_messages_audio.push(SentMessageAudio {
         message: message.clone(),
         bot_request: body,
+        seq,
     });
 
     make_telegram_result(message)
 }
 
-#[derive(Debug, Clone, Deserialize, SerializeRawFields)]
+#[derive(Debug, Clone, Serialize, Deserialize, SerializeRawFields)]
 pub struct SendMessageAudioBody {
     pub chat_id: BodyChatId,
     pub message_thread_id: Option<i64>,
@@ -94,6 +111,7 @@ pub struct SendMessageAudioBody {
     pub title: Option<String>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
+    pub allow_paid_broadcast: Option<bool>,
     pub message_effect_id: Option<EffectId>,
     pub reply_parameters: Option<ReplyParameters>,
     pub reply_markup: Option<ReplyMarkup>,
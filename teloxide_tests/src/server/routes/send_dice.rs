@@ -1,24 +1,30 @@
-use std::sync::Mutex;
+use std::{ops::RangeInclusive, sync::Mutex};
 
 use actix_web::{error::ErrorBadRequest, web, Responder};
-use serde::Deserialize;
-use teloxide::types::{BusinessConnectionId, DiceEmoji, ReplyMarkup, ReplyParameters};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use teloxide::types::{
+    BusinessConnectionId, DiceEmoji, EffectId, Me, ReplyMarkup, ReplyParameters,
+};
 
-use super::{make_telegram_result, BodyChatId};
+use super::{
+    check_effect_id, check_flood_limit, check_reply_markup, make_telegram_result, BodyChatId,
+};
 use crate::{
-    server::{routes::check_if_message_exists, SentMessageDice},
+    server::{routes::reply_to_message, SentMessageDice},
     state::State,
     MockMessageDice,
 };
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SendMessageDiceBody {
     pub chat_id: BodyChatId,
     pub message_thread_id: Option<i64>,
     pub emoji: Option<DiceEmoji>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
-    pub message_effect_id: Option<String>,
+    pub allow_paid_broadcast: Option<bool>,
+    pub message_effect_id: Option<EffectId>,
     pub reply_markup: Option<ReplyMarkup>,
     pub reply_parameters: Option<ReplyParameters>,
     pub business_connection_id: Option<BusinessConnectionId>,
@@ -27,26 +33,52 @@ pub struct SendMessageDiceBody {
 pub async fn send_dice(
     state: web::Data<Mutex<State>>,
     body: web::Json<SendMessageDiceBody>,
+    me: web::Data<Me>,
 ) -> impl Responder {
     let mut lock = state.lock().unwrap();
     let chat = body.chat_id.chat();
     let mut message = // Creates the message, which will be mutated to fit the needed shape
         MockMessageDice::new().chat(chat);
+    message.from = Some(me.user.clone());
     message.emoji = body.emoji.clone().unwrap_or(MockMessageDice::EMOJI);
-    // Random from 1 to 5 because it fits all the emoji
-    message.value = (1 + rand::random::<u8>() % 5) as u8;
-    if let Some(reply_parameters) = &body.reply_parameters {
-        check_if_message_exists!(lock, reply_parameters.message_id.0);
-    }
+    let range = dice_value_range(&message.emoji);
+    message.value = match lock.dice_values.pop_front() {
+        Some(value) if !range.contains(&value) => {
+            return ErrorBadRequest(format!(
+                "Dice value {value} is out of range {range:?} for {:?}",
+                message.emoji
+            ))
+            .into();
+        }
+        Some(value) => value,
+        None => lock.ids.0.random_range(range),
+    };
+    // Dice messages can't hold the replied message, but the reply still has to be valid
+    reply_to_message!(lock, body.reply_parameters, message.chat);
 
+    check_reply_markup!(body.reply_markup);
+    check_effect_id!(lock, body.message_effect_id);
+    check_flood_limit!(lock, body.allow_paid_broadcast);
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build(), &me);
+    lock.add_reply_markup(&message, body.reply_markup.clone());
 
     lock.responses.sent_messages.push(message.clone());
+    let seq = lock.next_seq();
     lock.responses.sent_messages_dice.push(SentMessageDice {
         message: message.clone(),
         bot_request: body.into_inner(),
+        seq,
     });
 
     make_telegram_result(message)
 }
+
+// The values Telegram can return for every emoji
+fn dice_value_range(emoji: &DiceEmoji) -> RangeInclusive<u8> {
+    match emoji {
+        DiceEmoji::Dice | DiceEmoji::Darts | DiceEmoji::Bowling => 1..=6,
+        DiceEmoji::Basketball | DiceEmoji::Football => 1..=5,
+        DiceEmoji::SlotMachine => 1..=64,
+    }
+}
@@ -1,21 +1,26 @@
 use std::{collections::HashMap, str::FromStr, sync::Mutex};
 
 use actix_multipart::Multipart;
-use actix_web::{error::ErrorBadRequest, web, Responder};
+use actix_web::{web, Responder};
 use mime::Mime;
-use rand::distr::{Alphanumeric, SampleString};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use teloxide::types::{
-    BusinessConnectionId, EffectId, FileId, FileUniqueId, Me, MessageEntity, ParseMode,
-    ReplyMarkup, ReplyParameters,
+    BusinessConnectionId, EffectId, FileMeta, Me, MessageEntity, ParseMode, ReplyMarkup,
+    ReplyParameters,
 };
 
-use super::{get_raw_multipart_fields, make_telegram_result, BodyChatId};
+use super::{
+    check_effect_id, check_flood_limit, check_markup, check_reply_markup, check_upload_size,
+    get_raw_multipart_fields, make_telegram_result, BodyChatId,
+};
 use crate::{
     dataset::MockMessageDocument,
     proc_macros::SerializeRawFields,
     server::{
-        routes::{check_if_message_exists, Attachment, FileType, SerializeRawFields},
+        routes::{
+            record_dropped_fields, reply_to_message, sent_attachment, sent_thumbnail, Attachment,
+            FileType, SerializeRawFields,
+        },
         SentMessageDocument,
     },
     state::State,
@@ -28,9 +33,23 @@ pub async fn send_document(
 ) -> impl Responder {
     let (fields, attachments) = get_raw_multipart_fields(&mut payload).await;
     let mut lock = state.lock().unwrap();
-    let body =
+    check_upload_size!(lock, attachments);
+    let mut body =
         SendMessageDocumentBody::serialize_raw_fields(&fields, &attachments, FileType::Document)
             .unwrap();
+    record_dropped_fields::<SendMessageDocumentBody>(
+        &mut lock,
+        "sendDocument",
+        &fields,
+        FileType::Document,
+    );
+    // The file and its thumbnail are attached in any order
+    let file = sent_attachment(&fields, &attachments, FileType::Document).unwrap();
+    (body.file_name, body.file_data) = (file.file_name.clone(), file.file_data.clone());
+    let thumbnail = sent_thumbnail(&fields, &attachments);
+    body.thumbnail = thumbnail
+        .as_ref()
+        .map(|thumbnail| thumbnail.file_data.clone());
     let chat = body.chat_id.chat();
 
     let mut message = // Creates the message, which will be mutated to fit the needed shape
@@ -41,55 +60,84 @@ pub async fn send_document(
     message.effect_id = body.message_effect_id.clone();
     message.business_connection_id = body.business_connection_id.clone();
 
-    if let Some(reply_parameters) = &body.reply_parameters {
-        check_if_message_exists!(lock, reply_parameters.message_id.0);
-        let reply_to_message = lock
-            .messages
-            .get_message(reply_parameters.message_id.0)
-            .unwrap();
-        message.reply_to_message = Some(Box::new(reply_to_message.clone()));
-    }
+    message.reply_to_message = reply_to_message!(lock, body.reply_parameters, message.chat);
     if let Some(ReplyMarkup::InlineKeyboard(markup)) = body.reply_markup.clone() {
         message.reply_markup = Some(markup);
     }
 
-    let file_id = FileId(Alphanumeric.sample_string(&mut rand::rng(), 16));
-    let file_unique_id = FileUniqueId(Alphanumeric.sample_string(&mut rand::rng(), 8));
+    let FileMeta {
+        id: file_id,
+        unique_id: file_unique_id,
+        size: file_size,
+    } = lock.file_meta(&body.file_data);
 
     message.file_name = Some(body.file_name.clone());
     message.file_id = file_id;
     message.file_unique_id = file_unique_id;
-    message.file_size = body.file_data.bytes().len() as u32;
-    message.mime_type = Some(
-        mime_guess::from_path(body.file_name.clone())
-            .first()
-            .unwrap_or(Mime::from_str("text/plain").unwrap()),
-    );
+    message.file_size = file_size;
+    message.mime_type = Some(document_mime_type(
+        &file,
+        body.disable_content_type_detection.unwrap_or(false),
+    ));
+    // Telegram makes thumbnails only for pictures and videos
+    let has_preview = message
+        .mime_type
+        .as_ref()
+        .is_some_and(|mime| [mime::IMAGE, mime::VIDEO].contains(&mime.type_()));
+    if thumbnail.is_some() || has_preview {
+        message.thumbnail = Some(lock.thumbnail(thumbnail.as_ref()));
+    }
     message.has_protected_content = body.protect_content.unwrap_or(false);
 
+    check_reply_markup!(body.reply_markup);
+    check_effect_id!(lock, body.message_effect_id);
+    check_markup!(
+        lock,
+        body.caption.as_deref().unwrap_or_default(),
+        body.parse_mode
+    );
+    check_flood_limit!(lock, body.allow_paid_broadcast);
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build(), &me);
+    lock.add_reply_markup(&message, body.reply_markup.clone());
 
     lock.files.push(teloxide::types::File {
         meta: message.document().unwrap().file.clone(),
         path: body.file_name.to_owned(),
     });
     lock.responses.sent_messages.push(message.clone());
+    let seq = lock.next_seq();
     lock.responses
         .sent_messages_document
         .push(SentMessageDocument {
             message: message.clone(),
             bot_request: body,
+            seq,
         });
 
     make_telegram_result(message)
 }
 
-#[derive(Debug, Clone, Deserialize, SerializeRawFields)]
+/// Returns the MIME type of a sent document. The content type the bot set for the file is kept,
+/// and if there is none, or it is the generic `application/octet-stream`, Telegram detects it,
+/// here by the file name. With the detection disabled, the generic type is kept too.
+fn document_mime_type(file: &Attachment, disable_content_type_detection: bool) -> Mime {
+    match &file.mime_type {
+        Some(mime) if *mime != mime::APPLICATION_OCTET_STREAM || disable_content_type_detection => {
+            mime.clone()
+        }
+        _ => mime_guess::from_path(&file.file_name)
+            .first()
+            .unwrap_or(Mime::from_str("text/plain").unwrap()),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SerializeRawFields)]
 pub struct SendMessageDocumentBody {
     pub chat_id: BodyChatId,
     pub file_name: String,
     pub file_data: String,
+    pub thumbnail: Option<String>,
     pub caption: Option<String>,
     pub message_thread_id: Option<i64>,
     pub parse_mode: Option<ParseMode>,
@@ -97,6 +145,7 @@ pub struct SendMessageDocumentBody {
     pub disable_content_type_detection: Option<bool>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
+    pub allow_paid_broadcast: Option<bool>,
     pub message_effect_id: Option<EffectId>,
     pub reply_markup: Option<ReplyMarkup>,
     pub reply_parameters: Option<ReplyParameters>,
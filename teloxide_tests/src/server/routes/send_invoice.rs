@@ -1,13 +1,15 @@
 use std::sync::Mutex;
 
 use actix_web::{web, Responder};
-use serde::Deserialize;
-use teloxide::types::{LabeledPrice, Me, ReplyMarkup, ReplyParameters};
+use serde::{Deserialize, Serialize};
+use teloxide::types::{EffectId, LabeledPrice, Me, ReplyMarkup, ReplyParameters};
 
-use super::{make_telegram_result, BodyChatId};
+use super::{
+    check_effect_id, check_flood_limit, check_reply_markup, make_telegram_result, BodyChatId,
+};
 use crate::{server::SentMessageInvoice, state::State, MockMessageInvoice};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SendMessageInvoiceBody {
     pub chat_id: BodyChatId,
     pub message_thread_id: Option<i64>,
@@ -22,9 +24,9 @@ pub struct SendMessageInvoiceBody {
     pub start_parameter: Option<String>,
     pub provider_data: Option<String>,
     pub photo_url: Option<String>,
-    pub photo_size: Option<String>,
-    pub photo_width: Option<String>,
-    pub photo_height: Option<String>,
+    pub photo_size: Option<u32>,
+    pub photo_width: Option<u32>,
+    pub photo_height: Option<u32>,
     pub need_name: Option<bool>,
     pub need_phone_number: Option<bool>,
     pub need_email: Option<bool>,
@@ -34,7 +36,8 @@ pub struct SendMessageInvoiceBody {
     pub is_flexible: Option<bool>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
-    pub message_effect_id: Option<String>,
+    pub allow_paid_broadcast: Option<bool>,
+    pub message_effect_id: Option<EffectId>,
     pub reply_parameters: Option<ReplyParameters>,
     pub reply_markup: Option<ReplyMarkup>,
 }
@@ -52,7 +55,10 @@ pub async fn send_invoice(
         .title(body.title.clone())
         .description(body.description.clone())
         .start_parameter(body.start_parameter.clone().unwrap_or("".to_owned()))
-        .total_amount(body.prices.first().unwrap().amount);
+        .currency(body.currency.clone())
+        .total_amount(body.prices.iter().map(|price| price.amount).sum());
+    // The invoice of the message has no provider, tip, photo or need_* fields, like in Telegram,
+    // they are only in the recorded request
     message.from = Some(me.user.clone());
 
     // Commented until teloxides new release
@@ -70,15 +76,21 @@ pub async fn send_invoice(
     //     message.reply_markup = Some(markup);
     // }
 
+    check_reply_markup!(body.reply_markup);
+    check_effect_id!(lock, body.message_effect_id);
+    check_flood_limit!(lock, body.allow_paid_broadcast);
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build(), &me);
+    lock.add_reply_markup(&message, body.reply_markup.clone());
 
     lock.responses.sent_messages.push(message.clone());
+    let seq = lock.next_seq();
     lock.responses
         .sent_messages_invoice
         .push(SentMessageInvoice {
             message: message.clone(),
             bot_request: body.into_inner(),
+            seq,
         });
 
     make_telegram_result(message)
@@ -1,19 +1,21 @@
 use std::sync::Mutex;
 
-use actix_web::{error::ErrorBadRequest, web, Responder};
-use serde::Deserialize;
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
 use teloxide::types::{
     BusinessConnectionId, EffectId, LivePeriod, Me, ReplyMarkup, ReplyParameters,
 };
 
-use super::{make_telegram_result, BodyChatId};
+use super::{
+    check_effect_id, check_flood_limit, check_reply_markup, make_telegram_result, BodyChatId,
+};
 use crate::{
-    server::{routes::check_if_message_exists, SentMessageLocation},
+    server::{routes::reply_to_message, SentMessageLocation},
     state::State,
     MockMessageLocation,
 };
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SendMessageLocationBody {
     pub chat_id: BodyChatId,
     pub latitude: f64,
@@ -25,6 +27,7 @@ pub struct SendMessageLocationBody {
     pub message_thread_id: Option<i64>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
+    pub allow_paid_broadcast: Option<bool>,
     pub message_effect_id: Option<EffectId>,
     pub reply_markup: Option<ReplyMarkup>,
     pub reply_parameters: Option<ReplyParameters>,
@@ -44,33 +47,35 @@ pub async fn send_location(
     message.from = Some(me.user.clone());
     message.horizontal_accuracy = body.horizontal_accuracy;
     message.live_period = body.live_period;
-    message.heading = body.heading;
-    message.proximity_alert_radius = body.proximity_alert_radius;
+    if body.live_period.is_some() {
+        // Telegram keeps the heading and the proximity alerts only for live locations
+        message.heading = body.heading;
+        message.proximity_alert_radius = body.proximity_alert_radius;
+    }
     message.has_protected_content = body.protect_content.unwrap_or(false);
     message.effect_id = body.message_effect_id.clone();
     message.business_connection_id = body.business_connection_id.clone();
 
-    if let Some(reply_parameters) = &body.reply_parameters {
-        check_if_message_exists!(lock, reply_parameters.message_id.0);
-        let reply_to_message = lock
-            .messages
-            .get_message(reply_parameters.message_id.0)
-            .unwrap();
-        message.reply_to_message = Some(Box::new(reply_to_message.clone()));
-    }
+    message.reply_to_message = reply_to_message!(lock, body.reply_parameters, message.chat);
     if let Some(ReplyMarkup::InlineKeyboard(markup)) = body.reply_markup.clone() {
         message.reply_markup = Some(markup);
     }
 
+    check_reply_markup!(body.reply_markup);
+    check_effect_id!(lock, body.message_effect_id);
+    check_flood_limit!(lock, body.allow_paid_broadcast);
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build(), &me);
+    lock.add_reply_markup(&message, body.reply_markup.clone());
 
     lock.responses.sent_messages.push(message.clone());
+    let seq = lock.next_seq();
     lock.responses
         .sent_messages_location
         .push(SentMessageLocation {
             message: message.clone(),
             bot_request: body.into_inner(),
+            seq,
         });
 
     make_telegram_result(message)
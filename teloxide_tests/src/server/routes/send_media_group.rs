@@ -2,24 +2,22 @@ use std::{collections::HashMap, sync::Mutex};
 
 use actix_multipart::Multipart;
 use actix_web::{error::ErrorBadRequest, web, Responder};
-use rand::distr::{Alphanumeric, SampleString};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use teloxide::types::{
-    BusinessConnectionId, EffectId, FileId, FileUniqueId, Me, MediaGroupId, Message, MessageEntity,
-    MessageId, ParseMode, ReplyParameters, Seconds,
+    BusinessConnectionId, EffectId, FileMeta, Me, MediaGroupId, Message, MessageEntity, MessageId,
+    ParseMode, ReplyParameters, Seconds,
 };
 
 use super::{
-    get_raw_multipart_fields, make_telegram_result, Attachment, BodyChatId, MediaGroupInputMedia,
-    MediaGroupInputMediaAudio, MediaGroupInputMediaDocument, MediaGroupInputMediaPhoto,
-    MediaGroupInputMediaVideo,
+    check_effect_id, check_flood_limit, check_markup, check_upload_size, get_raw_multipart_fields,
+    make_telegram_result, Attachment, BodyChatId, MediaGroupInputMedia, MediaGroupInputMediaAudio,
+    MediaGroupInputMediaDocument, MediaGroupInputMediaPhoto, MediaGroupInputMediaVideo,
 };
 use crate::{
-    server::{routes::check_if_message_exists, SentMediaGroup},
+    server::{routes::reply_to_message, SentMediaGroup},
     state::State,
-    MockMessageAudio, MockMessageDocument, MockMessagePhoto, MockMessageVideo, MockPhotoSize,
-    MockVideo,
+    MockMessageAudio, MockMessageDocument, MockMessagePhoto, MockMessageVideo, MockVideo,
 };
 
 pub async fn send_media_group(
@@ -29,6 +27,7 @@ pub async fn send_media_group(
 ) -> impl Responder {
     let (fields, attachments) = get_raw_multipart_fields(&mut payload).await;
     let mut lock = state.lock().unwrap();
+    check_upload_size!(lock, attachments);
     let body = SendMediaGroupBody::serialize_raw_fields(&fields, &attachments).unwrap();
     if body.media.len() > 10 {
         return ErrorBadRequest("Too many media items").into();
@@ -37,25 +36,39 @@ pub async fn send_media_group(
     }
     let chat = body.chat_id.chat();
     let protect_content = body.protect_content;
+    check_effect_id!(lock, body.message_effect_id);
+    for media in &body.media {
+        let (caption, parse_mode) = match media {
+            MediaGroupInputMedia::InputMediaAudio(audio) => (&audio.caption, audio.parse_mode),
+            MediaGroupInputMedia::InputMediaDocument(document) => {
+                (&document.caption, document.parse_mode)
+            }
+            MediaGroupInputMedia::InputMediaPhoto(photo) => (&photo.caption, photo.parse_mode),
+            MediaGroupInputMedia::InputMediaVideo(video) => (&video.caption, video.parse_mode),
+        };
+        check_markup!(lock, caption.as_deref().unwrap_or_default(), parse_mode);
+    }
     let message_effect_id = body.message_effect_id.clone();
     let business_connection_id = body.business_connection_id.clone();
-    let mut reply_to_message = None;
-    if let Some(reply_parameters) = &body.reply_parameters {
-        check_if_message_exists!(lock, reply_parameters.message_id.0);
-        // All of messages in the media group are replying to the same message
-        reply_to_message = Some(Box::new(
-            lock.messages
-                .get_message(reply_parameters.message_id.0)
-                .unwrap(),
-        ));
-    }
-    let media_group_id = MediaGroupId(Alphanumeric.sample_string(&mut rand::rng(), 16));
+    // All of messages in the media group are replying to the same message
+    let reply_to_message = reply_to_message!(lock, body.reply_parameters, chat);
+    check_flood_limit!(lock, body.allow_paid_broadcast, body.media.len());
+    let media_group_id = MediaGroupId(lock.ids.string(16));
 
     let mut messages: Vec<Message> = vec![];
 
     for media in &body.media {
-        let file_id = FileId(Alphanumeric.sample_string(&mut rand::rng(), 16));
-        let file_unique_id = FileUniqueId(Alphanumeric.sample_string(&mut rand::rng(), 8));
+        let file_data = match media {
+            MediaGroupInputMedia::InputMediaAudio(audio) => &audio.file_data,
+            MediaGroupInputMedia::InputMediaDocument(document) => &document.file_data,
+            MediaGroupInputMedia::InputMediaPhoto(photo) => &photo.file_data,
+            MediaGroupInputMedia::InputMediaVideo(video) => &video.file_data,
+        };
+        let FileMeta {
+            id: file_id,
+            unique_id: file_unique_id,
+            size: file_size,
+        } = lock.file_meta(file_data);
         let last_id = lock.messages.max_message_id();
         let message: Message;
         match media {
@@ -71,15 +84,22 @@ pub async fn send_media_group(
                 mock_message.media_group_id = Some(media_group_id.clone());
                 mock_message.performer = audio.performer.clone();
                 mock_message.title = audio.title.clone();
-                mock_message.duration = audio.duration.unwrap_or(Seconds::from_seconds(1));
+                mock_message.duration = audio
+                    .duration
+                    .or(lock.media_metadata(&audio.file_name).duration)
+                    .unwrap_or(Seconds::from_seconds(1));
                 mock_message.effect_id = message_effect_id.clone();
                 mock_message.business_connection_id = business_connection_id.clone();
 
                 mock_message.file_name = Some(audio.file_name.clone());
                 mock_message.file_id = file_id;
                 mock_message.file_unique_id = file_unique_id;
-                mock_message.file_size = audio.file_data.bytes().len() as u32;
+                mock_message.file_size = file_size;
                 mock_message.mime_type = mime_guess::from_path(&audio.file_name).first();
+                if audio.thumbnail.is_some() {
+                    mock_message.thumbnail =
+                        Some(lock.thumbnail(item_thumbnail(&audio.thumbnail).as_ref()));
+                }
 
                 mock_message.id = MessageId(last_id + 1);
                 message = mock_message.build();
@@ -106,8 +126,18 @@ pub async fn send_media_group(
                 mock_message.file_name = Some(document.file_name.clone());
                 mock_message.file_id = file_id;
                 mock_message.file_unique_id = file_unique_id;
-                mock_message.file_size = document.file_data.bytes().len() as u32;
+                mock_message.file_size = file_size;
                 mock_message.mime_type = mime_guess::from_path(&document.file_name).first();
+                // Telegram makes thumbnails only for pictures and videos
+                if document.thumbnail.is_some()
+                    || mock_message
+                        .mime_type
+                        .as_ref()
+                        .is_some_and(|mime| [mime::IMAGE, mime::VIDEO].contains(&mime.type_()))
+                {
+                    mock_message.thumbnail =
+                        Some(lock.thumbnail(item_thumbnail(&document.thumbnail).as_ref()));
+                }
 
                 mock_message.id = MessageId(last_id + 1);
                 message = mock_message.build();
@@ -126,25 +156,17 @@ pub async fn send_media_group(
                 mock_message.reply_to_message = reply_to_message.clone();
                 mock_message.caption = photo.caption.clone();
                 mock_message.caption_entities = photo.caption_entities.clone().unwrap_or_default();
+                mock_message.show_caption_above_media =
+                    photo.show_caption_above_media.unwrap_or(false);
+                mock_message.has_media_spoiler = photo.has_spoiler.unwrap_or(false);
                 mock_message.media_group_id = Some(media_group_id.clone());
                 mock_message.effect_id = message_effect_id.clone();
                 mock_message.business_connection_id = business_connection_id.clone();
 
-                let mut mock_photo = MockPhotoSize::new();
-
-                mock_photo.file_id = file_id;
-                mock_photo.file_unique_id = file_unique_id;
-                mock_photo.file_size = photo.file_data.bytes().len() as u32;
-
-                mock_message.photo = vec![mock_photo.build()];
+                mock_message.photo = lock.photo_sizes(&photo.file_data, &photo.file_name);
 
                 mock_message.id = MessageId(last_id + 1);
                 message = mock_message.build();
-
-                lock.files.push(teloxide::types::File {
-                    meta: message.photo().unwrap().first().unwrap().clone().file,
-                    path: photo.file_name.clone(),
-                });
             }
             MediaGroupInputMedia::InputMediaVideo(video) => {
                 let mut mock_message = MockMessageVideo::new();
@@ -155,20 +177,29 @@ pub async fn send_media_group(
                 mock_message.reply_to_message = reply_to_message.clone();
                 mock_message.caption = video.caption.clone();
                 mock_message.caption_entities = video.caption_entities.clone().unwrap_or_default();
+                mock_message.show_caption_above_media =
+                    video.show_caption_above_media.unwrap_or(false);
+                mock_message.has_media_spoiler = video.has_spoiler.unwrap_or(false);
                 mock_message.media_group_id = Some(media_group_id.clone());
                 mock_message.effect_id = message_effect_id.clone();
                 mock_message.business_connection_id = business_connection_id.clone();
 
                 let mut mock_video = MockVideo::new();
+                let metadata = lock.media_metadata(&video.file_name);
 
                 mock_video.mime_type = mime_guess::from_path(&video.file_name).first();
-                mock_video.width = video.width.unwrap_or(100);
-                mock_video.height = video.height.unwrap_or(100);
-                mock_video.duration = video.duration.unwrap_or(Seconds::from_seconds(1));
+                mock_video.width = video.width.or(metadata.width).unwrap_or(100);
+                mock_video.height = video.height.or(metadata.height).unwrap_or(100);
+                mock_video.duration = video
+                    .duration
+                    .or(metadata.duration)
+                    .unwrap_or(Seconds::from_seconds(1));
                 mock_video.file_id = file_id;
                 mock_video.file_unique_id = file_unique_id;
-                mock_video.file_size = video.file_data.bytes().len() as u32;
+                mock_video.file_size = file_size;
                 mock_video.file_name = Some(video.file_name.clone());
+                mock_video.thumbnail =
+                    Some(lock.thumbnail(item_thumbnail(&video.thumbnail).as_ref()));
 
                 mock_message.video = mock_video.build();
 
@@ -182,25 +213,28 @@ pub async fn send_media_group(
             }
         }
 
-        messages.push(message.clone());
-        lock.messages.add_message(message);
+        let message = lock.add_sent_message(message, &me);
+        messages.push(message);
     }
 
     lock.responses.sent_messages.extend(messages.clone());
+    let seq = lock.next_seq();
     lock.responses.sent_media_group.push(SentMediaGroup {
         messages: messages.clone(),
         bot_request: body,
+        seq,
     });
     make_telegram_result(messages)
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendMediaGroupBody {
     pub chat_id: BodyChatId,
     pub message_thread_id: Option<i64>,
     pub media: Vec<MediaGroupInputMedia>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
+    pub allow_paid_broadcast: Option<bool>,
     pub message_effect_id: Option<EffectId>,
     pub reply_parameters: Option<ReplyParameters>,
     pub business_connection_id: Option<BusinessConnectionId>,
@@ -215,20 +249,11 @@ impl SendMediaGroupBody {
         let mut media: Vec<MediaGroupInputMedia> = vec![];
         for raw_media_item in raw_media.iter() {
             let raw_media_string = raw_media_item.get("media").unwrap().as_str().unwrap();
-            let file_name;
-            let file_data;
-            if raw_media_string.starts_with("attach://") {
-                let raw_name = raw_media_string.strip_prefix("attach://").unwrap();
-                let attachment = attachments
-                    .values()
-                    .find(|a| a.raw_name == raw_name)
-                    .expect("No attachment was found!");
-                file_name = Some(attachment.file_name.clone());
-                file_data = attachment.file_data.clone();
-            } else {
-                file_name = None;
-                file_data = raw_media_item.get("media").unwrap().to_string();
-            }
+            let (file_name, file_data) = media_file(raw_media_string, attachments);
+            let thumbnail = raw_media_item
+                .get("thumbnail")
+                .and_then(|s| s.as_str())
+                .map(|s| media_file(s, attachments).1);
 
             let media_type = raw_media_item.get("type").unwrap();
             let caption = raw_media_item
@@ -280,6 +305,7 @@ impl SendMediaGroupBody {
                         duration,
                         performer,
                         title,
+                        thumbnail,
                     },
                 ));
             } else if media_type == "document" {
@@ -292,6 +318,7 @@ impl SendMediaGroupBody {
                         parse_mode,
                         caption_entities,
                         disable_content_type_detection,
+                        thumbnail,
                     },
                 ));
             } else if media_type == "photo" {
@@ -322,6 +349,7 @@ impl SendMediaGroupBody {
                         width,
                         height,
                         has_spoiler,
+                        thumbnail,
                     },
                 ));
             } else {
@@ -337,6 +365,9 @@ impl SendMediaGroupBody {
                 .get("disable_notification")
                 .map(|s| s.parse().unwrap()),
             protect_content: fields.get("protect_content").map(|s| s.parse().unwrap()),
+            allow_paid_broadcast: fields
+                .get("allow_paid_broadcast")
+                .map(|s| s.parse().unwrap()),
             message_effect_id: fields
                 .get("message_effect_id")
                 .map(|s| s.to_string().into()),
@@ -349,3 +380,35 @@ impl SendMediaGroupBody {
         })
     }
 }
+
+/// Returns the name and the data of a file of the media group, that is either attached or sent
+/// by its file id or url
+fn media_file(
+    raw_media_string: &str,
+    attachments: &HashMap<String, Attachment>,
+) -> (Option<String>, String) {
+    match raw_media_string.strip_prefix("attach://") {
+        Some(raw_name) => {
+            let attachment = attachments
+                .values()
+                .find(|a| a.raw_name == raw_name)
+                .expect("No attachment was found!");
+            (
+                Some(attachment.file_name.clone()),
+                attachment.file_data.clone(),
+            )
+        }
+        None => (None, raw_media_string.to_string()),
+    }
+}
+
+/// Returns the thumbnail of a media group item, like `sent_thumbnail` does for other media
+fn item_thumbnail(thumbnail: &Option<String>) -> Option<Attachment> {
+    thumbnail.as_ref().map(|file_data| Attachment {
+        raw_name: "thumbnail".to_string(),
+        file_name: "thumbnail.jpg".to_string(),
+        file_data: file_data.clone(),
+        mime_type: None,
+        size: 0,
+    })
+}
@@ -1,20 +1,23 @@
 use std::sync::Mutex;
 
-use actix_web::{error::ErrorBadRequest, web, Responder};
-use serde::Deserialize;
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
 use teloxide::types::{
     BusinessConnectionId, EffectId, LinkPreviewOptions, Me, MessageEntity, ParseMode, ReplyMarkup,
     ReplyParameters,
 };
 
-use super::{make_telegram_result, BodyChatId};
+use super::{
+    check_effect_id, check_entities, check_flood_limit, check_markup, check_reply_markup,
+    make_telegram_result, BodyChatId,
+};
 use crate::{
     dataset::message_common::MockMessageText,
-    server::{routes::check_if_message_exists, SentMessageText},
+    server::{routes::reply_to_message, SentMessageText},
     state::State,
 };
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SendMessageTextBody {
     pub chat_id: BodyChatId,
     pub text: String,
@@ -24,6 +27,7 @@ pub struct SendMessageTextBody {
     pub link_preview_options: Option<LinkPreviewOptions>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
+    pub allow_paid_broadcast: Option<bool>,
     pub message_effect_id: Option<EffectId>,
     pub reply_markup: Option<ReplyMarkup>,
     pub reply_parameters: Option<ReplyParameters>,
@@ -45,25 +49,27 @@ pub async fn send_message(
     message.business_connection_id = body.business_connection_id.clone();
 
     message.entities = body.entities.clone().unwrap_or_default();
-    if let Some(reply_parameters) = &body.reply_parameters {
-        check_if_message_exists!(lock, reply_parameters.message_id.0);
-        let reply_to_message = lock
-            .messages
-            .get_message(reply_parameters.message_id.0)
-            .unwrap();
-        message.reply_to_message = Some(Box::new(reply_to_message.clone()));
-    }
+    message.link_preview_options = body.link_preview_options.clone();
+    message.reply_to_message = reply_to_message!(lock, body.reply_parameters, message.chat);
     if let Some(ReplyMarkup::InlineKeyboard(markup)) = body.reply_markup.clone() {
         message.reply_markup = Some(markup);
     }
 
+    check_reply_markup!(body.reply_markup);
+    check_effect_id!(lock, body.message_effect_id);
+    check_entities!(body.text, body.entities);
+    check_markup!(lock, body.text, body.parse_mode);
+    check_flood_limit!(lock, body.allow_paid_broadcast);
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build(), &me);
+    lock.add_reply_markup(&message, body.reply_markup.clone());
 
     lock.responses.sent_messages.push(message.clone());
+    let seq = lock.next_seq();
     lock.responses.sent_messages_text.push(SentMessageText {
         message: message.clone(),
         bot_request: body.into_inner(),
+        seq,
     });
 
     make_telegram_result(message)
@@ -1,20 +1,25 @@
 use std::{collections::HashMap, sync::Mutex};
 
 use actix_multipart::Multipart;
-use actix_web::{error::ErrorBadRequest, web, Responder};
-use rand::distr::{Alphanumeric, SampleString};
-use serde::Deserialize;
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
 use teloxide::types::{
-    BusinessConnectionId, EffectId, FileId, FileUniqueId, LinkPreviewOptions, Me, MessageEntity,
-    ParseMode, ReplyMarkup, ReplyParameters,
+    BusinessConnectionId, EffectId, LinkPreviewOptions, Me, MessageEntity, ParseMode, ReplyMarkup,
+    ReplyParameters,
 };
 
-use super::{get_raw_multipart_fields, make_telegram_result, BodyChatId};
+use super::{
+    check_effect_id, check_flood_limit, check_markup, check_photo, check_reply_markup,
+    check_upload_size, get_raw_multipart_fields, make_telegram_result, BodyChatId,
+};
 use crate::{
-    dataset::{MockMessagePhoto, MockPhotoSize},
+    dataset::MockMessagePhoto,
     proc_macros::SerializeRawFields,
     server::{
-        routes::{check_if_message_exists, Attachment, FileType, SerializeRawFields},
+        routes::{
+            record_dropped_fields, reply_to_message, sent_attachment, Attachment, FileType,
+            SerializeRawFields,
+        },
         SentMessagePhoto,
     },
     state::State,
@@ -27,8 +32,10 @@ pub async fn send_photo(
 ) -> impl Responder {
     let (fields, attachments) = get_raw_multipart_fields(&mut payload).await;
     let mut lock = state.lock().unwrap();
+    check_upload_size!(lock, attachments);
     let body =
         SendMessagePhotoBody::serialize_raw_fields(&fields, &attachments, FileType::Photo).unwrap();
+    record_dropped_fields::<SendMessagePhotoBody>(&mut lock, "sendPhoto", &fields, FileType::Photo);
     let chat = body.chat_id.chat();
 
     let mut message = // Creates the message, which will be mutated to fit the needed shape
@@ -41,44 +48,40 @@ pub async fn send_photo(
     message.effect_id = body.message_effect_id.clone();
     message.business_connection_id = body.business_connection_id.clone();
 
-    if let Some(reply_parameters) = &body.reply_parameters {
-        check_if_message_exists!(lock, reply_parameters.message_id.0);
-        let reply_to_message = lock
-            .messages
-            .get_message(reply_parameters.message_id.0)
-            .unwrap();
-        message.reply_to_message = Some(Box::new(reply_to_message.clone()));
-    }
+    message.reply_to_message = reply_to_message!(lock, body.reply_parameters, message.chat);
     if let Some(ReplyMarkup::InlineKeyboard(markup)) = body.reply_markup.clone() {
         message.reply_markup = Some(markup);
     }
 
-    let file_id = FileId(Alphanumeric.sample_string(&mut rand::rng(), 16));
-    let file_unique_id = FileUniqueId(Alphanumeric.sample_string(&mut rand::rng(), 8));
-
-    message.photo = vec![MockPhotoSize::new()
-        .file_id(file_id)
-        .file_unique_id(file_unique_id)
-        .file_size(body.file_data.bytes().len() as u32)
-        .build()];
+    message.photo = lock.photo_sizes(&body.file_data, &body.file_name);
 
+    check_reply_markup!(body.reply_markup);
+    check_effect_id!(lock, body.message_effect_id);
+    if let Some(photo) = sent_attachment(&fields, &attachments, FileType::Photo) {
+        check_photo!(lock, photo);
+    }
+    check_markup!(
+        lock,
+        body.caption.as_deref().unwrap_or_default(),
+        body.parse_mode
+    );
+    check_flood_limit!(lock, body.allow_paid_broadcast);
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build(), &me);
+    lock.add_reply_markup(&message, body.reply_markup.clone());
 
-    lock.files.push(teloxide::types::File {
-        meta: message.photo().unwrap()[0].file.clone(),
-        path: body.file_name.to_owned(),
-    });
     lock.responses.sent_messages.push(message.clone());
+    let seq = lock.next_seq();
     lock.responses.sent_messages_photo.push(SentMessagePhoto {
         message: message.clone(),
         bot_request: body,
+        seq,
     });
 
     make_telegram_result(message)
 }
 
-#[derive(Debug, Clone, Deserialize, SerializeRawFields)]
+#[derive(Debug, Clone, Serialize, Deserialize, SerializeRawFields)]
 pub struct SendMessagePhotoBody {
     pub chat_id: BodyChatId,
     pub file_name: String,
@@ -90,6 +93,7 @@ pub struct SendMessagePhotoBody {
     pub link_preview_options: Option<LinkPreviewOptions>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
+    pub allow_paid_broadcast: Option<bool>,
     pub show_caption_above_media: Option<bool>,
     pub message_effect_id: Option<EffectId>,
     pub reply_markup: Option<ReplyMarkup>,
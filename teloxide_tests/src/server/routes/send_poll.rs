@@ -1,21 +1,24 @@
 use std::sync::Mutex;
 
-use actix_web::{error::ErrorBadRequest, web, Responder};
-use chrono::DateTime;
-use serde::Deserialize;
+use actix_web::{web, Responder};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use teloxide::types::{
     BusinessConnectionId, EffectId, InputPollOption, Me, MessageEntity, ParseMode, PollOption,
     PollType, ReplyMarkup, ReplyParameters, Seconds,
 };
 
-use super::{make_telegram_result, BodyChatId};
+use super::{
+    check_effect_id, check_flood_limit, check_markup, check_reply_markup, make_telegram_result,
+    BodyChatId,
+};
 use crate::{
-    server::{routes::check_if_message_exists, SentMessagePoll},
+    server::{routes::reply_to_message, SentMessagePoll},
     state::State,
     MockMessagePoll,
 };
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SendMessagePollBody {
     pub chat_id: BodyChatId,
     pub message_thread_id: Option<i64>,
@@ -31,10 +34,12 @@ pub struct SendMessagePollBody {
     pub explanation_parse_mode: Option<ParseMode>,
     pub explanation_entities: Option<Vec<MessageEntity>>,
     pub open_period: Option<Seconds>,
-    pub close_date: Option<u16>,
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    pub close_date: Option<DateTime<Utc>>,
     pub is_closed: Option<bool>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
+    pub allow_paid_broadcast: Option<bool>,
     pub message_effect_id: Option<EffectId>,
     pub reply_markup: Option<ReplyMarkup>,
     pub reply_parameters: Option<ReplyParameters>,
@@ -71,29 +76,39 @@ pub async fn send_poll(
     message.explanation = body.explanation.clone();
     message.explanation_entities = body.explanation_entities.clone();
     message.open_period = body.open_period;
-    message.close_date = DateTime::from_timestamp(body.close_date.unwrap_or(0) as i64, 0);
+    // Like Telegram, a poll with an open period gets the date it closes at
+    message.close_date = body.close_date.or_else(|| {
+        body.open_period
+            .map(|open_period| lock.now() + open_period.chrono_duration())
+    });
+    message.is_closed = body.is_closed.unwrap_or(false);
     message.effect_id = body.message_effect_id.clone();
     message.question_entities = body.question_entities.clone();
 
-    if let Some(reply_parameters) = &body.reply_parameters {
-        check_if_message_exists!(lock, reply_parameters.message_id.0);
-        let reply_to_message = lock
-            .messages
-            .get_message(reply_parameters.message_id.0)
-            .unwrap();
-        message.reply_to_message = Some(Box::new(reply_to_message.clone()));
-    }
+    message.reply_to_message = reply_to_message!(lock, body.reply_parameters, message.chat);
     if let Some(ReplyMarkup::InlineKeyboard(markup)) = body.reply_markup.clone() {
         message.reply_markup = Some(markup);
     }
 
+    check_reply_markup!(body.reply_markup);
+    check_effect_id!(lock, body.message_effect_id);
+    check_markup!(lock, body.question, body.question_parse_mode);
+    check_markup!(
+        lock,
+        body.explanation.as_deref().unwrap_or_default(),
+        body.explanation_parse_mode
+    );
+    check_flood_limit!(lock, body.allow_paid_broadcast);
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build(), &me);
+    lock.add_reply_markup(&message, body.reply_markup.clone());
 
     lock.responses.sent_messages.push(message.clone());
+    let seq = lock.next_seq();
     lock.responses.sent_messages_poll.push(SentMessagePoll {
         message: message.clone(),
         bot_request: body.into_inner(),
+        seq,
     });
 
     make_telegram_result(message)
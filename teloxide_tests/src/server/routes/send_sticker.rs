@@ -1,15 +1,20 @@
 use std::{collections::HashMap, sync::Mutex};
 
 use actix_multipart::Multipart;
-use actix_web::{error::ErrorBadRequest, web, Responder};
-use serde::Deserialize;
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
 use teloxide::types::{BusinessConnectionId, EffectId, Me, ReplyMarkup, ReplyParameters};
 
-use super::{get_raw_multipart_fields, make_telegram_result, BodyChatId};
+use super::{
+    check_effect_id, check_flood_limit, check_reply_markup, check_upload_size,
+    get_raw_multipart_fields, make_telegram_result, BodyChatId,
+};
 use crate::{
     proc_macros::SerializeRawFields,
     server::{
-        routes::{check_if_message_exists, Attachment, FileType, SerializeRawFields},
+        routes::{
+            record_dropped_fields, reply_to_message, Attachment, FileType, SerializeRawFields,
+        },
         SentMessageSticker,
     },
     state::State,
@@ -23,9 +28,16 @@ pub async fn send_sticker(
 ) -> impl Responder {
     let (fields, attachments) = get_raw_multipart_fields(&mut payload).await;
     let mut lock = state.lock().unwrap();
+    check_upload_size!(lock, attachments);
     let body =
         SendMessageStickerBody::serialize_raw_fields(&fields, &attachments, FileType::Sticker)
             .unwrap();
+    record_dropped_fields::<SendMessageStickerBody>(
+        &mut lock,
+        "sendSticker",
+        &fields,
+        FileType::Sticker,
+    );
     let chat = body.chat_id.chat();
 
     let mut message = MockMessageSticker::new().chat(chat);
@@ -35,40 +47,51 @@ pub async fn send_sticker(
     message.effect_id = body.message_effect_id.clone();
     message.business_connection_id = body.business_connection_id.clone();
 
-    // Idk how to get sticker kind and sticker format from this, sooooooooooo im not doing it,
-    // ain't nobody testing that
+    let file = lock.file_meta(&body.file_data);
+    message.file_id = file.id;
+    message.file_unique_id = file.unique_id;
+    message.file_size = file.size;
+    // Telegram finds out the format from the file itself, the extension is the closest thing
+    message = match body
+        .file_name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension)
+    {
+        Some("tgs") => message.animated(),
+        Some("webm") => message.video(),
+        _ => message,
+    };
 
-    if let Some(reply_parameters) = &body.reply_parameters {
-        check_if_message_exists!(lock, reply_parameters.message_id.0);
-        let reply_to_message = lock
-            .messages
-            .get_message(reply_parameters.message_id.0)
-            .unwrap();
-        message.reply_to_message = Some(Box::new(reply_to_message.clone()));
-    }
+    message.reply_to_message = reply_to_message!(lock, body.reply_parameters, message.chat);
     if let Some(ReplyMarkup::InlineKeyboard(markup)) = body.reply_markup.clone() {
         message.reply_markup = Some(markup);
     }
 
+    check_reply_markup!(body.reply_markup);
+    check_effect_id!(lock, body.message_effect_id);
+    check_flood_limit!(lock, body.allow_paid_broadcast);
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build(), &me);
+    lock.add_reply_markup(&message, body.reply_markup.clone());
 
     lock.files.push(teloxide::types::File {
         meta: message.sticker().unwrap().file.clone(),
         path: body.file_name.to_owned(),
     });
     lock.responses.sent_messages.push(message.clone());
+    let seq = lock.next_seq();
     lock.responses
         .sent_messages_sticker
         .push(SentMessageSticker {
             message: message.clone(),
             bot_request: body,
+            seq,
         });
 
     make_telegram_result(message)
 }
 
-#[derive(Debug, Clone, Deserialize, SerializeRawFields)]
+#[derive(Debug, Clone, Serialize, Deserialize, SerializeRawFields)]
 pub struct SendMessageStickerBody {
     pub chat_id: BodyChatId,
     pub file_name: String,
@@ -77,6 +100,7 @@ pub struct SendMessageStickerBody {
     pub emoji: Option<String>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
+    pub allow_paid_broadcast: Option<bool>,
     pub message_effect_id: Option<EffectId>,
     pub reply_markup: Option<ReplyMarkup>,
     pub reply_parameters: Option<ReplyParameters>,
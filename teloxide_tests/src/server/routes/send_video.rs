@@ -1,21 +1,26 @@
 use std::{collections::HashMap, str::FromStr, sync::Mutex};
 
 use actix_multipart::Multipart;
-use actix_web::{error::ErrorBadRequest, web, Responder};
+use actix_web::{web, Responder};
 use mime::Mime;
-use rand::distr::{Alphanumeric, SampleString};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use teloxide::types::{
-    BusinessConnectionId, EffectId, FileId, FileUniqueId, Me, MessageEntity, ParseMode,
-    ReplyMarkup, ReplyParameters, Seconds,
+    BusinessConnectionId, EffectId, FileMeta, Me, MessageEntity, ParseMode, ReplyMarkup,
+    ReplyParameters, Seconds,
 };
 
-use super::{get_raw_multipart_fields, make_telegram_result, BodyChatId};
+use super::{
+    check_effect_id, check_flood_limit, check_markup, check_reply_markup, check_upload_size,
+    get_raw_multipart_fields, make_telegram_result, BodyChatId,
+};
 use crate::{
     dataset::{MockMessageVideo, MockVideo},
     proc_macros::SerializeRawFields,
     server::{
-        routes::{check_if_message_exists, Attachment, FileType, SerializeRawFields},
+        routes::{
+            record_dropped_fields, reply_to_message, sent_file, sent_thumbnail, Attachment,
+            FileType, SerializeRawFields,
+        },
         SentMessageVideo,
     },
     state::State,
@@ -28,8 +33,16 @@ pub async fn send_video(
 ) -> impl Responder {
     let (fields, attachments) = get_raw_multipart_fields(&mut payload).await;
     let mut lock = state.lock().unwrap();
-    let body =
+    check_upload_size!(lock, attachments);
+    let mut body =
         SendMessageVideoBody::serialize_raw_fields(&fields, &attachments, FileType::Video).unwrap();
+    record_dropped_fields::<SendMessageVideoBody>(&mut lock, "sendVideo", &fields, FileType::Video);
+    // The file and its thumbnail are attached in any order
+    (body.file_name, body.file_data) = sent_file(&fields, &attachments, FileType::Video).unwrap();
+    let thumbnail = sent_thumbnail(&fields, &attachments);
+    body.thumbnail = thumbnail
+        .as_ref()
+        .map(|thumbnail| thumbnail.file_data.clone());
     let chat = body.chat_id.chat();
 
     let mut message = MockMessageVideo::new().chat(chat.clone());
@@ -41,50 +54,63 @@ pub async fn send_video(
     message.effect_id = body.message_effect_id.clone();
     message.business_connection_id = body.business_connection_id.clone();
 
-    if let Some(reply_parameters) = &body.reply_parameters {
-        check_if_message_exists!(lock, reply_parameters.message_id.0);
-        let reply_to_message = lock
-            .messages
-            .get_message(reply_parameters.message_id.0)
-            .unwrap();
-        message.reply_to_message = Some(Box::new(reply_to_message.clone()));
-    }
+    message.reply_to_message = reply_to_message!(lock, body.reply_parameters, message.chat);
 
     if let Some(ReplyMarkup::InlineKeyboard(markup)) = body.reply_markup.clone() {
         message.reply_markup = Some(markup);
     }
 
-    let file_id = FileId(Alphanumeric.sample_string(&mut rand::rng(), 16));
-    let file_unique_id = FileUniqueId(Alphanumeric.sample_string(&mut rand::rng(), 8));
+    let FileMeta {
+        id: file_id,
+        unique_id: file_unique_id,
+        size: file_size,
+    } = lock.file_meta(&body.file_data);
+    let metadata = lock.media_metadata(&body.file_name);
 
     message.video = MockVideo::new()
         .file_id(file_id)
         .file_unique_id(file_unique_id)
-        .file_size(body.file_data.bytes().len() as u32)
+        .file_size(file_size)
         .file_name(body.file_name.clone())
-        .width(body.width.unwrap_or(100))
-        .height(body.height.unwrap_or(100))
-        .duration(body.duration.unwrap_or(Seconds::from_seconds(1)))
+        .width(body.width.or(metadata.width).unwrap_or(100))
+        .height(body.height.or(metadata.height).unwrap_or(100))
+        .duration(
+            body.duration
+                .or(metadata.duration)
+                .unwrap_or(Seconds::from_seconds(1)),
+        )
         .mime_type(Mime::from_str("video/mp4").unwrap())
+        .thumbnail(lock.thumbnail(thumbnail.as_ref()))
         .build();
 
+    check_reply_markup!(body.reply_markup);
+    check_effect_id!(lock, body.message_effect_id);
+    check_markup!(
+        lock,
+        body.caption.as_deref().unwrap_or_default(),
+        body.parse_mode
+    );
+    check_flood_limit!(lock, body.allow_paid_broadcast);
     let last_id = lock.messages.max_message_id();
-    let message = lock.messages.add_message(message.id(last_id + 1).build());
+    let message = lock.add_sent_message(message.id(last_id + 1).build(), &me);
+    lock.add_reply_markup(&message, body.reply_markup.clone());
 
     lock.files.push(teloxide::types::File {
         meta: message.video().unwrap().file.clone(),
         path: body.file_name.to_owned(),
     });
     lock.responses.sent_messages.push(message.clone());
+    let seq = lock.next_seq();
     lock.responses.sent_messages_video.push(SentMessageVideo {
         message: message.clone(),
         bot_request: body,
+        seq,
     });
 
     make_telegram_result(message)
 }
 
-#[derive(Debug, Clone, Deserialize, SerializeRawFields)]
+#[derive(Debug, Clone, Serialize, Deserialize, SerializeRawFields)]
 pub struct SendMessageVideoBody {
     pub chat_id: BodyChatId,
     pub message_thread_id: Option<i64>,
@@ -93,6 +119,7 @@ pub struct SendMessageVideoBody {
     pub duration: Option<Seconds>,
     pub width: Option<u32>,
     pub height: Option<u32>,
+    pub thumbnail: Option<String>,
     pub caption: Option<String>,
     pub parse_mode: Option<ParseMode>,
     pub caption_entities: Option<Vec<MessageEntity>>,
@@ -101,6 +128,7 @@ pub struct SendMessageVideoBody {
     pub supports_streaming: Option<bool>,
     pub disable_notification: Option<bool>,
     pub protect_content: Option<bool>,
+    pub allow_paid_broadcast: Option<bool>,
     pub message_effect_id: Option<EffectId>,
     pub reply_markup: Option<ReplyMarkup>,
     pub reply_parameters: Option<ReplyParameters>,
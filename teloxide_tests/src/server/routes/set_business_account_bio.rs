@@ -0,0 +1,26 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::BusinessConnectionId;
+
+use super::make_telegram_result;
+use crate::state::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetBusinessAccountBioBody {
+    pub business_connection_id: BusinessConnectionId,
+    pub bio: Option<String>,
+}
+
+pub async fn set_business_account_bio(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<SetBusinessAccountBioBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    lock.responses
+        .set_business_account_bios
+        .push(body.into_inner());
+
+    make_telegram_result(true)
+}
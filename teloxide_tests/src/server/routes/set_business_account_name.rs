@@ -0,0 +1,27 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::BusinessConnectionId;
+
+use super::make_telegram_result;
+use crate::state::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetBusinessAccountNameBody {
+    pub business_connection_id: BusinessConnectionId,
+    pub first_name: String,
+    pub last_name: Option<String>,
+}
+
+pub async fn set_business_account_name(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<SetBusinessAccountNameBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    lock.responses
+        .set_business_account_names
+        .push(body.into_inner());
+
+    make_telegram_result(true)
+}
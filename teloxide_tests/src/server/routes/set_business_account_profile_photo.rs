@@ -0,0 +1,67 @@
+use std::sync::Mutex;
+
+use actix_multipart::Multipart;
+use actix_web::{error::ErrorBadRequest, web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::BusinessConnectionId;
+
+use super::{get_raw_multipart_fields, make_telegram_result};
+use crate::state::State;
+
+/// The photo that is set as a profile photo. Profile photos can only be uploaded as new files,
+/// so `photo` and `animation` are `attach://<file_attach_name>` links.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputProfilePhoto {
+    Static {
+        photo: String,
+    },
+    Animated {
+        animation: String,
+        main_frame_timestamp: Option<f64>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetBusinessAccountProfilePhotoBody {
+    pub business_connection_id: BusinessConnectionId,
+    pub photo: InputProfilePhoto,
+    pub is_public: Option<bool>,
+    pub file_name: String,
+    pub file_data: String,
+}
+
+pub async fn set_business_account_profile_photo(
+    mut payload: Multipart,
+    state: web::Data<Mutex<State>>,
+) -> impl Responder {
+    let (fields, attachments) = get_raw_multipart_fields(&mut payload).await;
+    let (Some(business_connection_id), Some(photo)) = (
+        fields.get("business_connection_id"),
+        fields
+            .get("photo")
+            .and_then(|photo| serde_json::from_str::<InputProfilePhoto>(photo).ok()),
+    ) else {
+        return ErrorBadRequest("Bad Request: invalid profile photo").into();
+    };
+    let Some(attachment) = attachments.values().next() else {
+        return ErrorBadRequest("Bad Request: profile photo file is not specified").into();
+    };
+
+    let body = SetBusinessAccountProfilePhotoBody {
+        business_connection_id: BusinessConnectionId(business_connection_id.clone()),
+        photo,
+        is_public: fields
+            .get("is_public")
+            .and_then(|is_public| serde_json::from_str(is_public).ok()),
+        file_name: attachment.file_name.clone(),
+        file_data: attachment.file_data.clone(),
+    };
+
+    let mut lock = state.lock().unwrap();
+    lock.responses
+        .set_business_account_profile_photos
+        .push(body);
+
+    make_telegram_result(true)
+}
@@ -0,0 +1,39 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::{ChatId, MenuButton};
+
+use super::{make_telegram_result, BodyChatId};
+use crate::state::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetChatMenuButtonBody {
+    pub chat_id: Option<BodyChatId>,
+    pub menu_button: Option<MenuButton>,
+}
+
+pub async fn set_chat_menu_button(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<SetChatMenuButtonBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    // `MenuButton::Default` resets the button, to the default one for a chat and to the list of
+    // commands for the default button
+    let menu_button = body
+        .menu_button
+        .clone()
+        .filter(|menu_button| *menu_button != MenuButton::Default);
+    match &body.chat_id {
+        Some(chat_id) => {
+            lock.chat_metadata
+                .entry(ChatId(chat_id.id()))
+                .or_default()
+                .menu_button = menu_button;
+        }
+        None => lock.default_menu_button = menu_button,
+    }
+    lock.responses.set_chat_menu_buttons.push(body.into_inner());
+
+    make_telegram_result(true)
+}
@@ -1,13 +1,16 @@
 use std::sync::Mutex;
 
-use actix_web::{error::ErrorBadRequest, web, Responder};
-use serde::Deserialize;
-use teloxide::types::ReactionType;
+use actix_web::{error::ErrorBadRequest, web, Responder, ResponseError};
+use serde::{Deserialize, Serialize};
+use teloxide::{
+    types::{ChatId, ReactionType},
+    ApiError,
+};
 
-use super::{make_telegram_result, BodyChatId};
+use super::{make_telegram_result, BodyChatId, BotApiError};
 use crate::{server::routes::check_if_message_exists, state::State};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SetMessageReactionBody {
     pub chat_id: BodyChatId,
     pub message_id: i32,
@@ -22,6 +25,24 @@ pub async fn set_message_reaction(
     let mut lock = state.lock().unwrap();
 
     check_if_message_exists!(lock, body.message_id);
+    // Only the reactions that the chat allows can be set, if its info says which ones it allows
+    let available_reactions = lock
+        .chat_metadata
+        .get(&ChatId(body.chat_id.id()))
+        .and_then(|metadata| metadata.full_info.as_ref())
+        .and_then(|full_info| full_info.available_reactions())
+        .map(|reactions| reactions.to_vec());
+    if let (true, Some(available_reactions)) = (lock.config.strict_validation, available_reactions)
+    {
+        let reactions = body.reaction.as_deref().unwrap_or_default();
+        if reactions
+            .iter()
+            .any(|reaction| !available_reactions.contains(reaction))
+        {
+            let error = ApiError::Unknown("Bad Request: REACTION_INVALID".to_string());
+            return BotApiError::new(error).error_response();
+        }
+    }
 
     lock.responses.set_message_reaction.push(body.into_inner());
 
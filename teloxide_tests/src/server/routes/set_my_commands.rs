@@ -1,13 +1,13 @@
 use std::sync::Mutex;
 
 use actix_web::{web, Responder};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use teloxide::types::{BotCommand, BotCommandScope};
 
 use super::make_telegram_result;
 use crate::state::State;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SetMyCommandsBody {
     pub commands: Vec<BotCommand>,
     pub scope: Option<BotCommandScope>,
@@ -19,6 +19,11 @@ pub async fn set_my_commands(
     body: web::Json<SetMyCommandsBody>,
 ) -> impl Responder {
     let mut lock = state.lock().unwrap();
+    lock.set_my_commands(
+        body.scope.clone(),
+        body.language_code.clone(),
+        body.commands.clone(),
+    );
     lock.responses.set_my_commands.push(body.into_inner());
 
     make_telegram_result(true)
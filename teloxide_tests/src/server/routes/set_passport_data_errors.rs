@@ -0,0 +1,26 @@
+use std::sync::Mutex;
+
+use actix_web::{web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::PassportElementError;
+
+use super::make_telegram_result;
+use crate::state::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetPassportDataErrorsBody {
+    pub user_id: u64,
+    pub errors: Vec<PassportElementError>,
+}
+
+pub async fn set_passport_data_errors(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<SetPassportDataErrorsBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    lock.responses
+        .set_passport_data_errors
+        .push(body.into_inner());
+
+    make_telegram_result(true)
+}
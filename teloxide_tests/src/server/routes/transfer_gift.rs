@@ -0,0 +1,46 @@
+use std::sync::Mutex;
+
+use actix_web::{error::ErrorBadRequest, web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::BusinessConnectionId;
+
+use super::make_telegram_result;
+use crate::{server::OwnedGift, state::State};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransferGiftBody {
+    pub business_connection_id: BusinessConnectionId,
+    pub owned_gift_id: String,
+    pub new_owner_chat_id: i64,
+    pub star_count: Option<u32>,
+}
+
+pub async fn transfer_gift(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<TransferGiftBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    let Some(index) = lock.find_business_gift(&body.owned_gift_id) else {
+        return ErrorBadRequest("Bad Request: gift not found").into();
+    };
+    let OwnedGift::Unique(unique) = &lock.business_gifts[index] else {
+        return ErrorBadRequest("Bad Request: only unique gifts can be transferred").into();
+    };
+    if !unique.can_be_transferred {
+        return ErrorBadRequest("Bad Request: gift can't be transferred").into();
+    }
+
+    let star_count = body.star_count.unwrap_or(0);
+    if star_count < unique.transfer_star_count.unwrap_or(0) {
+        return ErrorBadRequest("Bad Request: STARGIFT_TRANSFER_PAYMENT_REQUIRED").into();
+    }
+    if lock.business_star_balance < star_count as i64 {
+        return ErrorBadRequest("Bad Request: BALANCE_TOO_LOW").into();
+    }
+
+    lock.business_gifts.remove(index);
+    lock.business_star_balance -= star_count as i64;
+    lock.responses.transferred_gifts.push(body.into_inner());
+
+    make_telegram_result(true)
+}
@@ -1,12 +1,13 @@
 use std::sync::Mutex;
 
-use actix_web::{web, Responder};
-use serde::Deserialize;
+use actix_web::{error::ErrorBadRequest, web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::{ChatId, ChatMember, ChatMemberKind};
 
 use super::BodyChatId;
 use crate::{server::routes::make_telegram_result, state::State};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UnbanChatMemberBody {
     pub chat_id: BodyChatId,
     pub user_id: u64,
@@ -17,8 +18,29 @@ pub async fn unban_chat_member(
     state: web::Data<Mutex<State>>,
     body: web::Json<UnbanChatMemberBody>,
 ) -> impl Responder {
-    // Idk what to verify here
     let mut lock = state.lock().unwrap();
+    lock.lift_expired_restrictions();
+    let chat_id = body.chat_id.id();
+    let only_if_banned = body.only_if_banned.unwrap_or(false);
+    if let Some(ChatMember { user, kind }) = lock.get_chat_member(chat_id, body.user_id) {
+        // Without `only_if_banned`, Telegram makes sure the user isn't in the chat afterwards
+        let kind = match kind {
+            ChatMemberKind::Banned(_) => ChatMemberKind::Left,
+            kind if only_if_banned => kind,
+            ChatMemberKind::Owner(_) => {
+                return ErrorBadRequest("Bad Request: can't remove chat owner").into()
+            }
+            ChatMemberKind::Administrator(_) => {
+                return ErrorBadRequest("Bad Request: user is an administrator of the chat").into()
+            }
+            ChatMemberKind::Restricted(mut restricted) => {
+                restricted.is_member = false;
+                ChatMemberKind::Restricted(restricted)
+            }
+            ChatMemberKind::Member(_) | ChatMemberKind::Left => ChatMemberKind::Left,
+        };
+        lock.add_chat_member(ChatId(chat_id), ChatMember { user, kind });
+    }
     lock.responses.unbanned_chat_members.push(body.into_inner());
 
     make_telegram_result(true)
@@ -1,12 +1,13 @@
 use std::sync::Mutex;
 
 use actix_web::{web, Responder};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use teloxide::types::ChatId;
 
 use super::BodyChatId;
 use crate::{server::routes::make_telegram_result, state::State};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UnpinAllChatMessagesBody {
     pub chat_id: BodyChatId,
 }
@@ -16,6 +17,9 @@ pub async fn unpin_all_chat_messages(
     body: web::Json<UnpinAllChatMessagesBody>,
 ) -> impl Responder {
     let mut lock = state.lock().unwrap();
+    if let Some(metadata) = lock.chat_metadata.get_mut(&ChatId(body.chat_id.id())) {
+        metadata.pinned_messages.clear();
+    }
     lock.responses
         .unpinned_all_chat_messages
         .push(body.into_inner());
@@ -1,13 +1,13 @@
 use std::sync::Mutex;
 
 use actix_web::{error::ErrorBadRequest, web, Responder};
-use serde::Deserialize;
-use teloxide::types::BusinessConnectionId;
+use serde::{Deserialize, Serialize};
+use teloxide::types::{BusinessConnectionId, ChatId};
 
 use super::{check_if_message_exists, BodyChatId};
 use crate::{server::routes::make_telegram_result, state::State};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UnpinChatMessageBody {
     pub chat_id: BodyChatId,
     pub message_id: Option<i32>,
@@ -22,6 +22,17 @@ pub async fn unpin_chat_message(
     if let Some(message_id) = body.message_id {
         check_if_message_exists!(lock, message_id);
     }
+    // Without the id, the most recent pinned message is unpinned
+    if let Some(metadata) = lock.chat_metadata.get_mut(&ChatId(body.chat_id.id())) {
+        match body.message_id {
+            Some(message_id) => metadata
+                .pinned_messages
+                .retain(|pinned| pinned.id.0 != message_id),
+            None => {
+                metadata.pinned_messages.pop();
+            }
+        }
+    }
     lock.responses
         .unpinned_chat_messages
         .push(body.into_inner());
@@ -0,0 +1,100 @@
+use std::sync::Mutex;
+
+use actix_web::{error::ErrorBadRequest, web, Responder};
+use serde::{Deserialize, Serialize};
+use teloxide::types::BusinessConnectionId;
+
+use super::make_telegram_result;
+use crate::{
+    server::{
+        OwnedGift, OwnedGiftUnique, UniqueGift, UniqueGiftBackdrop, UniqueGiftBackdropColors,
+        UniqueGiftModel, UniqueGiftSymbol,
+    },
+    state::State,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpgradeGiftBody {
+    pub business_connection_id: BusinessConnectionId,
+    pub owned_gift_id: String,
+    pub keep_original_details: Option<bool>,
+    pub star_count: Option<u32>,
+}
+
+pub async fn upgrade_gift(
+    state: web::Data<Mutex<State>>,
+    body: web::Json<UpgradeGiftBody>,
+) -> impl Responder {
+    let mut lock = state.lock().unwrap();
+    let Some(index) = lock.find_business_gift(&body.owned_gift_id) else {
+        return ErrorBadRequest("Bad Request: gift not found").into();
+    };
+    let OwnedGift::Regular(regular) = lock.business_gifts[index].clone() else {
+        return ErrorBadRequest("Bad Request: gift is already unique").into();
+    };
+    if !regular.can_be_upgraded {
+        return ErrorBadRequest("Bad Request: gift can't be upgraded").into();
+    }
+
+    // If the upgrade was prepaid by the sender, it is free for the owner
+    let star_count = body.star_count.unwrap_or(0) as i64;
+    if regular.prepaid_upgrade_star_count.is_none() && star_count == 0 {
+        return ErrorBadRequest("Bad Request: STARGIFT_UPGRADE_PAYMENT_REQUIRED").into();
+    }
+    if lock.business_star_balance < star_count {
+        return ErrorBadRequest("Bad Request: BALANCE_TOO_LOW").into();
+    }
+
+    let number = lock
+        .business_gifts
+        .iter()
+        .filter(|owned_gift| matches!(owned_gift, OwnedGift::Unique(_)))
+        .count() as u32
+        + 1;
+    let base_name = regular
+        .gift
+        .sticker
+        .emoji
+        .clone()
+        .unwrap_or("Gift".to_string());
+    let unique_gift = UniqueGift {
+        name: format!("{base_name}-{number}"),
+        base_name,
+        number,
+        model: UniqueGiftModel {
+            name: "Model".to_string(),
+            sticker: regular.gift.sticker.clone(),
+            rarity_per_mille: 10,
+        },
+        symbol: UniqueGiftSymbol {
+            name: "Symbol".to_string(),
+            sticker: regular.gift.sticker.clone(),
+            rarity_per_mille: 10,
+        },
+        backdrop: UniqueGiftBackdrop {
+            name: "Backdrop".to_string(),
+            colors: UniqueGiftBackdropColors {
+                center_color: 0x5B8DEF,
+                edge_color: 0x3D5FA8,
+                symbol_color: 0x1E2F54,
+                text_color: 0xFFFFFF,
+            },
+            rarity_per_mille: 10,
+        },
+    };
+    let keep_original_details = body.keep_original_details.unwrap_or(false);
+
+    lock.business_star_balance -= star_count;
+    lock.business_gifts[index] = OwnedGift::Unique(OwnedGiftUnique {
+        gift: unique_gift,
+        owned_gift_id: regular.owned_gift_id,
+        sender_user: regular.sender_user.filter(|_| keep_original_details),
+        send_date: regular.send_date,
+        is_saved: regular.is_saved,
+        can_be_transferred: true,
+        transfer_star_count: None,
+    });
+    lock.responses.upgraded_gifts.push(body.into_inner());
+
+    make_telegram_result(true)
+}
@@ -1,20 +1,463 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use rand::{
+    distr::{Alphanumeric, SampleString},
+    rngs::StdRng,
+    SeedableRng,
+};
 use teloxide::{
     prelude::*,
-    types::{File, MessageId, MessageKind},
+    types::{
+        BotCommand, BotCommandScope, CallbackQuery, ChatFullInfo, ChatMember, ChatMemberKind,
+        ChatPermissions, CustomEmojiId, File, FileId, FileMeta, FileUniqueId,
+        InlineKeyboardButtonKind, LivePeriod, MaybeInaccessibleMessage, Me, Member, MenuButton,
+        MessageEntityKind, MessageId, MessageKind, PhotoSize, ReactionType, ReplyMarkup, Sticker,
+        UntilDate, UpdateKind, UserId,
+    },
+    ApiError,
+};
+
+use crate::{
+    media_metadata::MediaProbe,
+    server::{
+        messages::Messages, requests::ErrorLocalizer, routes::Attachment, BotRequest, OwnedGift,
+        SentCopyTextButton, SentLoginUrlButton,
+    },
+    transcript::TranscriptEntry,
+    utils::find_file,
+    MediaMetadata, MockMessageSticker, MockMessageText, Responses, RouteCoverage, StorageChaos,
 };
 
-use crate::{server::messages::Messages, utils::find_file, MockMessageText, Responses};
+/// Widths and heights of the sizes Telegram makes of a sent photo, from the smallest to the
+/// largest
+const PHOTO_SIZES: [(u32, u32); 4] = [(90, 51), (320, 180), (800, 450), (1280, 720)];
+/// Width and height of the thumbnails of videos, animations and documents
+const THUMBNAIL_SIZE: (u32, u32) = (320, 180);
+const THUMBNAIL_FILE_SIZE: u32 = 12345;
+/// The fields of a message that stay the same when it is edited, if the edited message doesn't
+/// have them. The content, like the text and its entities, is replaced as a whole.
+const KEPT_ON_EDIT: [&str; 16] = [
+    "from",
+    "sender_chat",
+    "message_thread_id",
+    "is_topic_message",
+    "reply_to_message",
+    "external_reply",
+    "quote",
+    "forward_origin",
+    "is_automatic_forward",
+    "via_bot",
+    "author_signature",
+    "media_group_id",
+    "has_protected_content",
+    "business_connection_id",
+    "reply_markup",
+    "effect_id",
+];
+
+/// Generates the random parts of the responses, like file ids. It can be seeded, so that the
+/// ids are the same on every run
+pub(crate) struct IdGenerator(pub StdRng);
+
+impl Default for IdGenerator {
+    fn default() -> Self {
+        Self(StdRng::from_os_rng())
+    }
+}
+
+impl IdGenerator {
+    pub(crate) fn seed(&mut self, seed: u64) {
+        self.0 = StdRng::seed_from_u64(seed);
+    }
+
+    pub(crate) fn string(&mut self, len: usize) -> String {
+        Alphanumeric.sample_string(&mut self.0, len)
+    }
+}
+
+/// Chat information that `getChat` returns, besides what is in the chat itself
+#[derive(Default, Clone)]
+pub(crate) struct ChatMetadata {
+    /// The info to return instead of the one made from the chat
+    pub full_info: Option<ChatFullInfo>,
+    pub description: Option<String>,
+    pub permissions: Option<ChatPermissions>,
+    /// The custom emoji id of the status and when it expires
+    pub emoji_status: Option<(CustomEmojiId, Option<DateTime<Utc>>)>,
+    pub profile_accent_color_id: Option<u8>,
+    pub has_restricted_voice_and_video_messages: Option<bool>,
+    /// From the oldest to the most recent pin
+    pub pinned_messages: Vec<Message>,
+    /// The menu button of the private chat, instead of the default one
+    pub menu_button: Option<MenuButton>,
+}
 
 #[derive(Default)]
 pub(crate) struct State {
     pub files: Vec<File>,
     pub responses: Responses,
     pub messages: Messages,
+    pub business_star_balance: i64,
+    pub business_gifts: Vec<OwnedGift>,
+    pub reply_markups: HashMap<MessageId, ReplyMarkup>,
+    /// How far the mock clock is ahead of the real one
+    pub clock_offset: TimeDelta,
+    /// How far the current dispatch is ahead of the mock clock, see `MockBot::dispatch_after`
+    pub dispatch_offset: TimeDelta,
+    pub expired_live_locations: HashSet<MessageId>,
+    /// Every request the bot has sent, unlike responses it isn't reset between dispatches
+    pub requests: Vec<BotRequest>,
+    /// Indices of the requests in `requests` that some assertion checked
+    pub asserted_requests: HashSet<usize>,
+    pub chat_members: HashMap<ChatId, Vec<ChatMember>>,
+    /// Contents of the files by their paths, the other files are downloaded as "Hello, world!"
+    pub file_contents: HashMap<String, Vec<u8>>,
+    pub chat_metadata: HashMap<ChatId, ChatMetadata>,
+    /// Updates that `getUpdates` returns, until the bot confirms them with the offset
+    pub pending_updates: Vec<Update>,
+    pub ids: IdGenerator,
+    /// Values of the next dice the bot sends, the others are random
+    pub dice_values: VecDeque<u8>,
+    /// The callback queries the bot got, by their ids, for checking the answers
+    pub callback_queries: HashMap<String, CallbackQuery>,
+    /// The menu button of the private chats that have no menu button of their own, set by
+    /// `setChatMenuButton` without a `chat_id`
+    pub default_menu_button: Option<MenuButton>,
+    /// Stickers of the custom emojis, that `getCustomEmojiStickers` returns
+    pub custom_emoji_stickers: HashMap<CustomEmojiId, Sticker>,
+    /// Sequence number of the last recorded response
+    pub seq: u64,
+    /// Paths of the files that `getFile` returns instead of the generated ones
+    pub file_paths: HashMap<FileId, String>,
+    /// Errors that `getFile` returns for the files
+    pub get_file_errors: HashMap<FileId, ApiError>,
+    /// The reactions of the users to the messages
+    pub message_reactions: HashMap<MessageId, HashMap<UserId, Vec<ReactionType>>>,
+    /// When the messages of the last second were sent, for the flood limit
+    pub recent_message_times: Vec<DateTime<Utc>>,
+    /// The balance of the bot, that paid broadcasts are paid from, in nanostars
+    pub bot_nanostar_balance: i64,
+    /// The updates and the requests of the bot in the order they happened, across dispatches
+    pub transcript: Vec<TranscriptEntry>,
+    /// The responses that changed after decoding, with their diffs
+    pub fidelity_errors: Vec<String>,
+    /// When the current dispatch started, by the mock clock
+    pub dispatch_started_at: DateTime<Utc>,
+    /// The commands of the bot by their scope and language code, set by `setMyCommands`
+    pub my_commands: HashMap<(BotCommandScope, Option<String>), Vec<BotCommand>>,
+    /// The methods the bot called, unlike `requests` it stays after `MockBot::reset`, so the
+    /// coverage report has the calls of all of the tests that shared the bot
+    pub coverage: RouteCoverage,
+    pub config: Config,
+}
+
+/// How the fake server behaves, set up by the `MockBot` methods. Unlike the rest of the state, it
+/// stays after `MockBot::reset`, so a configured bot can be shared between tests.
+#[derive(Default, Clone)]
+pub(crate) struct Config {
+    /// Whether the replies to unknown messages reply to placeholder messages instead of failing
+    pub lenient_replies: bool,
+    /// Whether the fake server rejects what Telegram rejects, like unknown message effect ids, see
+    /// `MockBot::validate_like_telegram`
+    pub strict_validation: bool,
+    /// How many messages the bot can send in a second, unlimited if `None`
+    pub flood_limit: Option<usize>,
+    /// The balance of the bot that it starts with, in nanostars
+    pub bot_nanostar_balance: i64,
+    /// The balance of the business account that it starts with
+    pub business_star_balance: i64,
+    /// Whether the responses are checked to decode into teloxide types without changes
+    pub check_fidelity: bool,
+    /// What goes wrong with the dialogue storage, see `MockBot::storage_chaos`
+    pub storage_chaos: StorageChaos,
+    /// Gives the metadata of the sent media files, see `MockBot::probe_media`
+    pub media_probe: Option<MediaProbe>,
+    /// Rewrites the descriptions of the returned errors, see `MockBot::localize_errors`
+    pub error_localizer: Option<ErrorLocalizer>,
+    /// Whether the responses of the previous dispatches are kept, see
+    /// `MockBot::accumulate_responses`
+    pub accumulate_responses: bool,
 }
 
 impl State {
+    /// Forgets everything but the config and the route coverage, with the balances back to the
+    /// ones the bot starts with
+    pub(crate) fn reset_to_config(&mut self) {
+        let config = std::mem::take(&mut self.config);
+        *self = State {
+            coverage: std::mem::take(&mut self.coverage),
+            bot_nanostar_balance: config.bot_nanostar_balance,
+            business_star_balance: config.business_star_balance,
+            config,
+            ..State::default()
+        };
+    }
+
     pub fn reset(&mut self) {
+        if !self.config.accumulate_responses {
+            self.clear_responses();
+        }
+        self.dispatch_started_at = self.now();
+    }
+
+    pub(crate) fn clear_responses(&mut self) {
         self.responses = Responses::default();
+        self.seq = 0;
+    }
+
+    /// Returns the sequence number for the next recorded response
+    pub(crate) fn next_seq(&mut self) -> u64 {
+        self.seq += 1;
+        self.seq
+    }
+
+    /// Returns the meta of a file the bot sent with `file_data`. If it is the id of a known file,
+    /// the file is sent again, like in Telegram, otherwise it is a new file.
+    pub(crate) fn file_meta(&mut self, file_data: &str) -> FileMeta {
+        if let Some(file) = self.files.iter().find(|file| file.id.0 == file_data) {
+            return file.meta.clone();
+        }
+        self.new_file_meta(file_data.len() as u32)
+    }
+
+    fn new_file_meta(&mut self, size: u32) -> FileMeta {
+        FileMeta {
+            id: FileId(self.ids.string(16)),
+            unique_id: FileUniqueId(self.ids.string(8)),
+            size,
+        }
+    }
+
+    /// Stores the file, unless it is already known, so that the bot can get it
+    fn store_file(&mut self, meta: &FileMeta, path: &str) {
+        if !self.files.iter().any(|file| file.id == meta.id) {
+            self.files.push(File {
+                meta: meta.clone(),
+                path: path.to_string(),
+            });
+        }
+    }
+
+    /// Returns the sizes of a photo the bot sent with `file_data`, from the smallest to the
+    /// largest, like Telegram does. The largest one is the sent file itself, and the smaller ones
+    /// are new files. All of them are stored, so that the bot can get any of them.
+    pub(crate) fn photo_sizes(&mut self, file_data: &str, path: &str) -> Vec<PhotoSize> {
+        let original = self.file_meta(file_data);
+        let (largest_width, largest_height) = PHOTO_SIZES[PHOTO_SIZES.len() - 1];
+        let mut sizes = vec![];
+        for (width, height) in PHOTO_SIZES[..PHOTO_SIZES.len() - 1].iter().copied() {
+            let scale = (width * height) as f64 / (largest_width * largest_height) as f64;
+            let size = ((original.size as f64 * scale) as u32).max(1);
+            sizes.push(PhotoSize {
+                file: self.new_file_meta(size),
+                width,
+                height,
+            });
+        }
+        sizes.push(PhotoSize {
+            file: original,
+            width: largest_width,
+            height: largest_height,
+        });
+
+        for size in sizes.iter() {
+            self.store_file(&size.file, path);
+        }
+        sizes
+    }
+
+    /// Returns the thumbnail of a video, animation or document the bot sent. It is the thumbnail
+    /// the bot sent, or, like in Telegram, a new one if there is no such thumbnail. The thumbnail
+    /// is stored, so that the bot can get it.
+    pub(crate) fn thumbnail(&mut self, thumbnail: Option<&Attachment>) -> PhotoSize {
+        let (file, path) = match thumbnail {
+            Some(thumbnail) => (
+                self.file_meta(&thumbnail.file_data),
+                thumbnail.file_name.as_str(),
+            ),
+            None => (self.new_file_meta(THUMBNAIL_FILE_SIZE), "thumbnail.jpg"),
+        };
+        self.store_file(&file, path);
+        let (width, height) = THUMBNAIL_SIZE;
+        PhotoSize {
+            file,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the metadata of a media file the bot sent, that the fake server uses when the bot
+    /// didn't set it in the request. Without a probe, or if the probe doesn't know the file, the
+    /// metadata is empty.
+    pub(crate) fn media_metadata(&self, file_name: &str) -> MediaMetadata {
+        self.config
+            .media_probe
+            .as_ref()
+            .and_then(|probe| probe(file_name))
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn seed_file(&mut self, file_id: &str, contents: Vec<u8>, path: &str) {
+        let meta = FileMeta {
+            id: FileId(file_id.to_string()),
+            unique_id: FileUniqueId(self.ids.string(8)),
+            size: contents.len() as u32,
+        };
+        self.files.retain(|file| file.id != meta.id);
+        self.files.push(File {
+            meta,
+            path: path.to_string(),
+        });
+        self.file_contents.insert(path.to_string(), contents);
+    }
+
+    pub(crate) fn get_chat_member(&self, chat_id: i64, user_id: u64) -> Option<ChatMember> {
+        self.chat_members
+            .get(&ChatId(chat_id))?
+            .iter()
+            .find(|member| member.user.id.0 == user_id)
+            .cloned()
+    }
+
+    /// Adds the member to the chat, replacing the member with the same user
+    pub(crate) fn add_chat_member(&mut self, chat_id: ChatId, member: ChatMember) {
+        let members = self.chat_members.entry(chat_id).or_default();
+        members.retain(|old_member| old_member.user.id != member.user.id);
+        members.push(member);
+    }
+
+    /// The current time of the mock clock
+    pub(crate) fn now(&self) -> DateTime<Utc> {
+        Utc::now() + self.clock_offset + self.dispatch_offset
+    }
+
+    /// The menu button of the chat with the `chat_id`, or the default one without it. The
+    /// default menu button opens the list of commands, until it is changed.
+    pub(crate) fn menu_button(&self, chat_id: Option<ChatId>) -> MenuButton {
+        chat_id
+            .and_then(|chat_id| self.chat_metadata.get(&chat_id)?.menu_button.clone())
+            .or_else(|| self.default_menu_button.clone())
+            .unwrap_or(MenuButton::Commands)
+    }
+
+    /// Sets the commands of the bot for the scope and the language code, like `setMyCommands`.
+    /// No scope is the default scope, and no commands delete the commands.
+    pub(crate) fn set_my_commands(
+        &mut self,
+        scope: Option<BotCommandScope>,
+        language_code: Option<String>,
+        commands: Vec<BotCommand>,
+    ) {
+        let key = (scope.unwrap_or(BotCommandScope::Default), language_code);
+        if commands.is_empty() {
+            self.my_commands.remove(&key);
+        } else {
+            self.my_commands.insert(key, commands);
+        }
+    }
+
+    /// The commands of the bot for exactly this scope and language code, like `getMyCommands`
+    /// returns them. Commands of other scopes and languages aren't used as a fallback.
+    pub(crate) fn my_commands(
+        &self,
+        scope: Option<BotCommandScope>,
+        language_code: Option<String>,
+    ) -> Vec<BotCommand> {
+        self.my_commands
+            .get(&(scope.unwrap_or(BotCommandScope::Default), language_code))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Turns the `until_date` of a restriction or a ban into the date it ends at. Like in
+    /// Telegram, restrictions and bans for less than 30 seconds or more than 366 days from the
+    /// mock clock time are forever.
+    pub(crate) fn until_date(&self, until_date: Option<DateTime<Utc>>) -> UntilDate {
+        let Some(until_date) = until_date else {
+            return UntilDate::Forever;
+        };
+        let duration = until_date - self.now();
+        if duration < TimeDelta::seconds(30) || duration > TimeDelta::days(366) {
+            return UntilDate::Forever;
+        }
+        UntilDate::Date(until_date)
+    }
+
+    /// Lifts the restrictions and the bans of the chat members that have ended at the current
+    /// mock clock time. Banned users can join the chat again, but aren't in it.
+    pub(crate) fn lift_expired_restrictions(&mut self) {
+        let now = self.now();
+        for member in self.chat_members.values_mut().flatten() {
+            let (until_date, is_member) = match &member.kind {
+                ChatMemberKind::Restricted(restricted) => {
+                    (restricted.until_date, restricted.is_member)
+                }
+                ChatMemberKind::Banned(banned) => (banned.until_date, false),
+                _ => continue,
+            };
+            if matches!(until_date, UntilDate::Date(date) if date <= now) {
+                member.kind = match is_member {
+                    true => ChatMemberKind::Member(Member { until_date: None }),
+                    false => ChatMemberKind::Left,
+                };
+            }
+        }
+    }
+
+    /// Flags all live locations that can't be updated anymore at the current mock clock time
+    pub(crate) fn expire_live_locations(&mut self) {
+        let now = self.now();
+        for message in &self.messages.messages {
+            let Some(LivePeriod::Timeframe(live_period)) =
+                message.location().and_then(|location| location.live_period)
+            else {
+                continue;
+            };
+            if message.date + live_period.chrono_duration() <= now {
+                self.expired_live_locations.insert(message.id);
+            }
+        }
+    }
+
+    /// Inserts the messages of the updates, so the bot can interact with them
+    pub(crate) fn insert_updates(&mut self, updates: &mut [Update]) {
+        for update in updates.iter_mut() {
+            match update.kind.clone() {
+                UpdateKind::Message(mut message) => {
+                    // Add the message to the list of messages, so the bot can interact with it
+                    self.add_message(&mut message);
+                    update.kind = UpdateKind::Message(message.clone());
+                }
+                UpdateKind::EditedMessage(mut message) => {
+                    self.edit_message(&mut message);
+                    update.kind = UpdateKind::EditedMessage(message.clone());
+                }
+                UpdateKind::ChannelPost(mut message) => {
+                    self.add_message(&mut message);
+                    update.kind = UpdateKind::ChannelPost(message.clone());
+                }
+                UpdateKind::EditedChannelPost(mut message) => {
+                    self.edit_message(&mut message);
+                    update.kind = UpdateKind::EditedChannelPost(message.clone());
+                }
+                UpdateKind::CallbackQuery(mut callback) => {
+                    if let Some(MaybeInaccessibleMessage::Regular(ref mut message)) =
+                        callback.message
+                    {
+                        self.add_message(message);
+                    }
+                    self.callback_queries
+                        .insert(callback.id.0.clone(), callback.clone());
+                    update.kind = UpdateKind::CallbackQuery(callback.clone());
+                }
+                UpdateKind::MyChatMember(updated) | UpdateKind::ChatMember(updated) => {
+                    // So that `getChatMember` agrees with the update
+                    self.add_chat_member(updated.chat.id, updated.new_chat_member);
+                }
+                _ => {}
+            }
+        }
     }
 
     pub(crate) fn add_message(&mut self, message: &mut Message) {
@@ -47,10 +490,118 @@ impl State {
                 self.add_message(reply_message);
             }
         }
+        self.remember_custom_emojis(message);
         log::debug!("Inserted message with {}.", message.id);
         self.messages.add_message(message.clone());
     }
 
+    /// Remembers the custom emojis of the message, so that the bot can get their stickers
+    fn remember_custom_emojis(&mut self, message: &Message) {
+        let entities = message
+            .parse_entities()
+            .into_iter()
+            .chain(message.parse_caption_entities())
+            .flatten();
+        for entity in entities {
+            if let MessageEntityKind::CustomEmoji { custom_emoji_id } = entity.kind() {
+                self.custom_emoji_stickers
+                    .entry(custom_emoji_id.clone())
+                    .or_insert_with(|| {
+                        MockMessageSticker::new()
+                            .custom_emoji_id(custom_emoji_id.0.clone())
+                            .emoji(entity.text())
+                            .build()
+                            .sticker()
+                            .unwrap()
+                            .clone()
+                    });
+            }
+        }
+    }
+
+    /// Remembers the reply markup the message was sent with. `Message` can only hold inline
+    /// keyboards, so reply keyboards, keyboard removals and force replies are stored here.
+    pub(crate) fn add_reply_markup(
+        &mut self,
+        message: &Message,
+        reply_markup: Option<ReplyMarkup>,
+    ) {
+        if let Some(reply_markup) = reply_markup {
+            self.record_special_buttons(message, &reply_markup);
+            self.reply_markups.insert(message.id, reply_markup);
+        }
+    }
+
+    /// Records the copy text and login url buttons of the markup, so that their exact payloads
+    /// can be checked
+    pub(crate) fn record_special_buttons(&mut self, message: &Message, reply_markup: &ReplyMarkup) {
+        let ReplyMarkup::InlineKeyboard(markup) = reply_markup else {
+            return;
+        };
+        for button in markup.inline_keyboard.iter().flatten() {
+            match &button.kind {
+                InlineKeyboardButtonKind::CopyText(copy_text) => {
+                    self.responses.copy_text_buttons.push(SentCopyTextButton {
+                        message: message.clone(),
+                        text: button.text.clone(),
+                        copy_text: copy_text.clone(),
+                    });
+                }
+                InlineKeyboardButtonKind::LoginUrl(login_url) => {
+                    self.responses.login_url_buttons.push(SentLoginUrlButton {
+                        message: message.clone(),
+                        text: button.text.clone(),
+                        login_url: login_url.clone(),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the index of the business account gift with `owned_gift_id`, if it exists
+    pub(crate) fn find_business_gift(&self, owned_gift_id: &str) -> Option<usize> {
+        self.business_gifts
+            .iter()
+            .position(|owned_gift| owned_gift.owned_gift_id() == Some(owned_gift_id))
+    }
+
+    /// Stores a message sent by the bot, making it look like it would in the real chat.
+    ///
+    /// If the chat is a channel the bot has seen posts from, the message is posted on behalf of
+    /// the channel, and is signed with the bot name if the posts in that channel are signed.
+    pub(crate) fn add_sent_message(&mut self, mut message: Message, me: &Me) -> Message {
+        let channel = self
+            .messages
+            .messages
+            .iter()
+            .find(|m| m.chat.id == message.chat.id && m.chat.is_channel())
+            .map(|m| m.chat.clone());
+
+        let mut author_signature = None;
+        if let Some(channel) = channel {
+            let signs_posts = self
+                .messages
+                .messages
+                .iter()
+                .any(|m| m.chat.id == channel.id && m.author_signature().is_some());
+            if signs_posts {
+                author_signature = Some(me.user.full_name());
+            }
+            message.sender_chat = Some(channel.clone());
+            message.chat = channel;
+            message.from = None;
+        } else {
+            message.sender_chat = None;
+        }
+        if let MessageKind::Common(ref mut common) = message.kind {
+            common.author_signature = author_signature;
+        }
+        message.date = self.now();
+
+        self.messages.add_message(message)
+    }
+
     pub(crate) fn edit_message(&mut self, message: &mut Message) {
         let old_message = self.messages.get_message(message.id.0);
 
@@ -75,7 +626,29 @@ impl State {
                 self.files.push(file);
             }
         }
+        // The edit keeps the original date and what the edited message left out
+        let old_message = serde_json::to_value(old_message.unwrap()).unwrap();
+        let mut edited_message = serde_json::to_value(&*message).unwrap();
+        edited_message["date"] = old_message["date"].clone();
+        for field in KEPT_ON_EDIT {
+            if let (None, Some(value)) = (edited_message.get(field), old_message.get(field)) {
+                edited_message[field] = value.clone();
+            }
+        }
+        if edited_message.get("edit_date").is_none() {
+            edited_message["edit_date"] = self.now().timestamp().into();
+        }
+        *message = serde_json::from_value(edited_message).expect("The edited message is valid");
+
         log::debug!("Edited message with {}.", message.id);
         self.messages.edit_message(message.clone());
     }
+
+    /// Sets the `edit_date` of the stored message to the current mock clock time, after a route
+    /// edited it
+    pub(crate) fn mark_edited(&mut self, message_id: i32) {
+        let now = self.now().timestamp();
+        self.messages
+            .edit_message_field(message_id, "edit_date", now);
+    }
 }
@@ -0,0 +1,149 @@
+//! Slow and failing dialogue storages, see [`MockBot::storage_chaos`]
+//!
+//! [`MockBot::storage_chaos`]: crate::MockBot::storage_chaos
+use std::{
+    error::Error,
+    fmt,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures_util::future::BoxFuture;
+use teloxide::{
+    dispatching::dialogue::{ErasedStorage, Storage},
+    types::ChatId,
+};
+
+use crate::state::State;
+
+/// What goes wrong with the dialogue storage of the bot, like Redis or Sqlite being slow or down
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+///
+/// let chaos = teloxide_tests::StorageChaos::new()
+///     .delay(Duration::from_millis(200))
+///     .fail_update(true);
+/// assert!(!chaos.fail_get);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StorageChaos {
+    /// How long every call to the storage takes
+    pub delay: Duration,
+    /// Whether getting a dialogue fails
+    pub fail_get: bool,
+    /// Whether updating or removing a dialogue fails
+    pub fail_update: bool,
+}
+
+impl StorageChaos {
+    /// Creates chaos that does nothing, a storage that is fast and never fails
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn fail_get(mut self, fail_get: bool) -> Self {
+        self.fail_get = fail_get;
+        self
+    }
+
+    pub fn fail_update(mut self, fail_update: bool) -> Self {
+        self.fail_update = fail_update;
+        self
+    }
+}
+
+/// The error of a storage call that failed because of [`StorageChaos`]
+#[derive(Debug)]
+pub struct StorageChaosError;
+
+impl fmt::Display for StorageChaosError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("The storage is down (injected by StorageChaos)")
+    }
+}
+
+impl Error for StorageChaosError {}
+
+/// Marks that the storage of the dialogues of type `D` is already wrapped
+pub(crate) struct ChaosStorageMarker<D>(PhantomData<fn() -> D>);
+
+impl<D> ChaosStorageMarker<D> {
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+/// Wraps the storage of the bot, applying the chaos that is currently set in the state
+pub(crate) struct ChaosStorage<D> {
+    inner: Arc<ErasedStorage<D>>,
+    state: Arc<Mutex<State>>,
+}
+
+impl<D> ChaosStorage<D> {
+    pub(crate) fn new(inner: Arc<ErasedStorage<D>>, state: Arc<Mutex<State>>) -> Self {
+        Self { inner, state }
+    }
+
+    async fn apply(&self, fails: impl Fn(&StorageChaos) -> bool) -> Result<(), StorageChaosError> {
+        let chaos = self.state.lock().unwrap().config.storage_chaos.clone();
+        if !chaos.delay.is_zero() {
+            tokio::time::sleep(chaos.delay).await;
+        }
+        if fails(&chaos) {
+            return Err(StorageChaosError);
+        }
+        Ok(())
+    }
+}
+
+impl<D> Storage<D> for ChaosStorage<D>
+where
+    D: 'static,
+{
+    type Error = Box<dyn Error + Send + Sync>;
+
+    fn remove_dialogue(
+        self: Arc<Self>,
+        chat_id: ChatId,
+    ) -> BoxFuture<'static, Result<(), Self::Error>>
+    where
+        D: Send + 'static,
+    {
+        Box::pin(async move {
+            self.apply(|chaos| chaos.fail_update).await?;
+            self.inner.clone().remove_dialogue(chat_id).await
+        })
+    }
+
+    fn update_dialogue(
+        self: Arc<Self>,
+        chat_id: ChatId,
+        dialogue: D,
+    ) -> BoxFuture<'static, Result<(), Self::Error>>
+    where
+        D: Send + 'static,
+    {
+        Box::pin(async move {
+            self.apply(|chaos| chaos.fail_update).await?;
+            self.inner.clone().update_dialogue(chat_id, dialogue).await
+        })
+    }
+
+    fn get_dialogue(
+        self: Arc<Self>,
+        chat_id: ChatId,
+    ) -> BoxFuture<'static, Result<Option<D>, Self::Error>> {
+        Box::pin(async move {
+            self.apply(|chaos| chaos.fail_get).await?;
+            self.inner.clone().get_dialogue(chat_id).await
+        })
+    }
+}
@@ -1,13 +1,15 @@
 use std::{
     fmt::Display,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
     thread,
+    time::Duration,
 };
 
-use chrono::Utc;
+use chrono::{DateTime, TimeDelta, Utc};
 use futures_util::future::BoxFuture;
 use serde::{Deserialize, Serialize};
 use teloxide::{
+    adaptors::{throttle::Limits, CacheMe, DefaultParseMode, Throttle},
     dispatching::{
         dialogue::{self, serializer::Json, ErasedStorage, InMemStorage, SqliteStorage, Storage},
         HandlerExt, UpdateFilterExt, UpdateHandler,
@@ -16,20 +18,38 @@ use teloxide::{
     error_handlers::ErrorHandler,
     macros::BotCommands,
     net::Download,
-    payloads::{BanChatMemberSetters, CopyMessageSetters, SendPhotoSetters, SendPollSetters},
+    payloads::{
+        BanChatMemberSetters, CopyMessageSetters, SendDiceSetters, SendPhotoSetters,
+        SendPollSetters,
+    },
     prelude::*,
     requests::Requester,
     sugar::request::RequestReplyExt,
     types::{
-        BotCommand, ChatAction, ChatPermissions, DiceEmoji, InlineKeyboardButton,
-        InlineKeyboardMarkup, InputFile, InputMedia, InputMediaAudio, InputMediaDocument,
-        InputMediaPhoto, InputMediaVideo, LabeledPrice, LinkPreviewOptions, Message, MessageEntity,
-        MessageId, PollOption, PollType, ReactionType, ReplyParameters, Update,
+        BotCommand, BotCommandScope, BusinessConnectionId, ButtonRequest, ChatAction,
+        ChatFullInfoKind, ChatInviteLink, ChatMemberKind, ChatMemberUpdated, ChatPermissions,
+        ChatType, CopyTextButton, CustomEmojiId, DiceEmoji, EffectId, FileId, ForceReply,
+        InlineKeyboardButton, InlineKeyboardMarkup, InlineQueryResult, InlineQueryResultArticle,
+        InputFile, InputMedia, InputMediaAudio, InputMediaDocument, InputMediaPhoto,
+        InputMediaVideo, InputMessageContent, InputMessageContentText, KeyboardButton,
+        KeyboardButtonRequestChat, KeyboardButtonRequestUsers, KeyboardMarkup, LabeledPrice,
+        LinkPreviewOptions, LoginUrl, Me, Member, MenuButton, Message, MessageEntity,
+        MessageEntityKind, MessageId, MessageKind, MessageOrigin, MessageReactionUpdated, Owner,
+        ParseMode, PassportElementError, PassportElementErrorKind, PassportElementErrorUnspecified,
+        PassportElementErrorUnspecifiedType, PollOption, PollType, ReactionType, ReplyMarkup,
+        ReplyParameters, RequestId, Seconds, ThreadId, UntilDate, Update, UpdateKind, WebAppInfo,
     },
+    ApiError,
 };
 
 use super::*;
-use crate::dataset::*;
+use crate::{
+    dataset::*,
+    json_contains,
+    matchers::Regex,
+    mock_bot::DistributionKey,
+    server::{routes::MediaGroupInputMedia, DroppedField, StarAmount},
+};
 
 //
 //
@@ -111,6 +131,56 @@ async fn test_assert_state() {
     assert_eq!(last_response.text(), Some("test"));
 }
 
+#[tokio::test]
+async fn test_try_set_state_with_wrong_type() {
+    let mut bot = MockBot::new(MockMessageText::new().text("test"), get_dialogue_schema());
+    assert_eq!(
+        bot.try_set_state(State::Start).await,
+        Err(StateStorageError::NoStorage)
+    );
+
+    bot.dependencies(deps![InMemStorage::<State>::new()]);
+    let error = bot.try_set_state("Start".to_string()).await.unwrap_err();
+    assert_eq!(
+        error,
+        StateStorageError::WrongStateType {
+            provided: "alloc::string::String",
+            expected: vec!["teloxide_tests::tests::State".to_string()],
+        }
+    );
+
+    assert_eq!(bot.try_set_state(State::NotStart).await, Ok(()));
+    bot.assert_state(State::NotStart).await;
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "The state is `i32`, but the storages are for `teloxide_tests::tests::State`!"
+)]
+async fn test_set_state_with_wrong_type() {
+    let mut bot = MockBot::new(MockMessageText::new().text("test"), get_dialogue_schema());
+    bot.dependencies(deps![InMemStorage::<State>::new().erase()]);
+
+    bot.set_state(1).await;
+}
+
+#[tokio::test]
+async fn test_wrong_state_type_message() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("test"),
+        get_erased_dialogue_schema(),
+    );
+    bot.dependencies(deps![InMemStorage::<State>::new().erase()]);
+
+    let error = bot.try_set_state(Some(1)).await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "The state is `core::option::Option<i32>`, but the storages are for \
+         `teloxide_tests::tests::State`! Did you specify the type ::<State> ?"
+    );
+    assert_eq!(bot.try_set_state(State::Start).await, Ok(()));
+}
+
 #[tokio::test]
 async fn test_try_get() {
     let mut bot = MockBot::new(MockMessageText::new().text("exit"), get_dialogue_schema());
@@ -127,6 +197,21 @@ async fn test_try_get() {
     assert_eq!(last_response.text(), Some("exit"));
 }
 
+#[tokio::test]
+async fn test_state_of_update_without_chat() {
+    let mut bot = MockBot::new(MockInlineQuery::new().query("test"), get_dialogue_schema());
+    let storage = InMemStorage::<State>::new();
+    bot.dependencies(deps![storage.clone()]);
+
+    bot.set_state(State::NotStart).await;
+
+    // Inline queries have no chat, so the dialogue is in the private chat with the user
+    let user_chat_id = ChatId(MockUser::ID as i64);
+    let state = storage.get_dialogue(user_chat_id).await.unwrap();
+    assert_eq!(state, Some(State::NotStart));
+    bot.assert_state(State::NotStart).await;
+}
+
 #[tokio::test]
 async fn test_echo_with_not_start_test() {
     let mut bot = MockBot::new(MockMessageText::new().text("test"), get_dialogue_schema());
@@ -143,1084 +228,5051 @@ async fn test_echo_with_not_start_test() {
     assert_eq!(last_response.text(), Some("Not start!"));
 }
 
-fn get_erased_dialogue_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>>
-{
-    dialogue::enter::<Update, ErasedStorage<State>, State, _>()
-        .branch(Update::filter_message().endpoint(handler_with_erased_state))
+#[tokio::test]
+async fn test_auto_respond() {
+    let mut bot = MockBot::new(MockMessageText::new().text("test"), get_dialogue_schema());
+    let storage = InMemStorage::<State>::new();
+    bot.dependencies(deps![storage]);
+    bot.set_state(State::Start).await;
+    bot.auto_respond("test", "hi");
+    bot.auto_respond("Not start!", "exit");
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let texts: Vec<_> = responses
+        .sent_messages
+        .iter()
+        .map(|message| message.text().unwrap())
+        .collect();
+    assert_eq!(texts, vec!["test", "Not start!", "exit"]);
+    let state: Option<State> = bot.try_get_state().await;
+    assert_eq!(state, None);
 }
 
-async fn handler_with_erased_state(
-    bot: Bot,
-    dialogue: ErasedDialogue,
-    msg: Message,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-    bot.send_message(msg.chat.id, msg.text().unwrap()).await?;
-    dialogue.update(State::NotStart).await?;
-    Ok(())
+#[tokio::test]
+#[should_panic(expected = "auto responses were sent")]
+async fn test_auto_respond_loop() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo loop"), get_schema());
+    bot.auto_respond("loop", "/echo loop");
+
+    bot.dispatch().await;
 }
 
 #[tokio::test]
-async fn test_erased_state() {
+async fn test_expectations() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema());
+    bot.expect("sendMessage")
+        .times(1)
+        .with(json_contains!({"chat_id": MockUser::ID, "text": "/echo hi"}));
+    bot.expect("deleteMessage").times(0);
+
+    bot.dispatch().await;
+
+    bot.verify_expectations();
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "sendMessage with {\"text\":\"bye\"}: expected at least once, was called 0 times"
+)]
+async fn test_unmet_expectations() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema());
+    bot.expect("sendMessage")
+        .with(json_contains!({"text": "bye"}));
+
+    bot.dispatch().await;
+
+    bot.verify_expectations();
+}
+
+#[tokio::test]
+#[should_panic(expected = "Unexpected calls:\n  sendMessage")]
+async fn test_unexpected_calls_on_drop() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema());
+    bot.expect("sendMessage")
+        .with(json_contains!({"text": "bye"}));
+
+    bot.dispatch().await;
+}
+
+#[tokio::test]
+async fn test_strict_asserted_requests() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema());
+    bot.strict();
+    bot.dispatch_and_check_last_text("/echo hi").await;
+
+    bot.update(MockMessageText::new().text("/echo bye"));
+    bot.dispatch().await;
+    bot.assert_request("sendMessage", json_contains!({"text": "/echo bye"}));
+
+    bot.verify_expectations();
+}
+
+#[tokio::test]
+#[should_panic(expected = "Unexpected bot requests, no assertion checked them:\n  sendMessage")]
+async fn test_strict_unasserted_requests() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema());
+    bot.strict();
+
+    bot.dispatch().await;
+}
+
+#[tokio::test]
+#[should_panic(expected = "No sendMessage request with {\"text\":\"bye\"} was sent!")]
+async fn test_assert_request_not_sent() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema());
+
+    bot.dispatch().await;
+
+    bot.assert_request("sendMessage", json_contains!({"text": "bye"}));
+}
+
+#[tokio::test]
+async fn test_update_interval() {
     let mut bot = MockBot::new(
-        MockMessageText::new().text("test"),
-        get_erased_dialogue_schema(),
+        vec![
+            MockMessageText::new().text("/echo 1"),
+            MockMessageText::new().text("/echo 2"),
+            MockMessageText::new().text("/echo 3"),
+        ],
+        get_schema(),
     );
-    let storage: MyStorage = SqliteStorage::open(":memory:", Json).await.unwrap().erase();
-    bot.dependencies(deps![storage]);
+    bot.set_update_interval(Duration::from_millis(200));
 
-    // This .dispatch is important?..
+    let start = std::time::Instant::now();
     bot.dispatch().await;
-    bot.dispatch_and_check_state(State::NotStart).await;
+
+    assert!(start.elapsed() >= Duration::from_millis(400));
+    let texts: Vec<_> = bot
+        .get_responses()
+        .sent_messages
+        .iter()
+        .map(|message| message.text().unwrap().to_string())
+        .collect();
+    assert_eq!(texts, vec!["/echo 1", "/echo 2", "/echo 3"]);
 }
 
-//
-//
-//
+#[tokio::test]
+async fn test_update_delays() {
+    let mut bot = MockBot::new(
+        vec![
+            MockMessageText::new().text("/echo 1"),
+            MockMessageText::new().text("/echo 2"),
+        ],
+        get_schema(),
+    );
+    bot.set_update_interval(Duration::from_secs(10));
+    bot.set_update_delays(vec![Duration::ZERO, Duration::from_millis(300)]);
 
-#[derive(BotCommands, Clone)]
-#[command(rename_rule = "lowercase")]
-pub enum AllCommands {
-    #[command()]
-    Echo,
-    #[command()]
-    Edit,
-    #[command()]
-    EditUnchanged,
-    #[command()]
-    Delete,
-    #[command()]
-    DeleteBatch,
-    #[command()]
-    EditReplyMarkup,
-    #[command()]
-    Photo,
-    #[command()]
-    Video,
-    #[command()]
-    Audio,
-    #[command()]
-    Voice,
-    #[command()]
-    VideoNote,
-    #[command()]
-    Document,
-    #[command()]
-    Animation,
-    #[command()]
-    Location,
-    #[command()]
-    Venue,
-    #[command()]
-    Contact,
-    #[command()]
-    Dice,
-    #[command()]
-    Poll,
-    #[command()]
-    Sticker,
-    #[command()]
-    MediaGroup,
-    #[command()]
-    Invoice,
-    #[command()]
-    EditCaption,
-    #[command()]
-    PinMessage,
-    #[command()]
-    ForwardMessage,
-    #[command()]
-    CopyMessage,
-    #[command()]
-    Ban,
-    #[command()]
-    Restrict,
-    #[command()]
-    ChatAction,
-    #[command()]
-    SetMessageReaction,
-    #[command()]
-    SetMyCommands,
-    #[command()]
-    Panic,
+    let start = std::time::Instant::now();
+    bot.dispatch().await;
+
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(300));
+    assert!(elapsed < Duration::from_secs(10));
+    assert_eq!(bot.get_responses().sent_messages.len(), 2);
 }
 
-async fn handler(
-    bot: Bot,
-    msg: Message,
-    cmd: AllCommands,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-    let sent_message = bot.send_message(msg.chat.id, msg.text().unwrap()).await?;
-    assert_eq!(msg.text().unwrap(), sent_message.text().unwrap()); // The message actually made it through!
-    let reply_options = ReplyParameters::new(msg.id);
-    let link_preview_options = LinkPreviewOptions {
-        is_disabled: true,
-        url: None,
-        prefer_small_media: false,
-        prefer_large_media: false,
-        show_above_text: false,
-    };
-    match cmd {
-        AllCommands::Echo => {}
-        AllCommands::Edit => {
-            bot.edit_message_text(msg.chat.id, sent_message.id, "edited")
-                .link_preview_options(link_preview_options)
-                .await?;
-        }
-        AllCommands::EditUnchanged => {
-            bot.edit_message_text(msg.chat.id, sent_message.id, msg.text().unwrap())
-                .link_preview_options(link_preview_options)
-                .await?;
-        }
-        AllCommands::Delete => {
-            bot.delete_message(msg.chat.id, sent_message.id).await?;
-        }
-        AllCommands::DeleteBatch => {
-            bot.delete_messages(msg.chat.id, vec![sent_message.id, MessageId(404)])
-                .await?;
-        }
-        AllCommands::EditReplyMarkup => {
-            bot.edit_message_reply_markup(msg.chat.id, sent_message.id)
-                .reply_markup(InlineKeyboardMarkup::new(vec![vec![
-                    InlineKeyboardButton::callback("test", "test"),
-                ]]))
-                .await?;
-        }
-        AllCommands::Photo => {
-            let photo = InputFile::memory("somedata".to_string()).file_name("test.jpg");
-            bot.send_photo(msg.chat.id, photo)
-                .caption("test")
-                .caption_entities(vec![MessageEntity::bold(0, 3)])
-                .reply_parameters(reply_options)
-                .await?;
-        }
-        AllCommands::Video => {
-            let video = InputFile::memory("somedata".to_string()).file_name("test.mp4");
-            bot.send_video(msg.chat.id, video)
-                .caption("test")
-                .caption_entities(vec![MessageEntity::bold(0, 3)])
-                .has_spoiler(true)
-                .reply_parameters(reply_options)
-                .await?;
-        }
-        AllCommands::Audio => {
-            let audio = InputFile::memory("somedata".to_string()).file_name("test.mp3");
-            bot.send_audio(msg.chat.id, audio)
-                .caption("test")
-                .caption_entities(vec![MessageEntity::bold(0, 3)])
-                .reply_parameters(reply_options)
-                .await?;
-        }
-        AllCommands::Voice => {
-            let voice = InputFile::memory("somedata".to_string()).file_name("test.mp3");
-            bot.send_voice(msg.chat.id, voice)
-                .caption("test")
-                .caption_entities(vec![MessageEntity::bold(0, 3)])
-                .reply_parameters(reply_options)
-                .await?;
-        }
-        AllCommands::VideoNote => {
-            let video_note = InputFile::memory("somedata".to_string()).file_name("test.mp4");
-            bot.send_video_note(msg.chat.id, video_note)
-                .reply_parameters(reply_options)
+#[tokio::test]
+async fn test_builder() {
+    let mut bot = MockBot::builder()
+        .handler_tree(get_dialogue_schema())
+        .update(MockMessageText::new().text("test"))
+        .dependencies(deps![InMemStorage::<State>::new()])
+        .me(MockMe::new().first_name("Builder"))
+        .build();
+    bot.set_state(State::Start).await;
+
+    bot.dispatch_and_check_last_text_and_state("test", State::NotStart)
+        .await;
+    assert_eq!(bot.me.first_name, "Builder");
+}
+
+#[tokio::test]
+async fn test_builder_distribution_function() {
+    let mut bot = MockBot::builder()
+        .distribution_function(|_| None::<DistributionKey>)
+        .handler_tree(get_schema())
+        .update(MockMessageText::new().text("/echo hi"))
+        .build();
+
+    bot.dispatch_and_check_last_text("/echo hi").await;
+}
+
+fn get_capabilities_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let me = bot.get_me().await?;
+        let text = format!(
+            "inline={} business={} groups={} web_app={}",
+            me.supports_inline_queries,
+            me.can_connect_to_business,
+            me.can_join_groups,
+            me.has_main_web_app
+        );
+        bot.send_message(message.chat.id, text).await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_me_presets() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_capabilities_schema());
+    bot.me(MockMe::inline_bot());
+    bot.dispatch_and_check_last_text("inline=true business=false groups=false web_app=false")
+        .await;
+
+    bot.me(MockMe::business_bot()
+        .can_join_groups(true)
+        .has_main_web_app(true));
+    bot.dispatch_and_check_last_text("inline=false business=true groups=true web_app=true")
+        .await;
+}
+
+fn get_identity_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot, me: Me| async move {
+        bot.send_message(message.chat.id, format!("I am {}", me.username()))
+            .await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_dispatch_as() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_identity_schema());
+
+    bot.dispatch_as(MockMe::new().id(42).username("shop_eu_bot"))
+        .await;
+    let message = bot.get_responses().sent_messages.pop().unwrap();
+    assert_eq!(message.text(), Some("I am shop_eu_bot"));
+    assert_eq!(message.from.unwrap().id, UserId(42));
+
+    bot.dispatch_and_check_last_text(format!("I am {}", MockMe::USERNAME).as_str())
+        .await;
+    assert_eq!(bot.me.username(), MockMe::USERNAME);
+}
+
+fn bot_fixture() -> DefaultMockBot {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema());
+    bot.me(MockMe::new().first_name("Fixture"));
+    bot
+}
+
+#[tokio::test]
+async fn test_reset() {
+    let mut bot = bot_fixture();
+    bot.auto_respond("nothing", "/echo loop");
+    bot.dispatch_and_check_last_text("/echo hi").await;
+
+    bot.reset();
+
+    assert!(bot.get_responses().sent_messages.is_empty());
+    assert_eq!(bot.me.first_name, "Fixture");
+    bot.dispatch_and_check_last_text("/echo hi").await;
+    assert_eq!(bot.get_responses().sent_messages[0].id.0, 2);
+}
+
+#[tokio::test]
+async fn test_reset_keeps_config() {
+    let mut bot = bot_fixture();
+    bot.set_flood_limit(0);
+    bot.bot_star_balance(5);
+    bot.dispatch().await;
+    assert!(bot.get_responses().sent_messages.is_empty());
+
+    bot.reset();
+
+    assert_eq!(bot.get_bot_star_balance().amount, 5);
+    bot.dispatch().await;
+    assert!(bot.get_responses().sent_messages.is_empty());
+}
+
+#[tokio::test]
+async fn test_accumulate_responses() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo one"), get_schema());
+    bot.accumulate_responses(true);
+
+    bot.dispatch().await;
+    bot.update(MockMessageText::new().text("/echo two"));
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let texts: Vec<_> = responses
+        .sent_messages_text
+        .iter()
+        .map(|sent| (sent.message.text().unwrap(), sent.seq))
+        .collect();
+    assert_eq!(texts, vec![("/echo one", 1), ("/echo two", 2)]);
+
+    bot.clear_responses();
+    assert!(bot.get_responses().sent_messages.is_empty());
+    bot.dispatch_and_check_last_text("/echo two").await;
+    assert_eq!(bot.get_responses().sent_messages_text[0].seq, 1);
+
+    // Resetting forgets the responses, but keeps accumulating them
+    bot.reset();
+    bot.dispatch().await;
+    bot.dispatch().await;
+    assert_eq!(bot.get_responses().sent_messages.len(), 2);
+
+    bot.accumulate_responses(false);
+    bot.dispatch().await;
+    assert_eq!(bot.get_responses().sent_messages.len(), 1);
+}
+
+#[tokio::test]
+async fn test_bot_is_send() {
+    fn assert_send<T: Send>(_: &T) {}
+
+    let bot = bot_fixture();
+    assert_send(&bot);
+    tokio::spawn(async move {
+        let mut bot = bot;
+        bot.dispatch_and_check_last_text("/echo hi").await;
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_default_parse_mode_requester() {
+    let handler_tree = Update::filter_message().endpoint(
+        |message: Message, bot: DefaultParseMode<Bot>| async move {
+            bot.send_message(message.chat.id, "<b>Hi</b>").await?;
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+        },
+    );
+    let mut bot = MockBot::new(MockMessageText::new().text("hi"), handler_tree);
+    bot.requester(|bot| bot.parse_mode(ParseMode::Html));
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(
+        responses.sent_messages_text[0].bot_request.parse_mode,
+        Some(ParseMode::Html)
+    );
+}
+
+#[tokio::test]
+async fn test_throttled_requester() {
+    let handler_tree = Update::filter_message().endpoint(
+        |message: Message, bot: Throttle<CacheMe<Bot>>| async move {
+            bot.send_message(message.chat.id, "Hi").await?;
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+        },
+    );
+    let mut bot = MockBot::new(MockMessageText::new().text("hi"), handler_tree);
+    bot.requester(|bot| bot.cache_me().throttle(Limits::default()));
+
+    bot.dispatch_and_check_last_text("Hi").await;
+}
+
+#[tokio::test]
+async fn test_cached_get_me() {
+    let handler_tree =
+        Update::filter_message().endpoint(|message: Message, bot: CacheMe<Bot>| async move {
+            for _ in 0..3 {
+                let me = bot.get_me().await?;
+                bot.send_message(message.chat.id, me.username()).await?;
+            }
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+        });
+    let mut bot = MockBot::new(MockMessageText::new().text("hi"), handler_tree);
+    bot.requester(|bot| bot.cache_me());
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages.len(), 3);
+    assert_eq!(responses.fetched_me.len(), 1);
+    assert_eq!(responses.fetched_me[0].username(), MockMe::USERNAME);
+}
+
+#[tokio::test]
+async fn test_seed_messages() {
+    let handler_tree = Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        bot.edit_message_text(message.chat.id, MessageId(50), "Edited")
+            .await?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+    });
+    let mut bot = MockBot::new(MockMessageText::new().text("edit"), handler_tree);
+    let seeded = bot.seed_messages(vec![
+        MockMessageText::new().text("First").id(50),
+        MockMessageText::new().text("Second"),
+    ]);
+    assert_eq!(seeded[0].id, MessageId(50));
+    assert_ne!(seeded[1].id, MessageId(50));
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert!(responses.sent_messages.is_empty());
+    assert_eq!(responses.edited_messages_text[0].message.id, MessageId(50));
+    assert_eq!(
+        responses.edited_messages_text[0].message.text(),
+        Some("Edited")
+    );
+}
+
+fn get_chat_members_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let user_id = UserId(message.text().unwrap().parse()?);
+        let member = bot.get_chat_member(message.chat.id, user_id).await?;
+        let administrators = bot.get_chat_administrators(message.chat.id).await?;
+        let text = format!(
+            "{:?} {}",
+            member.status(),
+            administrators
+                .iter()
+                .map(|admin| admin.user.first_name.clone())
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        bot.send_message(message.chat.id, text).await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_chat_members() {
+    let chat = MockGroupChat::new().build();
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("1").chat(chat.clone()),
+        get_chat_members_schema(),
+    );
+    bot.add_chat_member(
+        chat.id.0,
+        MockUser::new().id(1).first_name("Member"),
+        ChatMemberKind::Member(Member { until_date: None }),
+    );
+    bot.add_chat_member(
+        chat.id.0,
+        MockUser::new().id(2).first_name("Owner"),
+        ChatMemberKind::Owner(Owner {
+            custom_title: None,
+            is_anonymous: false,
+        }),
+    );
+    bot.add_chat_member(
+        chat.id.0 - 1,
+        MockUser::new().id(3).first_name("Other chat"),
+        ChatMemberKind::Owner(Owner {
+            custom_title: None,
+            is_anonymous: false,
+        }),
+    );
+
+    bot.dispatch_and_check_last_text("Member Owner").await;
+
+    bot.add_chat_member(
+        chat.id.0,
+        MockUser::new().id(1).first_name("Member"),
+        ChatMemberKind::Left,
+    );
+    bot.dispatch_and_check_last_text("Left Owner").await;
+    assert_eq!(bot.get_responses().fetched_chat_members[0].user_id, 1);
+}
+
+#[tokio::test]
+async fn test_unknown_chat_member() {
+    let mut bot = MockBot::new(MockMessageText::new().text("1"), get_chat_members_schema());
+
+    bot.dispatch().await;
+
+    assert!(bot.get_responses().sent_messages.is_empty());
+    assert!(bot.get_responses().fetched_chat_members.is_empty());
+}
+
+#[tokio::test]
+async fn test_seed_file() {
+    let handler_tree = Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let file = bot.get_file(FileId("seeded_id".to_string())).await?;
+        let mut contents = vec![];
+        bot.download_file(&file.path, &mut contents).await?;
+        bot.send_document(message.chat.id, InputFile::file_id(file.id.clone()))
+            .caption(String::from_utf8(contents)?)
+            .await?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+    });
+    let mut bot = MockBot::new(MockMessageText::new().text("file"), handler_tree);
+    bot.seed_file("seeded_id", b"Seeded contents".to_vec(), "files/seeded.txt");
+
+    bot.dispatch_and_check_last_text("Seeded contents").await;
+
+    let responses = bot.get_responses();
+    let document = responses.sent_messages[0].document().unwrap();
+    assert_eq!(document.file.id.0, "seeded_id");
+    assert_eq!(document.file.size, 15);
+}
+
+fn get_file_path_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let file_id = message.document().unwrap().file.id.clone();
+        let text = match bot.get_file(file_id).await {
+            Ok(file) => {
+                let mut contents = vec![];
+                bot.download_file(&file.path, &mut contents).await?;
+                format!("{} {}", file.path, String::from_utf8(contents)?)
+            }
+            Err(error) => format!("{error:?}"),
+        };
+        bot.send_message(message.chat.id, text).await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_get_file_path_and_errors() {
+    let message = MockMessageDocument::new().file_id("doc_id".into());
+    let mut bot = MockBot::new(message, get_file_path_schema());
+    bot.set_file_path("doc_id", "documents/file_0.pdf");
+
+    bot.dispatch_and_check_last_text("documents/file_0.pdf Hello, world!")
+        .await;
+
+    let too_big = ApiError::Unknown("Bad Request: file is too big".to_string());
+    bot.set_get_file_error("doc_id", too_big);
+    bot.dispatch_and_check_last_text(r#"Api(Unknown("Bad Request: file is too big"))"#)
+        .await;
+
+    bot.set_get_file_error("doc_id", ApiError::WrongFileId);
+    bot.dispatch_and_check_last_text("Api(WrongFileId)").await;
+}
+
+#[tokio::test]
+async fn test_localized_errors() {
+    let message = MockMessageDocument::new().file_id("doc_id".into());
+    let mut bot = MockBot::new(message, get_file_path_schema());
+    bot.set_get_file_error("doc_id", ApiError::WrongFileId);
+    bot.localize_errors(|description| description.replace("Bad Request", "Ungültige Anfrage"));
+
+    let localized = ApiError::WrongFileId
+        .to_string()
+        .replace("Bad Request", "Ungültige Anfrage");
+    assert!(localized.starts_with("Ungültige Anfrage: "));
+    bot.dispatch_and_check_last_text(format!("Api(Unknown({localized:?}))"))
+        .await;
+
+    // The canonical description of the same error is still recognized without localization
+    bot.localize_errors(|description| description.to_string());
+    bot.dispatch_and_check_last_text("Api(WrongFileId)").await;
+}
+
+fn get_large_file_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let file_id = message.document().unwrap().file.id.clone();
+        if let Err(error) = bot.get_file(file_id.clone()).await {
+            bot.send_message(message.chat.id, format!("{error:?}"))
                 .await?;
+            // Too big to download, so the bot uploads its own copy
+            let copy = InputFile::memory("a".repeat(51 * 1024 * 1024)).file_name("copy.pdf");
+            if let Err(error) = bot.send_document(message.chat.id, copy).await {
+                bot.send_message(message.chat.id, format!("{error:?}"))
+                    .await?;
+            }
         }
-        AllCommands::EditCaption => {
-            let photo = InputFile::file_id("fileid".into());
-            let photo_message = bot.send_photo(msg.chat.id, photo).await?;
-            bot.edit_message_caption(msg.chat.id, photo_message.id)
-                .caption("edited")
-                .await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_file_size_limits_when_validating_like_telegram() {
+    let message = MockMessageDocument::new()
+        .file_id("big_id".into())
+        .file_size(25 * 1024 * 1024);
+    let mut bot = MockBot::new(message, get_large_file_schema());
+    bot.validate_like_telegram();
+
+    bot.dispatch().await;
+
+    let texts: Vec<_> = bot
+        .get_responses()
+        .sent_messages
+        .iter()
+        .map(|message| message.text().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        texts,
+        vec![
+            r#"Api(Unknown("Bad Request: file is too big"))"#,
+            "Api(RequestEntityTooLarge)"
+        ]
+    );
+    bot.assert_request("sendMessage", json_contains!({}));
+    bot.assert_request("sendDocument", json_contains!({}));
+    bot.assert_request("sendMessage", json_contains!({}));
+}
+
+fn get_chat_info_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        if message.text() == Some("unpin") {
+            bot.unpin_chat_message(message.chat.id).await?;
         }
-        AllCommands::Document => {
-            let document = InputFile::memory("somedata".to_string()).file_name("test.txt");
-            let document_message = bot
-                .send_document(msg.chat.id, document)
-                .caption("test")
-                .caption_entities(vec![MessageEntity::bold(0, 3)])
-                .reply_parameters(reply_options)
-                .await?;
-            let gotten_document = bot
-                .get_file(document_message.document().unwrap().file.id.clone())
-                .await?;
+        let chat = bot.get_chat(message.chat.id).await?;
+        let text = format!(
+            "{:?} {:?} {:?}",
+            chat.description(),
+            chat.pinned_message
+                .as_ref()
+                .and_then(|pinned| pinned.text().map(str::to_string)),
+            chat.permissions()
+        );
+        bot.send_message(message.chat.id, text).await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_seeded_chat_info() {
+    let chat = MockGroupChat::new().build();
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("info").chat(chat.clone()),
+        get_chat_info_schema(),
+    );
+    let seeded = bot.seed_messages(vec![
+        MockMessageText::new().text("Old rules").chat(chat.clone()),
+        MockMessageText::new().text("Rules").chat(chat.clone()),
+    ]);
+    bot.set_pinned_message(&seeded[0]);
+    bot.set_pinned_message(&seeded[1]);
+    bot.set_chat_description(chat.id.0, "A group");
+    bot.set_chat_permissions(chat.id.0, ChatPermissions::SEND_MESSAGES);
+
+    bot.dispatch_and_check_last_text(
+        r#"Some("A group") Some("Rules") Some(ChatPermissions(SEND_MESSAGES))"#,
+    )
+    .await;
+
+    bot.update(MockMessageText::new().text("unpin").chat(chat.clone()));
+    bot.dispatch_and_check_last_text(
+        r#"Some("A group") Some("Old rules") Some(ChatPermissions(SEND_MESSAGES))"#,
+    )
+    .await;
+    assert_eq!(bot.get_responses().fetched_chats.len(), 1);
+}
+
+#[tokio::test]
+async fn test_chat_info_without_seeding() {
+    let mut bot = MockBot::new(MockMessageText::new().text("info"), get_chat_info_schema());
+
+    bot.dispatch_and_check_last_text("None None None").await;
+}
+
+#[tokio::test]
+async fn test_seeded_chat_full_info() {
+    let chat = MockSupergroupChat::new().build();
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("info").chat(chat.clone()),
+        get_chat_info_schema(),
+    );
+    let seeded = bot.seed_messages(vec![MockMessageText::new()
+        .text("Rules")
+        .chat(chat.clone())]);
+    bot.set_chat_full_info(
+        MockChatFullInfoSupergroup::new()
+            .id(chat.id.0)
+            .description("A supergroup")
+            .permissions(ChatPermissions::SEND_POLLS)
+            .pinned_message(Box::new(seeded[0].clone()))
+            .build(),
+    );
+
+    bot.dispatch_and_check_last_text(
+        r#"Some("A supergroup") Some("Rules") Some(ChatPermissions(SEND_POLLS))"#,
+    )
+    .await;
+
+    bot.set_chat_permissions(chat.id.0, ChatPermissions::SEND_MESSAGES);
+    bot.update(MockMessageText::new().text("unpin").chat(chat.clone()));
+    bot.dispatch_and_check_last_text(
+        r#"Some("A supergroup") None Some(ChatPermissions(SEND_MESSAGES))"#,
+    )
+    .await;
+}
+
+fn get_chat_extras_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let chat = bot.get_chat(message.chat.id).await?;
+        let restricted = match &chat.kind {
+            ChatFullInfoKind::Private(private) => private.has_restricted_voice_and_video_messages,
+            ChatFullInfoKind::Public(_) => false,
+        };
+        let text = format!(
+            "{:?} {:?} {:?} {restricted}",
+            chat.emoji_status_custom_emoji_id.map(|id| id.0),
+            chat.emoji_status_expiration_date
+                .map(|date| date.timestamp()),
+            chat.profile_accent_color_id,
+        );
+        bot.send_message(message.chat.id, text).await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_seeded_chat_extras() {
+    let chat = MockPrivateChat::new().build();
+    let mut bot = MockBot::new(
+        MockMessageText::new().chat(chat.clone()),
+        get_chat_extras_schema(),
+    );
+
+    bot.dispatch_and_check_last_text("None None None false")
+        .await;
+
+    let expiration_date = DateTime::from_timestamp(1_900_000_000, 0).unwrap();
+    bot.set_chat_emoji_status(chat.id.0, "5368324170671202286", Some(expiration_date));
+    bot.set_chat_profile_accent_color(chat.id.0, 3);
+    bot.set_restricted_voice_and_video_messages(chat.id.0, true);
+    bot.dispatch_and_check_last_text(
+        r#"Some("5368324170671202286") Some(1900000000) Some(3) true"#,
+    )
+    .await;
+
+    // The extras are applied on top of the seeded full info
+    bot.set_chat_full_info(
+        MockChatFullInfoPrivate::new()
+            .id(chat.id.0)
+            .profile_accent_color_id(5)
+            .build(),
+    );
+    bot.dispatch_and_check_last_text(
+        r#"Some("5368324170671202286") Some(1900000000) Some(3) true"#,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_environment_with_polling_bot() {
+    let mut env = MockEnvironment::start().await;
+    env.spawn(async {
+        Dispatcher::builder(Bot::from_env(), get_schema())
+            .build()
+            .dispatch()
+            .await;
+    });
+
+    env.send_update(MockMessageText::new().text("/echo hello"));
+    let sent_messages = env.wait_for_sent_messages(1).await;
+    assert_eq!(sent_messages[0].text(), Some("/echo hello"));
+
+    env.send_update(MockMessageText::new().text("/echo again"));
+    let sent_messages = env.wait_for_sent_messages(2).await;
+    assert_eq!(sent_messages[1].text(), Some("/echo again"));
+    env.assert_request("sendMessage", json_contains!({"text": "/echo again"}));
+    env.stop().await;
+}
+
+#[tokio::test]
+async fn test_standalone_server() {
+    let server = MockServer::start().await;
+    let seeded = server.seed_messages(MockMessageText::new().text("Old"));
+
+    let bot = Bot::from_env();
+    let sent = bot
+        .send_message(seeded[0].chat.id, "New")
+        .reply_to(seeded[0].id)
+        .await
+        .unwrap();
+    bot.edit_message_text(sent.chat.id, sent.id, "Edited")
+        .await
+        .unwrap();
+
+    let responses = server.get_responses();
+    assert_eq!(responses.sent_messages[0].text(), Some("New"));
+    assert_eq!(
+        responses.edited_messages_text[0].message.text(),
+        Some("Edited")
+    );
+    assert_eq!(server.get_requests().len(), 2);
+    server.assert_request("sendMessage", json_contains!({"text": "New"}));
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_isolated_env() {
+    let mut bot = MockBot::builder()
+        .handler_tree(get_schema())
+        .update(MockMessageText::new().text("/echo isolated"))
+        .isolate_env()
+        .build();
+    // No other bot runs while this one exists, so nothing else changes the env meanwhile
+    std::env::set_var("TELOXIDE_API_URL", "http://isolated.invalid/");
+
+    bot.dispatch_and_check_last_text("/echo isolated").await;
+
+    assert_eq!(
+        std::env::var("TELOXIDE_API_URL").unwrap(),
+        "http://isolated.invalid/"
+    );
+}
+
+#[tokio::test]
+async fn test_isolated_standalone_server() {
+    let server = MockServer::start_isolated(MockMe::new()).await;
+
+    server
+        .bot
+        .send_message(ChatId(MockUser::ID as i64), "Hello!")
+        .await
+        .unwrap();
+
+    // The port of the server is new, so the env can't have its url from before
+    assert_ne!(
+        std::env::var("TELOXIDE_API_URL").ok(),
+        Some(server.bot.api_url().to_string())
+    );
+    assert_eq!(server.get_responses().sent_messages.len(), 1);
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_seeded_ids() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/mediagroup"), get_schema());
+    let sent_ids = |bot: &MockBot<_, _>| {
+        let responses = bot.get_responses();
+        let media_group = &responses.sent_media_group[0];
+        let audio = media_group.messages[0].audio().unwrap();
+        (
+            audio.file.id.clone(),
+            audio.file.unique_id.clone(),
+            media_group.messages[0].media_group_id().cloned(),
+        )
+    };
+
+    bot.seed_rng(42);
+    bot.dispatch().await;
+    let first_ids = sent_ids(&bot);
+
+    bot.reset();
+    bot.seed_rng(42);
+    bot.dispatch().await;
+    assert_eq!(sent_ids(&bot), first_ids);
+
+    bot.reset();
+    bot.seed_rng(43);
+    bot.dispatch().await;
+    assert_ne!(sent_ids(&bot), first_ids);
+}
+
+fn get_erased_dialogue_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>>
+{
+    dialogue::enter::<Update, ErasedStorage<State>, State, _>()
+        .branch(Update::filter_message().endpoint(handler_with_erased_state))
+}
+
+async fn handler_with_erased_state(
+    bot: Bot,
+    dialogue: ErasedDialogue,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.send_message(msg.chat.id, msg.text().unwrap()).await?;
+    dialogue.update(State::NotStart).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_erased_state() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("test"),
+        get_erased_dialogue_schema(),
+    );
+    let storage: MyStorage = SqliteStorage::open(":memory:", Json).await.unwrap().erase();
+    bot.dependencies(deps![storage]);
+
+    // This .dispatch is important?..
+    bot.dispatch().await;
+    bot.dispatch_and_check_state(State::NotStart).await;
+}
+
+#[tokio::test]
+async fn test_storage_chaos() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("test"),
+        get_erased_dialogue_schema(),
+    );
+    let storage: MyStorage = InMemStorage::<State>::new().erase();
+    bot.dependencies(deps![storage]);
+    // Otherwise the dialogue would be created with an update, that fails
+    bot.set_state(State::Start).await;
+
+    bot.storage_chaos::<State>(StorageChaos::new().fail_update(true))
+        .await;
+    bot.dispatch_and_check_last_text("test").await;
+    bot.assert_state(State::Start).await;
+
+    bot.storage_chaos::<State>(StorageChaos::new().fail_get(true))
+        .await;
+    bot.dispatch().await;
+    assert!(bot.get_responses().sent_messages.is_empty());
+
+    bot.storage_chaos::<State>(StorageChaos::new().delay(Duration::from_millis(300)))
+        .await;
+    let started = std::time::Instant::now();
+    bot.dispatch_and_check_state(State::NotStart).await;
+    // Getting and updating the dialogue are both slow
+    assert!(started.elapsed() >= Duration::from_millis(600));
+}
+
+//
+//
+//
+
+#[derive(BotCommands, Clone)]
+#[command(rename_rule = "lowercase")]
+pub enum AllCommands {
+    #[command()]
+    Echo,
+    #[command()]
+    Edit,
+    #[command()]
+    EditUnchanged,
+    #[command()]
+    Delete,
+    #[command()]
+    DeleteBatch,
+    #[command()]
+    EditReplyMarkup,
+    #[command()]
+    Photo,
+    #[command()]
+    Video,
+    #[command()]
+    Audio,
+    #[command()]
+    Voice,
+    #[command()]
+    VideoNote,
+    #[command()]
+    Document,
+    #[command()]
+    Animation,
+    #[command()]
+    Location,
+    #[command()]
+    Venue,
+    #[command()]
+    Contact,
+    #[command()]
+    Dice,
+    #[command()]
+    Poll,
+    #[command()]
+    Sticker,
+    #[command()]
+    MediaGroup,
+    #[command()]
+    Invoice,
+    #[command()]
+    EditCaption,
+    #[command()]
+    PinMessage,
+    #[command()]
+    ForwardMessage,
+    #[command()]
+    CopyMessage,
+    #[command()]
+    Ban,
+    #[command()]
+    Restrict,
+    #[command()]
+    ChatAction,
+    #[command()]
+    SetMessageReaction,
+    #[command()]
+    SetMyCommands,
+    #[command()]
+    BusinessAccount,
+    #[command()]
+    Gifts,
+    #[command()]
+    RequestPeers,
+    #[command()]
+    ReplyKeyboard,
+    #[command()]
+    ForceReply,
+    #[command()]
+    SwitchInline,
+    #[command()]
+    SpecialButtons,
+    #[command()]
+    Entities,
+    #[command()]
+    EditLiveLocation(i32),
+    #[command()]
+    InvalidButton(String),
+    #[command()]
+    Panic,
+}
+
+async fn raw_bot_request(
+    bot: &Bot,
+    method: &str,
+    form: reqwest::multipart::Form,
+) -> Result<serde_json::Value, reqwest::Error> {
+    // Bot API 9.0 business methods don't have payloads in teloxide yet
+    let url = bot
+        .api_url()
+        .join(&format!("/bot{}/{method}", bot.token()))
+        .unwrap();
+    let response = bot.client().post(url).multipart(form).send().await?;
+    Ok(response.json::<serde_json::Value>().await?["result"].take())
+}
+
+async fn raw_json_bot_request(
+    bot: &Bot,
+    method: &str,
+    body: serde_json::Value,
+) -> Result<serde_json::Value, reqwest::Error> {
+    let url = bot
+        .api_url()
+        .join(&format!("/bot{}/{method}", bot.token()))
+        .unwrap();
+    let response = bot.client().post(url).json(&body).send().await?;
+    Ok(response.json::<serde_json::Value>().await?["result"].take())
+}
+
+async fn handler(
+    bot: Bot,
+    msg: Message,
+    cmd: AllCommands,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let sent_message = bot.send_message(msg.chat.id, msg.text().unwrap()).await?;
+    assert_eq!(msg.text().unwrap(), sent_message.text().unwrap()); // The message actually made it through!
+    let reply_options = ReplyParameters::new(msg.id);
+    let link_preview_options = LinkPreviewOptions {
+        is_disabled: true,
+        url: None,
+        prefer_small_media: false,
+        prefer_large_media: false,
+        show_above_text: false,
+    };
+    match cmd {
+        AllCommands::Echo => {}
+        AllCommands::Edit => {
+            bot.edit_message_text(msg.chat.id, sent_message.id, "edited")
+                .link_preview_options(link_preview_options)
+                .await?;
+        }
+        AllCommands::EditUnchanged => {
+            bot.edit_message_text(msg.chat.id, sent_message.id, msg.text().unwrap())
+                .link_preview_options(link_preview_options)
+                .await?;
+        }
+        AllCommands::Delete => {
+            bot.delete_message(msg.chat.id, sent_message.id).await?;
+        }
+        AllCommands::DeleteBatch => {
+            bot.delete_messages(msg.chat.id, vec![sent_message.id, MessageId(404)])
+                .await?;
+        }
+        AllCommands::EditReplyMarkup => {
+            bot.edit_message_reply_markup(msg.chat.id, sent_message.id)
+                .reply_markup(InlineKeyboardMarkup::new(vec![vec![
+                    InlineKeyboardButton::callback("test", "test"),
+                ]]))
+                .await?;
+        }
+        AllCommands::Entities => {
+            bot.send_message(msg.chat.id, "👋 Hello, world!")
+                .entities(vec![
+                    MessageEntity::bold(3, 5),
+                    MessageEntity::italic(10, 5),
+                ])
+                .await?;
+        }
+        AllCommands::Photo => {
+            let photo = InputFile::memory("somedata".to_string()).file_name("test.jpg");
+            bot.send_photo(msg.chat.id, photo)
+                .caption("test")
+                .caption_entities(vec![MessageEntity::bold(0, 3)])
+                .reply_parameters(reply_options)
+                .await?;
+        }
+        AllCommands::Video => {
+            let video = InputFile::memory("somedata".to_string()).file_name("test.mp4");
+            let thumbnail = InputFile::memory("thumbnail".to_string()).file_name("thumb.jpg");
+            bot.send_video(msg.chat.id, video)
+                .thumbnail(thumbnail)
+                .caption("test")
+                .caption_entities(vec![MessageEntity::bold(0, 3)])
+                .has_spoiler(true)
+                .reply_parameters(reply_options)
+                .await?;
+        }
+        AllCommands::Audio => {
+            let audio = InputFile::memory("somedata".to_string()).file_name("test.mp3");
+            bot.send_audio(msg.chat.id, audio)
+                .caption("test")
+                .caption_entities(vec![MessageEntity::bold(0, 3)])
+                .reply_parameters(reply_options)
+                .await?;
+        }
+        AllCommands::Voice => {
+            let voice = InputFile::memory("somedata".to_string()).file_name("test.mp3");
+            bot.send_voice(msg.chat.id, voice)
+                .caption("test")
+                .caption_entities(vec![MessageEntity::bold(0, 3)])
+                .reply_parameters(reply_options)
+                .await?;
+        }
+        AllCommands::VideoNote => {
+            let video_note = InputFile::memory("somedata".to_string()).file_name("test.mp4");
+            bot.send_video_note(msg.chat.id, video_note)
+                .reply_parameters(reply_options)
+                .await?;
+        }
+        AllCommands::EditCaption => {
+            let photo = InputFile::file_id("fileid".into());
+            let photo_message = bot.send_photo(msg.chat.id, photo).await?;
+            bot.edit_message_caption(msg.chat.id, photo_message.id)
+                .caption("edited")
+                .await?;
+        }
+        AllCommands::Document => {
+            let document = InputFile::memory("somedata".to_string()).file_name("test.txt");
+            let document_message = bot
+                .send_document(msg.chat.id, document)
+                .caption("test")
+                .caption_entities(vec![MessageEntity::bold(0, 3)])
+                .reply_parameters(reply_options)
+                .await?;
+            let gotten_document = bot
+                .get_file(document_message.document().unwrap().file.id.clone())
+                .await?;
+            assert!(
+                gotten_document.meta.unique_id
+                    == document_message.document().unwrap().file.unique_id
+            );
+            let mut dest = tokio::fs::File::create("test.txt").await?;
+
+            bot.download_file(&gotten_document.path, &mut dest).await?;
+            assert!(tokio::fs::read_to_string("test.txt").await.is_ok());
+            tokio::fs::remove_file("test.txt").await?;
+        }
+        AllCommands::Animation => {
+            let animation = InputFile::memory("somedata".to_string()).file_name("animation.mp4");
+            bot.send_animation(msg.chat.id, animation).await?;
+        }
+        AllCommands::Location => {
+            bot.send_location(msg.chat.id, 1.0, 1.0)
+                .live_period(60.into())
+                .reply_parameters(reply_options)
+                .await?;
+        }
+        AllCommands::EditLiveLocation(message_id) => {
+            bot.edit_message_live_location(msg.chat.id, MessageId(message_id), 2.0, 2.0)
+                .await?;
+        }
+        AllCommands::Venue => {
+            bot.send_venue(msg.chat.id, 1.0, 1.0, "test", "test")
+                .foursquare_id("4sq_id")
+                .foursquare_type("food/icecream")
+                .google_place_id("google_id")
+                .google_place_type("cafe")
+                .reply_parameters(reply_options)
+                .await?;
+        }
+        AllCommands::Contact => {
+            bot.send_contact(msg.chat.id, "123456789", "test")
+                .last_name("last")
+                .vcard("BEGIN:VCARD\nEND:VCARD")
+                .reply_parameters(reply_options)
+                .await?;
+        }
+        AllCommands::Dice => {
+            bot.send_dice(msg.chat.id).await?;
+        }
+        AllCommands::Poll => {
+            bot.send_poll(
+                msg.chat.id,
+                "what is test",
+                vec!["test".to_string().into(), "not test".to_string().into()],
+            )
+            .type_(PollType::Quiz)
+            .reply_parameters(reply_options)
+            .explanation("because test")
+            .correct_option_id(0)
+            .await?;
+        }
+        AllCommands::Sticker => {
+            let sticker = InputFile::memory("somedata".to_string()).file_name("test.webp");
+            bot.send_sticker(msg.chat.id, sticker)
+                .reply_parameters(reply_options)
+                .await?;
+        }
+        AllCommands::MediaGroup => {
+            let audio1 = InputFile::memory("somedata".to_string()).file_name("audio1.mp3");
+            let audio2 = InputFile::memory("somedata2".to_string()).file_name("audio2.mp3");
+            let media_group = vec![
+                InputMedia::Audio(InputMediaAudio::new(audio1.clone()).caption("test")),
+                InputMedia::Audio(InputMediaAudio::new(audio2.clone())),
+            ];
+            bot.send_media_group(msg.chat.id, media_group)
+                .reply_parameters(reply_options.clone())
+                .await?;
+
+            let document1 = InputFile::memory("somedata".to_string()).file_name("document1.txt");
+            let document2 = InputFile::memory("somedata2".to_string()).file_name("document2.txt");
+            let media_group = vec![
+                InputMedia::Document(InputMediaDocument::new(document1.clone()).caption("test")),
+                InputMedia::Document(InputMediaDocument::new(document2.clone())),
+            ];
+            bot.send_media_group(msg.chat.id, media_group)
+                .reply_parameters(reply_options.clone())
+                .await?;
+
+            let photo1 = InputFile::memory("somedata".to_string());
+            let photo2 = InputFile::memory("somedata2".to_string());
+            let media_group = vec![
+                InputMedia::Photo(InputMediaPhoto::new(photo1.clone()).caption("test")),
+                InputMedia::Photo(InputMediaPhoto::new(photo2.clone())),
+            ];
+            bot.send_media_group(msg.chat.id, media_group)
+                .reply_parameters(reply_options.clone())
+                .await?;
+
+            let video1 = InputFile::memory("somedata".to_string()).file_name("video1.mp4");
+            let video2 = InputFile::memory("somedata2".to_string()).file_name("video2.mp4");
+            let media_group = vec![
+                InputMedia::Video(InputMediaVideo::new(video1.clone()).caption("test")),
+                InputMedia::Video(InputMediaVideo::new(video2.clone())),
+            ];
+            bot.send_media_group(msg.chat.id, media_group)
+                .reply_parameters(reply_options)
+                .await?;
+        }
+        AllCommands::PinMessage => {
+            bot.pin_chat_message(msg.chat.id, sent_message.id).await?;
+            bot.unpin_chat_message(msg.chat.id).await?;
+            bot.unpin_all_chat_messages(msg.chat.id).await?;
+        }
+        AllCommands::ForwardMessage => {
+            bot.forward_message(msg.chat.id, msg.chat.id, sent_message.id)
+                .await?;
+        }
+        AllCommands::CopyMessage => {
+            let document = InputFile::memory("somedata".to_string()).file_name("test.txt");
+            let document_message = bot.send_document(msg.chat.id, document).await?;
+            bot.copy_message(msg.chat.id, msg.chat.id, document_message.id)
+                .caption("test")
+                .reply_markup(InlineKeyboardMarkup::new(vec![vec![
+                    InlineKeyboardButton::callback("test", "test"),
+                ]]))
+                .await?;
+        }
+        AllCommands::Ban => {
+            bot.ban_chat_member(msg.chat.id, msg.from.clone().unwrap().id)
+                .revoke_messages(true)
+                .await?;
+            // Test revoking messages
+            let result = bot.delete_message(msg.chat.id, msg.id).await;
+            assert!(result.is_err());
+            bot.unban_chat_member(msg.chat.id, msg.from.unwrap().id)
+                .await?;
+        }
+        AllCommands::Restrict => {
+            bot.restrict_chat_member(msg.chat.id, msg.from.unwrap().id, ChatPermissions::empty())
+                .await?;
+        }
+        AllCommands::ChatAction => {
+            bot.send_chat_action(msg.chat.id, ChatAction::Typing)
+                .await?;
+        }
+        AllCommands::SetMessageReaction => {
+            bot.set_message_reaction(msg.chat.id, msg.id)
+                .reaction(vec![ReactionType::Emoji {
+                    emoji: "👍".to_owned(),
+                }])
+                .await?;
+        }
+        AllCommands::SetMyCommands => {
+            bot.set_my_commands(vec![BotCommand {
+                command: String::from("test"),
+                description: String::from("test"),
+            }])
+            .await?;
+        }
+        AllCommands::BusinessAccount => {
+            let business_connection_id = "business_connection_id";
+            raw_json_bot_request(
+                &bot,
+                "ReadBusinessMessage",
+                serde_json::json!({
+                    "business_connection_id": business_connection_id,
+                    "chat_id": msg.chat.id,
+                    "message_id": msg.id.0,
+                }),
+            )
+            .await?;
+            raw_json_bot_request(
+                &bot,
+                "DeleteBusinessMessages",
+                serde_json::json!({
+                    "business_connection_id": business_connection_id,
+                    "message_ids": [sent_message.id.0],
+                }),
+            )
+            .await?;
+            raw_json_bot_request(
+                &bot,
+                "SetBusinessAccountName",
+                serde_json::json!({
+                    "business_connection_id": business_connection_id,
+                    "first_name": "Business",
+                }),
+            )
+            .await?;
+            raw_json_bot_request(
+                &bot,
+                "SetBusinessAccountBio",
+                serde_json::json!({
+                    "business_connection_id": business_connection_id,
+                    "bio": "Open 24/7",
+                }),
+            )
+            .await?;
+            let form = reqwest::multipart::Form::new()
+                .text("business_connection_id", business_connection_id)
+                .text("photo", r#"{"type":"static","photo":"attach://avatar"}"#)
+                .part(
+                    "avatar",
+                    reqwest::multipart::Part::bytes(b"photo".to_vec()).file_name("avatar.jpg"),
+                );
+            raw_bot_request(&bot, "SetBusinessAccountProfilePhoto", form).await?;
+
+            let star_balance = raw_json_bot_request(
+                &bot,
+                "GetBusinessAccountStarBalance",
+                serde_json::json!({ "business_connection_id": business_connection_id }),
+            )
+            .await?;
+            let gifts = raw_json_bot_request(
+                &bot,
+                "GetBusinessAccountGifts",
+                serde_json::json!({
+                    "business_connection_id": business_connection_id,
+                    "exclude_unsaved": true,
+                }),
+            )
+            .await?;
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "{} {}",
+                    star_balance["amount"], gifts["gifts"][0]["owned_gift_id"]
+                ),
+            )
+            .await?;
+        }
+        AllCommands::Gifts => {
+            let business_connection_id = "business_connection_id";
+            raw_json_bot_request(
+                &bot,
+                "ConvertGiftToStars",
+                serde_json::json!({
+                    "business_connection_id": business_connection_id,
+                    "owned_gift_id": "convertible",
+                }),
+            )
+            .await?;
+            raw_json_bot_request(
+                &bot,
+                "UpgradeGift",
+                serde_json::json!({
+                    "business_connection_id": business_connection_id,
+                    "owned_gift_id": "upgradable",
+                    "star_count": 25,
+                }),
+            )
+            .await?;
+            let gifts = raw_json_bot_request(
+                &bot,
+                "GetBusinessAccountGifts",
+                serde_json::json!({ "business_connection_id": business_connection_id }),
+            )
+            .await?;
+            bot.send_message(msg.chat.id, gifts["gifts"][0]["type"].to_string())
+                .await?;
+            raw_json_bot_request(
+                &bot,
+                "TransferGift",
+                serde_json::json!({
+                    "business_connection_id": business_connection_id,
+                    "owned_gift_id": "upgradable",
+                    "new_owner_chat_id": msg.chat.id,
+                }),
+            )
+            .await?;
+
+            let star_balance = raw_json_bot_request(
+                &bot,
+                "GetBusinessAccountStarBalance",
+                serde_json::json!({ "business_connection_id": business_connection_id }),
+            )
+            .await?;
+            let gifts = raw_json_bot_request(
+                &bot,
+                "GetBusinessAccountGifts",
+                serde_json::json!({ "business_connection_id": business_connection_id }),
+            )
+            .await?;
+            bot.send_message(
+                msg.chat.id,
+                format!("{} {}", star_balance["amount"], gifts["total_count"]),
+            )
+            .await?;
+        }
+        AllCommands::RequestPeers => {
+            let keyboard = KeyboardMarkup::new(vec![vec![
+                KeyboardButton::new("Pick users").request(ButtonRequest::RequestUsers(
+                    KeyboardButtonRequestUsers::new(RequestId(7)),
+                )),
+                KeyboardButton::new("Pick a channel").request(ButtonRequest::RequestChat(
+                    KeyboardButtonRequestChat::new(RequestId(8), true),
+                )),
+            ]]);
+            bot.send_message(msg.chat.id, "Who?")
+                .reply_markup(keyboard)
+                .await?;
+        }
+        AllCommands::ReplyKeyboard => {
+            let keyboard = KeyboardMarkup::new(vec![
+                vec![KeyboardButton::new("Yes"), KeyboardButton::new("No")],
+                vec![KeyboardButton::new("Cancel")],
+            ])
+            .one_time_keyboard()
+            .input_field_placeholder("Answer".to_string())
+            .selective();
+            bot.send_message(msg.chat.id, "Are you sure?")
+                .reply_markup(keyboard)
+                .await?;
+        }
+        AllCommands::ForceReply => {
+            bot.send_message(msg.chat.id, "What is your name?")
+                .reply_markup(ForceReply::new().input_field_placeholder("Name".to_string()))
+                .await?;
+        }
+        AllCommands::SwitchInline => {
+            bot.send_message(msg.chat.id, "Share it!")
+                .reply_markup(InlineKeyboardMarkup::new(vec![vec![
+                    InlineKeyboardButton::switch_inline_query("Share", "cats"),
+                    InlineKeyboardButton::switch_inline_query_current_chat("Here", "dogs"),
+                ]]))
+                .await?;
+        }
+        AllCommands::SpecialButtons => {
+            bot.send_message(msg.chat.id, "Your promo code")
+                .reply_markup(InlineKeyboardMarkup::new(vec![vec![
+                    InlineKeyboardButton::copy_text_button(
+                        "Copy",
+                        CopyTextButton {
+                            text: "PROMO".to_string(),
+                        },
+                    ),
+                    InlineKeyboardButton::login(
+                        "Log in",
+                        LoginUrl {
+                            url: "https://example.com/login".parse().unwrap(),
+                            forward_text: None,
+                            bot_username: None,
+                            request_write_access: Some(true),
+                        },
+                    ),
+                ]]))
+                .await?;
+        }
+        AllCommands::InvalidButton(kind) => {
+            let button = match kind.as_str() {
+                "copy_text" => InlineKeyboardButton::copy_text_button(
+                    "Copy",
+                    CopyTextButton {
+                        text: "a".repeat(257),
+                    },
+                ),
+                _ => InlineKeyboardButton::login(
+                    "Log in",
+                    LoginUrl {
+                        url: "http://example.com/login".parse().unwrap(),
+                        forward_text: None,
+                        bot_username: None,
+                        request_write_access: None,
+                    },
+                ),
+            };
+            bot.send_message(msg.chat.id, "Invalid button")
+                .reply_markup(InlineKeyboardMarkup::new(vec![vec![button]]))
+                .await?;
+        }
+        AllCommands::Panic => {
+            // This message id does not exist
+            bot.send_message(msg.chat.id, "test")
+                .reply_to(MessageId(344382918))
+                .await?;
+        }
+        AllCommands::Invoice => {
+            bot.send_invoice(
+                msg.chat.id,
+                "Absolutely Nothing",
+                "Demo",
+                "test_payload",
+                "XTR",
+                vec![LabeledPrice {
+                    label: "Stars".into(),
+                    amount: 1,
+                }],
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn callback_handler(
+    bot: Bot,
+    call: CallbackQuery,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    bot.answer_callback_query(call.id)
+        .text(call.data.unwrap())
+        .await?;
+    Ok(())
+}
+
+async fn inline_query_handler(
+    bot: Bot,
+    query: InlineQuery,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let article = InlineQueryResultArticle::new(
+        "1",
+        query.query.clone(),
+        InputMessageContent::Text(InputMessageContentText::new(query.query)),
+    );
+    bot.answer_inline_query(query.id, vec![article.into()])
+        .await?;
+    Ok(())
+}
+
+async fn passport_data_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let element = &msg.passport_data().unwrap().data[0];
+    bot.set_passport_data_errors(
+        msg.from.as_ref().unwrap().id,
+        vec![PassportElementError::new(
+            "Please use another email",
+            PassportElementErrorKind::Unspecified(PassportElementErrorUnspecified::new(
+                PassportElementErrorUnspecifiedType::Unspecified,
+                element.hash.clone(),
+            )),
+        )],
+    )
+    .await?;
+    Ok(())
+}
+
+async fn shared_peers_handler(
+    bot: Bot,
+    msg: Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let text = match &msg.kind {
+        MessageKind::UsersShared(shared) => format!(
+            "{} users for {}",
+            shared.users_shared.users.len(),
+            shared.users_shared.request_id
+        ),
+        MessageKind::ChatShared(shared) => format!(
+            "{} for {}",
+            shared.chat_shared.chat_id, shared.chat_shared.request_id
+        ),
+        _ => unreachable!(),
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+fn get_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    dptree::entry()
+        .branch(
+            Update::filter_message()
+                .filter_command::<AllCommands>()
+                .endpoint(handler),
+        )
+        .branch(
+            Update::filter_message()
+                .filter(|msg: Message| msg.passport_data().is_some())
+                .endpoint(passport_data_handler),
+        )
+        .branch(
+            Update::filter_message()
+                .filter(|msg: Message| {
+                    matches!(
+                        msg.kind,
+                        MessageKind::UsersShared(_) | MessageKind::ChatShared(_)
+                    )
+                })
+                .endpoint(shared_peers_handler),
+        )
+        .branch(
+            Update::filter_edited_message()
+                .filter_command::<AllCommands>()
+                .branch(case![AllCommands::ForwardMessage].endpoint(handler)),
+        )
+        .branch(
+            Update::filter_channel_post()
+                .filter_command::<AllCommands>()
+                .endpoint(handler),
+        )
+        .branch(Update::filter_callback_query().endpoint(callback_handler))
+        .branch(Update::filter_inline_query().endpoint(inline_query_handler))
+}
+
+#[tokio::test]
+async fn test_echo() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo echo"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_response = bot.get_responses().sent_messages.pop().unwrap();
+
+    assert_eq!(last_response.text(), Some("/echo echo"));
+}
+
+#[tokio::test]
+async fn test_check_last_text_with_matchers() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo 12345"), get_schema());
+
+    bot.dispatch_and_check_last_text(Regex::new(r"^/echo \d+$").unwrap())
+        .await;
+    bot.dispatch_and_check_last_text(|text: &str| text.ends_with("345"))
+        .await;
+    bot.dispatch_and_check_last_text(String::from("/echo 12345"))
+        .await;
+}
+
+#[tokio::test]
+async fn test_check_last_text_with_string_reference() {
+    let mut bot = MockBot::new(MockMessageText::new().text("test"), get_dialogue_schema());
+    bot.dependencies(deps![InMemStorage::<State>::new()]);
+    let echo = "test".to_string();
+    let not_start = "Not start!".to_string();
+
+    bot.dispatch_and_check_last_text(&echo).await;
+    bot.dispatch_and_check_last_text_and_state(&not_start, State::Start)
+        .await;
+    bot.dispatch_and_check_last_text_and_state_discriminant(&echo, State::NotStart)
+        .await;
+}
+
+#[tokio::test]
+#[should_panic(expected = "Texts are not equal!")]
+async fn test_check_last_text_with_wrong_regex() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo echo"), get_schema());
+
+    bot.dispatch_and_check_last_text(Regex::new(r"^/echo \d+$").unwrap())
+        .await;
+}
+
+#[tokio::test]
+async fn test_responses_summary() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/entities"), get_schema());
+    assert_eq!(bot.get_responses().summary(), "No requests were sent");
+
+    bot.dispatch().await;
+
+    // The private chat has the id of the user
+    let chat_id = MockUser::ID;
+    assert_eq!(
+        bot.get_responses().to_string(),
+        format!(
+            "sendMessage chat={chat_id}: \"/entities\"\n\
+             sendMessage chat={chat_id}: \"👋 Hello, world!\""
+        )
+    );
+
+    bot.update(MockMessageText::new().text(format!("/echo {}", "a".repeat(100))));
+    bot.dispatch().await;
+
+    let summary = bot.get_responses().summary();
+    assert!(summary.ends_with(&format!("{}...\"", "a".repeat(54))));
+}
+
+#[tokio::test]
+async fn test_find_messages() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/edit"), get_schema());
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let texts: Vec<_> = responses
+        .find_messages(|message| message.text().is_some_and(|text| text.contains("edit")))
+        .into_iter()
+        .map(|message| message.text().unwrap())
+        .collect();
+    assert_eq!(texts, vec!["/edit", "edited"]);
+    assert_eq!(responses.messages_in_chat(MockUser::ID as i64).len(), 2);
+    assert!(responses.messages_in_chat(-100).is_empty());
+
+    // The fake server has the sent message only as it is after the edit, and the user's message
+    let stored: Vec<_> = bot
+        .messages_in_chat(MockUser::ID as i64)
+        .into_iter()
+        .map(|message| message.text().unwrap().to_string())
+        .collect();
+    assert_eq!(stored, vec!["/edit", "edited"]);
+    assert!(bot
+        .find_messages(|message| message.from.as_ref().is_some_and(|from| from.is_bot))
+        .iter()
+        .all(|message| message.text() == Some("edited")));
+}
+
+#[tokio::test]
+async fn test_responses_json() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/photo"), get_schema());
+    bot.dispatch().await;
+    let responses = bot.get_responses();
+    let path = std::env::temp_dir().join("teloxide_tests_responses.json");
+
+    responses.write_json(&path).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let read_responses = Responses::read_json(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        json["sent_messages_photo"][0]["bot_request"]["caption"],
+        "test"
+    );
+    // Dates are stored as unix time, just like in the Bot API, so only the rest is compared
+    assert_eq!(read_responses.summary(), responses.summary());
+    let sent_photo = &read_responses.sent_messages_photo[0];
+    assert_eq!(
+        sent_photo.message.id,
+        responses.sent_messages_photo[0].message.id
+    );
+    assert_eq!(sent_photo.bot_request.caption, Some("test".to_string()));
+    assert_eq!(sent_photo.bot_request.file_name, "test.jpg");
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_panic() {
+    // Nothing else should fail because this panics
+    let bot = MockBot::new(MockMessageText::new().text("/panic"), get_schema());
+
+    // To actually keep the bot in scope
+    if true {
+        panic!("Expected panic");
+    }
+
+    drop(bot);
+}
+
+#[tokio::test]
+async fn test_handler_panic_diagnostics() {
+    let handler_tree = Update::filter_message().endpoint(|message: Message| async move {
+        if message.text() == Some("/crash") {
+            panic!("Unexpected command");
+        }
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+    });
+    let mut bot = MockBot::new(
+        vec![
+            MockMessageText::new().text("hi"),
+            MockMessageText::new().text("/crash"),
+        ],
+        handler_tree,
+    );
+
+    let error = tokio::spawn(async move { bot.dispatch().await })
+        .await
+        .unwrap_err();
+
+    let payload = error.into_panic();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(message.starts_with("The handler panicked at teloxide_tests/src/tests.rs:"));
+    assert!(message.contains("Unexpected command"));
+    assert!(message.contains("\"/crash\""));
+    assert!(!message.contains("\"hi\""));
+    assert!(message.contains("Backtrace:"));
+}
+
+pub struct MyErrorHandler {
+    errors: Arc<RwLock<Vec<String>>>,
+}
+
+impl MyErrorHandler {
+    pub fn new() -> Self {
+        Self {
+            errors: Arc::new(RwLock::new(vec![])),
+        }
+    }
+
+    pub fn errors(&self) -> Vec<String> {
+        self.errors.read().unwrap().clone()
+    }
+}
+
+impl<E> ErrorHandler<E> for MyErrorHandler
+where
+    E: std::fmt::Debug + Display + 'static + Sync + Send,
+{
+    fn handle_error(self: Arc<Self>, error: E) -> BoxFuture<'static, ()> {
+        thread::spawn(|| {
+            respond_to_error();
+        })
+        .join()
+        .unwrap();
+
+        self.errors.write().unwrap().push(format!("{error:?}"));
+        Box::pin(async {})
+    }
+}
+
+#[tokio::main]
+async fn respond_to_error() {
+    let bot = Bot::from_env();
+    bot.send_message(ChatId(MockUser::ID as i64), "Error detected!")
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_error_handler() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/panic"), get_schema());
+    let error_handler = Arc::new(MyErrorHandler::new());
+    bot.error_handler(error_handler.clone());
+
+    bot.dispatch_and_check_last_text("Error detected!").await;
+
+    let errors = error_handler.errors();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("Message not found"));
+}
+
+#[tokio::test]
+async fn test_no_updates() {
+    let empty: Vec<MockMessageDice> = vec![];
+    let mut bot = MockBot::new(empty, get_schema());
+
+    // This shouldn't panic
+    bot.dispatch().await;
+
+    // Just to test that everything is fine
+    bot.update(MockMessageText::new().text("/echo echo"));
+    bot.dispatch_and_check_last_text("/echo echo").await;
+}
+
+#[tokio::test]
+async fn test_lenient_replies() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/panic"), get_schema());
+    bot.set_lenient_replies(true);
+
+    bot.dispatch().await;
+
+    let sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let placeholder = sent_message.reply_to_message().unwrap();
+    assert_eq!(placeholder.id, MessageId(344382918));
+    assert_eq!(placeholder.chat.id, sent_message.chat.id);
+}
+
+fn get_reply_without_message_schema(
+) -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let reply_parameters =
+            ReplyParameters::new(MessageId(344382918)).allow_sending_without_reply();
+        bot.send_message(message.chat.id, "test")
+            .reply_parameters(reply_parameters)
+            .await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_allow_sending_without_reply() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_reply_without_message_schema());
+
+    bot.dispatch().await;
+
+    let sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    assert!(sent_message.reply_to_message().is_none());
+}
+
+#[tokio::test]
+async fn test_send_photo() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/photo"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let last_sent_photo = bot.get_responses().sent_messages_photo.pop().unwrap();
+    assert_eq!(last_sent_message.caption(), Some("test"));
+    assert_eq!(
+        last_sent_message.reply_to_message().unwrap().text(),
+        Some("/photo")
+    );
+    assert_eq!(last_sent_message.caption_entities().unwrap().len(), 1);
+    assert_eq!(last_sent_photo.bot_request.file_name, "test.jpg");
+    assert_eq!(last_sent_photo.bot_request.file_data, "somedata");
+}
+
+fn get_photo_with_unknown_fields_schema(
+) -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        // teloxide sends only the fields the fake server knows
+        bot.send_photo(message.chat.id, InputFile::file_id("some_file_id".into()))
+            .caption("test")
+            .disable_notification(true)
+            .await?;
+        let form = reqwest::multipart::Form::new()
+            .text("chat_id", message.chat.id.to_string())
+            .text("photo", "some_file_id")
+            .text("caption", "test")
+            .text("disable_notification", "sometimes")
+            .text("unknown_field", "value");
+        raw_bot_request(&bot, "SendPhoto", form).await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_dropped_fields() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        get_photo_with_unknown_fields_schema(),
+    );
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages_photo.len(), 2);
+    assert_eq!(
+        responses.sent_messages_photo[1].bot_request.caption,
+        Some("test".to_string())
+    );
+    let dropped_fields = responses.dropped_fields;
+    assert_eq!(dropped_fields.len(), 2);
+    assert_eq!(dropped_fields[0].method, "sendPhoto");
+    assert_eq!(dropped_fields[0].field, "disable_notification");
+    assert_eq!(dropped_fields[0].value, "sometimes");
+    assert!(dropped_fields[0].error.is_some());
+    assert_eq!(
+        dropped_fields[1],
+        DroppedField {
+            method: "sendPhoto".to_string(),
+            field: "unknown_field".to_string(),
+            value: "value".to_string(),
+            error: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_sent_photo_sizes() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/photo"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let sizes = last_sent_message.photo().unwrap();
+    assert_eq!(sizes.len(), 4);
+    assert!(sizes
+        .windows(2)
+        .all(|pair| pair[0].width < pair[1].width && pair[0].file.id != pair[1].file.id));
+    // The largest size is the sent file itself
+    assert_eq!(sizes.last().unwrap().file.size, "somedata".len() as u32);
+}
+
+#[tokio::test]
+async fn test_send_video() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/video"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let last_sent_video = bot.get_responses().sent_messages_video.pop().unwrap();
+    assert_eq!(last_sent_message.caption(), Some("test"));
+    assert_eq!(
+        last_sent_message.reply_to_message().unwrap().text(),
+        Some("/video")
+    );
+    assert_eq!(last_sent_message.caption_entities().unwrap().len(), 1);
+    assert_eq!(last_sent_video.bot_request.file_name, "test.mp4");
+    assert_eq!(last_sent_video.bot_request.file_data, "somedata");
+    assert_eq!(
+        last_sent_video.bot_request.thumbnail,
+        Some("thumbnail".to_string())
+    );
+    let thumbnail = last_sent_message
+        .video()
+        .unwrap()
+        .thumbnail
+        .clone()
+        .unwrap();
+    assert_eq!(thumbnail.file.size, "thumbnail".len() as u32);
+}
+
+#[tokio::test]
+async fn test_send_audio() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/audio"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let last_sent_audio = bot.get_responses().sent_messages_audio.pop().unwrap();
+    assert_eq!(last_sent_message.caption(), Some("test"));
+    assert_eq!(
+        last_sent_message.reply_to_message().unwrap().text(),
+        Some("/audio")
+    );
+    assert_eq!(last_sent_message.caption_entities().unwrap().len(), 1);
+    assert_eq!(last_sent_audio.bot_request.file_name, "test.mp3");
+    assert_eq!(last_sent_audio.bot_request.file_data, "somedata");
+}
+
+#[tokio::test]
+async fn test_send_voice() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/voice"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let last_sent_voice = bot.get_responses().sent_messages_voice.pop().unwrap();
+    assert_eq!(last_sent_message.caption(), Some("test"));
+    assert_eq!(
+        last_sent_message.reply_to_message().unwrap().text(),
+        Some("/voice")
+    );
+    assert_eq!(last_sent_message.caption_entities().unwrap().len(), 1);
+    assert_eq!(last_sent_voice.bot_request.file_name, "test.mp3");
+    assert_eq!(last_sent_voice.bot_request.file_data, "somedata");
+}
+
+#[tokio::test]
+async fn test_send_video_note() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/videonote"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let last_sent_video_note = bot.get_responses().sent_messages_video_note.pop().unwrap();
+    assert_eq!(
+        last_sent_message.reply_to_message().unwrap().text(),
+        Some("/videonote")
+    );
+    assert_eq!(last_sent_video_note.bot_request.file_name, "test.mp4");
+    assert_eq!(last_sent_video_note.bot_request.file_data, "somedata");
+}
+
+fn get_banner_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let banner = InputFile::memory("banner".to_string()).file_name("banner.jpg");
+        if let Err(error) = bot.send_photo(message.chat.id, banner).await {
+            bot.send_message(message.chat.id, format!("{error:?}"))
+                .await?;
+            let cropped = InputFile::memory("cropped".to_string()).file_name("cropped.jpg");
+            bot.send_photo(message.chat.id, cropped).await?;
+        }
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_photo_dimensions_when_validating_like_telegram() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_banner_schema());
+    bot.probe_media(|file_name| match file_name {
+        "banner.jpg" => Some(MediaMetadata::new().width(4200).height(200)),
+        "cropped.jpg" => Some(MediaMetadata::new().width(2000).height(200)),
+        _ => None,
+    });
+    bot.validate_like_telegram();
+
+    bot.dispatch().await;
+
+    assert_eq!(
+        bot.get_responses().sent_messages[0].text(),
+        Some(r#"Api(Unknown("Bad Request: PHOTO_INVALID_DIMENSIONS"))"#)
+    );
+    assert_eq!(bot.get_responses().sent_messages_photo.len(), 1);
+    bot.assert_request("sendPhoto", json_contains!({}));
+    bot.assert_request("sendMessage", json_contains!({}));
+    bot.assert_request("sendPhoto", json_contains!({}));
+}
+
+#[tokio::test]
+async fn test_probe_media() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/video"), get_schema());
+    bot.probe_media(|file_name| match file_name {
+        "test.mp4" => Some(
+            MediaMetadata::new()
+                .duration(Seconds::from_seconds(42))
+                .width(1920)
+                .height(1080),
+        ),
+        _ => None,
+    });
+
+    bot.dispatch().await;
+
+    let video = bot.get_responses().sent_messages_video[0]
+        .message
+        .video()
+        .unwrap()
+        .clone();
+    assert_eq!(video.duration, Seconds::from_seconds(42));
+    assert_eq!((video.width, video.height), (1920, 1080));
+
+    bot.update(MockMessageText::new().text("/videonote"));
+    bot.dispatch().await;
+    let video_note = bot.get_responses().sent_messages_video_note[0]
+        .message
+        .video_note()
+        .unwrap()
+        .clone();
+    assert_eq!(video_note.duration, Seconds::from_seconds(42));
+    assert_eq!(video_note.length, 1920);
+
+    // The probe doesn't know the file, so the defaults are used
+    bot.update(MockMessageText::new().text("/voice"));
+    bot.dispatch().await;
+    let voice = bot.get_responses().sent_messages_voice[0]
+        .message
+        .voice()
+        .unwrap()
+        .clone();
+    assert_eq!(voice.duration, Seconds::from_seconds(0));
+}
+
+#[tokio::test]
+async fn test_send_document() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/document"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let last_sent_photo = bot.get_responses().sent_messages_document.pop().unwrap();
+    assert_eq!(last_sent_message.caption(), Some("test"));
+    assert_eq!(
+        last_sent_message.reply_to_message().unwrap().text(),
+        Some("/document")
+    );
+    assert_eq!(last_sent_message.caption_entities().unwrap().len(), 1);
+    assert_eq!(last_sent_photo.bot_request.file_name, "test.txt");
+    // Text documents have no previews
+    assert!(last_sent_message.document().unwrap().thumbnail.is_none());
+}
+
+#[tokio::test]
+async fn test_send_animation() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/animation"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let last_sent_animation = bot.get_responses().sent_messages_animation.pop().unwrap();
+    assert_eq!(
+        last_sent_message.animation().unwrap().file_name,
+        Some("animation.mp4".to_string())
+    );
+    assert_eq!(last_sent_animation.bot_request.file_name, "animation.mp4");
+    assert!(last_sent_message.animation().unwrap().thumbnail.is_some());
+}
+
+fn get_document_mime_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let documents = [
+            ("report.bin", "application/pdf", false),
+            ("archive.zip", "application/octet-stream", true),
+            ("archive.zip", "application/octet-stream", false),
+        ];
+        for (file_name, mime_type, disable_detection) in documents {
+            let part = reqwest::multipart::Part::bytes(b"data".to_vec())
+                .file_name(file_name)
+                .mime_str(mime_type)?;
+            let form = reqwest::multipart::Form::new()
+                .text("chat_id", message.chat.id.to_string())
+                .text(
+                    "disable_content_type_detection",
+                    disable_detection.to_string(),
+                )
+                .part("document", part);
+            raw_bot_request(&bot, "SendDocument", form).await?;
+        }
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_document_mime_type() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_document_mime_schema());
+
+    bot.dispatch().await;
+
+    let mime_types: Vec<String> = bot
+        .get_responses()
+        .sent_messages_document
+        .iter()
+        .map(|sent| {
+            let document = sent.message.document().unwrap();
+            document.mime_type.as_ref().unwrap().to_string()
+        })
+        .collect();
+    assert_eq!(
+        mime_types,
+        vec![
+            "application/pdf",
+            "application/octet-stream",
+            "application/zip"
+        ]
+    );
+}
+
+fn get_raw_document_with_thumbnail_schema(
+) -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        // The order of the parts shouldn't matter, so it is sent a few times
+        for _ in 0..10 {
+            let document = reqwest::multipart::Part::text("report").file_name("report.pdf");
+            let thumbnail = reqwest::multipart::Part::text("preview").file_name("preview.jpg");
+            let form = reqwest::multipart::Form::new()
+                .text("chat_id", message.chat.id.to_string())
+                .part("thumbnail", thumbnail)
+                .part("document", document);
+            raw_bot_request(&bot, "SendDocument", form).await?;
+        }
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_raw_document_with_thumbnail() {
+    let mut bot = MockBot::new(
+        MockMessageText::new(),
+        get_raw_document_with_thumbnail_schema(),
+    );
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages_document.len(), 10);
+    for sent in responses.sent_messages_document {
+        assert_eq!(sent.bot_request.file_name, "report.pdf");
+        assert_eq!(sent.bot_request.file_data, "report");
+        assert_eq!(sent.bot_request.thumbnail.as_deref(), Some("preview"));
+    }
+}
+
+#[tokio::test]
+async fn test_send_media_group() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/mediagroup"), get_schema());
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+
+    let audio_group = responses.sent_media_group[0].clone();
+    assert_eq!(
+        audio_group.messages.first().unwrap().caption(),
+        Some("test")
+    );
+    assert_eq!(
+        audio_group
+            .messages
+            .first()
+            .unwrap()
+            .audio()
+            .unwrap()
+            .file_name,
+        Some("audio1.mp3".to_string())
+    );
+    assert_eq!(
+        audio_group
+            .messages
+            .first()
+            .unwrap()
+            .reply_to_message()
+            .unwrap()
+            .text(),
+        Some("/mediagroup")
+    );
+    assert_eq!(audio_group.bot_request.media.len(), 2);
+
+    let document_group = responses.sent_media_group[1].clone();
+    assert_eq!(
+        document_group.messages.first().unwrap().caption(),
+        Some("test")
+    );
+    assert_eq!(
+        document_group
+            .messages
+            .first()
+            .unwrap()
+            .document()
+            .unwrap()
+            .file_name,
+        Some("document1.txt".to_string())
+    );
+    assert_eq!(
+        document_group
+            .messages
+            .first()
+            .unwrap()
+            .reply_to_message()
+            .unwrap()
+            .text(),
+        Some("/mediagroup")
+    );
+    assert_eq!(document_group.bot_request.media.len(), 2);
+
+    let photo_group = responses.sent_media_group[2].clone();
+    assert_eq!(
+        photo_group.messages.first().unwrap().caption(),
+        Some("test")
+    );
+    assert!(!photo_group
+        .messages
+        .first()
+        .unwrap()
+        .photo()
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        photo_group
+            .messages
+            .first()
+            .unwrap()
+            .reply_to_message()
+            .unwrap()
+            .text(),
+        Some("/mediagroup")
+    );
+    assert_eq!(photo_group.bot_request.media.len(), 2);
+
+    let video_group = responses.sent_media_group[3].clone();
+    assert_eq!(
+        video_group.messages.first().unwrap().caption(),
+        Some("test")
+    );
+    assert_eq!(
+        video_group
+            .messages
+            .first()
+            .unwrap()
+            .video()
+            .unwrap()
+            .file_name,
+        Some("video1.mp4".to_string())
+    );
+    assert_eq!(
+        video_group
+            .messages
+            .first()
+            .unwrap()
+            .reply_to_message()
+            .unwrap()
+            .text(),
+        Some("/mediagroup")
+    );
+    assert_eq!(video_group.bot_request.media.len(), 2);
+}
+
+fn get_spoiler_album_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let photo = InputFile::memory("photo".to_string()).file_name("photo.jpg");
+        let video = InputFile::memory("video".to_string()).file_name("video.mp4");
+        let thumbnail = InputFile::memory("thumbnail".to_string()).file_name("thumb.jpg");
+        let media_group = vec![
+            InputMedia::Photo(
+                InputMediaPhoto::new(photo)
+                    .caption("<b>Spoiler</b>")
+                    .parse_mode(ParseMode::Html)
+                    .show_caption_above_media(true)
+                    .spoiler(),
+            ),
+            InputMedia::Video(InputMediaVideo::new(video).thumbnail(thumbnail)),
+        ];
+        bot.send_media_group(message.chat.id, media_group).await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_media_group_item_options() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_spoiler_album_schema());
+
+    bot.dispatch().await;
+
+    let media_group = bot.get_responses().sent_media_group[0].clone();
+    let MediaGroupInputMedia::InputMediaPhoto(photo) = &media_group.bot_request.media[0] else {
+        panic!("The first item isn't a photo");
+    };
+    assert_eq!(photo.parse_mode, Some(ParseMode::Html));
+    assert_eq!(photo.has_spoiler, Some(true));
+    let MediaGroupInputMedia::InputMediaVideo(video) = &media_group.bot_request.media[1] else {
+        panic!("The second item isn't a video");
+    };
+    assert_eq!(video.thumbnail, Some("thumbnail".to_string()));
+
+    let photo_message = &media_group.messages[0];
+    assert!(photo_message.has_media_spoiler());
+    assert!(photo_message.show_caption_above_media());
+    let video_message = &media_group.messages[1];
+    assert!(!video_message.has_media_spoiler());
+    let thumbnail = video_message.video().unwrap().thumbnail.clone().unwrap();
+    assert_eq!(thumbnail.file.size, "thumbnail".len() as u32);
+}
+
+#[tokio::test]
+async fn test_send_location() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/location"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let last_sent_location = bot.get_responses().sent_messages_location.pop().unwrap();
+    assert_eq!(
+        last_sent_message.reply_to_message().unwrap().text(),
+        Some("/location")
+    );
+    assert_eq!(last_sent_message.location().unwrap().latitude, 1.0);
+    assert_eq!(last_sent_message.location().unwrap().longitude, 1.0);
+    assert_eq!(last_sent_location.bot_request.live_period, Some(60.into()));
+}
+
+#[tokio::test]
+async fn test_edit_live_location() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/location"), get_schema());
+    bot.dispatch().await;
+    let location_message = bot.get_responses().sent_messages.pop().unwrap();
+
+    bot.advance_time(Duration::from_secs(30));
+    bot.update(MockMessageText::new().text(format!("/editlivelocation {}", location_message.id)));
+    bot.dispatch().await;
+
+    assert!(!bot.is_live_location_expired(&location_message));
+    let edited_location = bot
+        .get_responses()
+        .edited_messages_live_location
+        .pop()
+        .unwrap();
+    assert_eq!(edited_location.message.id, location_message.id);
+    assert_eq!(edited_location.message.location().unwrap().latitude, 2.0);
+    assert_eq!(
+        edited_location.message.location().unwrap().live_period,
+        Some(60.into())
+    );
+}
+
+fn get_courier_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let chat_id = message.chat.id;
+        let courier = bot
+            .send_location(chat_id, 50.45, 30.52)
+            .live_period(600.into())
+            .horizontal_accuracy(25.0)
+            .heading(90)
+            .proximity_alert_radius(300)
+            .await?;
+        bot.edit_message_live_location(chat_id, courier.id, 50.46, 30.53)
+            .horizontal_accuracy(10.0)
+            .heading(180)
+            .proximity_alert_radius(100)
+            .await?;
+        bot.send_location(chat_id, 50.47, 30.54)
+            .horizontal_accuracy(5.0)
+            .heading(270)
+            .await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_location_accuracy_heading_and_proximity() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_courier_schema());
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let live = responses.sent_messages_location[0]
+        .message
+        .location()
+        .unwrap();
+    assert_eq!(live.horizontal_accuracy, Some(25.0));
+    assert_eq!(live.heading, Some(90));
+    assert_eq!(live.proximity_alert_radius, Some(300));
+
+    let edited = &responses.edited_messages_live_location[0];
+    let location = edited.message.location().unwrap();
+    assert_eq!(location.horizontal_accuracy, Some(10.0));
+    assert_eq!(location.heading, Some(180));
+    assert_eq!(location.proximity_alert_radius, Some(100));
+    assert_eq!(location.live_period, Some(600.into()));
+    assert_eq!(edited.bot_request.heading, Some(180));
+
+    // A static location has no heading, but the request still has it
+    let sent_static = &responses.sent_messages_location[1];
+    let location = sent_static.message.location().unwrap();
+    assert_eq!(location.horizontal_accuracy, Some(5.0));
+    assert_eq!(location.heading, None);
+    assert_eq!(sent_static.bot_request.heading, Some(270));
+}
+
+#[tokio::test]
+async fn test_edit_expired_live_location() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/location"), get_schema());
+    bot.dispatch().await;
+    let location_message = bot.get_responses().sent_messages.pop().unwrap();
+
+    bot.advance_time(Duration::from_secs(61));
+    bot.update(MockMessageText::new().text(format!("/editlivelocation {}", location_message.id)));
+    bot.dispatch().await;
+
+    assert!(bot.is_live_location_expired(&location_message));
+    assert!(bot.get_responses().edited_messages_live_location.is_empty());
+    // Messages sent after the clock moved are dated with the mock time
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    assert!(last_sent_message.date - location_message.date >= chrono::Duration::seconds(61));
+}
+
+#[tokio::test]
+async fn test_send_venue() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/venue"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_venue = bot.get_responses().sent_messages_venue.pop().unwrap();
+    let last_sent_message = last_sent_venue.message;
+    assert_eq!(
+        last_sent_message.reply_to_message().unwrap().text(),
+        Some("/venue")
+    );
+    assert_eq!(last_sent_message.venue().unwrap().location.latitude, 1.0);
+    assert_eq!(last_sent_message.venue().unwrap().location.longitude, 1.0);
+    assert_eq!(last_sent_message.venue().unwrap().title, "test");
+    assert_eq!(last_sent_message.venue().unwrap().address, "test");
+
+    let venue = last_sent_message.venue().unwrap();
+    let request = last_sent_venue.bot_request;
+    assert_eq!(request.foursquare_id.as_deref(), Some("4sq_id"));
+    assert_eq!(venue.foursquare_id, request.foursquare_id);
+    assert_eq!(venue.foursquare_type.as_deref(), Some("food/icecream"));
+    assert_eq!(venue.foursquare_type, request.foursquare_type);
+    assert_eq!(venue.google_place_id.as_deref(), Some("google_id"));
+    assert_eq!(venue.google_place_id, request.google_place_id);
+    assert_eq!(venue.google_place_type.as_deref(), Some("cafe"));
+    assert_eq!(venue.google_place_type, request.google_place_type);
+}
+
+#[tokio::test]
+async fn test_send_contact() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/contact"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_contact = bot.get_responses().sent_messages_contact.pop().unwrap();
+    let last_sent_message = last_sent_contact.message;
+    assert_eq!(
+        last_sent_message.reply_to_message().unwrap().text(),
+        Some("/contact")
+    );
+    assert_eq!(
+        last_sent_message.contact().unwrap().phone_number,
+        "123456789"
+    );
+    assert_eq!(last_sent_message.contact().unwrap().first_name, "test");
+
+    let contact = last_sent_message.contact().unwrap();
+    let request = last_sent_contact.bot_request;
+    assert_eq!(contact.last_name.as_deref(), Some("last"));
+    assert_eq!(contact.last_name, request.last_name);
+    assert_eq!(contact.vcard.as_deref(), Some("BEGIN:VCARD\nEND:VCARD"));
+    assert_eq!(contact.vcard, request.vcard);
+}
+
+#[tokio::test]
+async fn test_send_dice() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/dice"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_contact = bot.get_responses().sent_messages_dice.pop().unwrap();
+    let last_sent_message = last_sent_contact.message;
+    assert_eq!(last_sent_message.dice().unwrap().emoji, DiceEmoji::Dice);
+    assert!(last_sent_message.dice().unwrap().value < 100);
+}
+
+fn get_dice_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let slot_machine = bot
+            .send_dice(message.chat.id)
+            .emoji(DiceEmoji::SlotMachine)
+            .await?;
+        let dice = bot.send_dice(message.chat.id).await?;
+        let text = format!(
+            "{} {}",
+            slot_machine.dice().unwrap().value,
+            dice.dice().unwrap().value
+        );
+        bot.send_message(message.chat.id, text).await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_set_next_dice_value() {
+    let mut bot = MockBot::new(MockMessageText::new().text("roll"), get_dice_schema());
+    bot.set_next_dice_value(64);
+    bot.set_next_dice_value(3);
+
+    bot.dispatch_and_check_last_text("64 3").await;
+}
+
+fn get_album_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let text = format!("{} {}", message.id, message.media_group_id().unwrap().0);
+        bot.send_message(message.chat.id, text).await?;
+        Ok(())
+    })
+}
+
+fn get_added_to_group_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>>
+{
+    dptree::entry()
+        .branch(Update::filter_my_chat_member().endpoint(
+            |updated: ChatMemberUpdated, bot: Bot| async move {
+                let text = format!("{:?}", updated.new_chat_member.status());
+                bot.send_message(updated.chat.id, text).await?;
+                Ok(())
+            },
+        ))
+        .branch(Update::filter_message().endpoint(
+            |message: Message, bot: Bot, me: Me| async move {
+                let new_members = message.new_chat_members().unwrap();
+                let member = bot.get_chat_member(message.chat.id, me.id).await?;
+                let text = format!("{} {:?}", new_members[0].id, member.status());
+                bot.send_message(message.chat.id, text).await?;
+                Ok(())
+            },
+        ))
+}
+
+#[tokio::test]
+async fn test_bot_added_to_group() {
+    let chat = MockGroupChat::new().id(-42).build();
+    let mut bot = MockBot::new(
+        MockBotAddedToGroup::new().chat(chat.clone()).promoted(),
+        get_added_to_group_schema(),
+    );
+
+    bot.dispatch().await;
+
+    let sent_messages = bot.get_responses().sent_messages;
+    let texts: Vec<_> = sent_messages.iter().map(|m| m.text().unwrap()).collect();
+    assert_eq!(
+        texts,
+        vec![
+            "Member".to_string(),
+            format!("{} Administrator", MockMe::ID),
+            "Administrator".to_string(),
+        ]
+    );
+    assert!(sent_messages.iter().all(|m| m.chat.id == chat.id));
+}
+
+fn get_group_scenario_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>>
+{
+    dptree::entry()
+        .branch(Update::filter_chat_member().endpoint(
+            |updated: ChatMemberUpdated, bot: Bot| async move {
+                let text = format!(
+                    "{} {:?} {}",
+                    updated.new_chat_member.user.id,
+                    updated.new_chat_member.status(),
+                    updated.invite_link.is_some()
+                );
+                bot.send_message(updated.chat.id, text).await?;
+                Ok(())
+            },
+        ))
+        .branch(
+            Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+                let text = if let Some(members) = message.new_chat_members() {
+                    format!("{} joined {}", members[0].id, message.id)
+                } else if let Some(member) = message.left_chat_member() {
+                    format!("{} left {}", member.id, message.id)
+                } else {
+                    format!("{} {}", message.chat.title().unwrap(), message.id)
+                };
+                bot.send_message(message.chat.id, text).await?;
+                Ok(())
+            }),
+        )
+}
+
+#[tokio::test]
+async fn test_group_scenario() {
+    let invite_link = ChatInviteLink {
+        invite_link: "https://t.me/+invite".to_string(),
+        creator: MockUser::new().build(),
+        creates_join_request: false,
+        is_primary: true,
+        is_revoked: false,
+        name: None,
+        expire_date: None,
+        member_limit: None,
+        pending_join_request_count: None,
+        subscription_period: None,
+        subscription_price: None,
+    };
+    let scenario = MockGroupScenario::new()
+        .first_message_id(10)
+        .user_joined_via_link(MockUser::new().id(1), invite_link)
+        .user_promoted(MockUser::new().id(1))
+        .title_changed("Renamed")
+        .user_left(MockUser::new().id(1));
+    let mut bot = MockBot::new(scenario, get_group_scenario_schema());
+
+    bot.dispatch().await;
+
+    let sent_messages = bot.get_responses().sent_messages;
+    let texts: Vec<_> = sent_messages.iter().map(|m| m.text().unwrap()).collect();
+    assert_eq!(
+        texts,
+        vec![
+            "1 Member true",
+            "1 joined 10",
+            "1 Administrator false",
+            "Renamed 11",
+            "1 Left false",
+            "1 left 12",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_bench() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo"), get_schema());
+
+    let report = bot.bench(30).await;
+
+    assert_eq!(report.updates, 30);
+    assert!(report.throughput() > 0.0);
+    let stats = &report.latencies["message"];
+    assert_eq!(stats.count, 30);
+    assert!(stats.p50 <= stats.p90 && stats.p90 <= stats.p99 && stats.p99 <= stats.max);
+    assert_eq!(bot.get_responses().sent_messages.len(), 30);
+    assert!(report.to_string().starts_with("30 updates in"));
+
+    // The bot is left as it was
+    assert_eq!(bot.updates.len(), 1);
+    bot.dispatch().await;
+    assert_eq!(bot.get_responses().sent_messages.len(), 1);
+}
+
+fn get_timed_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let echo = |bot: Bot, message: Message| async move {
+        if message.text() == Some("/panic") {
+            panic!("Panicked on purpose");
+        }
+        bot.send_message(message.chat.id, message.text().unwrap())
+            .await?;
+        Ok(())
+    };
+    Update::filter_message()
+        .branch(crate::bench::timed(
+            "echo",
+            dptree::filter(|message: Message| message.text() != Some("hi")).endpoint(echo),
+        ))
+        .branch(crate::bench::timed("greet", dptree::endpoint(echo)))
+}
+
+#[tokio::test]
+async fn test_bench_timed_handlers() {
+    let mut bot = MockBot::new(
+        vec![
+            MockMessageText::new().text("/echo"),
+            MockMessageText::new().text("hi"),
+        ],
+        get_timed_schema(),
+    );
+
+    let report = bot.bench(6).await;
+
+    assert_eq!(report.latencies["message"].count, 6);
+    assert_eq!(report.handlers["echo"].count, 3);
+    assert_eq!(report.handlers["greet"].count, 3);
+    assert!(report.to_string().contains("handler greet: 3 updates"));
+    // Outside of bench the branches just handle the updates
+    bot.dispatch().await;
+    assert_eq!(bot.get_responses().sent_messages.len(), 2);
+}
+
+#[tokio::test]
+async fn test_bench_panic_restores_bot() {
+    use futures_util::FutureExt;
+
+    let mut bot = MockBot::new(MockMessageText::new().text("/panic"), get_timed_schema());
+
+    let result = std::panic::AssertUnwindSafe(bot.bench(3))
+        .catch_unwind()
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(bot.updates.len(), 1);
+    assert!(bot
+        .dependencies
+        .try_get::<crate::bench::LatencyRecorder>()
+        .is_none());
+    bot.update(MockMessageText::new().text("/echo"));
+    bot.dispatch_and_check_last_text("/echo").await;
+}
+
+#[tokio::test]
+async fn test_album() {
+    let album = MockAlbum::new()
+        .item(MockMessagePhoto::new())
+        .item(MockMessageVideo::new())
+        .item(MockMessagePhoto::new());
+    let mut bot = MockBot::new(album, get_album_schema());
+
+    bot.dispatch().await;
+
+    let sent_messages = bot.get_responses().sent_messages;
+    let texts: Vec<_> = sent_messages.iter().map(|m| m.text().unwrap()).collect();
+    let group_id = MockAlbum::MEDIA_GROUP_ID;
+    assert_eq!(
+        texts,
+        vec![
+            format!("1 {group_id}"),
+            format!("2 {group_id}"),
+            format!("3 {group_id}")
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_dice_value_out_of_range() {
+    let mut bot = MockBot::new(MockMessageText::new().text("roll"), get_dice_schema());
+    bot.set_next_dice_value(64);
+    bot.set_next_dice_value(7);
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages_dice.len(), 1);
+    assert!(responses.sent_messages_text.is_empty());
+}
+
+fn get_custom_emoji_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let mut ids: Vec<CustomEmojiId> = message
+            .parse_entities()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|entity| match entity.kind() {
+                MessageEntityKind::CustomEmoji { custom_emoji_id } => Some(custom_emoji_id.clone()),
+                _ => None,
+            })
+            .collect();
+        ids.extend([CustomEmojiId("222".into()), CustomEmojiId("333".into())]);
+        let stickers = bot.get_custom_emoji_stickers(ids).await?;
+        let text: String = stickers
+            .iter()
+            .filter_map(|sticker| sticker.emoji.clone())
+            .collect();
+        let entity_id = match message.text() {
+            Some("bad") => "not a number".to_string(),
+            _ => stickers[0].custom_emoji_id().unwrap().0.clone(),
+        };
+        bot.send_message(message.chat.id, text)
+            .entities(vec![MessageEntity::custom_emoji(
+                CustomEmojiId(entity_id),
+                0,
+                2,
+            )])
+            .await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_custom_emoji_stickers() {
+    let message = MockMessageText::new()
+        .text("👍")
+        .entities(vec![MessageEntity::custom_emoji(
+            CustomEmojiId("111".into()),
+            0,
+            2,
+        )]);
+    let mut bot = MockBot::new(message, get_custom_emoji_schema());
+    let sticker = MockMessageSticker::new()
+        .custom_emoji_id("222")
+        .emoji("🔥")
+        .build();
+    bot.add_custom_emoji_sticker(sticker.sticker().unwrap().clone());
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    // The unknown custom emoji is skipped
+    assert_eq!(responses.sent_messages[0].text(), Some("👍🔥"));
+    assert_eq!(
+        responses.fetched_custom_emoji_stickers[0]
+            .custom_emoji_ids
+            .len(),
+        3
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_custom_emoji_entity() {
+    let message = MockMessageText::new()
+        .text("bad")
+        .entities(vec![MessageEntity::custom_emoji(
+            CustomEmojiId("111".into()),
+            0,
+            2,
+        )]);
+    let mut bot = MockBot::new(message, get_custom_emoji_schema());
+
+    bot.dispatch().await;
+
+    assert!(bot.get_responses().sent_messages.is_empty());
+}
+
+#[tokio::test]
+async fn test_response_fidelity() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/poll"), get_schema());
+    bot.check_response_fidelity();
+
+    for command in [
+        "/poll",
+        "/photo",
+        "/mediagroup",
+        "/copymessage",
+        "/invoice",
+        "/chataction",
+    ] {
+        bot.update(MockMessageText::new().text(command));
+        bot.dispatch().await;
+        assert!(!bot.get_responses().sent_messages.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn test_send_poll() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/poll"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_contact = bot.get_responses().sent_messages_poll.pop().unwrap();
+    let last_sent_message = last_sent_contact.message;
+    assert_eq!(
+        last_sent_message.reply_to_message().unwrap().text(),
+        Some("/poll")
+    );
+    assert_eq!(last_sent_message.poll().unwrap().question, "what is test");
+    assert_eq!(
+        last_sent_message.poll().unwrap().options,
+        vec![
+            PollOption {
+                text: "test".to_string(),
+                text_entities: None,
+                voter_count: 0
+            },
+            PollOption {
+                text: "not test".to_string(),
+                text_entities: None,
+                voter_count: 0
+            }
+        ],
+    );
+    assert_eq!(
+        last_sent_message.poll().unwrap().explanation,
+        Some("because test".to_string())
+    );
+    assert_eq!(last_sent_message.poll().unwrap().poll_type, PollType::Quiz);
+    assert_eq!(last_sent_message.poll().unwrap().correct_option_id, Some(0));
+}
+
+fn get_timed_quiz_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let options = vec!["yes".to_string().into(), "no".to_string().into()];
+        bot.send_poll(message.chat.id, "timed", options.clone())
+            .type_(PollType::Quiz)
+            .correct_option_id(0)
+            .explanation("see the docs")
+            .explanation_entities(vec![MessageEntity::bold(4, 4)])
+            .open_period(60)
+            .await?;
+        bot.send_poll(message.chat.id, "closed", options)
+            .close_date(DateTime::from_timestamp(2_000_000_000, 0).unwrap())
+            .is_closed(true)
+            .await?;
+        Ok(())
+    })
+}
+
+fn get_quiz_relay_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let chat_id = message.chat.id;
+        let options = vec!["yes".to_string().into(), "no".to_string().into()];
+        let quiz = bot
+            .send_poll(chat_id, "Is it a quiz?", options)
+            .type_(PollType::Quiz)
+            .correct_option_id(0)
+            .await?;
+        let forwarded = bot.forward_message(chat_id, chat_id, quiz.id).await?;
+        if let Err(error) = bot.copy_message(chat_id, chat_id, forwarded.id).await {
+            bot.send_message(chat_id, format!("{error:?}")).await?;
+        }
+        bot.copy_message(chat_id, chat_id, quiz.id).await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_quiz_forward_and_copy() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_quiz_relay_schema());
+    bot.validate_like_telegram();
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let quiz = &responses.sent_messages_poll[0].message;
+    assert_eq!(quiz.poll().unwrap().correct_option_id, Some(0));
+    let forwarded = &responses.forwarded_messages[0].message;
+    assert_eq!(forwarded.poll().unwrap().correct_option_id, None);
+    assert_eq!(
+        responses.sent_messages[2].text(),
+        Some(r#"Api(Unknown("Bad Request: message can't be copied"))"#)
+    );
+    let copied_id = responses.copied_messages[0].message_id;
+    let copied = bot
+        .find_messages(|message| message.id == copied_id)
+        .pop()
+        .unwrap();
+    assert_eq!(copied.poll().unwrap().correct_option_id, Some(0));
+
+    bot.assert_request("sendPoll", json_contains!({}));
+    bot.assert_request("forwardMessage", json_contains!({}));
+    bot.assert_request("copyMessage", json_contains!({}));
+    bot.assert_request("sendMessage", json_contains!({}));
+    bot.assert_request("copyMessage", json_contains!({}));
+}
+
+#[tokio::test]
+async fn test_send_poll_timing_and_explanation() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_timed_quiz_schema());
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let timed = &responses.sent_messages_poll[0];
+    let poll = timed.message.poll().unwrap();
+    assert_eq!(poll.open_period, Some(Seconds::from_seconds(60)));
+    let open_for = poll.close_date.unwrap() - timed.message.date;
+    assert!((open_for - TimeDelta::seconds(60)).abs() < TimeDelta::seconds(1));
+    assert!(!poll.is_closed);
+    assert_eq!(
+        poll.explanation_entities,
+        Some(vec![MessageEntity::bold(4, 4)])
+    );
+    assert_eq!(
+        timed.bot_request.explanation_entities,
+        Some(vec![MessageEntity::bold(4, 4)])
+    );
+
+    let closed = &responses.sent_messages_poll[1];
+    let poll = closed.message.poll().unwrap();
+    assert_eq!(poll.open_period, None);
+    assert_eq!(poll.close_date, DateTime::from_timestamp(2_000_000_000, 0));
+    assert!(poll.is_closed);
+    assert_eq!(
+        closed.bot_request.close_date,
+        DateTime::from_timestamp(2_000_000_000, 0)
+    );
+}
+
+#[tokio::test]
+async fn test_send_sticker() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/sticker"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_contact = bot.get_responses().sent_messages_sticker.pop().unwrap();
+    let last_sent_message = last_sent_contact.message;
+    assert_eq!(
+        last_sent_message.reply_to_message().unwrap().text(),
+        Some("/sticker")
+    );
+    assert_eq!(last_sent_message.sticker().unwrap().emoji, None);
+}
+
+fn get_sticker_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let animated = InputFile::memory("animated".to_string()).file_name("sticker.tgs");
+        bot.send_sticker(message.chat.id, animated)
+            .emoji("🔥")
+            .await?;
+        let video = InputFile::memory("video".to_string()).file_name("sticker.webm");
+        bot.send_sticker(message.chat.id, video).await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_send_sticker_formats() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_sticker_schema());
+
+    bot.dispatch().await;
+
+    let sent_messages = bot.get_responses().sent_messages;
+    let animated = sent_messages[0].sticker().unwrap();
+    assert!(animated.is_animated());
+    assert_eq!(animated.emoji.as_deref(), Some("🔥"));
+    assert_eq!(animated.file.size, "animated".len() as u32);
+    let video = sent_messages[1].sticker().unwrap();
+    assert!(video.is_video());
+    assert_ne!(video.file.id, animated.file.id);
+}
+
+#[tokio::test]
+async fn test_edit_message() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/edit"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let last_edited_response = bot.get_responses().edited_messages_text.pop().unwrap();
+
+    assert_eq!(last_sent_message.text(), Some("/edit"));
+    assert_eq!(last_edited_response.message.text(), Some("edited"));
+    assert_eq!(
+        last_edited_response
+            .bot_request
+            .link_preview_options
+            .unwrap()
+            .is_disabled,
+        true
+    );
+    assert_eq!(
+        last_edited_response
+            .message
+            .link_preview_options()
+            .unwrap()
+            .is_disabled,
+        true
+    );
+}
+
+fn get_formatted_message_schema(
+) -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        bot.send_message(message.chat.id, "Hello https://example.com")
+            .entities(vec![MessageEntity::bold(0, 5)])
+            .link_preview_options(LinkPreviewOptions {
+                is_disabled: false,
+                url: None,
+                prefer_small_media: true,
+                prefer_large_media: false,
+                show_above_text: true,
+            })
+            .message_effect_id(EffectId::from("5104841245755180586".to_string()))
+            .await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_send_message_stores_formatting() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_formatted_message_schema());
+
+    bot.dispatch().await;
+
+    let message = bot.get_responses().sent_messages.pop().unwrap();
+    assert_eq!(message.entities(), Some(&[MessageEntity::bold(0, 5)][..]));
+    let link_preview_options = message.link_preview_options().unwrap();
+    assert!(link_preview_options.prefer_small_media);
+    assert!(link_preview_options.show_above_text);
+    assert_eq!(
+        message.effect_id().unwrap().to_string(),
+        "5104841245755180586"
+    );
+    bot.assert_last_entity(MessageEntityKind::Bold, "Hello");
+}
+
+fn get_effect_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let effect_id = message.text().unwrap().to_string();
+        bot.send_message(message.chat.id, "Congratulations!")
+            .message_effect_id(EffectId::from(effect_id))
+            .await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_message_effects() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("5046509860389126442"),
+        get_effect_schema(),
+    );
+
+    bot.dispatch().await;
+    bot.assert_last_message_effect(Some("🎉"));
+    bot.assert_last_message_effect(Some("5046509860389126442"));
+
+    // Unknown effects are only rejected when validating like Telegram
+    bot.update(MockMessageText::new().text("123"));
+    bot.dispatch().await;
+    bot.assert_last_message_effect(Some("123"));
+}
+
+#[tokio::test]
+async fn test_unknown_message_effect_when_validating_like_telegram() {
+    let mut bot = MockBot::new(MockMessageText::new().text("123"), get_effect_schema());
+    let error_handler = Arc::new(MyErrorHandler::new());
+    bot.error_handler(error_handler.clone());
+    bot.validate_like_telegram();
+
+    bot.dispatch().await;
+
+    let errors = error_handler.errors();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("EFFECT_ID_INVALID"));
+    bot.assert_request("sendMessage", json_contains!({"message_effect_id": "123"}));
+    bot.assert_request("sendMessage", json_contains!({"text": "Error detected!"}));
+}
+
+#[tokio::test]
+async fn test_strict_and_validation_are_separate() {
+    // The strict mode only requires the requests to be asserted
+    let mut bot = MockBot::new(MockMessageText::new().text("123"), get_effect_schema());
+    bot.strict();
+    bot.dispatch().await;
+    bot.assert_last_message_effect(Some("123"));
+    bot.assert_request("sendMessage", json_contains!({"message_effect_id": "123"}));
+    drop(bot);
+
+    // And validating like Telegram doesn't require them to be
+    let mut bot = MockBot::new(MockMessageText::new().text("123"), get_effect_schema());
+    bot.validate_like_telegram();
+    bot.reset();
+    bot.dispatch().await;
+    assert!(bot.get_responses().sent_messages.is_empty());
+}
+
+fn get_markup_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let (parse_mode, text) = message.text().unwrap().split_once(' ').unwrap();
+        let parse_mode = match parse_mode {
+            "html" => ParseMode::Html,
+            _ => ParseMode::MarkdownV2,
+        };
+        bot.send_message(message.chat.id, text)
+            .parse_mode(parse_mode)
+            .await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_markup_when_validating_like_telegram() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("html <b>Hello</b> <i>world</i>"),
+        get_markup_schema(),
+    );
+    let error_handler = Arc::new(MyErrorHandler::new());
+    bot.error_handler(error_handler.clone());
+    bot.validate_like_telegram();
+
+    bot.dispatch().await;
+    bot.assert_request("sendMessage", json_contains!({"parse_mode": "HTML"}));
+
+    bot.update(MockMessageText::new().text("html <b>Hello <i>world</b></i>"));
+    bot.dispatch().await;
+    bot.update(MockMessageText::new().text("md Price: 1.5"));
+    bot.dispatch().await;
+
+    let errors = error_handler.errors();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("can't parse entities: Unmatched end tag at byte offset 17"));
+    assert!(errors[1].contains("can't parse entities: Character '.' is reserved"));
+    bot.assert_request("sendMessage", json_contains!({"parse_mode": "HTML"}));
+    bot.assert_request("sendMessage", json_contains!({"parse_mode": "MarkdownV2"}));
+    bot.assert_request("sendMessage", json_contains!({"text": "Error detected!"}));
+    bot.assert_request("sendMessage", json_contains!({"text": "Error detected!"}));
+}
+
+#[tokio::test]
+async fn test_edit_message_unchanged() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/editunchanged"), get_schema());
+    let error_handler = Arc::new(MyErrorHandler::new());
+    bot.error_handler(error_handler.clone());
+
+    bot.dispatch().await;
+
+    assert!(bot.get_responses().edited_messages_text.is_empty());
+    let errors = error_handler.errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0], "Api(MessageNotModified)");
+}
+
+fn get_edit_entities_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let sent = bot.send_message(message.chat.id, "hello world").await?;
+        bot.edit_message_text(message.chat.id, sent.id, "hello world")
+            .entities(vec![MessageEntity::bold(0, 5)])
+            .await?;
+        // Only the formatting changes, which is still an edit
+        bot.edit_message_text(message.chat.id, sent.id, "hello world")
+            .entities(vec![MessageEntity::italic(6, 5)])
+            .await?;
+        bot.edit_message_text(message.chat.id, sent.id, "hello world")
+            .entities(vec![MessageEntity::italic(6, 5)])
+            .await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_edit_message_entities() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("hi"),
+        get_edit_entities_schema(),
+    );
+    let error_handler = Arc::new(MyErrorHandler::new());
+    bot.error_handler(error_handler.clone());
+
+    bot.dispatch().await;
+
+    let edited = bot.get_responses().edited_messages_text;
+    assert_eq!(edited.len(), 2);
+    assert_eq!(
+        edited[0].message.entities(),
+        Some(&[MessageEntity::bold(0, 5)][..])
+    );
+    assert_eq!(
+        edited[1].bot_request.entities,
+        Some(vec![MessageEntity::italic(6, 5)])
+    );
+    assert_eq!(
+        edited[1].message.entities(),
+        Some(&[MessageEntity::italic(6, 5)][..])
+    );
+    assert_eq!(error_handler.errors(), vec!["Api(MessageNotModified)"]);
+}
+
+#[tokio::test]
+async fn test_edit_caption() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/editcaption"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let last_edited_response = bot.get_responses().edited_messages_caption.pop().unwrap();
+
+    assert_eq!(last_sent_message.caption(), None);
+    assert_eq!(last_edited_response.message.caption(), Some("edited"));
+}
+
+#[tokio::test]
+async fn test_edit_reply_markup() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/editreplymarkup"),
+        get_schema(),
+    );
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let last_edited_response = bot
+        .get_responses()
+        .edited_messages_reply_markup
+        .pop()
+        .unwrap();
+
+    assert_eq!(last_sent_message.reply_markup(), None);
+    assert_eq!(
+        last_edited_response
+            .message
+            .reply_markup()
+            .unwrap()
+            .inline_keyboard[0][0]
+            .text,
+        "test"
+    );
+}
+
+#[tokio::test]
+async fn test_delete_message() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/delete"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let last_deleted_response = bot.get_responses().deleted_messages.pop().unwrap();
+
+    assert_eq!(last_sent_message.text(), Some("/delete"));
+    assert_eq!(last_deleted_response.message.id, last_sent_message.id);
+}
+
+#[tokio::test]
+async fn test_delete_messages() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/deletebatch"), get_schema());
+
+    bot.dispatch().await;
+
+    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
+    let last_deleted_response = bot.get_responses().deleted_messages.pop().unwrap();
+
+    assert_eq!(last_sent_message.text(), Some("/deletebatch"));
+    assert_eq!(last_deleted_response.message.id, last_sent_message.id);
+    let outcomes = bot
+        .get_responses()
+        .delete_outcomes
+        .iter()
+        .map(|outcome| (outcome.message_id, outcome.deleted))
+        .collect::<Vec<_>>();
+    assert_eq!(outcomes, [(last_sent_message.id.0, true), (404, false)]);
+}
+
+fn get_delete_missing_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>>
+{
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        // The message with id 100 is in another chat
+        let missing = bot.delete_message(message.chat.id, MessageId(404)).await;
+        let other_chat = bot.delete_message(message.chat.id, MessageId(100)).await;
+        let text = format!("{missing:?} {other_chat:?}");
+        bot.send_message(message.chat.id, text).await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_delete_missing_message() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_delete_missing_schema());
+    bot.seed_messages(vec![MockMessageText::new()
+        .id(100)
+        .chat(MockGroupChat::new().build())]);
+
+    bot.dispatch_and_check_last_text(
+        "Err(Api(MessageToDeleteNotFound)) Err(Api(MessageToDeleteNotFound))",
+    )
+    .await;
+
+    let responses = bot.get_responses();
+    assert!(responses.deleted_messages.is_empty());
+    let outcomes = responses
+        .delete_outcomes
+        .iter()
+        .map(|outcome| (outcome.message_id, outcome.deleted))
+        .collect::<Vec<_>>();
+    assert_eq!(outcomes, [(404, false), (100, false)]);
+}
+
+#[tokio::test]
+async fn test_responses_seq() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/edit"), get_schema());
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages_text[0].seq, 1);
+    assert_eq!(responses.edited_messages_text[0].seq, 2);
+
+    bot.update(MockMessageText::new().text("/delete"));
+    bot.dispatch().await;
+
+    // The numbering starts over with the responses of every dispatch
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages_text[0].seq, 1);
+    assert_eq!(responses.deleted_messages[0].seq, 2);
+}
+
+#[tokio::test]
+async fn test_answer_callback_query() {
+    let mut bot = MockBot::new(MockCallbackQuery::new().data("test"), get_schema());
+
+    bot.dispatch().await;
+
+    let answered_callback = bot.get_responses().answered_callback_queries.pop().unwrap();
+
+    assert_eq!(answered_callback.text, Some("test".to_string()));
+}
+
+fn get_callback_alert_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>>
+{
+    Update::filter_callback_query().endpoint(|call: CallbackQuery, bot: Bot| async move {
+        if call.data.as_deref() == Some("toast") {
+            bot.answer_callback_query(call.id).text("toast").await?;
+            return Ok(());
+        }
+        bot.answer_callback_query(call.id)
+            .text("Are you sure?")
+            .show_alert(true)
+            .url("https://t.me/test_bot?start=game".parse().unwrap())
+            .cache_time(30)
+            .await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_callback_answer_fields() {
+    let mut bot = MockBot::new(MockCallbackQuery::new(), get_callback_alert_schema());
+
+    bot.dispatch().await;
+
+    bot.assert_last_callback_answer_text("Are you sure?");
+    bot.assert_last_callback_answer_alert(true);
+    bot.assert_last_callback_answer_url(Some("https://t.me/test_bot?start=game"));
+    bot.assert_last_callback_answer_cache_time(30);
+
+    bot.update(MockCallbackQuery::new().data("toast"));
+    bot.dispatch().await;
+
+    bot.assert_last_callback_answer_text("toast");
+    bot.assert_last_callback_answer_alert(false);
+    bot.assert_last_callback_answer_url(None);
+    bot.assert_last_callback_answer_cache_time(0);
+}
+
+fn get_game_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_callback_query().endpoint(|call: CallbackQuery, bot: Bot| async move {
+        let url = match call.game_short_name {
+            Some(game) => format!("https://example.com/games/{game}"),
+            None => call.data.unwrap(),
+        };
+        bot.answer_callback_query(call.id)
+            .url(url.parse().unwrap())
+            .await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_callback_answer_url_when_validating_like_telegram() {
+    let mut bot = MockBot::new(
+        MockCallbackQuery::new().game_short_name("tetris"),
+        get_game_schema(),
+    );
+    let error_handler = Arc::new(MyErrorHandler::new());
+    bot.error_handler(error_handler.clone());
+    bot.validate_like_telegram();
+
+    bot.dispatch().await;
+    bot.assert_last_callback_answer_url(Some("https://example.com/games/tetris"));
+    // Other queries can only open the bot
+    bot.update(MockCallbackQuery::new().data("https://t.me/test_bot?start=tetris"));
+    bot.dispatch().await;
+    bot.assert_last_callback_answer_url(Some("https://t.me/test_bot?start=tetris"));
+    bot.update(MockCallbackQuery::new().data("https://example.com/games/tetris"));
+    bot.dispatch().await;
+
+    let errors = error_handler.errors();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("URL_INVALID"));
+    bot.assert_request("answerCallbackQuery", json_contains!({}));
+    bot.assert_request("answerCallbackQuery", json_contains!({}));
+    bot.assert_request(
+        "answerCallbackQuery",
+        json_contains!({"url": "https://example.com/games/tetris"}),
+    );
+    bot.assert_request("sendMessage", json_contains!({"text": "Error detected!"}));
+}
+
+#[tokio::test]
+async fn test_click_inline_button() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/editreplymarkup"),
+        get_schema(),
+    );
+    bot.dispatch().await;
+    // The markup was added by an edit, so the sent message doesn't have it yet
+    let sent_message = bot.get_responses().sent_messages.last().unwrap().clone();
+
+    bot.click_inline_button(&sent_message, "test");
+    bot.dispatch().await;
+
+    let answered_callback = bot.get_responses().answered_callback_queries.pop().unwrap();
+    assert_eq!(answered_callback.text, Some("test".to_string()));
+}
+
+#[tokio::test]
+#[should_panic(expected = "The message has no \"Next\" callback button!")]
+async fn test_click_missing_inline_button() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/editreplymarkup"),
+        get_schema(),
+    );
+    bot.dispatch().await;
+    let sent_message = bot.get_responses().sent_messages.last().unwrap().clone();
+
+    bot.click_inline_button(&sent_message, "Next");
+}
+
+fn page_keyboard(page: u8) -> InlineKeyboardMarkup {
+    let mut row = vec![];
+    if page > 1 {
+        row.push(InlineKeyboardButton::callback(
+            "Back",
+            format!("page:{}", page - 1),
+        ));
+    }
+    if page < 3 {
+        row.push(InlineKeyboardButton::callback(
+            "Next",
+            format!("page:{}", page + 1),
+        ));
+    }
+    InlineKeyboardMarkup::new(vec![row])
+}
+
+fn get_pagination_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    dptree::entry()
+        .branch(
+            Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+                bot.send_message(message.chat.id, "Page 1")
+                    .reply_markup(page_keyboard(1))
+                    .await?;
+                Ok(())
+            }),
+        )
+        .branch(Update::filter_callback_query().endpoint(
+            |call: CallbackQuery, bot: Bot| async move {
+                let page: u8 = call.data.as_deref().unwrap()["page:".len()..].parse()?;
+                let message = call.regular_message().unwrap();
+                bot.edit_message_text(message.chat.id, message.id, format!("Page {page}"))
+                    .reply_markup(page_keyboard(page))
+                    .await?;
+                bot.answer_callback_query(call.id).await?;
+                Ok(())
+            },
+        ))
+}
+
+#[tokio::test]
+async fn test_edits_keep_date() {
+    let date = Utc::now() - TimeDelta::hours(1);
+    let reply_to = MockMessageText::new().text("Hi").id(99).build();
+    let user_message = MockMessageText::new()
+        .text("/list")
+        .id(100)
+        .date(date)
+        .reply_to(reply_to);
+    let mut bot = MockBot::new(user_message, get_pagination_schema());
+    bot.dispatch().await;
+    let page = bot.get_responses().sent_messages.pop().unwrap();
+    assert_eq!(page.edit_date(), None);
+
+    bot.click_inline_button(&page, "Next");
+    bot.dispatch().await;
+    let edited_page = bot
+        .get_responses()
+        .edited_messages_text
+        .pop()
+        .unwrap()
+        .message;
+    assert_eq!(edited_page.text(), Some("Page 2"));
+    assert_eq!(edited_page.date.timestamp(), page.date.timestamp());
+    assert!(edited_page.edit_date().is_some());
+
+    // The user edits the text, the edited message leaves out the reply
+    bot.update(MockEditedMessage::new(
+        MockMessageText::new().text("/list again").id(100).build(),
+    ));
+    bot.dispatch().await;
+    let edited_message = bot
+        .find_messages(|message| message.id.0 == 100)
+        .pop()
+        .unwrap();
+    assert_eq!(edited_message.text(), Some("/list again"));
+    assert_eq!(edited_message.date.timestamp(), date.timestamp());
+    assert_eq!(edited_message.reply_to_message().unwrap().id.0, 99);
+    assert!(edited_message.edit_date().is_some());
+}
+
+#[tokio::test]
+async fn test_walk_pages() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_pagination_schema());
+    bot.dispatch().await;
+    let message = bot.get_responses().sent_messages.pop().unwrap();
+
+    let pages = bot.walk_pages(&message, "Next").await;
+
+    let texts: Vec<_> = pages.iter().map(|page| page.text().unwrap()).collect();
+    assert_eq!(texts, vec!["Page 1", "Page 2", "Page 3"]);
+    assert_eq!(pages[2].reply_markup(), Some(&page_keyboard(3)));
+    assert_eq!(
+        bot.keyboard_history(&message),
+        vec![page_keyboard(1), page_keyboard(2), page_keyboard(3)]
+    );
+
+    let pages = bot.walk_pages(&message, "Back").await;
+    let texts: Vec<_> = pages.iter().map(|page| page.text().unwrap()).collect();
+    assert_eq!(texts, vec!["Page 3", "Page 2", "Page 1"]);
+}
+
+#[tokio::test]
+async fn test_transcript() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/list"),
+        get_pagination_schema(),
+    );
+    bot.dispatch().await;
+    let message = bot.get_responses().sent_messages.pop().unwrap();
+    bot.click_inline_button(&message, "Next");
+    bot.dispatch().await;
+
+    assert_eq!(
+        bot.transcript(),
+        "User: /list\n\
+         Bot: Page 1\n\
+         User clicked \"Next\"\n\
+         Bot edited msg 2: Page 2\n\
+         Bot answered the callback query"
+    );
+
+    bot.reset();
+    assert_eq!(bot.transcript(), "");
+}
+
+#[tokio::test]
+async fn test_on_request() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/list"),
+        get_pagination_schema(),
+    );
+    let methods = Arc::new(Mutex::new(vec![]));
+    bot.on_request({
+        let methods = methods.clone();
+        move |method, _| methods.lock().unwrap().push(method.to_string())
+    });
+    bot.on_request(|method, body| {
+        if method == "editMessageText" {
             assert!(
-                gotten_document.meta.unique_id
-                    == document_message.document().unwrap().file.unique_id
+                body["reply_markup"].is_object(),
+                "The page lost its keyboard"
             );
-            let mut dest = tokio::fs::File::create("test.txt").await?;
-
-            bot.download_file(&gotten_document.path, &mut dest).await?;
-            assert!(tokio::fs::read_to_string("test.txt").await.is_ok());
-            tokio::fs::remove_file("test.txt").await?;
-        }
-        AllCommands::Animation => {
-            let animation = InputFile::memory("somedata".to_string()).file_name("animation.mp4");
-            bot.send_animation(msg.chat.id, animation).await?;
-        }
-        AllCommands::Location => {
-            bot.send_location(msg.chat.id, 1.0, 1.0)
-                .live_period(60.into())
-                .reply_parameters(reply_options)
-                .await?;
-        }
-        AllCommands::Venue => {
-            bot.send_venue(msg.chat.id, 1.0, 1.0, "test", "test")
-                .reply_parameters(reply_options)
-                .await?;
-        }
-        AllCommands::Contact => {
-            bot.send_contact(msg.chat.id, "123456789", "test")
-                .reply_parameters(reply_options)
-                .await?;
-        }
-        AllCommands::Dice => {
-            bot.send_dice(msg.chat.id).await?;
         }
-        AllCommands::Poll => {
-            bot.send_poll(
-                msg.chat.id,
-                "what is test",
-                vec!["test".to_string().into(), "not test".to_string().into()],
-            )
-            .type_(PollType::Quiz)
-            .reply_parameters(reply_options)
-            .explanation("because test")
-            .correct_option_id(0)
-            .await?;
-        }
-        AllCommands::Sticker => {
-            let sticker = InputFile::memory("somedata".to_string()).file_name("test.webp");
-            bot.send_sticker(msg.chat.id, sticker)
-                .reply_parameters(reply_options)
-                .await?;
-        }
-        AllCommands::MediaGroup => {
-            let audio1 = InputFile::memory("somedata".to_string()).file_name("audio1.mp3");
-            let audio2 = InputFile::memory("somedata2".to_string()).file_name("audio2.mp3");
-            let media_group = vec![
-                InputMedia::Audio(InputMediaAudio::new(audio1.clone()).caption("test")),
-                InputMedia::Audio(InputMediaAudio::new(audio2.clone())),
-            ];
-            bot.send_media_group(msg.chat.id, media_group)
-                .reply_parameters(reply_options.clone())
-                .await?;
+    });
 
-            let document1 = InputFile::memory("somedata".to_string()).file_name("document1.txt");
-            let document2 = InputFile::memory("somedata2".to_string()).file_name("document2.txt");
-            let media_group = vec![
-                InputMedia::Document(InputMediaDocument::new(document1.clone()).caption("test")),
-                InputMedia::Document(InputMediaDocument::new(document2.clone())),
-            ];
-            bot.send_media_group(msg.chat.id, media_group)
-                .reply_parameters(reply_options.clone())
-                .await?;
+    bot.dispatch().await;
+    let message = bot.get_responses().sent_messages.pop().unwrap();
+    bot.click_inline_button(&message, "Next");
+    bot.dispatch().await;
 
-            let photo1 = InputFile::memory("somedata".to_string());
-            let photo2 = InputFile::memory("somedata2".to_string());
-            let media_group = vec![
-                InputMedia::Photo(InputMediaPhoto::new(photo1.clone()).caption("test")),
-                InputMedia::Photo(InputMediaPhoto::new(photo2.clone())),
-            ];
-            bot.send_media_group(msg.chat.id, media_group)
-                .reply_parameters(reply_options.clone())
-                .await?;
+    let methods = methods.lock().unwrap().clone();
+    assert_eq!(
+        methods
+            .iter()
+            .filter(|method| *method != "getMe")
+            .collect::<Vec<_>>(),
+        ["sendMessage", "editMessageText", "answerCallbackQuery"]
+    );
+}
 
-            let video1 = InputFile::memory("somedata".to_string()).file_name("video1.mp4");
-            let video2 = InputFile::memory("somedata2".to_string()).file_name("video2.mp4");
-            let media_group = vec![
-                InputMedia::Video(InputMediaVideo::new(video1.clone()).caption("test")),
-                InputMedia::Video(InputMediaVideo::new(video2.clone())),
-            ];
-            bot.send_media_group(msg.chat.id, media_group)
-                .reply_parameters(reply_options)
-                .await?;
-        }
-        AllCommands::PinMessage => {
-            bot.pin_chat_message(msg.chat.id, sent_message.id).await?;
-            bot.unpin_chat_message(msg.chat.id).await?;
-            bot.unpin_all_chat_messages(msg.chat.id).await?;
-        }
-        AllCommands::ForwardMessage => {
-            bot.forward_message(msg.chat.id, msg.chat.id, sent_message.id)
-                .await?;
-        }
-        AllCommands::CopyMessage => {
-            let document = InputFile::memory("somedata".to_string()).file_name("test.txt");
-            let document_message = bot.send_document(msg.chat.id, document).await?;
-            bot.copy_message(msg.chat.id, msg.chat.id, document_message.id)
-                .caption("test")
-                .reply_markup(InlineKeyboardMarkup::new(vec![vec![
-                    InlineKeyboardButton::callback("test", "test"),
-                ]]))
-                .await?;
-        }
-        AllCommands::Ban => {
-            bot.ban_chat_member(msg.chat.id, msg.from.clone().unwrap().id)
-                .revoke_messages(true)
-                .await?;
-            // Test revoking messages
-            let result = bot.delete_message(msg.chat.id, msg.id).await;
-            assert!(result.is_err());
-            bot.unban_chat_member(msg.chat.id, msg.from.unwrap().id)
-                .await?;
-        }
-        AllCommands::Restrict => {
-            bot.restrict_chat_member(msg.chat.id, msg.from.unwrap().id, ChatPermissions::empty())
-                .await?;
-        }
-        AllCommands::ChatAction => {
-            bot.send_chat_action(msg.chat.id, ChatAction::Typing)
-                .await?;
-        }
-        AllCommands::SetMessageReaction => {
-            bot.set_message_reaction(msg.chat.id, msg.id)
-                .reaction(vec![ReactionType::Emoji {
-                    emoji: "👍".to_owned(),
-                }])
-                .await?;
-        }
-        AllCommands::SetMyCommands => {
-            bot.set_my_commands(vec![BotCommand {
-                command: String::from("test"),
-                description: String::from("test"),
-            }])
-            .await?;
-        }
-        AllCommands::Panic => {
-            // This message id does not exist
-            bot.send_message(msg.chat.id, "test")
-                .reply_to(MessageId(344382918))
-                .await?;
-        }
-        AllCommands::Invoice => {
-            bot.send_invoice(
-                msg.chat.id,
-                "Absolutely Nothing",
-                "Demo",
-                "test_payload",
-                "XTR",
-                vec![LabeledPrice {
-                    label: "Stars".into(),
-                    amount: 1,
-                }],
-            )
-            .await?;
+#[tokio::test]
+#[should_panic(expected = "No brand footer in \"Page 1\"")]
+async fn test_on_request_failure() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/list"),
+        get_pagination_schema(),
+    );
+    bot.on_request(|method, body| {
+        if method == "sendMessage" {
+            let text = body["text"].as_str().unwrap();
+            assert!(text.ends_with("— Acme"), "No brand footer in {text:?}");
         }
+    });
+
+    bot.dispatch().await;
+}
+
+fn get_unsupported_method_schema(
+) -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        bot.send_message(message.chat.id, "test").await?;
+        bot.send_message(message.chat.id, "test").await?;
+        // The fake server fails the methods it doesn't implement
+        let _ = raw_json_bot_request(&bot, "SendGift", serde_json::json!({})).await;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_route_coverage() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_unsupported_method_schema());
+
+    bot.dispatch().await;
+    // The calls before a reset are still counted
+    bot.reset();
+    bot.dispatch().await;
+
+    let coverage = bot.route_coverage();
+    assert_eq!(coverage.calls["sendMessage"], 4);
+    assert_eq!(coverage.calls["sendGift"], 2);
+    assert_eq!(coverage.unsupported(), ["sendGift"]);
+    let uncalled = coverage.uncalled();
+    assert!(uncalled.contains(&"sendPhoto".to_string()));
+    assert!(!uncalled.contains(&"sendMessage".to_string()));
+
+    let report = coverage.to_string();
+    assert!(report.contains("sendMessage: 4\n"));
+    assert!(report.contains("Not implemented: sendGift\n"));
+
+    let mut total = coverage.clone();
+    total.merge(&coverage);
+    assert_eq!(total.calls["sendMessage"], 8);
+}
+
+#[test]
+fn test_merge_coverage_in_parallel() {
+    let path = std::env::temp_dir().join(format!(
+        "teloxide_tests_parallel_coverage_{}.json",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    let mut coverage = RouteCoverage::default();
+    coverage.calls.insert("sendMessage".to_string(), 1);
+
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let (path, coverage) = (path.clone(), coverage.clone());
+            std::thread::spawn(move || {
+                for _ in 0..10 {
+                    coverage.merge_into_json(&path).unwrap();
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
     }
-    Ok(())
+
+    let total = RouteCoverage::read_json(&path).unwrap();
+    assert_eq!(total.calls["sendMessage"], 80);
+    std::fs::remove_file(&path).unwrap();
+}
+
+fn get_update_id_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|update: Update, message: Message, bot: Bot| async move {
+        let text = format!("{}: {}", update.id.0, message.text().unwrap());
+        bot.send_message(message.chat.id, text).await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_update_delivery() {
+    let mut bot = MockBot::new(
+        vec![
+            MockMessageText::new().text("first"),
+            MockMessageText::new().text("second"),
+        ],
+        get_update_id_schema(),
+    );
+    let [first_id, second_id] = [0, 1].map(|i| bot.updates[i].id.0);
+    let sent_texts = |bot: &DefaultMockBot| {
+        bot.get_responses()
+            .sent_messages
+            .iter()
+            .map(|message| message.text().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    bot.set_update_delivery(UpdateDelivery::Duplicated);
+    bot.dispatch().await;
+    assert_eq!(
+        sent_texts(&bot),
+        [
+            format!("{first_id}: first"),
+            format!("{first_id}: first"),
+            format!("{second_id}: second"),
+            format!("{second_id}: second"),
+        ]
+    );
+
+    bot.set_update_delivery(UpdateDelivery::Reversed);
+    bot.dispatch().await;
+    assert_eq!(
+        sent_texts(&bot),
+        [format!("{second_id}: second"), format!("{first_id}: first")]
+    );
+}
+
+#[tokio::test]
+async fn test_switch_inline_query() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/switchinline"), get_schema());
+    bot.dispatch().await;
+    let sent_message = bot.get_responses().sent_messages.last().unwrap().clone();
+
+    bot.switch_inline_query(&sent_message, "Share");
+    let UpdateKind::InlineQuery(query) = &bot.updates[0].kind else {
+        panic!("The update is not an inline query!");
+    };
+    assert_eq!(query.chat_type, Some(ChatType::Private));
+    bot.dispatch().await;
+
+    let answered_query = bot.get_responses().answered_inline_queries.pop().unwrap();
+    let InlineQueryResult::Article(article) = &answered_query.results[0] else {
+        panic!("The result is not an article!");
+    };
+    assert_eq!(article.title, "cats");
+
+    bot.switch_inline_query(&sent_message, "Here");
+    let UpdateKind::InlineQuery(query) = &bot.updates[0].kind else {
+        panic!("The update is not an inline query!");
+    };
+    assert_eq!(query.query, "dogs");
+    assert_eq!(query.chat_type, Some(ChatType::Sender));
+}
+
+#[tokio::test]
+#[should_panic(expected = "The message has no \"Send\" switch inline query button!")]
+async fn test_switch_missing_inline_query_button() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/switchinline"), get_schema());
+    bot.dispatch().await;
+    let sent_message = bot.get_responses().sent_messages.last().unwrap().clone();
+
+    bot.switch_inline_query(&sent_message, "Send");
+}
+
+#[tokio::test]
+async fn test_special_buttons() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/specialbuttons"), get_schema());
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let sent_message = responses.sent_messages.last().unwrap();
+    let copy_text_button = &responses.copy_text_buttons[0];
+    assert_eq!(copy_text_button.message.id, sent_message.id);
+    assert_eq!(copy_text_button.text, "Copy");
+    assert_eq!(copy_text_button.copy_text.text, "PROMO");
+    let login_url_button = &responses.login_url_buttons[0];
+    assert_eq!(login_url_button.message.id, sent_message.id);
+    assert_eq!(login_url_button.text, "Log in");
+    assert_eq!(
+        login_url_button.login_url.url.as_str(),
+        "https://example.com/login"
+    );
+    assert_eq!(login_url_button.login_url.request_write_access, Some(true));
 }
 
-async fn callback_handler(
-    bot: Bot,
-    call: CallbackQuery,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-    bot.answer_callback_query(call.id)
-        .text(call.data.unwrap())
-        .await?;
-    Ok(())
+#[tokio::test]
+async fn test_invalid_copy_text_button() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/invalidbutton copy_text"),
+        get_schema(),
+    );
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    // Only the echo of the command was sent
+    assert_eq!(responses.sent_messages.len(), 1);
+    assert!(responses.copy_text_buttons.is_empty());
 }
 
-fn get_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
-    dptree::entry()
-        .branch(
-            Update::filter_message()
-                .filter_command::<AllCommands>()
-                .endpoint(handler),
-        )
-        .branch(
-            Update::filter_edited_message()
-                .filter_command::<AllCommands>()
-                .branch(case![AllCommands::ForwardMessage].endpoint(handler)),
-        )
-        .branch(Update::filter_callback_query().endpoint(callback_handler))
+#[tokio::test]
+async fn test_invalid_login_url_button() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/invalidbutton login_url"),
+        get_schema(),
+    );
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages.len(), 1);
+    assert!(responses.login_url_buttons.is_empty());
 }
 
 #[tokio::test]
-async fn test_echo() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/echo echo"), get_schema());
+async fn test_assert_entity() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/entities"), get_schema());
 
     bot.dispatch().await;
 
-    let last_response = bot.get_responses().sent_messages.pop().unwrap();
+    let sent_message = bot.get_responses().sent_messages.last().unwrap().clone();
+    bot.assert_entity(&sent_message, MessageEntityKind::Bold, "Hello");
+    bot.assert_last_entity(MessageEntityKind::Italic, "world");
 
-    assert_eq!(last_response.text(), Some("/echo echo"));
+    bot.update(MockMessageText::new().text("/photo"));
+    bot.dispatch().await;
+
+    // Caption entities are checked for media
+    bot.assert_last_entity(MessageEntityKind::Bold, "tes");
 }
 
 #[tokio::test]
-#[should_panic]
-async fn test_panic() {
-    // Nothing else should fail because this panics
-    let bot = MockBot::new(MockMessageText::new().text("/panic"), get_schema());
+async fn test_last_sent_message_as_markup() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/entities"), get_schema());
 
-    // To actually keep the bot in scope
-    if true {
-        panic!("Expected panic");
-    }
+    bot.dispatch().await;
 
-    drop(bot);
-}
+    let responses = bot.get_responses();
+    assert_eq!(
+        responses.last_sent_message_as_html(),
+        "👋 <b>Hello</b>, <i>world</i>!"
+    );
+    assert_eq!(
+        responses.last_sent_message_as_markdown_v2(),
+        "👋 *Hello*, _\rworld_\r\\!"
+    );
 
-pub struct MyErrorHandler {
-    errors: Arc<RwLock<Vec<String>>>,
+    bot.update(MockMessageText::new().text("/photo"));
+    bot.dispatch().await;
+
+    // Captions are rendered for media
+    assert_eq!(
+        bot.get_responses().last_sent_message_as_html(),
+        "<b>tes</b>t"
+    );
 }
 
-impl MyErrorHandler {
-    pub fn new() -> Self {
-        Self {
-            errors: Arc::new(RwLock::new(vec![])),
-        }
-    }
+#[tokio::test]
+#[should_panic(expected = "The message has no Bold entity with \"world\" text!")]
+async fn test_assert_missing_entity() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/entities"), get_schema());
 
-    pub fn errors(&self) -> Vec<String> {
-        self.errors.read().unwrap().clone()
-    }
+    bot.dispatch().await;
+
+    bot.assert_last_entity(MessageEntityKind::Bold, "world");
 }
 
-impl<E> ErrorHandler<E> for MyErrorHandler
-where
-    E: std::fmt::Debug + Display + 'static + Sync + Send,
-{
-    fn handle_error(self: Arc<Self>, error: E) -> BoxFuture<'static, ()> {
-        thread::spawn(|| {
-            respond_to_error();
-        })
-        .join()
-        .unwrap();
+#[tokio::test]
+async fn test_pin_message() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/pinmessage"), get_schema());
 
-        self.errors.write().unwrap().push(format!("{error:?}"));
-        Box::pin(async {})
-    }
+    bot.dispatch().await;
+
+    let pinned_message = bot.get_responses().pinned_chat_messages.pop();
+    let unpinned_message = bot.get_responses().unpinned_chat_messages.pop();
+    let unpinned_all_chat_messages = bot.get_responses().unpinned_all_chat_messages.pop();
+
+    assert!(pinned_message.is_some());
+    assert!(unpinned_message.is_some());
+    assert!(unpinned_all_chat_messages.is_some());
 }
 
-#[tokio::main]
-async fn respond_to_error() {
-    let bot = Bot::from_env();
-    bot.send_message(ChatId(MockUser::ID as i64), "Error detected!")
-        .await
-        .unwrap();
+fn get_relay_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let chat_id = message.chat.id;
+        let forwarded = bot.forward_message(chat_id, chat_id, message.id).await?;
+        let forwarded_again = bot.forward_message(chat_id, chat_id, forwarded.id).await?;
+        bot.copy_message(chat_id, chat_id, forwarded_again.id)
+            .await?;
+        Ok(())
+    })
 }
 
 #[tokio::test]
-async fn test_error_handler() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/panic"), get_schema());
-    let error_handler = Arc::new(MyErrorHandler::new());
-    bot.error_handler(error_handler.clone());
+async fn test_forward_origin_chain() {
+    let mut bot = MockBot::new(MockMessageText::new().text("Leak"), get_relay_schema());
+    bot.dispatch().await;
 
-    bot.dispatch_and_check_last_text("Error detected!").await;
+    let responses = bot.get_responses();
+    let forwarded = &responses.forwarded_messages[0].message;
+    let forwarded_again = &responses.forwarded_messages[1].message;
+    assert_eq!(
+        forwarded.forward_from_user().unwrap().id,
+        UserId(MockUser::ID)
+    );
+    // The second forward points to the user, not to the bot that forwarded it first
+    assert_eq!(forwarded_again.forward_origin(), forwarded.forward_origin());
 
-    let errors = error_handler.errors();
-    assert_eq!(errors.len(), 1);
-    assert!(errors[0].contains("Message not found"));
+    let copied_id = responses.copied_messages[0].message_id;
+    let copied = bot
+        .find_messages(|message| message.id == copied_id)
+        .pop()
+        .unwrap();
+    assert_eq!(copied.text(), Some("Leak"));
+    assert_eq!(copied.forward_origin(), None);
 }
 
 #[tokio::test]
-async fn test_no_updates() {
-    let empty: Vec<MockMessageDice> = vec![];
-    let mut bot = MockBot::new(empty, get_schema());
-
-    // This shouldn't panic
+async fn test_venue_place_ids_relay() {
+    let venue = MockMessageVenue::new()
+        .foursquare_id("4sq_id")
+        .google_place_id("google_id")
+        .google_place_type("cafe");
+    let mut bot = MockBot::new(venue, get_relay_schema());
     bot.dispatch().await;
 
-    // Just to test that everything is fine
-    bot.update(MockMessageText::new().text("/echo echo"));
-    bot.dispatch_and_check_last_text("/echo echo").await;
+    let responses = bot.get_responses();
+    let copied_id = responses.copied_messages[0].message_id;
+    let copied = bot
+        .find_messages(|message| message.id == copied_id)
+        .pop()
+        .unwrap();
+    for message in [&responses.forwarded_messages[1].message, &copied] {
+        let venue = message.venue().unwrap();
+        assert_eq!(venue.foursquare_id.as_deref(), Some("4sq_id"));
+        assert_eq!(venue.google_place_id.as_deref(), Some("google_id"));
+        assert_eq!(venue.google_place_type.as_deref(), Some("cafe"));
+    }
 }
 
 #[tokio::test]
-async fn test_send_photo() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/photo"), get_schema());
+async fn test_forward_message() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/forwardmessage"), get_schema());
 
     bot.dispatch().await;
 
-    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
-    let last_sent_photo = bot.get_responses().sent_messages_photo.pop().unwrap();
-    assert_eq!(last_sent_message.caption(), Some("test"));
+    let responses = bot.get_responses();
+    let first_sent_message = responses.sent_messages.first().unwrap();
+    let last_sent_message = responses.sent_messages.last().unwrap();
+
+    assert_eq!(last_sent_message.text(), Some("/forwardmessage"));
     assert_eq!(
-        last_sent_message.reply_to_message().unwrap().text(),
-        Some("/photo")
+        last_sent_message.forward_date(),
+        Some(first_sent_message.date)
     );
-    assert_eq!(last_sent_message.caption_entities().unwrap().len(), 1);
-    assert_eq!(last_sent_photo.bot_request.file_name, "test.jpg");
-    assert_eq!(last_sent_photo.bot_request.file_data, "somedata");
+    assert_eq!(
+        last_sent_message.forward_from_user().unwrap().id,
+        first_sent_message.from.as_ref().unwrap().id
+    );
+    assert_eq!(responses.forwarded_messages.len(), 1);
+    assert_eq!(&responses.forwarded_messages[0].message, last_sent_message);
 }
 
 #[tokio::test]
-async fn test_send_video() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/video"), get_schema());
+async fn test_channel_post_author_signature() {
+    let channel = MockChannelChat::new().build();
+    let channel_post = MockChannelPost::new(
+        MockMessageText::new()
+            .text("/forwardmessage")
+            .chat(channel.clone())
+            .author_signature("Admin")
+            .build(),
+    );
+    let mut bot = MockBot::new(channel_post, get_schema());
 
     bot.dispatch().await;
 
-    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
-    let last_sent_video = bot.get_responses().sent_messages_video.pop().unwrap();
-    assert_eq!(last_sent_message.caption(), Some("test"));
-    assert_eq!(
-        last_sent_message.reply_to_message().unwrap().text(),
-        Some("/video")
+    let responses = bot.get_responses();
+    let first_sent_message = responses.sent_messages.first().unwrap();
+    let last_sent_message = responses.sent_messages.last().unwrap();
+
+    assert_eq!(first_sent_message.chat, channel);
+    assert_eq!(first_sent_message.sender_chat, Some(channel.clone()));
+    assert_eq!(first_sent_message.from, None);
+    assert_eq!(first_sent_message.author_signature(), Some("Test Bot"));
+    match last_sent_message.forward_origin().unwrap() {
+        MessageOrigin::Channel {
+            author_signature, ..
+        } => assert_eq!(author_signature.as_deref(), Some("Test Bot")),
+        origin => panic!("Unexpected forward origin: {origin:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_unsigned_channel_post() {
+    let channel_post = MockChannelPost::new(
+        MockMessageText::new()
+            .text("/echo")
+            .chat(MockChannelChat::new().build())
+            .build(),
     );
-    assert_eq!(last_sent_message.caption_entities().unwrap().len(), 1);
-    assert_eq!(last_sent_video.bot_request.file_name, "test.mp4");
-    assert_eq!(last_sent_video.bot_request.file_data, "somedata");
+    let mut bot = MockBot::new(channel_post, get_schema());
+
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let sent_message = responses.sent_messages.last().unwrap();
+    assert!(sent_message.chat.is_channel());
+    assert_eq!(sent_message.author_signature(), None);
 }
 
 #[tokio::test]
-async fn test_send_audio() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/audio"), get_schema());
+async fn test_copy_message() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/copymessage"), get_schema());
 
     bot.dispatch().await;
 
-    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
-    let last_sent_audio = bot.get_responses().sent_messages_audio.pop().unwrap();
+    let responses = bot.get_responses();
+    let second_sent_message = responses.sent_messages.get(1).unwrap();
+    let last_sent_message = responses.sent_messages.last().unwrap();
+
+    assert!(second_sent_message.caption().is_none());
+    assert!(last_sent_message.document().is_some());
     assert_eq!(last_sent_message.caption(), Some("test"));
     assert_eq!(
-        last_sent_message.reply_to_message().unwrap().text(),
-        Some("/audio")
+        last_sent_message.reply_markup().unwrap().inline_keyboard[0][0].text,
+        "test"
     );
-    assert_eq!(last_sent_message.caption_entities().unwrap().len(), 1);
-    assert_eq!(last_sent_audio.bot_request.file_name, "test.mp3");
-    assert_eq!(last_sent_audio.bot_request.file_data, "somedata");
 }
 
 #[tokio::test]
-async fn test_send_voice() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/voice"), get_schema());
+async fn test_ban_and_unban() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/ban"), get_schema());
 
     bot.dispatch().await;
 
-    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
-    let last_sent_voice = bot.get_responses().sent_messages_voice.pop().unwrap();
-    assert_eq!(last_sent_message.caption(), Some("test"));
-    assert_eq!(
-        last_sent_message.reply_to_message().unwrap().text(),
-        Some("/voice")
-    );
-    assert_eq!(last_sent_message.caption_entities().unwrap().len(), 1);
-    assert_eq!(last_sent_voice.bot_request.file_name, "test.mp3");
-    assert_eq!(last_sent_voice.bot_request.file_data, "somedata");
+    let responses = bot.get_responses();
+    let banned_user = responses.banned_chat_members.last().unwrap();
+    let unbanned_user = responses.unbanned_chat_members.last().unwrap();
+
+    assert_eq!(banned_user.user_id, MockUser::ID);
+    assert_eq!(unbanned_user.user_id, MockUser::ID);
 }
 
 #[tokio::test]
-async fn test_send_video_note() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/videonote"), get_schema());
+async fn test_restrict() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/restrict"), get_schema());
 
     bot.dispatch().await;
 
-    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
-    let last_sent_video_note = bot.get_responses().sent_messages_video_note.pop().unwrap();
+    let responses = bot.get_responses();
+    let restricted_user = responses.restricted_chat_members.last().unwrap();
+
+    assert_eq!(restricted_user.user_id, MockUser::ID);
+    assert_eq!(restricted_user.permissions, ChatPermissions::empty());
+}
+
+fn get_moderation_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let user_id = message.from.as_ref().unwrap().id;
+        match message.text().unwrap() {
+            "/mute" => {
+                bot.restrict_chat_member(message.chat.id, user_id, ChatPermissions::SEND_POLLS)
+                    .until_date(Utc::now() + TimeDelta::hours(1))
+                    .await?;
+            }
+            "/ban" => {
+                bot.ban_chat_member(message.chat.id, user_id)
+                    .until_date(Utc::now() + TimeDelta::days(1))
+                    .await?;
+            }
+            "/unban" => {
+                bot.unban_chat_member(message.chat.id, user_id)
+                    .only_if_banned(true)
+                    .await?;
+            }
+            "/kick" => {
+                bot.unban_chat_member(message.chat.id, user_id).await?;
+            }
+            _ => {
+                let member = bot.get_chat_member(message.chat.id, user_id).await?;
+                let status = match &member.kind {
+                    ChatMemberKind::Restricted(restricted) => format!(
+                        "restricted until {:?}, messages: {}, polls: {}, photos: {}",
+                        restricted.until_date,
+                        restricted.can_send_messages,
+                        restricted.can_send_polls,
+                        restricted.can_send_photos
+                    ),
+                    ChatMemberKind::Banned(banned) => {
+                        format!("banned until {:?}", banned.until_date)
+                    }
+                    kind => format!("{:?}", kind.status()),
+                };
+                bot.send_message(message.chat.id, status).await?;
+            }
+        }
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_restrict_until_date() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/mute"),
+        get_moderation_schema(),
+    );
+    bot.dispatch().await;
+    let restricted = bot.get_responses().restricted_chat_members[0].clone();
+    let until_date = restricted.until_date().unwrap();
+    assert_eq!(restricted.until_date, Some(until_date.timestamp()));
+
+    bot.update(MockMessageText::new().text("/status"));
+    bot.dispatch().await;
+    // Polls imply messages without independent permissions
     assert_eq!(
-        last_sent_message.reply_to_message().unwrap().text(),
-        Some("/videonote")
+        bot.get_responses().sent_messages[0].text().unwrap(),
+        format!(
+            "restricted until {:?}, messages: true, polls: true, photos: false",
+            UntilDate::Date(until_date)
+        )
     );
-    assert_eq!(last_sent_video_note.bot_request.file_name, "test.mp4");
-    assert_eq!(last_sent_video_note.bot_request.file_data, "somedata");
+
+    bot.advance_time(Duration::from_secs(2 * 3600));
+    bot.dispatch().await;
+    assert_eq!(bot.get_responses().sent_messages[0].text(), Some("Member"));
 }
 
 #[tokio::test]
-async fn test_send_document() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/document"), get_schema());
-
+async fn test_ban_until_date() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/ban"), get_moderation_schema());
     bot.dispatch().await;
+    let banned = bot.get_responses().banned_chat_members[0].clone();
+    let until_date = banned.until_date().unwrap();
+    assert_eq!(banned.until_date, Some(until_date.timestamp()));
 
-    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
-    let last_sent_photo = bot.get_responses().sent_messages_document.pop().unwrap();
-    assert_eq!(last_sent_message.caption(), Some("test"));
+    bot.update(MockMessageText::new().text("/status"));
+    bot.dispatch().await;
     assert_eq!(
-        last_sent_message.reply_to_message().unwrap().text(),
-        Some("/document")
+        bot.get_responses().sent_messages[0].text().unwrap(),
+        format!("banned until {:?}", UntilDate::Date(until_date))
     );
-    assert_eq!(last_sent_message.caption_entities().unwrap().len(), 1);
-    assert_eq!(last_sent_photo.bot_request.file_name, "test.txt");
+
+    // The ban is lifted, but the user has to join the chat again
+    bot.advance_time(Duration::from_secs(2 * 24 * 3600));
+    bot.dispatch().await;
+    assert_eq!(bot.get_responses().sent_messages[0].text(), Some("Left"));
 }
 
 #[tokio::test]
-async fn test_send_animation() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/animation"), get_schema());
+async fn test_unban_only_if_banned() {
+    let chat = MockGroupChat::new().build();
+    let status_update = MockMessageText::new().text("/status").chat(chat.clone());
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/unban").chat(chat.clone()),
+        get_moderation_schema(),
+    );
+    bot.add_chat_member(
+        chat.id.0,
+        MockUser::new(),
+        ChatMemberKind::Member(Member { until_date: None }),
+    );
 
+    // A member stays in the chat
     bot.dispatch().await;
-
-    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
-    let last_sent_animation = bot.get_responses().sent_messages_animation.pop().unwrap();
     assert_eq!(
-        last_sent_message.animation().unwrap().file_name,
-        Some("animation.mp4".to_string())
+        bot.get_responses().unbanned_chat_members[0].only_if_banned,
+        Some(true)
     );
-    assert_eq!(last_sent_animation.bot_request.file_name, "animation.mp4");
+    bot.update(status_update.clone());
+    bot.dispatch().await;
+    assert_eq!(bot.get_responses().sent_messages[0].text(), Some("Member"));
+
+    // But is removed from it without `only_if_banned`
+    bot.update(MockMessageText::new().text("/kick").chat(chat.clone()));
+    bot.dispatch().await;
+    bot.update(status_update.clone());
+    bot.dispatch().await;
+    assert_eq!(bot.get_responses().sent_messages[0].text(), Some("Left"));
+
+    bot.update(MockMessageText::new().text("/ban").chat(chat.clone()));
+    bot.dispatch().await;
+    bot.update(MockMessageText::new().text("/unban").chat(chat.clone()));
+    bot.dispatch().await;
+    bot.update(status_update);
+    bot.dispatch().await;
+    assert_eq!(bot.get_responses().sent_messages[0].text(), Some("Left"));
 }
 
 #[tokio::test]
-async fn test_send_media_group() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/mediagroup"), get_schema());
+async fn test_send_chat_action() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/chataction"), get_schema());
 
     bot.dispatch().await;
 
     let responses = bot.get_responses();
+    let last_chat_action = responses.sent_chat_actions.last().unwrap();
 
-    let audio_group = responses.sent_media_group[0].clone();
-    assert_eq!(
-        audio_group.messages.first().unwrap().caption(),
-        Some("test")
-    );
-    assert_eq!(
-        audio_group
-            .messages
-            .first()
-            .unwrap()
-            .audio()
-            .unwrap()
-            .file_name,
-        Some("audio1.mp3".to_string())
-    );
-    assert_eq!(
-        audio_group
-            .messages
-            .first()
-            .unwrap()
-            .reply_to_message()
-            .unwrap()
-            .text(),
-        Some("/mediagroup")
-    );
-    assert_eq!(audio_group.bot_request.media.len(), 2);
+    assert_eq!(last_chat_action.bot_request.action, "typing");
+}
 
-    let document_group = responses.sent_media_group[1].clone();
-    assert_eq!(
-        document_group.messages.first().unwrap().caption(),
-        Some("test")
-    );
-    assert_eq!(
-        document_group
-            .messages
-            .first()
-            .unwrap()
-            .document()
-            .unwrap()
-            .file_name,
-        Some("document1.txt".to_string())
-    );
-    assert_eq!(
-        document_group
-            .messages
-            .first()
-            .unwrap()
-            .reply_to_message()
-            .unwrap()
-            .text(),
-        Some("/mediagroup")
-    );
-    assert_eq!(document_group.bot_request.media.len(), 2);
+fn get_upload_document_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>>
+{
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        bot.send_chat_action(message.chat.id, ChatAction::UploadDocument)
+            .await?;
+        let document = InputFile::memory("report").file_name("report.txt");
+        bot.send_document(message.chat.id, document).await?;
+        Ok(())
+    })
+}
 
-    let photo_group = responses.sent_media_group[2].clone();
-    assert_eq!(
-        photo_group.messages.first().unwrap().caption(),
-        Some("test")
-    );
-    assert!(!photo_group
-        .messages
-        .first()
-        .unwrap()
-        .photo()
-        .unwrap()
-        .is_empty());
-    assert_eq!(
-        photo_group
-            .messages
-            .first()
-            .unwrap()
-            .reply_to_message()
-            .unwrap()
-            .text(),
-        Some("/mediagroup")
+#[tokio::test]
+async fn test_chat_action_seq() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/report"),
+        get_upload_document_schema(),
     );
-    assert_eq!(photo_group.bot_request.media.len(), 2);
 
-    let video_group = responses.sent_media_group[3].clone();
-    assert_eq!(
-        video_group.messages.first().unwrap().caption(),
-        Some("test")
-    );
-    assert_eq!(
-        video_group
-            .messages
-            .first()
-            .unwrap()
-            .video()
-            .unwrap()
-            .file_name,
-        Some("video1.mp4".to_string())
-    );
-    assert_eq!(
-        video_group
-            .messages
-            .first()
-            .unwrap()
-            .reply_to_message()
-            .unwrap()
-            .text(),
-        Some("/mediagroup")
-    );
-    assert_eq!(video_group.bot_request.media.len(), 2);
+    bot.dispatch().await;
+
+    let responses = bot.get_responses();
+    let action = &responses.sent_chat_actions[0];
+    let document = &responses.sent_messages_document[0];
+    assert_eq!(action.bot_request.action, "upload_document");
+    assert!(action.seq < document.seq);
+}
+
+fn get_thread_chat_action_schema(
+) -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        bot.send_chat_action(message.chat.id, ChatAction::RecordVoice)
+            .message_thread_id(ThreadId(MessageId(7)))
+            .business_connection_id(BusinessConnectionId("business".to_string()))
+            .await?;
+        Ok(())
+    })
 }
 
 #[tokio::test]
-async fn test_send_location() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/location"), get_schema());
+async fn test_send_chat_action_to_thread() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("action"),
+        get_thread_chat_action_schema(),
+    );
 
     bot.dispatch().await;
 
-    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
-    let last_sent_location = bot.get_responses().sent_messages_location.pop().unwrap();
+    let responses = bot.get_responses();
+    let sent = responses.sent_chat_actions.last().unwrap();
+    assert_eq!(sent.bot_request.action, "record_voice");
+    assert_eq!(sent.bot_request.message_thread_id, Some(7));
     assert_eq!(
-        last_sent_message.reply_to_message().unwrap().text(),
-        Some("/location")
+        sent.bot_request.business_connection_id,
+        Some(BusinessConnectionId("business".to_string()))
     );
-    assert_eq!(last_sent_message.location().unwrap().latitude, 1.0);
-    assert_eq!(last_sent_message.location().unwrap().longitude, 1.0);
-    assert_eq!(last_sent_location.bot_request.live_period, Some(60.into()));
+}
+
+fn get_slow_typing_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        bot.send_chat_action(message.chat.id, ChatAction::UploadPhoto)
+            .await?;
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        bot.send_chat_action(message.chat.id, ChatAction::Typing)
+            .await?;
+        bot.send_message(message.chat.id, "Done").await?;
+        Ok(())
+    })
 }
 
 #[tokio::test]
-async fn test_send_venue() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/venue"), get_schema());
+async fn test_chat_action_sent_within() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_slow_typing_schema());
+    bot.advance_time(Duration::from_secs(3600));
 
     bot.dispatch().await;
 
-    let last_sent_venue = bot.get_responses().sent_messages_venue.pop().unwrap();
-    let last_sent_message = last_sent_venue.message;
-    assert_eq!(
-        last_sent_message.reply_to_message().unwrap().text(),
-        Some("/venue")
-    );
-    assert_eq!(last_sent_message.venue().unwrap().location.latitude, 1.0);
-    assert_eq!(last_sent_message.venue().unwrap().location.longitude, 1.0);
-    assert_eq!(last_sent_message.venue().unwrap().title, "test");
-    assert_eq!(last_sent_message.venue().unwrap().address, "test");
+    bot.assert_chat_action_sent_within(ChatAction::UploadPhoto, Duration::from_secs(1));
+    bot.assert_chat_action_sent_within(ChatAction::Typing, Duration::from_secs(5));
 }
 
 #[tokio::test]
-async fn test_send_contact() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/contact"), get_schema());
-
+async fn test_dispatch_after() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema());
     bot.dispatch().await;
+    let sent_at = bot.get_responses().sent_messages[0].date;
 
-    let last_sent_contact = bot.get_responses().sent_messages_contact.pop().unwrap();
-    let last_sent_message = last_sent_contact.message;
-    assert_eq!(
-        last_sent_message.reply_to_message().unwrap().text(),
-        Some("/contact")
-    );
-    assert_eq!(
-        last_sent_message.contact().unwrap().phone_number,
-        "123456789"
-    );
-    assert_eq!(last_sent_message.contact().unwrap().first_name, "test");
+    bot.dispatch_after(Duration::from_secs(3 * 24 * 60 * 60))
+        .await;
+    let later = bot.get_responses().sent_messages[0].date;
+    assert!(later - sent_at >= TimeDelta::days(3));
+
+    bot.dispatch().await;
+    let back = bot.get_responses().sent_messages[0].date;
+    assert!(back - sent_at < TimeDelta::days(1));
 }
 
 #[tokio::test]
-async fn test_send_dice() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/dice"), get_schema());
+#[should_panic(expected = "The \"typing\" chat action was sent")]
+async fn test_chat_action_sent_too_late() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_slow_typing_schema());
 
     bot.dispatch().await;
 
-    let last_sent_contact = bot.get_responses().sent_messages_dice.pop().unwrap();
-    let last_sent_message = last_sent_contact.message;
-    assert_eq!(last_sent_message.dice().unwrap().emoji, DiceEmoji::Dice);
-    assert!(last_sent_message.dice().unwrap().value < 100);
+    bot.assert_chat_action_sent_within(ChatAction::Typing, Duration::from_secs(1));
 }
 
 #[tokio::test]
-async fn test_send_poll() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/poll"), get_schema());
+#[should_panic(expected = "No \"record_voice\" chat actions were sent!")]
+async fn test_chat_action_not_sent() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_slow_typing_schema());
 
     bot.dispatch().await;
 
-    let last_sent_contact = bot.get_responses().sent_messages_poll.pop().unwrap();
-    let last_sent_message = last_sent_contact.message;
-    assert_eq!(
-        last_sent_message.reply_to_message().unwrap().text(),
-        Some("/poll")
-    );
-    assert_eq!(last_sent_message.poll().unwrap().question, "what is test");
-    assert_eq!(
-        last_sent_message.poll().unwrap().options,
-        vec![
-            PollOption {
-                text: "test".to_string(),
-                text_entities: None,
-                voter_count: 0
-            },
-            PollOption {
-                text: "not test".to_string(),
-                text_entities: None,
-                voter_count: 0
+    bot.assert_chat_action_sent_within(ChatAction::RecordVoice, Duration::from_secs(5));
+}
+
+type BroadcastErrors = Arc<RwLock<Vec<String>>>;
+
+fn get_broadcast_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(
+        |message: Message, bot: Bot, errors: BroadcastErrors| async move {
+            let paid = message.text() == Some("paid");
+            for i in 0..5 {
+                let result = bot
+                    .send_message(message.chat.id, format!("News {i}"))
+                    .allow_paid_broadcast(paid)
+                    .await;
+                if let Err(error) = result {
+                    errors.write().unwrap().push(format!("{error:?}"));
+                    break;
+                }
             }
-        ],
-    );
-    assert_eq!(
-        last_sent_message.poll().unwrap().explanation,
-        Some("because test".to_string())
-    );
-    assert_eq!(last_sent_message.poll().unwrap().poll_type, PollType::Quiz);
-    assert_eq!(last_sent_message.poll().unwrap().correct_option_id, Some(0));
+            Ok(())
+        },
+    )
 }
 
 #[tokio::test]
-async fn test_send_sticker() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/sticker"), get_schema());
+async fn test_flood_limit() {
+    let errors = BroadcastErrors::default();
+    let mut bot = MockBot::new(MockMessageText::new().text("free"), get_broadcast_schema());
+    bot.dependencies(deps![errors.clone()]);
+    bot.set_flood_limit(3);
 
     bot.dispatch().await;
 
-    let last_sent_contact = bot.get_responses().sent_messages_sticker.pop().unwrap();
-    let last_sent_message = last_sent_contact.message;
+    assert_eq!(bot.get_responses().sent_messages.len(), 3);
     assert_eq!(
-        last_sent_message.reply_to_message().unwrap().text(),
-        Some("/sticker")
+        errors.read().unwrap().as_slice(),
+        ["RetryAfter(Seconds(1))"]
     );
-    assert_eq!(last_sent_message.sticker().unwrap().emoji, None);
 }
 
 #[tokio::test]
-async fn test_edit_message() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/edit"), get_schema());
+async fn test_paid_broadcast() {
+    let errors = BroadcastErrors::default();
+    let mut bot = MockBot::new(MockMessageText::new().text("paid"), get_broadcast_schema());
+    bot.dependencies(deps![errors.clone()]);
+    bot.set_flood_limit(3);
+    bot.bot_star_balance(1);
 
     bot.dispatch().await;
 
-    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
-    let last_edited_response = bot.get_responses().edited_messages_text.pop().unwrap();
-
-    assert_eq!(last_sent_message.text(), Some("/edit"));
-    assert_eq!(last_edited_response.message.text(), Some("edited"));
-    assert_eq!(
-        last_edited_response
-            .bot_request
-            .link_preview_options
-            .unwrap()
-            .is_disabled,
-        true
+    // The two messages over the limit cost 0.1 stars each
+    assert_eq!(bot.get_responses().sent_messages.len(), 5);
+    assert!(errors.read().unwrap().is_empty());
+    assert_eq!(
+        bot.get_bot_star_balance(),
+        StarAmount {
+            amount: 0,
+            nanostar_amount: Some(800_000_000),
+        }
     );
 }
 
 #[tokio::test]
-async fn test_edit_message_unchanged() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/editunchanged"), get_schema());
-    let error_handler = Arc::new(MyErrorHandler::new());
-    bot.error_handler(error_handler.clone());
+async fn test_paid_broadcast_without_stars() {
+    let errors = BroadcastErrors::default();
+    let mut bot = MockBot::new(MockMessageText::new().text("paid"), get_broadcast_schema());
+    bot.dependencies(deps![errors.clone()]);
+    bot.set_flood_limit(3);
 
     bot.dispatch().await;
 
-    assert!(bot.get_responses().edited_messages_text.is_empty());
-    let errors = error_handler.errors();
-    assert_eq!(errors.len(), 1);
-    assert_eq!(errors[0], "Api(MessageNotModified)");
+    assert_eq!(bot.get_responses().sent_messages.len(), 3);
+    assert_eq!(errors.read().unwrap().len(), 1);
+    assert!(errors.read().unwrap()[0].contains("BALANCE_TOO_LOW"));
+}
+
+fn get_reaction_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message_reaction_updated().endpoint(
+        |reaction: MessageReactionUpdated, bot: Bot| async move {
+            let emojis = |reactions: &[ReactionType]| {
+                reactions
+                    .iter()
+                    .filter_map(|reaction| reaction.emoji().cloned())
+                    .collect::<Vec<_>>()
+                    .join("")
+            };
+            let text = format!(
+                "{} on {}: {:?} -> {:?}",
+                reaction.user().unwrap().id,
+                reaction.message_id,
+                emojis(&reaction.old_reaction),
+                emojis(&reaction.new_reaction),
+            );
+            bot.send_message(reaction.chat.id, text).await?;
+            Ok(())
+        },
+    )
 }
 
 #[tokio::test]
-async fn test_edit_caption() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/editcaption"), get_schema());
+async fn test_react_to_message() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_reaction_schema());
+    let message = bot.seed_messages(vec![MockMessageText::new().text("Vote!").id(100)]);
 
+    bot.react_to_message(message[0].id.0, "👍", MockUser::new().id(1).build());
+    bot.react_to_message(message[0].id.0, "👍", MockUser::new().id(2).build());
     bot.dispatch().await;
 
-    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
-    let last_edited_response = bot.get_responses().edited_messages_caption.pop().unwrap();
+    let texts = bot
+        .get_responses()
+        .sent_messages
+        .iter()
+        .map(|message| message.text().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        texts,
+        [r#"1 on 100: "" -> "👍""#, r#"2 on 100: "" -> "👍""#]
+    );
 
-    assert_eq!(last_sent_message.caption(), None);
-    assert_eq!(last_edited_response.message.caption(), Some("edited"));
+    // The first reaction after a dispatch replaces the updates
+    bot.react_to_message(message[0].id.0, "🔥", MockUser::new().id(1).build());
+    bot.dispatch_and_check_last_text(r#"1 on 100: "👍" -> "🔥""#)
+        .await;
+    assert_eq!(bot.get_responses().sent_messages.len(), 1);
 }
 
 #[tokio::test]
-async fn test_edit_reply_markup() {
+async fn test_set_message_reaction() {
     let mut bot = MockBot::new(
-        MockMessageText::new().text("/editreplymarkup"),
+        MockMessageText::new().text("/setmessagereaction"),
         get_schema(),
     );
 
     bot.dispatch().await;
 
-    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
-    let last_edited_response = bot
-        .get_responses()
-        .edited_messages_reply_markup
-        .pop()
-        .unwrap();
+    let responses = bot.get_responses();
+    let last_reaction = responses.set_message_reaction.last().unwrap();
 
-    assert_eq!(last_sent_message.reply_markup(), None);
     assert_eq!(
-        last_edited_response
-            .message
-            .reply_markup()
-            .unwrap()
-            .inline_keyboard[0][0]
-            .text,
-        "test"
+        last_reaction.reaction.clone().unwrap()[0],
+        ReactionType::Emoji {
+            emoji: "👍".to_owned()
+        }
     );
 }
 
+fn get_big_reaction_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        for emoji in ["🔥", "👍"] {
+            let reaction = ReactionType::Emoji {
+                emoji: emoji.to_string(),
+            };
+            let result = bot
+                .set_message_reaction(message.chat.id, message.id)
+                .reaction(vec![reaction])
+                .is_big(true)
+                .await;
+            if let Err(error) = result {
+                bot.send_message(message.chat.id, format!("{error:?}"))
+                    .await?;
+            }
+        }
+        Ok(())
+    })
+}
+
 #[tokio::test]
-async fn test_delete_message() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/delete"), get_schema());
+async fn test_reactions_allowed_in_chat() {
+    let chat = MockSupergroupChat::new().build();
+    let mut bot = MockBot::new(
+        MockMessageText::new().chat(chat.clone()),
+        get_big_reaction_schema(),
+    );
+    bot.set_chat_full_info(
+        MockChatFullInfoSupergroup::new()
+            .id(chat.id.0)
+            .available_reactions(vec![ReactionType::Emoji {
+                emoji: "👍".to_string(),
+            }])
+            .build(),
+    );
+    bot.validate_like_telegram();
 
     bot.dispatch().await;
 
-    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
-    let last_deleted_response = bot.get_responses().deleted_messages.pop().unwrap();
+    let responses = bot.get_responses();
+    assert_eq!(
+        responses.sent_messages[0].text(),
+        Some(r#"Api(Unknown("Bad Request: REACTION_INVALID"))"#)
+    );
+    assert_eq!(responses.set_message_reaction.len(), 1);
+    assert_eq!(responses.set_message_reaction[0].is_big, Some(true));
+    bot.assert_request("setMessageReaction", json_contains!({"is_big": true}));
+    bot.assert_request("sendMessage", json_contains!({}));
+    bot.assert_request("setMessageReaction", json_contains!({"is_big": true}));
+}
 
-    assert_eq!(last_sent_message.text(), Some("/delete"));
-    assert_eq!(last_deleted_response.message.id, last_sent_message.id);
+fn get_menu_button_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let chat_button = match message.text().unwrap() {
+            "/reset" => MenuButton::Default,
+            _ => MenuButton::WebApp {
+                text: "Shop".to_string(),
+                web_app: WebAppInfo {
+                    url: "https://example.com/shop".parse().unwrap(),
+                },
+            },
+        };
+        bot.set_chat_menu_button()
+            .chat_id(message.chat.id)
+            .menu_button(chat_button)
+            .await?;
+        bot.set_chat_menu_button().await?;
+        let chat_button = bot.get_chat_menu_button().chat_id(message.chat.id).await?;
+        bot.send_message(message.chat.id, format!("{chat_button:?}"))
+            .await?;
+        Ok(())
+    })
 }
 
 #[tokio::test]
-async fn test_delete_messages() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/deletebatch"), get_schema());
-
+async fn test_chat_menu_buttons() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/setup"),
+        get_menu_button_schema(),
+    );
     bot.dispatch().await;
 
-    let last_sent_message = bot.get_responses().sent_messages.pop().unwrap();
-    let last_deleted_response = bot.get_responses().deleted_messages.pop().unwrap();
+    let responses = bot.get_responses();
+    assert_eq!(responses.set_chat_menu_buttons.len(), 2);
+    assert_eq!(
+        responses.set_chat_menu_buttons[0]
+            .chat_id
+            .as_ref()
+            .unwrap()
+            .id(),
+        MockUser::ID as i64
+    );
+    assert!(responses.set_chat_menu_buttons[1].chat_id.is_none());
+    assert!(responses.sent_messages[0]
+        .text()
+        .unwrap()
+        .starts_with("WebApp"));
+    assert_eq!(bot.menu_button(None), MenuButton::Commands);
+    // Other chats have the default button
+    assert_eq!(bot.menu_button(Some(1)), MenuButton::Commands);
 
-    assert_eq!(last_sent_message.text(), Some("/deletebatch"));
-    assert_eq!(last_deleted_response.message.id, last_sent_message.id);
+    bot.update(MockMessageText::new().text("/reset"));
+    bot.dispatch().await;
+    assert_eq!(
+        bot.get_responses().sent_messages[0].text(),
+        Some("Commands")
+    );
+    assert_eq!(
+        bot.menu_button(Some(MockUser::ID as i64)),
+        MenuButton::Commands
+    );
 }
 
 #[tokio::test]
-async fn test_answer_callback_query() {
-    let mut bot = MockBot::new(MockCallbackQuery::new().data("test"), get_schema());
+async fn test_set_my_commands() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/setmycommands"), get_schema());
 
     bot.dispatch().await;
 
-    let answered_callback = bot.get_responses().answered_callback_queries.pop().unwrap();
+    let responses = bot.get_responses();
+    let set_commands = responses.set_my_commands.last().unwrap();
 
-    assert_eq!(answered_callback.text, Some("test".to_string()));
+    assert_eq!(
+        set_commands.commands.first(),
+        Some(&BotCommand {
+            command: String::from("test"),
+            description: String::from("test")
+        })
+    );
+}
+
+fn get_command_menu_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let commands = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| BotCommand::new(*name, format!("{name} command")))
+                .collect::<Vec<_>>()
+        };
+        bot.set_my_commands(commands(&["start"])).await?;
+        bot.set_my_commands(commands(&["start", "help"])).await?;
+        bot.set_my_commands(commands(&["start", "stats"]))
+            .scope(BotCommandScope::AllGroupChats)
+            .language_code("de")
+            .await?;
+        bot.set_my_commands(commands(&["ban"]))
+            .scope(BotCommandScope::AllChatAdministrators)
+            .await?;
+        bot.delete_my_commands()
+            .scope(BotCommandScope::AllChatAdministrators)
+            .await?;
+        let group_commands = bot
+            .get_my_commands()
+            .scope(BotCommandScope::AllGroupChats)
+            .language_code("de")
+            .await?;
+        bot.send_message(message.chat.id, group_commands.len().to_string())
+            .await?;
+        Ok(())
+    })
 }
 
 #[tokio::test]
-async fn test_pin_message() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/pinmessage"), get_schema());
+async fn test_command_menus() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/setup"),
+        get_command_menu_schema(),
+    );
 
     bot.dispatch().await;
 
-    let pinned_message = bot.get_responses().pinned_chat_messages.pop();
-    let unpinned_message = bot.get_responses().unpinned_chat_messages.pop();
-    let unpinned_all_chat_messages = bot.get_responses().unpinned_all_chat_messages.pop();
+    let responses = bot.get_responses();
+    assert_eq!(responses.sent_messages.last().unwrap().text(), Some("2"));
+    assert_eq!(responses.deleted_my_commands.len(), 1);
+    bot.assert_commands(vec![
+        (
+            BotCommandScope::Default,
+            None,
+            vec![("start", "start command"), ("help", "help command")],
+        ),
+        (
+            BotCommandScope::AllGroupChats,
+            Some("de"),
+            vec![("start", "start command"), ("stats", "stats command")],
+        ),
+    ]);
+    assert!(bot
+        .commands(Some(BotCommandScope::AllGroupChats), None)
+        .is_empty());
+}
 
-    assert!(pinned_message.is_some());
-    assert!(unpinned_message.is_some());
-    assert!(unpinned_all_chat_messages.is_some());
+#[tokio::test]
+#[should_panic(expected = "The commands of the bot are not equal!")]
+async fn test_command_menus_mismatch() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/setup"),
+        get_command_menu_schema(),
+    );
+
+    bot.dispatch().await;
+
+    bot.assert_commands(vec![(
+        BotCommandScope::Default,
+        None,
+        vec![("start", "start command")],
+    )]);
 }
 
 #[tokio::test]
-async fn test_forward_message() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/forwardmessage"), get_schema());
+async fn test_business_account() {
+    let mut bot = MockBot::new(
+        MockMessageText::new().text("/businessaccount"),
+        get_schema(),
+    );
+    bot.business_star_balance(250);
+    bot.business_gift(MockOwnedGift::new().owned_gift_id("unsaved"));
+    bot.business_gift(MockOwnedGift::new().owned_gift_id("saved").is_saved(true));
 
     bot.dispatch().await;
 
     let responses = bot.get_responses();
-    let first_sent_message = responses.sent_messages.first().unwrap();
-    let last_sent_message = responses.sent_messages.last().unwrap();
+    assert_eq!(responses.read_business_messages.len(), 1);
+    assert_eq!(
+        responses.read_business_messages[0].chat_id,
+        MockMessageText::new().build().chat.id.0
+    );
 
-    assert_eq!(last_sent_message.text(), Some("/forwardmessage"));
+    let echo_message_id = responses.sent_messages[0].id.0;
     assert_eq!(
-        last_sent_message.forward_date(),
-        Some(first_sent_message.date)
+        responses.deleted_business_messages[0].message_ids,
+        vec![echo_message_id]
     );
     assert_eq!(
-        last_sent_message.forward_from_user().unwrap().id,
-        first_sent_message.from.as_ref().unwrap().id
+        responses.set_business_account_names[0].first_name,
+        "Business"
+    );
+    assert_eq!(
+        responses.set_business_account_bios[0].bio.as_deref(),
+        Some("Open 24/7")
+    );
+
+    let profile_photo = &responses.set_business_account_profile_photos[0];
+    assert_eq!(
+        profile_photo.photo,
+        server::InputProfilePhoto::Static {
+            photo: "attach://avatar".to_string()
+        }
+    );
+    assert_eq!(profile_photo.file_name, "avatar.jpg");
+    assert_eq!(profile_photo.file_data, "photo");
+
+    assert_eq!(responses.fetched_business_account_star_balances.len(), 1);
+    assert_eq!(
+        responses.fetched_business_account_gifts[0].exclude_unsaved,
+        Some(true)
+    );
+    assert_eq!(
+        responses.sent_messages.last().unwrap().text(),
+        Some("250 \"saved\"")
     );
-    assert_eq!(responses.forwarded_messages.len(), 1);
-    assert_eq!(&responses.forwarded_messages[0].message, last_sent_message);
 }
 
 #[tokio::test]
-async fn test_copy_message() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/copymessage"), get_schema());
+async fn test_convert_upgrade_and_transfer_gifts() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/gifts"), get_schema());
+    bot.business_star_balance(100);
+    bot.business_gift(
+        MockOwnedGift::new()
+            .owned_gift_id("convertible")
+            .convert_star_count(10u32),
+    );
+    bot.business_gift(
+        MockOwnedGift::new()
+            .owned_gift_id("upgradable")
+            .can_be_upgraded(true),
+    );
 
     bot.dispatch().await;
 
     let responses = bot.get_responses();
-    let second_sent_message = responses.sent_messages.get(1).unwrap();
-    let last_sent_message = responses.sent_messages.last().unwrap();
-
-    assert!(second_sent_message.caption().is_none());
-    assert!(last_sent_message.document().is_some());
-    assert_eq!(last_sent_message.caption(), Some("test"));
     assert_eq!(
-        last_sent_message.reply_markup().unwrap().inline_keyboard[0][0].text,
-        "test"
+        responses.converted_gifts_to_stars[0].owned_gift_id,
+        "convertible"
     );
+    assert_eq!(responses.upgraded_gifts[0].star_count, Some(25));
+    assert_eq!(responses.transferred_gifts[0].owned_gift_id, "upgradable");
+
+    let sent_texts: Vec<_> = responses
+        .sent_messages
+        .iter()
+        .map(|message| message.text().unwrap())
+        .collect();
+    // 100 + 10 for the conversion - 25 for the upgrade
+    assert_eq!(sent_texts[1..], ["\"unique\"", "85 0"]);
 }
 
 #[tokio::test]
-async fn test_ban_and_unban() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/ban"), get_schema());
+async fn test_upgrade_gift_with_low_balance() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/gifts"), get_schema());
+    bot.business_gift(
+        MockOwnedGift::new()
+            .owned_gift_id("convertible")
+            .convert_star_count(10u32),
+    );
+    bot.business_gift(
+        MockOwnedGift::new()
+            .owned_gift_id("upgradable")
+            .can_be_upgraded(true),
+    );
 
     bot.dispatch().await;
 
     let responses = bot.get_responses();
-    let banned_user = responses.banned_chat_members.last().unwrap();
-    let unbanned_user = responses.unbanned_chat_members.last().unwrap();
-
-    assert_eq!(banned_user.user_id, MockUser::ID);
-    assert_eq!(unbanned_user.user_id, MockUser::ID);
+    // The conversion gave only 10 stars, and the upgrade costs 25
+    assert_eq!(responses.converted_gifts_to_stars.len(), 1);
+    assert!(responses.upgraded_gifts.is_empty());
+    assert!(responses.transferred_gifts.is_empty());
 }
 
 #[tokio::test]
-async fn test_restrict() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/restrict"), get_schema());
+async fn test_set_passport_data_errors() {
+    let mut bot = MockBot::new(MockMessagePassportData::new(), get_schema());
 
     bot.dispatch().await;
 
     let responses = bot.get_responses();
-    let restricted_user = responses.restricted_chat_members.last().unwrap();
+    let passport_data_errors = responses.set_passport_data_errors.last().unwrap();
 
-    assert_eq!(restricted_user.user_id, MockUser::ID);
-    assert_eq!(restricted_user.permissions, ChatPermissions::empty());
+    assert_eq!(passport_data_errors.user_id, MockUser::ID);
+    assert_eq!(
+        passport_data_errors.errors[0].kind,
+        PassportElementErrorKind::Unspecified(PassportElementErrorUnspecified::new(
+            PassportElementErrorUnspecifiedType::Unspecified,
+            MockMessagePassportData::ELEMENT_HASH,
+        ))
+    );
 }
 
 #[tokio::test]
-async fn test_send_chat_action() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/chataction"), get_schema());
+async fn test_share_users_and_chat() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/requestpeers"), get_schema());
+    bot.dispatch().await;
+    let keyboard_message = bot.get_responses().sent_messages.last().unwrap().clone();
 
+    bot.share_users(
+        &keyboard_message,
+        vec![MockSharedUser::new().build(), MockSharedUser::new().build()],
+    );
     bot.dispatch().await;
+    assert_eq!(
+        bot.get_responses().sent_messages.last().unwrap().text(),
+        Some("2 users for 7")
+    );
 
-    let responses = bot.get_responses();
-    let last_chat_action = responses.sent_chat_actions.last().unwrap();
+    bot.share_chat(&keyboard_message, -100123);
+    bot.dispatch().await;
+    assert_eq!(
+        bot.get_responses().sent_messages.last().unwrap().text(),
+        Some("-100123 for 8")
+    );
+}
 
-    assert_eq!(last_chat_action.action, "typing");
+#[tokio::test]
+#[should_panic(expected = "doesn't have a request_users keyboard button")]
+async fn test_share_users_without_button() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/echo hi"), get_schema());
+    bot.dispatch().await;
+    let message = bot.get_responses().sent_messages.last().unwrap().clone();
+
+    bot.share_users(&message, vec![]);
 }
 
 #[tokio::test]
-async fn test_set_message_reaction() {
-    let mut bot = MockBot::new(
-        MockMessageText::new().text("/setmessagereaction"),
-        get_schema(),
-    );
+async fn test_reply_keyboard() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/replykeyboard"), get_schema());
 
     bot.dispatch().await;
 
     let responses = bot.get_responses();
-    let last_reaction = responses.set_message_reaction.last().unwrap();
-
+    let sent_message = responses.sent_messages_text.last().unwrap();
+    assert!(matches!(
+        sent_message.bot_request.reply_markup,
+        Some(ReplyMarkup::Keyboard(_))
+    ));
     assert_eq!(
-        last_reaction.reaction.clone().unwrap()[0],
-        ReactionType::Emoji {
-            emoji: "👍".to_owned()
-        }
+        bot.get_reply_markup(&sent_message.message),
+        sent_message.bot_request.reply_markup
     );
+
+    bot.assert_last_reply_keyboard(vec![vec!["Yes", "No"], vec!["Cancel"]]);
+    bot.assert_last_input_field_placeholder("Answer");
+    bot.assert_last_one_time_keyboard(true);
+    bot.assert_last_selective(true);
 }
 
 #[tokio::test]
-async fn test_set_my_commands() {
-    let mut bot = MockBot::new(MockMessageText::new().text("/setmycommands"), get_schema());
+async fn test_press_reply_button() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/replykeyboard"), get_schema());
+    bot.dispatch().await;
+    let keyboard_message = bot.get_responses().sent_messages.last().unwrap().clone();
+
+    bot.press_reply_button("Cancel");
+
+    let UpdateKind::Message(message) = &bot.updates[0].kind else {
+        panic!("The update is not a message!");
+    };
+    assert_eq!(message.text(), Some("Cancel"));
+    assert_eq!(message.chat.id, keyboard_message.chat.id);
+    assert_eq!(message.from.as_ref().unwrap().id.0, MockUser::ID);
+}
 
+#[tokio::test]
+#[should_panic(expected = "The reply keyboard has no \"Maybe\" button!")]
+async fn test_press_missing_reply_button() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/replykeyboard"), get_schema());
     bot.dispatch().await;
 
-    let responses = bot.get_responses();
-    let set_commands = responses.set_my_commands.last().unwrap();
+    bot.press_reply_button("Maybe");
+}
 
-    assert_eq!(
-        set_commands.commands.first(),
-        Some(&BotCommand {
-            command: String::from("test"),
-            description: String::from("test")
-        })
-    );
+#[tokio::test]
+async fn test_force_reply() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/forcereply"), get_schema());
+
+    bot.dispatch().await;
+
+    bot.assert_last_input_field_placeholder("Name");
+    bot.assert_last_selective(false);
+}
+
+#[tokio::test]
+#[should_panic(expected = "Keyboard layouts are not equal!")]
+async fn test_reply_keyboard_layout_mismatch() {
+    let mut bot = MockBot::new(MockMessageText::new().text("/replykeyboard"), get_schema());
+
+    bot.dispatch().await;
+
+    bot.assert_last_reply_keyboard(vec![vec!["Yes"], vec!["No"]]);
 }
 
 #[tokio::test]
@@ -1238,6 +5290,72 @@ async fn test_send_invoice() {
     );
 }
 
+fn get_donation_schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Update::filter_message().endpoint(|message: Message, bot: Bot| async move {
+        let prices = vec![
+            LabeledPrice::new("Coffee", 300),
+            LabeledPrice::new("Cookie", 150),
+        ];
+        bot.send_invoice(
+            message.chat.id,
+            "Donation",
+            "Buy us a coffee",
+            "donation",
+            "USD",
+            prices,
+        )
+        .provider_token("provider_token")
+        .provider_data(r#"{"receipt":true}"#)
+        .max_tip_amount(1000)
+        .suggested_tip_amounts([100, 200, 500])
+        .photo_url(reqwest::Url::parse("https://example.com/coffee.jpg")?)
+        .photo_size(12345)
+        .photo_width(640)
+        .photo_height(480)
+        .need_email(true)
+        .send_email_to_provider(true)
+        .is_flexible(true)
+        .await?;
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn test_invoice_provider_fields() {
+    let mut bot = MockBot::new(MockMessageText::new(), get_donation_schema());
+
+    bot.dispatch().await;
+
+    let sent = bot.get_responses().sent_messages_invoice[0].clone();
+    let invoice = sent.message.invoice().unwrap();
+    assert_eq!(invoice.currency, "USD");
+    assert_eq!(invoice.total_amount, 450);
+
+    let request = sent.bot_request;
+    assert_eq!(request.provider_token.as_deref(), Some("provider_token"));
+    assert_eq!(
+        request.provider_data.as_deref(),
+        Some(r#"{"receipt":true}"#)
+    );
+    assert_eq!(request.max_tip_amount, Some(1000));
+    assert_eq!(request.suggested_tip_amounts, Some(vec![100, 200, 500]));
+    assert_eq!(
+        request.photo_url.as_deref(),
+        Some("https://example.com/coffee.jpg")
+    );
+    assert_eq!(
+        (
+            request.photo_size,
+            request.photo_width,
+            request.photo_height
+        ),
+        (Some(12345), Some(640), Some(480))
+    );
+    assert_eq!(request.need_email, Some(true));
+    assert_eq!(request.send_email_to_provider, Some(true));
+    assert_eq!(request.is_flexible, Some(true));
+}
+
 #[tokio::test]
 async fn test_edited_message() {
     let mock_message = MockMessageText::new().text("/forwardmessage first");
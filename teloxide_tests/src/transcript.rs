@@ -0,0 +1,167 @@
+//! A readable log of the conversation between the user and the bot, see
+//! [`MockBot::transcript`]
+//!
+//! [`MockBot::transcript`]: crate::MockBot::transcript
+use serde_json::Value;
+use teloxide::types::{InlineKeyboardButtonKind, Message, Update, UpdateKind};
+
+use crate::server::{sent_message_method, BotRequest};
+
+/// Something that happened in the conversation
+#[derive(Clone, Debug)]
+pub(crate) enum TranscriptEntry {
+    /// The update was fed to the bot
+    Update(Update),
+    /// The bot sent the request to the fake server
+    Request(BotRequest),
+}
+
+/// Renders the entries one per line, `getMe` calls of the dispatcher are left out
+pub(crate) fn render(entries: &[TranscriptEntry]) -> String {
+    entries
+        .iter()
+        .filter_map(|entry| match entry {
+            TranscriptEntry::Update(update) => Some(update_line(update)),
+            TranscriptEntry::Request(request) if request.method == "getMe" => None,
+            TranscriptEntry::Request(request) => Some(request_line(request)),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn update_line(update: &Update) -> String {
+    match &update.kind {
+        UpdateKind::Message(message)
+        | UpdateKind::ChannelPost(message)
+        | UpdateKind::BusinessMessage(message) => format!("User: {}", describe_message(message)),
+        UpdateKind::EditedMessage(message)
+        | UpdateKind::EditedChannelPost(message)
+        | UpdateKind::EditedBusinessMessage(message) => format!(
+            "User edited msg {}: {}",
+            message.id,
+            describe_message(message)
+        ),
+        UpdateKind::CallbackQuery(query) => {
+            let data = query.data.clone().unwrap_or_default();
+            // The text of the button is more readable than its data
+            let button = query
+                .regular_message()
+                .and_then(|message| message.reply_markup())
+                .into_iter()
+                .flat_map(|markup| markup.inline_keyboard.iter().flatten())
+                .find(|button| {
+                    matches!(&button.kind, InlineKeyboardButtonKind::CallbackData(button_data)
+                        if *button_data == data)
+                })
+                .map_or(data, |button| button.text.clone());
+            format!("User clicked {button:?}")
+        }
+        UpdateKind::InlineQuery(query) => format!("User searched inline: {}", query.query),
+        _ => {
+            let kind = serde_json::to_value(update)
+                .ok()
+                .and_then(|update| {
+                    update
+                        .as_object()?
+                        .keys()
+                        .find(|key| *key != "update_id")
+                        .cloned()
+                })
+                .unwrap_or_else(|| "unknown".to_string());
+            format!("User sent a {kind} update")
+        }
+    }
+}
+
+fn describe_message(message: &Message) -> String {
+    if let Some(text) = message.text() {
+        return text.to_string();
+    }
+    let kind = match sent_message_method(message) {
+        "sendMessage" => "message".to_string(),
+        method => subject(method),
+    };
+    match message.caption() {
+        Some(caption) => format!("[{kind}] {caption}"),
+        None => format!("[{kind}]"),
+    }
+}
+
+fn request_line(request: &BotRequest) -> String {
+    let body = &request.body;
+    let field = |name: &str| match &body[name] {
+        Value::String(value) => value.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    };
+    let with_text = |line: String, text: String| match text.is_empty() {
+        true => line,
+        false => format!("{line}: {text}"),
+    };
+    let message = || match body["inline_message_id"].as_str() {
+        Some(_) => "an inline message".to_string(),
+        None => format!("msg {}", field("message_id")),
+    };
+
+    match request.method.as_str() {
+        "sendMessage" => format!("Bot: {}", field("text")),
+        "sendChatAction" => format!("Bot sent the {:?} chat action", field("action")),
+        "sendMediaGroup" => format!(
+            "Bot sent a media group of {}",
+            body["media"].as_array().map_or(0, Vec::len)
+        ),
+        "sendPoll" => format!("Bot sent a poll: {}", field("question")),
+        method if method.starts_with("send") => {
+            let subject = subject(method);
+            let article = match subject.starts_with(['a', 'e', 'i', 'o', 'u']) {
+                true => "an",
+                false => "a",
+            };
+            with_text(format!("Bot sent {article} {subject}"), field("caption"))
+        }
+        "editMessageText" => format!("Bot edited {}: {}", message(), field("text")),
+        "editMessageCaption" => with_text(
+            format!("Bot edited the caption of {}", message()),
+            field("caption"),
+        ),
+        "editMessageReplyMarkup" => format!("Bot edited the keyboard of {}", message()),
+        "editMessageLiveLocation" => format!("Bot moved the live location of {}", message()),
+        "deleteMessage" => format!("Bot deleted msg {}", field("message_id")),
+        "deleteMessages" => {
+            let ids = body["message_ids"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("Bot deleted msgs {ids}")
+        }
+        "forwardMessage" | "copyMessage" => format!(
+            "Bot {} msg {} to chat {}",
+            if request.method == "forwardMessage" {
+                "forwarded"
+            } else {
+                "copied"
+            },
+            field("message_id"),
+            field("chat_id")
+        ),
+        "answerCallbackQuery" => {
+            with_text("Bot answered the callback query".to_string(), field("text"))
+        }
+        method => format!("Bot called {method}"),
+    }
+}
+
+// `sendVideoNote` -> `video note`
+fn subject(method: &str) -> String {
+    let mut subject = String::new();
+    for c in method.trim_start_matches("send").chars() {
+        if c.is_uppercase() && !subject.is_empty() {
+            subject.push(' ');
+        }
+        subject.extend(c.to_lowercase());
+    }
+    subject
+}
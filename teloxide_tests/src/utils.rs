@@ -1,5 +1,8 @@
 use serde_json::Value;
-use teloxide::{prelude::*, types::FileMeta};
+use teloxide::{
+    prelude::*,
+    types::{FileMeta, MaybeAnonymousUser, UpdateKind},
+};
 
 macro_rules! assert_eqn {
     ($actual:expr, $expected:expr $(,)?) => {
@@ -58,18 +61,27 @@ pub fn find_file(value: Value) -> Option<FileMeta> {
     None
 }
 
-pub fn find_chat_id(value: Value) -> Option<i64> {
-    // Recursively searches for chat id
-    if let Value::Object(map) = value {
-        for (k, v) in map {
-            if k == "chat" {
-                return v["id"].as_i64();
-            } else if let Some(found) = find_chat_id(v) {
-                return Some(found);
-            }
-        }
+/// Finds the chat of the dialogue the update belongs to. Updates without a chat, like inline
+/// queries or payments, come from a user, so their dialogue is the private chat with the user.
+pub fn find_chat_id(update: &Update) -> Option<ChatId> {
+    if let Some(chat) = update.chat() {
+        return Some(chat.id);
     }
-    None
+    let user = match &update.kind {
+        UpdateKind::InlineQuery(query) => &query.from,
+        UpdateKind::ChosenInlineResult(result) => &result.from,
+        UpdateKind::CallbackQuery(query) => &query.from,
+        UpdateKind::ShippingQuery(query) => &query.from,
+        UpdateKind::PreCheckoutQuery(query) => &query.from,
+        UpdateKind::PurchasedPaidMedia(purchased) => &purchased.from,
+        UpdateKind::BusinessConnection(connection) => &connection.user,
+        UpdateKind::PollAnswer(answer) => match &answer.voter {
+            MaybeAnonymousUser::User(user) => user,
+            MaybeAnonymousUser::Chat(chat) => return Some(chat.id),
+        },
+        _ => return None,
+    };
+    Some(user.id.into())
 }
 
 /// A key that defines the parallelism of updates
@@ -226,8 +226,50 @@ pub fn serialize_raw_fields_derive(input: TokenStream) -> TokenStream {
         }
     });
 
+    let field_checkers = fields.iter().filter(|field| field.ident.as_ref().unwrap() != "file_name" && field.ident.as_ref().unwrap() != "file_data").map(|field| {
+        let field_type = &field.ty;
+        let key = field.ident.as_ref().unwrap().to_string();
+
+        // Strings are taken as is, everything else can fail to parse
+        let type_string = field_type.clone().to_token_stream().to_string();
+        if type_string == syn::parse_str::<syn::Type>("Option<String>").unwrap().to_token_stream().to_string()
+            || type_string == syn::parse_str::<syn::Type>("String").unwrap().to_token_stream().to_string()
+        {
+            quote! {
+                #key => None,
+            }
+        } else {
+            quote! {
+                #key => serde_json::from_str::<#field_type>(value).err().map(|error| Some(error.to_string())),
+            }
+        }
+    });
+
     let expanded = quote! {
         impl SerializeRawFields for #name {
+            fn dropped_raw_fields(
+                fields: &HashMap<String, String>,
+                file_type: FileType,
+            ) -> Vec<(String, Option<String>)> {
+                let mut dropped = vec![];
+                for (key, value) in fields.iter() {
+                    if key == file_type.field_name() {
+                        // The file is in file_name and file_data
+                        continue;
+                    }
+                    let error = match key.as_str() {
+                        #(#field_checkers)*
+                        _ => Some(None),
+                    };
+                    if let Some(error) = error {
+                        dropped.push((key.clone(), error));
+                    }
+                }
+                dropped.sort();
+                dropped
+            }
+
+
             fn serialize_raw_fields(
                 fields: &HashMap<String, String>,
                 attachments: &HashMap<String, Attachment>,